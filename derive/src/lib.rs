@@ -0,0 +1,172 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Derive macros for `pizza-common`, enabled through its `derive` feature.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::format_ident;
+use quote::quote;
+use syn::parse_macro_input;
+use syn::parse_quote;
+use syn::Data;
+use syn::DeriveInput;
+use syn::Fields;
+use syn::Index;
+
+/// Derives `pizza_common::mem::HeapSize` by summing the heap sizes of all
+/// fields, and reports each field as a child in `report()`.
+///
+/// Fields marked `#[heap_size(skip)]` are not counted, e.g. shared handles
+/// whose memory is accounted for elsewhere. Every type parameter must
+/// implement `HeapSize`.
+#[proc_macro_derive(HeapSize, attributes(heap_size))]
+pub fn derive_heap_size(input: TokenStream) -> TokenStream {
+    let mut input = parse_macro_input!(input as DeriveInput);
+    match expand_heap_size(&mut input) {
+        Ok(tokens) => tokens.into(),
+        Err(error) => error.to_compile_error().into(),
+    }
+}
+
+fn is_skipped(field: &syn::Field) -> syn::Result<bool> {
+    let mut skip = false;
+    for attr in field
+        .attrs
+        .iter()
+        .filter(|a| a.path().is_ident("heap_size"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("skip") {
+                skip = true;
+                Ok(())
+            } else {
+                Err(meta.error("expected `skip`"))
+            }
+        })?;
+    }
+    Ok(skip)
+}
+
+/// Returns the pattern binding all counted fields of a struct or variant,
+/// and the (name, binding) of each of them.
+fn bind_fields(fields: &Fields) -> syn::Result<(TokenStream2, Vec<(String, syn::Ident)>)> {
+    let mut bindings = Vec::new();
+    let pattern = match fields {
+        Fields::Named(named) => {
+            let mut parts = Vec::new();
+            for field in &named.named {
+                let ident = field.ident.as_ref().unwrap();
+                if is_skipped(field)? {
+                    continue;
+                }
+                let binding = format_ident!("__{}", ident);
+                parts.push(quote!(#ident: #binding));
+                bindings.push((ident.to_string(), binding));
+            }
+            quote!({ #(#parts,)* .. })
+        }
+        Fields::Unnamed(unnamed) => {
+            let mut parts = Vec::new();
+            for (i, field) in unnamed.unnamed.iter().enumerate() {
+                let index = Index::from(i);
+                if is_skipped(field)? {
+                    continue;
+                }
+                let binding = format_ident!("__{}", i);
+                parts.push(quote!(#index: #binding));
+                bindings.push((i.to_string(), binding));
+            }
+            quote!({ #(#parts,)* .. })
+        }
+        Fields::Unit => quote!(),
+    };
+    Ok((pattern, bindings))
+}
+
+fn expand_heap_size(input: &mut DeriveInput) -> syn::Result<TokenStream2> {
+    let krate = quote!(::pizza_common::mem);
+    let name = &input.ident;
+
+    let mut arms = Vec::new();
+    let mut report_arms = Vec::new();
+    let variants: Vec<(TokenStream2, &Fields)> = match &input.data {
+        Data::Struct(data) => vec![(quote!(Self), &data.fields)],
+        Data::Enum(data) => data
+            .variants
+            .iter()
+            .map(|variant| {
+                let ident = &variant.ident;
+                (quote!(Self::#ident), &variant.fields)
+            })
+            .collect(),
+        Data::Union(_) => {
+            return Err(syn::Error::new_spanned(
+                &input.ident,
+                "HeapSize cannot be derived for unions",
+            ))
+        }
+    };
+    for (path, fields) in variants {
+        let (pattern, bindings) = bind_fields(fields)?;
+        let sizes = bindings
+            .iter()
+            .map(|(_, binding)| quote!(#krate::HeapSize::heap_size(#binding)));
+        arms.push(quote!(#path #pattern => 0 #(+ #sizes)*,));
+        let children = bindings
+            .iter()
+            .map(|(field, binding)| quote!(.child(#krate::HeapSize::report(#binding, #field))));
+        report_arms.push(quote!(#path #pattern => report #(#children)*,));
+    }
+
+    let params: Vec<syn::Ident> = input
+        .generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect();
+    let where_clause = input.generics.make_where_clause();
+    for param in params {
+        where_clause
+            .predicates
+            .push(parse_quote!(#param: #krate::HeapSize));
+    }
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics #krate::HeapSize for #name #ty_generics #where_clause {
+            fn heap_size(&self) -> usize {
+                #[allow(unused_variables)]
+                match self {
+                    #(#arms)*
+                }
+            }
+
+            fn report(&self, name: &str) -> #krate::MemoryReport {
+                let report = #krate::MemoryReport::new(name, #krate::HeapSize::deep_size(self));
+                #[allow(unused_variables)]
+                match self {
+                    #(#report_arms)*
+                }
+            }
+        }
+    })
+}
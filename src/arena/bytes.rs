@@ -0,0 +1,172 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::error::Error;
+use crate::mem::HeapSize;
+use crate::utils::units::ByteSize;
+use alloc::format;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt;
+
+/// An arena for variable-length byte slices.
+///
+/// Slices are copied back to back into chunks that are never reallocated,
+/// so references returned by [`alloc`](Self::alloc) stay valid until the
+/// arena is reset or dropped. Chunks double in size like the chunks of
+/// [`Arena`](super::Arena), and a slice larger than that gets a chunk of
+/// its own.
+pub struct ByteArena {
+    max_memory_bytes: usize,
+    chunks: RefCell<Vec<Vec<u8>>>,
+    total_memory_used: RefCell<usize>,
+}
+
+impl fmt::Debug for ByteArena {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ByteArena")
+            .field("max_memory_bytes", &self.max_memory_bytes)
+            .field("total_chunks", &self.total_chunks())
+            .field("total_memory_used", &*self.total_memory_used.borrow())
+            .finish()
+    }
+}
+
+impl ByteArena {
+    pub fn new(initial_chunk_capacity: usize, max_memory_bytes: impl Into<ByteSize>) -> Self {
+        Self {
+            max_memory_bytes: max_memory_bytes.into().as_usize(),
+            chunks: RefCell::new(alloc::vec![Vec::with_capacity(initial_chunk_capacity)]),
+            total_memory_used: RefCell::new(0),
+        }
+    }
+
+    /// Copies `bytes` into the arena.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc(&self, bytes: &[u8]) -> Result<&mut [u8], Error> {
+        let mut chunks = self.chunks.borrow_mut();
+        let mut total_memory_used = self.total_memory_used.borrow_mut();
+
+        if bytes.len() > self.max_memory_bytes.saturating_sub(*total_memory_used) {
+            return Err(Error::CapacityExceeded(format!(
+                "Arena capacity exceeded, {}+{}/{}",
+                *total_memory_used,
+                bytes.len(),
+                self.max_memory_bytes
+            )));
+        }
+
+        let last_index = chunks.len() - 1;
+        let last_chunk = &mut chunks[last_index];
+        if last_chunk.is_empty() {
+            // Nothing points into an empty chunk yet, so it may grow in place
+            last_chunk.reserve(bytes.len());
+        } else if last_chunk.capacity() - last_chunk.len() < bytes.len() {
+            let new_capacity = (last_chunk.capacity() * 2).max(bytes.len());
+            chunks.push(Vec::with_capacity(new_capacity));
+        }
+
+        let chunk = chunks.last_mut().unwrap();
+        let start = chunk.len();
+        chunk.extend_from_slice(bytes);
+        *total_memory_used += bytes.len();
+
+        unsafe {
+            Ok(core::slice::from_raw_parts_mut(
+                chunk.as_mut_ptr().add(start),
+                bytes.len(),
+            ))
+        }
+    }
+
+    /// Copies `s` into the arena.
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_str(&self, s: &str) -> Result<&mut str, Error> {
+        let bytes = self.alloc(s.as_bytes())?;
+        // SAFETY: The bytes are a copy of a `str`.
+        Ok(unsafe { core::str::from_utf8_unchecked_mut(bytes) })
+    }
+
+    pub fn total_chunks(&self) -> usize {
+        self.chunks.borrow().len()
+    }
+
+    /// Number of bytes stored in the arena.
+    pub fn total_memory_usage(&self) -> usize {
+        *self.total_memory_used.borrow()
+    }
+
+    /// Drops all slices, keeping only the largest chunk for reuse.
+    pub fn reset(&mut self) {
+        let chunks = self.chunks.get_mut();
+        let mut largest = chunks.pop().unwrap();
+        largest.clear();
+        chunks.clear();
+        chunks.push(largest);
+        *self.total_memory_used.get_mut() = 0;
+    }
+}
+
+impl HeapSize for ByteArena {
+    fn heap_size(&self) -> usize {
+        self.chunks.borrow().heap_size()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alloc_keeps_addresses() {
+        let mut arena = ByteArena::new(4, 1024);
+        let first = arena.alloc(b"abc").unwrap();
+        let second = arena.alloc_str("defgh").unwrap();
+        let third = arena.alloc(b"").unwrap();
+        let large = arena.alloc(&[7; 100]).unwrap();
+        first[0] = b'x';
+        second.make_ascii_uppercase();
+
+        assert_eq!(first, b"xbc");
+        assert_eq!(second, "DEFGH");
+        assert!(third.is_empty());
+        assert_eq!(large.len(), 100);
+        assert_eq!(arena.total_chunks(), 3);
+        assert_eq!(arena.total_memory_usage(), 108);
+
+        arena.reset();
+        assert_eq!(arena.total_chunks(), 1);
+        assert_eq!(arena.total_memory_usage(), 0);
+        // Only the 100 byte chunk is kept.
+        let chunk_list = arena.chunks.borrow().capacity() * size_of::<Vec<u8>>();
+        assert_eq!(arena.heap_size(), chunk_list + 100);
+    }
+
+    #[test]
+    fn test_limit() {
+        let arena = ByteArena::new(4, 5);
+        arena.alloc(b"abc").unwrap();
+        assert!(arena.alloc(b"def").is_err());
+        arena.alloc(b"de").unwrap();
+        assert_eq!(arena.total_memory_usage(), 5);
+    }
+}
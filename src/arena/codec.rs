@@ -0,0 +1,576 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! A compact binary encoding of [`Arena`], as an alternative to serde for
+//! large arenas.
+//!
+//! The encoding is a [`Header`] with format id [`ARENA_FORMAT`] followed by
+//! a little endian payload that keeps the chunk layout:
+//!
+//! ```text
+//! max_items u64 | max_memory_bytes u64 | total_items u64 | total_memory_used u64
+//! chunk count u64 | per chunk: capacity u64, len u64, items
+//! snapshot count u64 | per snapshot: chunk index u64, chunk len u64
+//! ```
+//!
+//! Items encode themselves through [`ItemCodec`]. Decoding recomputes the
+//! memory in use rather than trusting `total_memory_used`.
+
+use super::shallow_mem_size;
+use super::Arena;
+use super::GrowthPolicy;
+use crate::serde_ext::Header;
+use crate::serde_ext::HeaderError;
+use crate::store::PersistStore;
+use crate::store::RecoverableStore;
+use crate::store::StoreError;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt;
+
+/// Format id of encoded arenas in their [`Header`].
+pub const ARENA_FORMAT: [u8; 4] = *b"PZAR";
+/// Version of the layout written by [`Arena::to_bytes`].
+pub const ARENA_VERSION: u16 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArenaCodecError {
+    Header(HeaderError),
+    /// The payload ended in the middle of a value.
+    UnexpectedEof,
+    /// The payload decoded, but does not describe a consistent arena.
+    Invalid(&'static str),
+}
+
+impl fmt::Display for ArenaCodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArenaCodecError::Header(err) => write!(f, "invalid arena header: {}", err),
+            ArenaCodecError::UnexpectedEof => f.write_str("unexpected end of arena data"),
+            ArenaCodecError::Invalid(reason) => write!(f, "invalid arena data: {}", reason),
+        }
+    }
+}
+
+impl From<ArenaCodecError> for StoreError {
+    fn from(err: ArenaCodecError) -> Self {
+        match err {
+            ArenaCodecError::Header(err) => StoreError::Header(err),
+            err => StoreError::Payload(alloc::string::ToString::to_string(&err)),
+        }
+    }
+}
+
+impl From<HeaderError> for ArenaCodecError {
+    fn from(err: HeaderError) -> Self {
+        ArenaCodecError::Header(err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<ArenaCodecError> for std::io::Error {
+    fn from(err: ArenaCodecError) -> Self {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            alloc::string::ToString::to_string(&err),
+        )
+    }
+}
+
+/// Binary encoding of arena items.
+///
+/// Integers and floats are written as little endian, strings and vectors
+/// with a `u64` length prefix.
+pub trait ItemCodec: Sized {
+    fn encode(&self, out: &mut Vec<u8>);
+
+    /// Decodes a value from the front of `input`, advancing it.
+    fn decode(input: &mut &[u8]) -> Result<Self, ArenaCodecError>;
+}
+
+fn take<'a>(input: &mut &'a [u8], len: usize) -> Result<&'a [u8], ArenaCodecError> {
+    if input.len() < len {
+        return Err(ArenaCodecError::UnexpectedEof);
+    }
+    let (head, tail) = input.split_at(len);
+    *input = tail;
+    Ok(head)
+}
+
+fn decode_len(input: &mut &[u8]) -> Result<usize, ArenaCodecError> {
+    usize::try_from(u64::decode(input)?).map_err(|_| ArenaCodecError::Invalid("length overflow"))
+}
+
+macro_rules! impl_item_codec_le {
+    ($($ty:ty),*) => {
+        $(
+            impl ItemCodec for $ty {
+                fn encode(&self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_le_bytes());
+                }
+
+                fn decode(input: &mut &[u8]) -> Result<Self, ArenaCodecError> {
+                    let bytes = take(input, size_of::<$ty>())?;
+                    Ok(<$ty>::from_le_bytes(bytes.try_into().unwrap()))
+                }
+            }
+        )*
+    };
+}
+
+impl_item_codec_le!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+impl ItemCodec for usize {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (*self as u64).encode(out)
+    }
+
+    fn decode(input: &mut &[u8]) -> Result<Self, ArenaCodecError> {
+        decode_len(input)
+    }
+}
+
+impl ItemCodec for bool {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8)
+    }
+
+    fn decode(input: &mut &[u8]) -> Result<Self, ArenaCodecError> {
+        match u8::decode(input)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(ArenaCodecError::Invalid("bool out of range")),
+        }
+    }
+}
+
+impl ItemCodec for String {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.len().encode(out);
+        out.extend_from_slice(self.as_bytes());
+    }
+
+    fn decode(input: &mut &[u8]) -> Result<Self, ArenaCodecError> {
+        let len = decode_len(input)?;
+        let bytes = take(input, len)?;
+        core::str::from_utf8(bytes)
+            .map(String::from)
+            .map_err(|_| ArenaCodecError::Invalid("string is not UTF-8"))
+    }
+}
+
+impl<T: ItemCodec> ItemCodec for Vec<T> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.len().encode(out);
+        for item in self {
+            item.encode(out);
+        }
+    }
+
+    fn decode(input: &mut &[u8]) -> Result<Self, ArenaCodecError> {
+        let len = decode_len(input)?;
+        // Every item takes at least a byte, except for zero sized ones,
+        // which do not allocate.
+        let mut items = Vec::with_capacity(len.min(input.len()));
+        for _ in 0..len {
+            items.push(T::decode(input)?);
+        }
+        Ok(items)
+    }
+}
+
+impl<T: ItemCodec> ItemCodec for Option<T> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.is_some().encode(out);
+        if let Some(value) = self {
+            value.encode(out);
+        }
+    }
+
+    fn decode(input: &mut &[u8]) -> Result<Self, ArenaCodecError> {
+        if bool::decode(input)? {
+            Ok(Some(T::decode(input)?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+macro_rules! impl_item_codec_tuple {
+    ($($name:ident)+) => {
+        impl<$($name: ItemCodec),+> ItemCodec for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn encode(&self, out: &mut Vec<u8>) {
+                let ($($name,)+) = self;
+                $($name.encode(out);)+
+            }
+
+            fn decode(input: &mut &[u8]) -> Result<Self, ArenaCodecError> {
+                Ok(($($name::decode(input)?,)+))
+            }
+        }
+    };
+}
+
+impl_item_codec_tuple!(A);
+impl_item_codec_tuple!(A B);
+impl_item_codec_tuple!(A B C);
+impl_item_codec_tuple!(A B C D);
+
+/// How [`Arena::read_from_with`] lays out the decoded items.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChunkLayout {
+    /// Rebuild every chunk with its original capacity, so that snapshots stay
+    /// valid and later allocations continue exactly as before.
+    /// [`ArenaHandle`](super::ArenaHandle) generations are not encoded, so
+    /// handles should not be carried over to the decoded arena.
+    #[default]
+    Preserve,
+    /// Put all items into a single chunk of exactly the right size.
+    /// Snapshots are remapped onto it, handles are not.
+    Compact,
+}
+
+impl<T: ItemCodec> Arena<T> {
+    /// Encodes the arena, including its header.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the arena has deallocated slots, see
+    /// [`with_free_list`](Self::with_free_list).
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.save()
+    }
+
+    fn encode_payload_into(&self, payload: &mut Vec<u8>) {
+        assert!(
+            !self.has_vacant_slots(),
+            "cannot encode an arena with deallocated slots"
+        );
+        let chunks = self.chunks.borrow();
+        let snapshot_offsets = self.snapshot_offsets.borrow();
+
+        self.max_items.encode(payload);
+        self.max_memory_bytes.encode(payload);
+        self.total_items.borrow().encode(payload);
+        self.total_memory_used.borrow().encode(payload);
+        chunks.len().encode(payload);
+        for chunk in chunks.iter() {
+            chunk.capacity().encode(payload);
+            chunk.len().encode(payload);
+            for item in chunk {
+                item.encode(payload);
+            }
+        }
+        snapshot_offsets.len().encode(payload);
+        for &(chunk_index, chunk_len) in snapshot_offsets.iter() {
+            chunk_index.encode(payload);
+            chunk_len.encode(payload);
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_to(&self, writer: &mut impl std::io::Write) -> std::io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+
+    /// Decodes an arena written by [`to_bytes`](Self::to_bytes), keeping
+    /// its chunk layout.
+    pub fn read_from(bytes: &[u8]) -> Result<Self, ArenaCodecError> {
+        Self::read_from_with(bytes, ChunkLayout::Preserve)
+    }
+
+    /// Decodes an arena written by [`to_bytes`](Self::to_bytes).
+    ///
+    /// Memory is accounted for shallowly, use
+    /// [`with_deep_accounting`](Self::with_deep_accounting) to switch.
+    pub fn read_from_with(bytes: &[u8], layout: ChunkLayout) -> Result<Self, ArenaCodecError> {
+        let (header, payload) = Header::parse_with_payload(bytes)?;
+        header.expect(ARENA_FORMAT, ARENA_VERSION)?;
+        Self::decode_payload_with(payload, layout)
+    }
+
+    fn decode_payload_with(mut input: &[u8], layout: ChunkLayout) -> Result<Self, ArenaCodecError> {
+        let input = &mut input;
+
+        let max_items = usize::decode(input)?;
+        let max_memory_bytes = usize::decode(input)?;
+        let total_items = usize::decode(input)?;
+        // Recomputed below, the items are accounted for shallowly.
+        let _total_memory_used = usize::decode(input)?;
+        if total_items > max_items {
+            return Err(ArenaCodecError::Invalid("more items than max_items"));
+        }
+        // Every item takes at least a byte, so a corrupted count fails here
+        // rather than reserving memory for it.
+        if size_of::<T>() != 0 && total_items > input.len() {
+            return Err(ArenaCodecError::UnexpectedEof);
+        }
+
+        let chunk_count = usize::decode(input)?;
+        let mut chunks = Vec::new();
+        for _ in 0..chunk_count {
+            let capacity = usize::decode(input)?;
+            let len = usize::decode(input)?;
+            if len > capacity {
+                return Err(ArenaCodecError::Invalid("chunk longer than its capacity"));
+            }
+            let mut chunk = Vec::new();
+            let reserve = match layout {
+                ChunkLayout::Preserve => capacity,
+                ChunkLayout::Compact => len.min(input.len()),
+            };
+            chunk
+                .try_reserve_exact(reserve)
+                .map_err(|_| ArenaCodecError::Invalid("chunk capacity too large"))?;
+            for _ in 0..len {
+                chunk.push(T::decode(input)?);
+            }
+            chunks.push(chunk);
+        }
+        if chunks.is_empty() {
+            return Err(ArenaCodecError::Invalid("arena without chunks"));
+        }
+        if chunks.iter().map(Vec::len).sum::<usize>() != total_items {
+            return Err(ArenaCodecError::Invalid("item count mismatch"));
+        }
+
+        let snapshot_count = usize::decode(input)?;
+        let mut snapshot_offsets = Vec::with_capacity(snapshot_count.min(input.len()));
+        for _ in 0..snapshot_count {
+            let chunk_index = usize::decode(input)?;
+            let chunk_len = usize::decode(input)?;
+            if chunks.get(chunk_index).is_none_or(|c| chunk_len > c.len()) {
+                return Err(ArenaCodecError::Invalid("snapshot out of bounds"));
+            }
+            snapshot_offsets.push((chunk_index, chunk_len));
+        }
+        if !input.is_empty() {
+            return Err(ArenaCodecError::Invalid("trailing bytes"));
+        }
+
+        if layout == ChunkLayout::Compact {
+            let starts: Vec<usize> = chunks
+                .iter()
+                .scan(0, |start, chunk| {
+                    let chunk_start = *start;
+                    *start += chunk.len();
+                    Some(chunk_start)
+                })
+                .collect();
+            for (chunk_index, chunk_len) in &mut snapshot_offsets {
+                *chunk_len += starts[*chunk_index];
+                *chunk_index = 0;
+            }
+            let mut items = Vec::with_capacity(total_items);
+            for chunk in chunks {
+                items.extend(chunk);
+            }
+            chunks = alloc::vec![items];
+        }
+        let total_memory_used = chunks.iter().flatten().map(shallow_mem_size).sum();
+
+        Ok(Arena {
+            max_items,
+            max_memory_bytes,
+            chunks: RefCell::new(chunks),
+            snapshot_offsets: RefCell::new(snapshot_offsets),
+            total_items: RefCell::new(total_items),
+            total_memory_used: RefCell::new(total_memory_used),
+            generation: RefCell::new(0),
+            mem_size: shallow_mem_size,
+            free_list: None,
+            initial_item_capacity: 1,
+            growth: GrowthPolicy::default(),
+            spare_chunks: RefCell::new(Vec::new()),
+            rollback_generations: RefCell::new(Vec::new()),
+        })
+    }
+}
+
+impl<T: ItemCodec> PersistStore for Arena<T> {
+    const FORMAT: [u8; 4] = ARENA_FORMAT;
+    const VERSION: u16 = ARENA_VERSION;
+
+    fn encode_payload(&self, out: &mut Vec<u8>) {
+        self.encode_payload_into(out)
+    }
+
+    /// Fails instead of panicking like [`save`](PersistStore::save) if the
+    /// arena has deallocated slots.
+    #[cfg(feature = "std")]
+    fn checkpoint(&self, path: impl AsRef<std::path::Path>) -> std::io::Result<()> {
+        if self.has_vacant_slots() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "cannot encode an arena with deallocated slots",
+            ));
+        }
+        crate::store::persist::write_atomically(path.as_ref(), &self.save())
+    }
+}
+
+impl<T: ItemCodec> RecoverableStore for Arena<T> {
+    fn decode_payload(_version: u16, payload: &[u8]) -> Result<Self, StoreError> {
+        Ok(Self::decode_payload_with(payload, ChunkLayout::Preserve)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+    use alloc::vec;
+
+    fn sample() -> Arena<(u32, String, Option<Vec<u8>>)> {
+        let arena = Arena::new(2, 1000, 1 << 20);
+        for i in 0..5u32 {
+            arena
+                .alloc((
+                    i,
+                    format!("item-{}", i),
+                    (i % 2 == 0).then(|| vec![i as u8; 3]),
+                ))
+                .unwrap();
+            if i % 2 == 1 {
+                arena.snapshot();
+            }
+        }
+        arena
+    }
+
+    #[test]
+    fn test_round_trip_preserves_layout() {
+        let arena = sample();
+        let bytes = arena.to_bytes();
+        let decoded = Arena::read_from(&bytes).unwrap();
+
+        assert_eq!(
+            decoded.iter().collect::<Vec<_>>(),
+            arena.iter().collect::<Vec<_>>()
+        );
+        let capacities = |a: &Arena<_>| {
+            a.chunks
+                .borrow()
+                .iter()
+                .map(Vec::capacity)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(capacities(&decoded), capacities(&arena));
+        assert_eq!(
+            *decoded.snapshot_offsets.borrow(),
+            *arena.snapshot_offsets.borrow()
+        );
+        assert_eq!(decoded.total_items(), 5);
+        assert_eq!(decoded.total_memory_usage(), arena.total_memory_usage());
+        assert_eq!(decoded.max_items, 1000);
+        assert_eq!(decoded.get_snapshot(1).len(), 4);
+        assert_eq!(decoded.to_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_memory_is_recomputed() {
+        let arena = Arena::new(4, 10, 100);
+        arena.alloc(1u64).unwrap();
+        arena.alloc(2u64).unwrap();
+        let mut payload = arena.to_bytes()[crate::serde_ext::HEADER_LEN..].to_vec();
+        // Claim more memory than the limit, as corrupted input might.
+        payload[24..32].copy_from_slice(&1000u64.to_le_bytes());
+        let mut bytes = Header::new(ARENA_FORMAT, ARENA_VERSION, &payload)
+            .to_bytes()
+            .to_vec();
+        bytes.extend_from_slice(&payload);
+
+        for layout in [ChunkLayout::Preserve, ChunkLayout::Compact] {
+            let decoded = Arena::<u64>::read_from_with(&bytes, layout).unwrap();
+            assert_eq!(decoded.total_memory_usage(), 16);
+            decoded.alloc(3).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_compact_layout() {
+        let arena = sample();
+        let decoded = Arena::read_from_with(&arena.to_bytes(), ChunkLayout::Compact).unwrap();
+        assert_eq!(decoded.total_chunks(), 1);
+        for snapshot in 0..2 {
+            assert_eq!(decoded.get_snapshot(snapshot), arena.get_snapshot(snapshot));
+        }
+        decoded.alloc((9, String::new(), None)).unwrap();
+        assert_eq!(decoded.total_items(), 6);
+    }
+
+    #[test]
+    fn test_rejects_bad_input() {
+        let bytes = Arena::<u64>::new(4, 10, 100).to_bytes();
+        assert!(Arena::<u64>::read_from(&bytes).is_ok());
+        assert!(matches!(
+            Arena::<u64>::read_from(&bytes[..bytes.len() - 1]),
+            Err(ArenaCodecError::Header(_))
+        ));
+
+        // A chunk claiming more items than it has bytes for.
+        let arena = Arena::new(4, 10, 100);
+        arena.alloc(1u64).unwrap();
+        let mut payload = arena.to_bytes()[crate::serde_ext::HEADER_LEN..].to_vec();
+        payload.truncate(payload.len() - 16);
+        let mut bytes = Header::new(ARENA_FORMAT, ARENA_VERSION, &payload)
+            .to_bytes()
+            .to_vec();
+        bytes.extend_from_slice(&payload);
+        assert_eq!(
+            Arena::<u64>::read_from(&bytes).unwrap_err(),
+            ArenaCodecError::UnexpectedEof
+        );
+
+        // Item counts beyond the limit or the input.
+        let frame = |counts: [u64; 5]| {
+            let mut payload = Vec::new();
+            for count in counts {
+                count.encode(&mut payload);
+            }
+            let mut bytes = Header::new(ARENA_FORMAT, ARENA_VERSION, &payload)
+                .to_bytes()
+                .to_vec();
+            bytes.extend_from_slice(&payload);
+            bytes
+        };
+        assert_eq!(
+            Arena::<u64>::read_from(&frame([1, 100, 2, 0, 1])).unwrap_err(),
+            ArenaCodecError::Invalid("more items than max_items")
+        );
+        assert_eq!(
+            Arena::<u64>::read_from(&frame([u64::MAX, 100, 1 << 40, 0, 1])).unwrap_err(),
+            ArenaCodecError::UnexpectedEof
+        );
+
+        let bytes = Header::new(*b"ELSE", 1, b"").to_bytes();
+        assert_eq!(
+            Arena::<u64>::read_from(&bytes).unwrap_err(),
+            ArenaCodecError::Header(HeaderError::UnexpectedFormat {
+                expected: ARENA_FORMAT,
+                found: *b"ELSE"
+            })
+        );
+    }
+}
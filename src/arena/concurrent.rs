@@ -0,0 +1,510 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::error::Error;
+use crate::mem::HeapSize;
+use crate::utils::units::ByteSize;
+use alloc::boxed::Box;
+use alloc::format;
+use alloc::vec::Vec;
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::hint;
+use core::mem::size_of;
+use core::mem::MaybeUninit;
+use core::ptr;
+use core::sync::atomic::AtomicBool;
+use core::sync::atomic::AtomicPtr;
+use core::sync::atomic::AtomicUsize;
+use core::sync::atomic::Ordering;
+
+/// Enough segments to address every index, as each one doubles in size.
+const MAX_SEGMENTS: usize = usize::BITS as usize;
+
+struct Slot<T> {
+    ready: AtomicBool,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// An append-only vector that can be pushed to from several threads at
+/// once. Elements never move: segment `s` holds `first_capacity << s` slots
+/// and is allocated on first use, so earlier segments stay in place.
+struct SegmentedVec<T> {
+    first_capacity: usize,
+    segments: [AtomicPtr<Slot<T>>; MAX_SEGMENTS],
+}
+
+impl<T> SegmentedVec<T> {
+    fn new(first_capacity: usize) -> Self {
+        Self {
+            first_capacity: first_capacity.max(1).next_power_of_two(),
+            segments: [const { AtomicPtr::new(ptr::null_mut()) }; MAX_SEGMENTS],
+        }
+    }
+
+    fn segment_capacity(&self, segment: usize) -> usize {
+        self.first_capacity << segment
+    }
+
+    /// Maps an index to its segment and the offset within it.
+    fn locate(&self, index: usize) -> (usize, usize) {
+        let block = index / self.first_capacity + 1;
+        let segment = (usize::BITS - 1 - block.leading_zeros()) as usize;
+        let start = self.first_capacity * ((1 << segment) - 1);
+        (segment, index - start)
+    }
+
+    fn allocated_segments(&self) -> impl Iterator<Item = (usize, *mut Slot<T>)> + '_ {
+        self.segments
+            .iter()
+            .map(|segment| segment.load(Ordering::Acquire))
+            .take_while(|segment| !segment.is_null())
+            .enumerate()
+    }
+
+    fn slot(&self, index: usize) -> Option<&Slot<T>> {
+        let (segment, offset) = self.locate(index);
+        let slots = self.segments[segment].load(Ordering::Acquire);
+        // SAFETY: A non-null segment points to `segment_capacity` slots that
+        // live until `self` is dropped or cleared through `&mut self`.
+        (!slots.is_null()).then(|| unsafe { &*slots.add(offset) })
+    }
+
+    fn slot_or_allocate(&self, index: usize) -> &Slot<T> {
+        let (segment, offset) = self.locate(index);
+        let mut slots = self.segments[segment].load(Ordering::Acquire);
+        if slots.is_null() {
+            let fresh: Box<[Slot<T>]> = (0..self.segment_capacity(segment))
+                .map(|_| Slot {
+                    ready: AtomicBool::new(false),
+                    value: UnsafeCell::new(MaybeUninit::uninit()),
+                })
+                .collect();
+            let fresh = Box::into_raw(fresh) as *mut Slot<T>;
+            slots = match self.segments[segment].compare_exchange(
+                ptr::null_mut(),
+                fresh,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => fresh,
+                Err(installed) => {
+                    // Another thread won the race, free our copy.
+                    // SAFETY: `fresh` came from `Box::into_raw` above and
+                    // was never shared.
+                    unsafe { drop(self.segment_box(segment, fresh)) };
+                    installed
+                }
+            };
+        }
+        // SAFETY: See `slot`.
+        unsafe { &*slots.add(offset) }
+    }
+
+    /// Rebuilds the box of a segment.
+    ///
+    /// # Safety
+    ///
+    /// `slots` must be the pointer installed for `segment`, and the box
+    /// must not be used by anyone else afterwards.
+    unsafe fn segment_box(&self, segment: usize, slots: *mut Slot<T>) -> Box<[Slot<T>]> {
+        let len = self.segment_capacity(segment);
+        Box::from_raw(ptr::slice_from_raw_parts_mut(slots, len))
+    }
+
+    /// Writes the element at `index`, which the caller reserved exclusively.
+    fn write(&self, index: usize, value: T) -> &T {
+        let slot = self.slot_or_allocate(index);
+        // SAFETY: The index was handed out once, so nobody else writes this
+        // slot, and readers ignore it until `ready` is set.
+        unsafe {
+            (*slot.value.get()).write(value);
+        }
+        slot.ready.store(true, Ordering::Release);
+        // SAFETY: The value was just initialized and is never mutated again.
+        unsafe { (*slot.value.get()).assume_init_ref() }
+    }
+
+    /// Returns the element at `index` if it has been written.
+    fn get(&self, index: usize) -> Option<&T> {
+        let slot = self.slot(index)?;
+        // SAFETY: `ready` is only set after the value was initialized.
+        slot.ready
+            .load(Ordering::Acquire)
+            .then(|| unsafe { (*slot.value.get()).assume_init_ref() })
+    }
+
+    /// Returns the element at a reserved `index`, waiting for a concurrent
+    /// writer to finish.
+    fn get_reserved(&self, index: usize) -> &T {
+        loop {
+            if let Some(value) = self.get(index) {
+                return value;
+            }
+            hint::spin_loop();
+        }
+    }
+
+    /// Drops all elements and frees all segments.
+    fn clear(&mut self) {
+        for (segment, slots) in self.allocated_segments().collect::<Vec<_>>() {
+            // SAFETY: `&mut self` guarantees no other access to the slots.
+            let mut slots = unsafe { self.segment_box(segment, slots) };
+            for slot in slots.iter_mut() {
+                if *slot.ready.get_mut() {
+                    // SAFETY: Ready slots hold an initialized value.
+                    unsafe { slot.value.get_mut().assume_init_drop() };
+                }
+            }
+            self.segments[segment] = AtomicPtr::new(ptr::null_mut());
+        }
+    }
+}
+
+impl<T> Drop for SegmentedVec<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+/// A thread-safe counterpart of [`Arena`](super::Arena) that can be
+/// allocated from by several threads through a shared reference.
+///
+/// Items are stored in segments that double in size and never move, so
+/// references returned by [`alloc`](Self::alloc) stay valid for the
+/// lifetime of the arena, as with `Arena`. Items are addressed by the index
+/// returned from [`advanced_alloc`](Self::advanced_alloc), in allocation
+/// order. Since other threads may read an item as soon as it is allocated,
+/// allocation hands out shared rather than mutable references.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::arena::ConcurrentArena;
+///
+/// let arena = ConcurrentArena::new(16, 1_000, 1 << 20);
+/// std::thread::scope(|scope| {
+///     for thread in 0..4 {
+///         let arena = &arena;
+///         scope.spawn(move || {
+///             for i in 0..10 {
+///                 arena.alloc(thread * 100 + i).unwrap();
+///             }
+///         });
+///     }
+/// });
+/// assert_eq!(arena.total_items(), 40);
+/// assert_eq!(arena.iter().sum::<i32>(), (0..4).map(|t| t * 1000 + 45).sum::<i32>());
+/// ```
+pub struct ConcurrentArena<T> {
+    max_items: usize,
+    max_memory_bytes: usize,
+    items: SegmentedVec<T>,
+    snapshot_offsets: SegmentedVec<usize>,
+    total_snapshots: AtomicUsize,
+    total_items: AtomicUsize,
+    total_memory_used: AtomicUsize,
+}
+
+// SAFETY: Items are only written once through exclusively reserved slots
+// and are shared as `&T` afterwards, which needs `T: Sync`; they are moved
+// in from the allocating thread and dropped by whichever thread drops the
+// arena, which needs `T: Send`.
+unsafe impl<T: Send + Sync> Sync for ConcurrentArena<T> {}
+unsafe impl<T: Send> Send for ConcurrentArena<T> {}
+
+impl<T> fmt::Debug for ConcurrentArena<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConcurrentArena")
+            .field("max_items", &self.max_items)
+            .field("max_memory_bytes", &self.max_memory_bytes)
+            .field("total_chunks", &self.total_chunks())
+            .field(
+                "total_snapshots",
+                &self.total_snapshots.load(Ordering::Relaxed),
+            )
+            .field("total_items", &self.total_items())
+            .field("total_memory_used", &self.total_memory_usage())
+            .finish()
+    }
+}
+
+impl<T> ConcurrentArena<T> {
+    /// Creates an arena whose first chunk holds `initial_item_capacity`
+    /// items, rounded up to a power of two.
+    pub fn new(
+        initial_item_capacity: usize,
+        max_items: usize,
+        max_memory_bytes: impl Into<ByteSize>,
+    ) -> Self {
+        Self {
+            max_items,
+            max_memory_bytes: max_memory_bytes.into().as_usize(),
+            items: SegmentedVec::new(initial_item_capacity),
+            snapshot_offsets: SegmentedVec::new(8),
+            total_snapshots: AtomicUsize::new(0),
+            total_items: AtomicUsize::new(0),
+            total_memory_used: AtomicUsize::new(0),
+        }
+    }
+
+    pub fn must_alloc(&self, value: T) -> &T {
+        self.alloc(value).unwrap()
+    }
+
+    pub fn alloc(&self, value: T) -> Result<&T, Error> {
+        let (_, v) = self.advanced_alloc(value)?;
+        Ok(v)
+    }
+
+    /// Allocates an item and returns its index along with a reference.
+    pub fn advanced_alloc(&self, value: T) -> Result<(usize, &T), Error> {
+        let element_size = size_of::<T>();
+        let exceeded = || {
+            Error::CapacityExceeded(format!(
+                "Arena capacity exceeded, {}/{}, {}/{}",
+                self.total_items(),
+                self.max_items,
+                self.total_memory_usage(),
+                self.max_memory_bytes
+            ))
+        };
+
+        self.total_memory_used
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |used| {
+                used.checked_add(element_size)
+                    .filter(|used| *used <= self.max_memory_bytes)
+            })
+            .map_err(|_| exceeded())?;
+        let index = self
+            .total_items
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |items| {
+                (items < self.max_items).then_some(items + 1)
+            });
+        let Ok(index) = index else {
+            self.total_memory_used
+                .fetch_sub(element_size, Ordering::Relaxed);
+            return Err(exceeded());
+        };
+        Ok((index, self.items.write(index, value)))
+    }
+
+    /// Returns the item at `index`, or `None` if it was not allocated or
+    /// another thread is still writing it.
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.items.get(index)
+    }
+
+    /// Number of chunks allocated so far.
+    pub fn total_chunks(&self) -> usize {
+        self.items.allocated_segments().count()
+    }
+
+    pub fn total_items(&self) -> usize {
+        self.total_items.load(Ordering::Acquire)
+    }
+
+    pub fn total_memory_usage(&self) -> usize {
+        self.total_memory_used.load(Ordering::Relaxed)
+    }
+
+    /// Records the current number of items and returns the snapshot id.
+    /// The snapshot covers every allocation that started before it.
+    pub fn snapshot(&self) -> usize {
+        let items = self.total_items();
+        let id = self.total_snapshots.fetch_add(1, Ordering::AcqRel);
+        self.snapshot_offsets.write(id, items);
+        id
+    }
+
+    /// Returns the items of a snapshot in allocation order, waiting for
+    /// allocations still in flight when the snapshot was taken.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the snapshot does not exist.
+    pub fn get_snapshot(&self, snapshot: usize) -> Vec<&T> {
+        assert!(
+            snapshot < self.total_snapshots.load(Ordering::Acquire),
+            "unknown snapshot {}",
+            snapshot
+        );
+        let items = *self.snapshot_offsets.get_reserved(snapshot);
+        (0..items).map(|i| self.items.get_reserved(i)).collect()
+    }
+
+    /// Iterates over the items allocated when the iterator was created, in
+    /// allocation order, waiting for allocations still in flight.
+    pub fn iter(&self) -> impl Iterator<Item = &T> + '_ {
+        (0..self.total_items()).map(|i| self.items.get_reserved(i))
+    }
+
+    /// Drops all items and snapshots.
+    pub fn reset(&mut self) {
+        self.items.clear();
+        self.snapshot_offsets.clear();
+        *self.total_snapshots.get_mut() = 0;
+        *self.total_items.get_mut() = 0;
+        *self.total_memory_used.get_mut() = 0;
+    }
+}
+
+impl<T: HeapSize> HeapSize for ConcurrentArena<T> {
+    fn heap_size(&self) -> usize {
+        let segments: usize = self
+            .items
+            .allocated_segments()
+            .map(|(segment, _)| self.items.segment_capacity(segment) * size_of::<Slot<T>>())
+            .sum();
+        let snapshots: usize = self
+            .snapshot_offsets
+            .allocated_segments()
+            .map(|(segment, _)| {
+                self.snapshot_offsets.segment_capacity(segment) * size_of::<Slot<usize>>()
+            })
+            .sum();
+        segments + snapshots + self.iter().map(HeapSize::heap_size).sum::<usize>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+    use alloc::string::ToString;
+    use alloc::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_locate() {
+        let items: SegmentedVec<u8> = SegmentedVec::new(3);
+        assert_eq!(items.first_capacity, 4);
+        assert_eq!(items.locate(0), (0, 0));
+        assert_eq!(items.locate(3), (0, 3));
+        assert_eq!(items.locate(4), (1, 0));
+        assert_eq!(items.locate(11), (1, 7));
+        assert_eq!(items.locate(12), (2, 0));
+        assert_eq!(items.locate(27), (2, 15));
+        assert_eq!(items.locate(28), (3, 0));
+    }
+
+    #[test]
+    fn test_alloc_get_and_snapshots() {
+        let arena = ConcurrentArena::new(2, 100, 1024);
+        let first = arena.alloc("a".to_string()).unwrap();
+        let (index, _) = arena.advanced_alloc("b".to_string()).unwrap();
+        assert_eq!(index, 1);
+        let snapshot = arena.snapshot();
+        arena.alloc("c".to_string()).unwrap();
+
+        // The first item did not move when the second chunk was allocated.
+        assert_eq!(first, "a");
+        assert_eq!(arena.total_chunks(), 2);
+        assert_eq!(arena.get(2).map(String::as_str), Some("c"));
+        assert_eq!(arena.get(3), None);
+        assert_eq!(arena.get_snapshot(snapshot), ["a", "b"]);
+        assert_eq!(arena.iter().count(), 3);
+        assert_eq!(
+            arena.total_memory_usage(),
+            3 * core::mem::size_of::<String>()
+        );
+    }
+
+    #[test]
+    fn test_limits() {
+        let arena = ConcurrentArena::new(4, 2, 1024);
+        arena.alloc(1u64).unwrap();
+        arena.alloc(2u64).unwrap();
+        assert!(arena.alloc(3u64).is_err());
+        assert_eq!(arena.total_memory_usage(), 16);
+
+        let arena = ConcurrentArena::new(4, 100, 20);
+        arena.alloc(1u64).unwrap();
+        arena.alloc(2u64).unwrap();
+        assert!(arena.alloc(3u64).is_err());
+        assert_eq!(arena.total_items(), 2);
+    }
+
+    #[test]
+    fn test_reset_drops_items() {
+        let tracker = Arc::new(());
+        let mut arena = ConcurrentArena::new(1, 100, 1 << 20);
+        for _ in 0..10 {
+            arena.alloc(Arc::clone(&tracker)).unwrap();
+        }
+        arena.snapshot();
+        assert_eq!(Arc::strong_count(&tracker), 11);
+        arena.reset();
+        assert_eq!(Arc::strong_count(&tracker), 1);
+        assert_eq!(arena.total_items(), 0);
+        assert_eq!(arena.total_chunks(), 0);
+        arena.alloc(Arc::clone(&tracker)).unwrap();
+        drop(arena);
+        assert_eq!(Arc::strong_count(&tracker), 1);
+    }
+
+    #[test]
+    fn test_concurrent_alloc() {
+        const THREADS: usize = 8;
+        const PER_THREAD: usize = 1_000;
+        let arena = ConcurrentArena::new(1, usize::MAX, usize::MAX);
+        let snapshots: Vec<usize> = thread::scope(|scope| {
+            let handles: Vec<_> = (0..THREADS)
+                .map(|thread| {
+                    let arena = &arena;
+                    scope.spawn(move || {
+                        let mut snapshots = Vec::new();
+                        for i in 0..PER_THREAD {
+                            let (index, value) = arena.advanced_alloc((thread, i)).unwrap();
+                            assert_eq!(*value, (thread, i));
+                            assert_eq!(arena.get(index), Some(&(thread, i)));
+                            if i % 250 == 0 {
+                                snapshots.push(arena.snapshot());
+                            }
+                        }
+                        snapshots
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        });
+
+        assert_eq!(arena.total_items(), THREADS * PER_THREAD);
+        let mut seen: Vec<(usize, usize)> = arena.iter().copied().collect();
+        seen.sort_unstable();
+        let expected: Vec<(usize, usize)> = (0..THREADS)
+            .flat_map(|thread| (0..PER_THREAD).map(move |i| (thread, i)))
+            .collect();
+        assert_eq!(seen, expected);
+
+        // Each thread's items appear in its own allocation order, and every
+        // snapshot is a prefix of the arena.
+        let all: Vec<&(usize, usize)> = arena.iter().collect();
+        for snapshot in snapshots {
+            let items = arena.get_snapshot(snapshot);
+            assert_eq!(items, all[..items.len()]);
+        }
+    }
+}
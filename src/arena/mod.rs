@@ -20,8 +20,12 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+use crate::error::Error;
+use crate::mem::HeapSize;
+use crate::mem::MemSize;
+use crate::mem::MemoryReport;
+use crate::utils::units::ByteSize;
 use alloc::format;
-use alloc::string::String;
 use alloc::vec;
 use alloc::vec::Vec;
 use core::cell::RefCell;
@@ -29,6 +33,166 @@ use core::fmt;
 use core::marker::PhantomData;
 use core::mem::size_of;
 
+mod bytes;
+mod codec;
+mod concurrent;
+
+pub use bytes::ByteArena;
+pub use codec::ArenaCodecError;
+pub use codec::ChunkLayout;
+pub use codec::ItemCodec;
+pub use codec::ARENA_FORMAT;
+pub use codec::ARENA_VERSION;
+pub use concurrent::ConcurrentArena;
+
+/// A typed reference to an item allocated in an [`Arena`].
+///
+/// Handles remember the generation of the arena they were allocated in.
+/// [`Arena::reset`] starts a new generation, after which old handles are
+/// stale and [`Arena::get`] / [`Arena::get_mut`] return `None` for them
+/// instead of an unrelated item. With a free list, each slot also counts
+/// how often it was reused, so handles to deallocated items are stale too.
+pub struct ArenaHandle<T> {
+    generation: u32,
+    slot_generation: u32,
+    chunk_index: usize,
+    element_index: usize,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<T> ArenaHandle<T> {
+    pub fn chunk_index(&self) -> usize {
+        self.chunk_index
+    }
+
+    pub fn element_index(&self) -> usize {
+        self.element_index
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+// Manual impls, so that handles are `Copy` and comparable whatever `T` is.
+impl<T> Clone for ArenaHandle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ArenaHandle<T> {}
+
+impl<T> PartialEq for ArenaHandle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.generation == other.generation
+            && self.slot_generation == other.slot_generation
+            && self.chunk_index == other.chunk_index
+            && self.element_index == other.element_index
+    }
+}
+
+impl<T> Eq for ArenaHandle<T> {}
+
+impl<T> core::hash::Hash for ArenaHandle<T> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.generation.hash(state);
+        self.slot_generation.hash(state);
+        self.chunk_index.hash(state);
+        self.element_index.hash(state);
+    }
+}
+
+impl<T> fmt::Debug for ArenaHandle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArenaHandle")
+            .field("generation", &self.generation)
+            .field("slot_generation", &self.slot_generation)
+            .field("chunk_index", &self.chunk_index)
+            .field("element_index", &self.element_index)
+            .finish()
+    }
+}
+
+/// Slot bookkeeping of an arena with a free list.
+#[derive(Clone, Debug, Default)]
+struct FreeList {
+    /// Generation of every slot, mirroring the chunks. Even generations are
+    /// occupied slots, odd ones were deallocated.
+    slots: Vec<Vec<u32>>,
+    /// Deallocated slots as (chunk_index, element_index), reused last in
+    /// first out.
+    vacant: Vec<(usize, usize)>,
+}
+
+impl FreeList {
+    fn is_vacant(&self, chunk_index: usize, element_index: usize) -> bool {
+        self.slots[chunk_index][element_index] % 2 == 1
+    }
+
+    /// Starts tracking `count` new occupied slots at the end of a chunk.
+    fn push_slots(&mut self, chunk_index: usize, count: usize) {
+        if self.slots.len() <= chunk_index {
+            self.slots.resize_with(chunk_index + 1, Vec::new);
+        }
+        let chunk = &mut self.slots[chunk_index];
+        chunk.resize(chunk.len() + count, 0);
+    }
+}
+
+/// How the capacity of a new chunk is chosen once the last chunk is full.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum GrowthPolicy {
+    /// Each chunk doubles the capacity of the previous one.
+    #[default]
+    Doubling,
+    /// Every new chunk holds the given number of items.
+    Fixed(usize),
+    /// Chunks double in capacity up to the given number of items.
+    Capped(usize),
+}
+
+impl GrowthPolicy {
+    /// Capacity of the chunk following one with capacity `last`, at least 1.
+    pub fn next_capacity(&self, last: usize) -> usize {
+        let capacity = match *self {
+            GrowthPolicy::Doubling => last.saturating_mul(2),
+            GrowthPolicy::Fixed(capacity) => capacity,
+            GrowthPolicy::Capped(max) => last.saturating_mul(2).min(max),
+        };
+        capacity.max(1)
+    }
+}
+
+/// Charges an item its inline size only, for types without [`MemSize`].
+fn shallow_mem_size<T>(_: &T) -> usize {
+    size_of::<T>()
+}
+
+/// A chunked arena addressed by [`ArenaHandle`]s.
+///
+/// # Aliasing
+///
+/// Items are read through shared references: [`get`](Self::get), the
+/// iterators and snapshots. Changing an item through its handle, with
+/// [`borrow_mut`](Self::borrow_mut), [`update`](Self::update) or
+/// [`get_mut`](Self::get_mut), needs exclusive access to the arena, so the
+/// borrow checker rules out a change while any item is being read:
+///
+/// ```compile_fail
+/// use pizza_common::arena::Arena;
+///
+/// let mut arena = Arena::new(4, 100, 1024);
+/// let (handle, _) = arena.advanced_alloc(1).unwrap();
+/// let first = arena.iter().next().unwrap();
+/// *arena.borrow_mut(handle).unwrap() = 2;
+/// assert_eq!(*first, 1);
+/// ```
+///
+/// The `&mut T` returned by [`alloc`](Self::alloc) is not tracked by these
+/// checks. Use it only to initialize the item, before any other access to
+/// the arena, and keep the handle from
+/// [`advanced_alloc`](Self::advanced_alloc) for later changes.
 pub struct Arena<T> {
     max_items: usize,
     max_memory_bytes: usize,
@@ -36,6 +200,15 @@ pub struct Arena<T> {
     snapshot_offsets: RefCell<Vec<(usize, usize)>>, // Stores (last_chunk_index, last_chunk_len)
     total_items: RefCell<usize>,
     total_memory_used: RefCell<usize>,
+    generation: RefCell<u32>,  // Bumped on reset to invalidate handles
+    mem_size: fn(&T) -> usize, // Bytes charged per item against max_memory_bytes
+    free_list: Option<RefCell<FreeList>>, // Only when slots can be deallocated
+    initial_item_capacity: usize, // Capacity of the first chunk after a reset
+    growth: GrowthPolicy,
+    spare_chunks: RefCell<Vec<Vec<T>>>, // Emptied chunks kept for reuse
+    // Slot generations bumped by `rollback_to`, mirroring the chunks as far
+    // as slots were rolled back
+    rollback_generations: RefCell<Vec<Vec<u32>>>,
 }
 
 impl<T> fmt::Debug for Arena<T>
@@ -56,6 +229,9 @@ where
             .field("snapshot_offsets", &snapshot_offsets) // Debug output for snapshot offsets
             .field("total_items", &*total_items) // Dereference to get the value
             .field("total_memory_used", &*total_memory_used) // Dereference to get the value
+            .field("generation", &*self.generation.borrow())
+            .field("free_list", &self.free_list)
+            .field("growth", &self.growth)
             .finish()
     }
 }
@@ -64,31 +240,108 @@ impl<T> Arena<T>
 where
     T: fmt::Debug + Clone,
 {
-    pub fn new(initial_item_capacity: usize, max_items: usize, max_memory_bytes: usize) -> Self {
+    /// `max_memory_bytes` is a byte count or a [`ByteSize`] such as
+    /// `ByteSize::mb(64)`.
+    pub fn new(
+        initial_item_capacity: usize,
+        max_items: usize,
+        max_memory_bytes: impl Into<ByteSize>,
+    ) -> Self {
         Self {
             chunks: RefCell::new(vec![Vec::with_capacity(initial_item_capacity)]),
             snapshot_offsets: RefCell::new(Vec::new()),
             max_items,
-            max_memory_bytes,
+            max_memory_bytes: max_memory_bytes.into().as_usize(),
             total_items: RefCell::new(0),
             total_memory_used: RefCell::new(0),
+            generation: RefCell::new(0),
+            mem_size: shallow_mem_size,
+            free_list: None,
+            initial_item_capacity,
+            growth: GrowthPolicy::default(),
+            spare_chunks: RefCell::new(Vec::new()),
+            rollback_generations: RefCell::new(Vec::new()),
+        }
+    }
+
+    /// Sets how the capacity of new chunks grows, doubling by default.
+    pub fn with_growth_policy(mut self, growth: GrowthPolicy) -> Self {
+        self.growth = growth;
+        self
+    }
+
+    /// Returns a new empty chunk with room for at least `min_capacity`
+    /// items, reusing a spare chunk kept by
+    /// [`reset_keep_capacity`](Self::reset_keep_capacity) if one is large
+    /// enough.
+    fn next_chunk(&self, last_capacity: usize, min_capacity: usize) -> Vec<T> {
+        let mut spare_chunks = self.spare_chunks.borrow_mut();
+        if let Some(index) = spare_chunks
+            .iter()
+            .position(|chunk| chunk.capacity() >= min_capacity)
+        {
+            return spare_chunks.swap_remove(index);
         }
+        // Grow from at least the initial capacity, as the last chunk may
+        // have been shrunk below it
+        let last_capacity = last_capacity.max(self.initial_item_capacity);
+        Vec::with_capacity(self.growth.next_capacity(last_capacity).max(min_capacity))
+    }
+
+    /// Enables [`dealloc`](Self::dealloc), after which allocations reuse
+    /// deallocated slots before growing the arena.
+    ///
+    /// Snapshots cannot be combined with slot reuse, as a reused slot would
+    /// change the contents of earlier snapshots. Neither serde nor the
+    /// binary codec record deallocated slots, so an arena with vacant slots
+    /// fails to serialize and panics in [`to_bytes`](Self::to_bytes).
+    ///
+    /// # Panics
+    ///
+    /// Panics if the arena already has snapshots.
+    pub fn with_free_list(mut self) -> Self {
+        assert!(
+            self.snapshot_offsets.get_mut().is_empty(),
+            "an arena with snapshots cannot use a free list"
+        );
+        let mut free_list = FreeList::default();
+        for (chunk_index, chunk) in self.chunks.get_mut().iter().enumerate() {
+            free_list.push_slots(chunk_index, chunk.len());
+        }
+        self.free_list = Some(RefCell::new(free_list));
+        self
+    }
+
+    /// Charges items their [`MemSize`] instead of `size_of::<T>()`, so the
+    /// memory limit also covers what the items own on the heap.
+    ///
+    /// Sizes are measured when an item is allocated. The memory usage of
+    /// items already in the arena, e.g. after deserialization, is recomputed.
+    pub fn with_deep_accounting(mut self) -> Self
+    where
+        T: MemSize,
+    {
+        self.mem_size = T::mem_size;
+        let total_memory_used = self.iter().map(T::mem_size).sum();
+        *self.total_memory_used.get_mut() = total_memory_used;
+        self
     }
 
     pub fn must_alloc(&self, value: T) -> &mut T {
         self.alloc(value).unwrap()
     }
 
-    pub fn alloc(&self, value: T) -> Result<&mut T, String> {
+    pub fn alloc(&self, value: T) -> Result<&mut T, Error> {
         // Call the `alloc` method to do the allocation and return only the reference
-        let (_, _, v) = self.advanced_alloc(value)?;
+        let (_, v) = self.advanced_alloc(value)?;
         Ok(v)
     }
 
-    pub fn advanced_alloc(&self, value: T) -> Result<(usize, usize, &mut T), String> {
+    #[allow(clippy::mut_from_ref)]
+    pub fn advanced_alloc(&self, value: T) -> Result<(ArenaHandle<T>, &mut T), Error> {
         let mut chunks = self.chunks.borrow_mut();
         let last_index = chunks.len() - 1;
-        let element_size = size_of::<T>();
+        let element_size = (self.mem_size)(&value);
 
         let mut total_items = self.total_items.borrow_mut();
         let mut total_memory_used = self.total_memory_used.borrow_mut();
@@ -96,43 +349,141 @@ where
         if *total_items < self.max_items
             && *total_memory_used + element_size <= self.max_memory_bytes
         {
-            let (chunk_index, element_index) =
-                if chunks[last_index].len() < chunks[last_index].capacity() {
-                    // Add to the last chunk
-                    chunks[last_index].push(value);
-                    (last_index, chunks[last_index].len() - 1)
-                } else {
-                    // Create a new chunk with double the capacity of the last chunk
-                    let new_capacity = chunks[last_index].capacity() * 2;
-                    let mut new_chunk = Vec::with_capacity(new_capacity);
-                    new_chunk.push(value);
-                    chunks.push(new_chunk);
-                    let new_chunk_index = chunks.len() - 1;
-                    (new_chunk_index, 0)
-                };
+            let mut free_list = self.free_list.as_ref().map(RefCell::borrow_mut);
+            let reused = free_list
+                .as_mut()
+                .and_then(|free_list| free_list.vacant.pop());
+            let (chunk_index, element_index) = if let Some((chunk_index, element_index)) = reused {
+                // Overwrite a deallocated slot, dropping its old value
+                chunks[chunk_index][element_index] = value;
+                (chunk_index, element_index)
+            } else if chunks[last_index].len() < chunks[last_index].capacity() {
+                // Add to the last chunk
+                chunks[last_index].push(value);
+                (last_index, chunks[last_index].len() - 1)
+            } else {
+                // Start a new chunk as the growth policy dictates
+                let mut new_chunk = self.next_chunk(chunks[last_index].capacity(), 1);
+                new_chunk.push(value);
+                chunks.push(new_chunk);
+                let new_chunk_index = chunks.len() - 1;
+                (new_chunk_index, 0)
+            };
 
             *total_items += 1;
             *total_memory_used += element_size;
 
-            // Return a mutable reference to the newly pushed element along with the indices
+            let slot_generation = match free_list.as_mut() {
+                Some(free_list) if reused.is_some() => {
+                    let slot = &mut free_list.slots[chunk_index][element_index];
+                    *slot = slot.wrapping_add(1);
+                    *slot
+                }
+                Some(free_list) => {
+                    free_list.push_slots(chunk_index, 1);
+                    0
+                }
+                None => self.rollback_generation(chunk_index, element_index),
+            };
+
+            // Return a mutable reference to the newly pushed element along with its handle
+            let handle = ArenaHandle {
+                generation: *self.generation.borrow(),
+                slot_generation,
+                chunk_index,
+                element_index,
+                _marker: PhantomData,
+            };
             let chunk = &mut chunks[chunk_index];
-            unsafe {
-                Ok((
-                    chunk_index,
-                    element_index,
-                    &mut *chunk.as_mut_ptr().add(element_index),
-                ))
-            }
+            unsafe { Ok((handle, &mut *chunk.as_mut_ptr().add(element_index))) }
         } else {
-            Err(format!(
+            Err(Error::CapacityExceeded(format!(
                 "Arena capacity exceeded, {}/{}, {}/{}",
                 *total_items, self.max_items, *total_memory_used, self.max_memory_bytes
+            )))
+        }
+    }
+
+    /// Allocates a copy of `values` as one contiguous slice.
+    pub fn alloc_slice(&self, values: &[T]) -> Result<&mut [T], Error> {
+        let bytes = values.iter().map(self.mem_size).sum();
+        self.alloc_contiguous(values.len(), bytes, |chunk| chunk.extend_from_slice(values))
+    }
+
+    /// Allocates all items of `values` as one contiguous slice.
+    ///
+    /// Nothing is allocated if the items do not fit within the limits.
+    pub fn alloc_extend<I>(&self, values: I) -> Result<&mut [T], Error>
+    where
+        I: IntoIterator<Item = T>,
+    {
+        let mut values: Vec<T> = values.into_iter().collect();
+        let bytes = values.iter().map(self.mem_size).sum();
+        self.alloc_contiguous(values.len(), bytes, |chunk| chunk.append(&mut values))
+    }
+
+    /// Checks the limits for `len` more items and lets `fill` push them into
+    /// a chunk with room for all of them, without moving existing items.
+    #[allow(clippy::mut_from_ref)]
+    fn alloc_contiguous<F>(&self, len: usize, bytes: usize, fill: F) -> Result<&mut [T], Error>
+    where
+        F: FnOnce(&mut Vec<T>),
+    {
+        let mut chunks = self.chunks.borrow_mut();
+        let mut total_items = self.total_items.borrow_mut();
+        let mut total_memory_used = self.total_memory_used.borrow_mut();
+
+        if len > self.max_items.saturating_sub(*total_items)
+            || bytes > self.max_memory_bytes.saturating_sub(*total_memory_used)
+        {
+            return Err(Error::CapacityExceeded(format!(
+                "Arena capacity exceeded, {}+{}/{}, {}+{}/{}",
+                *total_items, len, self.max_items, *total_memory_used, bytes, self.max_memory_bytes
+            )));
+        }
+        if len == 0 {
+            return Ok(&mut []);
+        }
+
+        let last_index = chunks.len() - 1;
+        let last_chunk = &mut chunks[last_index];
+        if last_chunk.is_empty() {
+            // Nothing points into an empty chunk yet, so it may grow in place
+            last_chunk.reserve(len);
+        } else if last_chunk.capacity() - last_chunk.len() < len {
+            let new_chunk = self.next_chunk(last_chunk.capacity(), len);
+            chunks.push(new_chunk);
+        }
+
+        let chunk_index = chunks.len() - 1;
+        let chunk = &mut chunks[chunk_index];
+        let start = chunk.len();
+        fill(chunk);
+        debug_assert_eq!(chunk.len(), start + len);
+        *total_items += len;
+        *total_memory_used += bytes;
+        if let Some(free_list) = &self.free_list {
+            free_list.borrow_mut().push_slots(chunk_index, len);
+        }
+
+        unsafe {
+            Ok(core::slice::from_raw_parts_mut(
+                chunk.as_mut_ptr().add(start),
+                len,
             ))
         }
     }
 
-    // Retrieve a reference to an element using its index
-    pub fn get(&self, chunk_index: usize, element_index: usize) -> Option<core::cell::Ref<T>> {
+    // Retrieve a reference to an element using its handle, `None` if the handle is stale
+    pub fn get(&self, handle: ArenaHandle<T>) -> Option<core::cell::Ref<'_, T>> {
+        if !self.is_live(handle) {
+            return None;
+        }
+        let ArenaHandle {
+            chunk_index,
+            element_index,
+            ..
+        } = handle;
         let chunks = self.chunks.borrow();
 
         // Ensure the chunk_index and element_index are within bounds
@@ -150,6 +501,103 @@ where
         }
     }
 
+    /// Mutably borrows the item of `handle`, `None` if the handle is stale.
+    ///
+    /// Changes made through the borrow are not charged against the memory
+    /// limit; use [`update`](Self::update) when they change the item's size.
+    pub fn borrow_mut(&mut self, handle: ArenaHandle<T>) -> Option<&mut T> {
+        self.get_mut(handle)
+    }
+
+    /// Calls `f` on the item of `handle` and returns its result, `None` if
+    /// the handle is stale.
+    ///
+    /// The memory usage of the arena is adjusted by any change in the item's
+    /// charged size, which may take it past `max_memory_bytes`.
+    pub fn update<R>(&mut self, handle: ArenaHandle<T>, f: impl FnOnce(&mut T) -> R) -> Option<R> {
+        let mem_size = self.mem_size;
+        let item = self.get_mut(handle)?;
+        let before = mem_size(item);
+        let result = f(item);
+        let after = mem_size(item);
+        let total_memory_used = self.total_memory_used.get_mut();
+        // Saturates, as the item may have grown uncharged through `borrow_mut`
+        *total_memory_used = (*total_memory_used + after).saturating_sub(before);
+        Some(result)
+    }
+
+    pub fn get_mut(&mut self, handle: ArenaHandle<T>) -> Option<&mut T> {
+        if !self.is_live(handle) {
+            return None;
+        }
+        self.chunks
+            .get_mut()
+            .get_mut(handle.chunk_index)?
+            .get_mut(handle.element_index)
+    }
+
+    /// Whether `handle` is from the current generation and, with a free
+    /// list, its slot still holds the item it was allocated for.
+    fn is_live(&self, handle: ArenaHandle<T>) -> bool {
+        if handle.generation != *self.generation.borrow() {
+            return false;
+        }
+        match &self.free_list {
+            Some(free_list) => free_list
+                .borrow()
+                .slots
+                .get(handle.chunk_index)
+                .and_then(|slots| slots.get(handle.element_index))
+                .is_some_and(|&slot| slot == handle.slot_generation),
+            None => {
+                handle.slot_generation
+                    == self.rollback_generation(handle.chunk_index, handle.element_index)
+            }
+        }
+    }
+
+    /// How often the slot was released by [`rollback_to`](Self::rollback_to).
+    fn rollback_generation(&self, chunk_index: usize, element_index: usize) -> u32 {
+        self.rollback_generations
+            .borrow()
+            .get(chunk_index)
+            .and_then(|slots| slots.get(element_index))
+            .copied()
+            .unwrap_or(0)
+    }
+
+    /// Deallocates the item of `handle`, so that its slot can be reused.
+    ///
+    /// The item is dropped when its slot is reused or the arena is reset.
+    /// Returns `false` if the handle is stale.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the arena was not created
+    /// [`with_free_list`](Self::with_free_list).
+    pub fn dealloc(&mut self, handle: ArenaHandle<T>) -> bool {
+        assert!(self.free_list.is_some(), "arena has no free list");
+        if !self.is_live(handle) {
+            return false;
+        }
+        let ArenaHandle {
+            chunk_index,
+            element_index,
+            ..
+        } = handle;
+        let free_list = self.free_list.as_mut().unwrap().get_mut();
+        let slot = &mut free_list.slots[chunk_index][element_index];
+        *slot = slot.wrapping_add(1);
+        free_list.vacant.push((chunk_index, element_index));
+
+        let item = &self.chunks.get_mut()[chunk_index][element_index];
+        *self.total_items.get_mut() -= 1;
+        // Saturates, as the item may have grown uncharged since allocation
+        let total_memory_used = self.total_memory_used.get_mut();
+        *total_memory_used = total_memory_used.saturating_sub((self.mem_size)(item));
+        true
+    }
+
     pub fn total_chunks(&self) -> usize {
         let chunks = self.chunks.borrow();
         chunks.len()
@@ -163,7 +611,16 @@ where
         *self.total_memory_used.borrow()
     }
 
+    /// Records the current items and returns the snapshot id.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the arena has a free list.
     pub fn snapshot(&self) -> usize {
+        assert!(
+            self.free_list.is_none(),
+            "an arena with a free list cannot take snapshots"
+        );
         let chunks = self.chunks.borrow();
         let last_chunk_index = chunks.len() - 1;
         let last_chunk_len = chunks[last_chunk_index].len();
@@ -195,23 +652,161 @@ where
         result
     }
 
+    /// Drops every item allocated after `snapshot`, restoring the arena to
+    /// the state it was in when the snapshot was taken.
+    ///
+    /// The snapshot itself stays valid, later ones are discarded. Handles to
+    /// dropped items become stale, even once their slots are reused.
+    pub fn rollback_to(&mut self, snapshot: usize) -> Result<(), Error> {
+        let snapshot_offsets = self.snapshot_offsets.get_mut();
+        let Some(&(last_chunk_index, last_chunk_len)) = snapshot_offsets.get(snapshot) else {
+            return Err(Error::NotFound(format!(
+                "Unknown snapshot {}, {} snapshots taken",
+                snapshot,
+                snapshot_offsets.len()
+            )));
+        };
+        snapshot_offsets.truncate(snapshot + 1);
+
+        let chunks = self.chunks.get_mut();
+        let (kept, dropped) = chunks.split_at(last_chunk_index + 1);
+        let dropped = kept[last_chunk_index][last_chunk_len..]
+            .iter()
+            .chain(dropped.iter().flatten());
+        let (removed, removed_bytes) = dropped.fold((0, 0), |(items, bytes), item| {
+            (items + 1, bytes + (self.mem_size)(item))
+        });
+
+        // Invalidate the handles of the dropped items
+        let rollback_generations = self.rollback_generations.get_mut();
+        for (chunk_index, chunk) in chunks.iter().enumerate().skip(last_chunk_index) {
+            let start = if chunk_index == last_chunk_index {
+                last_chunk_len
+            } else {
+                0
+            };
+            if start == chunk.len() {
+                continue;
+            }
+            if rollback_generations.len() <= chunk_index {
+                rollback_generations.resize_with(chunk_index + 1, Vec::new);
+            }
+            let slots = &mut rollback_generations[chunk_index];
+            if slots.len() < chunk.len() {
+                slots.resize(chunk.len(), 0);
+            }
+            for slot in &mut slots[start..chunk.len()] {
+                *slot = slot.wrapping_add(1);
+            }
+        }
+
+        chunks.truncate(last_chunk_index + 1);
+        chunks[last_chunk_index].truncate(last_chunk_len);
+
+        *self.total_items.get_mut() -= removed;
+        // Saturates, as dropped items may have grown uncharged
+        let total_memory_used = self.total_memory_used.get_mut();
+        *total_memory_used = total_memory_used.saturating_sub(removed_bytes);
+        Ok(())
+    }
+
+    /// Drops all items and snapshots and releases the allocated chunks,
+    /// restarting with a chunk of the initial item capacity.
     pub fn reset(&self) {
         let mut chunks = self.chunks.borrow_mut();
         chunks.clear();
-        chunks.push(Vec::with_capacity(1)); // Restart with initial capacity
+        chunks.push(Vec::with_capacity(self.initial_item_capacity));
+        self.spare_chunks.borrow_mut().clear();
+        // The new generation already invalidates every handle
+        self.rollback_generations.borrow_mut().clear();
+        self.snapshot_offsets.borrow_mut().clear();
         *self.total_items.borrow_mut() = 0;
         *self.total_memory_used.borrow_mut() = 0;
+        let mut generation = self.generation.borrow_mut();
+        *generation = generation.wrapping_add(1);
+        if let Some(free_list) = &self.free_list {
+            *free_list.borrow_mut() = FreeList::default();
+        }
+    }
+
+    /// Like [`reset`](Self::reset), but keeps the allocated chunks so the
+    /// arena can be refilled without allocating again.
+    pub fn reset_keep_capacity(&mut self) {
+        let chunks = self.chunks.get_mut();
+        let spare_chunks = self.spare_chunks.get_mut();
+        for mut chunk in chunks.drain(1..) {
+            chunk.clear();
+            spare_chunks.push(chunk);
+        }
+        chunks[0].clear();
+        self.rollback_generations.get_mut().clear();
+        self.snapshot_offsets.get_mut().clear();
+        *self.total_items.get_mut() = 0;
+        *self.total_memory_used.get_mut() = 0;
+        let generation = self.generation.get_mut();
+        *generation = generation.wrapping_add(1);
+        if let Some(free_list) = &mut self.free_list {
+            *free_list.get_mut() = FreeList::default();
+        }
+    }
+
+    /// Releases spare chunks and the unused capacity of every chunk.
+    ///
+    /// The next allocation starts a new chunk as the growth policy dictates,
+    /// growing from at least the initial chunk capacity.
+    pub fn shrink_to_fit(&mut self) {
+        let spare_chunks = self.spare_chunks.get_mut();
+        spare_chunks.clear();
+        spare_chunks.shrink_to_fit();
+        let chunks = self.chunks.get_mut();
+        for chunk in chunks.iter_mut() {
+            chunk.shrink_to_fit();
+        }
+        chunks.shrink_to_fit();
+        self.snapshot_offsets.get_mut().shrink_to_fit();
+    }
+}
+
+impl<T: HeapSize> HeapSize for Arena<T> {
+    fn heap_size(&self) -> usize {
+        let free_list = self.free_list.as_ref().map_or(0, |free_list| {
+            let free_list = free_list.borrow();
+            free_list.slots.heap_size() + free_list.vacant.heap_size()
+        });
+        self.chunks.borrow().heap_size()
+            + self.spare_chunks.borrow().heap_size()
+            + self.rollback_generations.borrow().heap_size()
+            + self.snapshot_offsets.borrow().heap_size()
+            + free_list
+    }
+
+    /// Reports the item storage separately from the snapshot bookkeeping.
+    fn report(&self, name: &str) -> MemoryReport {
+        MemoryReport::new(name, self.deep_size())
+            .child(self.chunks.borrow().report("chunks"))
+            .child(self.spare_chunks.borrow().report("spare_chunks"))
+            .child(self.snapshot_offsets.borrow().report("snapshots"))
     }
 }
 
 pub struct ArenaIterator<'a, T> {
     chunks: core::cell::Ref<'a, Vec<Vec<T>>>,
+    free_list: Option<core::cell::Ref<'a, FreeList>>, // To skip deallocated slots
     pub batch_size: usize,
     chunk_index: usize,
     item_index: usize,
     pub next_value: Option<T>, //for VectorIterator only
 }
 
+impl<'a, T> ArenaIterator<'a, T> {
+    /// Returns up to `batch_size` more items, or an empty batch once the
+    /// arena is exhausted.
+    pub fn next_batch(&mut self) -> Vec<&'a T> {
+        let batch_size = self.batch_size.max(1);
+        self.by_ref().take(batch_size).collect()
+    }
+}
+
 impl<'a, T> Iterator for ArenaIterator<'a, T> {
     type Item = &'a T;
 
@@ -226,6 +821,11 @@ impl<'a, T> Iterator for ArenaIterator<'a, T> {
             if self.item_index < chunk.len() {
                 let item = &chunk[self.item_index];
                 self.item_index += 1;
+                if let Some(free_list) = &self.free_list {
+                    if free_list.is_vacant(self.chunk_index, self.item_index - 1) {
+                        continue;
+                    }
+                }
                 return Some(unsafe { &*(item as *const T) });
             } else {
                 self.chunk_index += 1;
@@ -236,9 +836,16 @@ impl<'a, T> Iterator for ArenaIterator<'a, T> {
 }
 
 impl<T> Arena<T> {
+    fn has_vacant_slots(&self) -> bool {
+        self.free_list
+            .as_ref()
+            .is_some_and(|free_list| !free_list.borrow().vacant.is_empty())
+    }
+
     pub fn iter_with_batch_size(&self, batch_size: usize) -> ArenaIterator<'_, T> {
         ArenaIterator {
             chunks: self.chunks.borrow(),
+            free_list: self.free_list.as_ref().map(RefCell::borrow),
             chunk_index: 0,
             item_index: 0,
             batch_size,
@@ -249,6 +856,60 @@ impl<T> Arena<T> {
     pub fn iter(&self) -> ArenaIterator<'_, T> {
         self.iter_with_batch_size(512)
     }
+
+    /// Iterates over the items one chunk at a time, skipping empty chunks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the arena has deallocated slots, which a slice cannot skip.
+    pub fn chunks_iter(&self) -> ArenaChunks<'_, T> {
+        assert!(
+            !self.has_vacant_slots(),
+            "cannot slice an arena with deallocated slots"
+        );
+        ArenaChunks {
+            chunks: self.chunks.borrow(),
+            chunk_index: 0,
+        }
+    }
+
+    /// Iterates over the items on the rayon thread pool, one task per chunk.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the arena has deallocated slots, see
+    /// [`chunks_iter`](Self::chunks_iter).
+    #[cfg(feature = "rayon")]
+    pub fn par_iter(&self) -> impl rayon::iter::ParallelIterator<Item = &T>
+    where
+        T: Sync,
+    {
+        use rayon::prelude::*;
+
+        let chunks: Vec<&[T]> = self.chunks_iter().collect();
+        chunks.into_par_iter().flat_map(|chunk| chunk.par_iter())
+    }
+}
+
+/// Iterator over the chunks of an [`Arena`], see [`Arena::chunks_iter`].
+pub struct ArenaChunks<'a, T> {
+    chunks: core::cell::Ref<'a, Vec<Vec<T>>>,
+    chunk_index: usize,
+}
+
+impl<'a, T> Iterator for ArenaChunks<'a, T> {
+    type Item = &'a [T];
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(chunk) = self.chunks.get(self.chunk_index) {
+            self.chunk_index += 1;
+            if !chunk.is_empty() {
+                // Chunks never reallocate, so the slice outlives the borrow
+                return Some(unsafe { core::slice::from_raw_parts(chunk.as_ptr(), chunk.len()) });
+            }
+        }
+        None
+    }
 }
 
 use serde::de::MapAccess;
@@ -265,6 +926,11 @@ impl<T: Serialize> Serialize for Arena<T> {
     where
         S: Serializer,
     {
+        if self.has_vacant_slots() {
+            return Err(serde::ser::Error::custom(
+                "cannot serialize an arena with deallocated slots",
+            ));
+        }
         // We need to manually serialize each field
         let mut state = serializer.serialize_struct("Arena", 6)?;
         state.serialize_field("max_items", &self.max_items)?;
@@ -404,6 +1070,13 @@ impl<'de, T: Deserialize<'de>> Deserialize<'de> for Arena<T> {
                     snapshot_offsets: RefCell::new(snapshot_offsets),
                     total_items: RefCell::new(total_items),
                     total_memory_used: RefCell::new(total_memory_used),
+                    generation: RefCell::new(0),
+                    mem_size: shallow_mem_size,
+                    free_list: None,
+                    initial_item_capacity: 1,
+                    growth: GrowthPolicy::default(),
+                    spare_chunks: RefCell::new(Vec::new()),
+                    rollback_generations: RefCell::new(Vec::new()),
                 })
             }
         }
@@ -424,9 +1097,11 @@ impl<'de, T: Deserialize<'de>> Deserialize<'de> for Arena<T> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::string::String;
     use std::println;
 
     #[test]
+    #[allow(clippy::unnecessary_cast)]
     fn test_arena_allocation_and_snapshots() {
         let arena = Arena::new(4, 1000300, 1024 * 1024 * 1024);
 
@@ -520,6 +1195,224 @@ mod tests {
         assert_eq!(arena.total_memory_usage(), 0);
     }
 
+    #[test]
+    fn test_rollback_to() {
+        let mut arena = Arena::new(2, 100, 1024);
+        arena.alloc(1u64).unwrap();
+        let first = arena.snapshot();
+        for i in 2..=4 {
+            arena.alloc(i).unwrap();
+        }
+        let second = arena.snapshot();
+        for i in 5..=10 {
+            arena.alloc(i).unwrap();
+        }
+        let third = arena.snapshot();
+        assert_eq!(arena.total_chunks(), 3);
+
+        arena.rollback_to(second).unwrap();
+        assert_eq!(arena.iter().copied().collect::<Vec<_>>(), [1, 2, 3, 4]);
+        assert_eq!(arena.total_chunks(), 2);
+        assert_eq!(arena.total_items(), 4);
+        assert_eq!(arena.total_memory_usage(), 32);
+        assert!(arena.rollback_to(third).is_err());
+
+        // Allocation resumes right after the snapshot.
+        arena.alloc(42).unwrap();
+        assert_eq!(arena.get_snapshot(second), [&1, &2, &3, &4]);
+        arena.rollback_to(second).unwrap();
+        arena.rollback_to(first).unwrap();
+        assert_eq!(arena.iter().copied().collect::<Vec<_>>(), [1]);
+        assert_eq!(arena.total_memory_usage(), 8);
+
+        arena.reset();
+        assert!(arena.rollback_to(first).is_err());
+    }
+
+    #[test]
+    fn test_rollback_invalidates_handles() {
+        let mut arena = Arena::new(2, 100, 1024);
+        let (kept, _) = arena.advanced_alloc(1).unwrap();
+        let snapshot = arena.snapshot();
+        let (dropped, _) = arena.advanced_alloc(2).unwrap();
+        let (in_new_chunk, _) = arena.advanced_alloc(3).unwrap();
+        assert_eq!(in_new_chunk.chunk_index(), 1);
+
+        arena.rollback_to(snapshot).unwrap();
+        assert!(arena.get(dropped).is_none());
+        assert!(arena.get(in_new_chunk).is_none());
+
+        // The new items take the slots of the dropped ones.
+        let (reused, _) = arena.advanced_alloc(4).unwrap();
+        let (reused_in_new_chunk, _) = arena.advanced_alloc(5).unwrap();
+        assert_eq!(
+            (reused.chunk_index(), reused.element_index()),
+            (dropped.chunk_index(), dropped.element_index())
+        );
+        assert!(arena.get(dropped).is_none());
+        assert!(arena.get_mut(in_new_chunk).is_none());
+        assert_eq!(arena.get(kept).as_deref(), Some(&1));
+        assert_eq!(arena.get(reused).as_deref(), Some(&4));
+        assert_eq!(arena.get(reused_in_new_chunk).as_deref(), Some(&5));
+
+        arena.rollback_to(snapshot).unwrap();
+        arena.alloc(6).unwrap();
+        assert!(arena.get(reused).is_none());
+        assert!(arena.get(dropped).is_none());
+    }
+
+    #[test]
+    fn test_alloc_slice_and_extend() {
+        let arena = Arena::new(4, 100, 1024);
+        let first = arena.alloc(0u32).unwrap();
+        let head = arena.alloc_slice(&[1, 2]).unwrap();
+        assert_eq!(arena.total_chunks(), 1);
+
+        // Does not fit in the first chunk, so it starts a new one.
+        let tail = arena.alloc_extend(3..13).unwrap();
+        assert_eq!(tail.len(), 10);
+        assert_eq!(arena.total_chunks(), 2);
+        tail[0] = 30;
+        head[1] = 20;
+        *first = 100;
+
+        assert_eq!(arena.total_items(), 13);
+        assert_eq!(arena.total_memory_usage(), 13 * 4);
+        assert_eq!(
+            arena.iter().copied().collect::<Vec<_>>(),
+            [100, 1, 20, 30, 4, 5, 6, 7, 8, 9, 10, 11, 12]
+        );
+        assert!(arena.alloc_slice(&[]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_alloc_slice_limits() {
+        let arena = Arena::new(4, 5, 1024);
+        arena.alloc_slice(&[1u8, 2, 3]).unwrap();
+        assert!(arena.alloc_extend([4, 5, 6]).is_err());
+        assert_eq!(arena.total_items(), 3);
+        arena.alloc_extend([4, 5]).unwrap();
+
+        let arena = Arena::new(4, 100, 10);
+        assert!(arena.alloc_slice(&[1u32, 2, 3]).is_err());
+        assert_eq!(arena.total_memory_usage(), 0);
+        arena.alloc_slice(&[1u32, 2]).unwrap();
+
+        let arena = Arena::new(4, usize::MAX, ByteSize::b(10));
+        assert!(arena.alloc_slice(&[1u32, 2, 3]).is_err());
+    }
+
+    #[test]
+    fn test_deep_accounting() {
+        let item = size_of::<String>();
+        let arena = Arena::new(4, 100, 2 * item + 20).with_deep_accounting();
+        arena.alloc(String::with_capacity(10)).unwrap();
+        assert_eq!(arena.total_memory_usage(), item + 10);
+        assert!(arena.alloc(String::with_capacity(11)).is_err());
+        assert!(arena.alloc_slice(&[String::new(), String::new()]).is_err());
+        arena.alloc_extend([String::with_capacity(10)]).unwrap();
+        assert_eq!(arena.total_memory_usage(), 2 * item + 20);
+
+        // The shallow fallback ignores the heap.
+        let shallow = Arena::new(4, 100, 2 * item);
+        shallow.alloc(String::with_capacity(1000)).unwrap();
+        shallow.alloc(String::with_capacity(1000)).unwrap();
+        assert_eq!(shallow.total_memory_usage(), 2 * item);
+
+        // Switching modes recomputes the usage of existing items.
+        let deep = shallow.with_deep_accounting();
+        assert_eq!(deep.total_memory_usage(), 2 * item + 2000);
+    }
+
+    #[test]
+    fn test_deep_accounting_rollback() {
+        let mut arena = Arena::new(1, 100, usize::MAX).with_deep_accounting();
+        arena.alloc(String::from("a")).unwrap();
+        let snapshot = arena.snapshot();
+        arena.alloc(String::from("bb")).unwrap();
+        arena.alloc_slice(&[String::from("ccc")]).unwrap();
+        arena.rollback_to(snapshot).unwrap();
+        assert_eq!(arena.total_memory_usage(), size_of::<String>() + 1);
+    }
+
+    #[test]
+    fn test_free_list_reuses_slots() {
+        let mut arena = Arena::new(2, 3, 1024).with_free_list();
+        let (a, _) = arena.advanced_alloc(String::from("a")).unwrap();
+        let (b, _) = arena.advanced_alloc(String::from("b")).unwrap();
+        arena.alloc_slice(&[String::from("c")]).unwrap();
+        assert!(arena.alloc(String::from("d")).is_err());
+
+        assert!(arena.dealloc(a));
+        assert!(!arena.dealloc(a));
+        assert!(arena.get(a).is_none());
+        assert_eq!(arena.total_items(), 2);
+        assert_eq!(
+            arena.iter().map(String::as_str).collect::<Vec<_>>(),
+            ["b", "c"]
+        );
+
+        // The freed slot is reused, old handles to it stay stale.
+        let (d, _) = arena.advanced_alloc(String::from("d")).unwrap();
+        assert_eq!(
+            (d.chunk_index(), d.element_index()),
+            (a.chunk_index(), a.element_index())
+        );
+        assert_ne!(d, a);
+        assert!(arena.get(a).is_none());
+        assert!(arena.get_mut(a).is_none());
+        assert_eq!(arena.get(d).unwrap().as_str(), "d");
+        assert_eq!(arena.get(b).unwrap().as_str(), "b");
+        assert_eq!(arena.total_chunks(), 2);
+        assert_eq!(arena.total_memory_usage(), 3 * size_of::<String>());
+
+        arena.dealloc(b);
+        assert!(serde_json::to_string(&arena).is_err());
+        arena.reset();
+        assert!(serde_json::to_string(&arena).is_ok());
+        assert!(arena.get(d).is_none());
+        let (e, _) = arena.advanced_alloc(String::from("e")).unwrap();
+        assert_eq!(arena.get(e).unwrap().as_str(), "e");
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot take snapshots")]
+    fn test_free_list_disables_snapshots() {
+        let arena = Arena::new(2, 3, 1024).with_free_list();
+        arena.alloc(1).unwrap();
+        arena.snapshot();
+    }
+
+    #[test]
+    fn test_chunks_iter_and_batches() {
+        let arena = Arena::new(0, 100, 1024);
+        for i in 0..10u32 {
+            arena.alloc(i).unwrap();
+        }
+        let chunks: Vec<&[u32]> = arena.chunks_iter().collect();
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks.concat(), (0..10).collect::<Vec<_>>());
+
+        let mut iter = arena.iter_with_batch_size(4);
+        assert_eq!(iter.next_batch(), [&0, &1, &2, &3]);
+        assert_eq!(iter.next_batch(), [&4, &5, &6, &7]);
+        assert_eq!(iter.next_batch(), [&8, &9]);
+        assert!(iter.next_batch().is_empty());
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn test_par_iter() {
+        use rayon::prelude::*;
+
+        let arena = Arena::new(16, usize::MAX, usize::MAX);
+        for i in 0..100_000u64 {
+            arena.alloc(i).unwrap();
+        }
+        assert_eq!(arena.par_iter().sum::<u64>(), (0..100_000).sum::<u64>());
+        assert_eq!(arena.par_iter().count(), 100_000);
+    }
+
     #[test]
     fn test_arena_iterator() {
         let arena = Arena::new(4, 1000, 1024 * 1024 * 1024);
@@ -578,6 +1471,13 @@ mod tests {
             snapshot_offsets: RefCell::new(Vec::new()),
             total_items: RefCell::new(0),
             total_memory_used: RefCell::new(0),
+            generation: RefCell::new(0),
+            mem_size: shallow_mem_size,
+            free_list: None,
+            initial_item_capacity: 4,
+            growth: GrowthPolicy::default(),
+            spare_chunks: RefCell::new(Vec::new()),
+            rollback_generations: RefCell::new(Vec::new()),
         };
 
         let a: String = "Hello, World!".into();
@@ -588,26 +1488,208 @@ mod tests {
 
         let b = "Hello, again!".into();
         // Test advanced_alloc function to get an index
-        let (chunk_index, id, elem_ref1) =
-            arena.advanced_alloc(b).expect("Advanced allocation failed");
+        let (handle, elem_ref1) = arena.advanced_alloc(b).expect("Advanced allocation failed");
 
-        println!("{:?},{:?}", chunk_index, id);
+        println!("{:?}", handle);
 
-        // Retrieve the element using the index and verify it
-        let element = arena.get(chunk_index, id).expect("Element not found");
+        // Retrieve the element using the handle and verify it
+        let element = arena.get(handle).expect("Element not found");
         assert_eq!(element.as_str(), "Hello, again!");
         println!("{:?}", element);
 
         //update a
         elem_ref.push_str("!!!");
-        let element = arena.get(0, 0).expect("Element not found");
+        let first = ArenaHandle {
+            generation: 0,
+            slot_generation: 0,
+            chunk_index: 0,
+            element_index: 0,
+            _marker: PhantomData,
+        };
+        let element = arena.get(first).expect("Element not found");
         println!("{:?}", element);
         assert_eq!(element.as_str(), "Hello, World!!!!");
 
         //update b
         elem_ref1.push_str("???");
-        let element = arena.get(chunk_index, id).expect("Element not found");
+        let element = arena.get(handle).expect("Element not found");
         assert_eq!(element.as_str(), "Hello, again!???");
         println!("{:?}", element);
     }
+
+    #[test]
+    fn test_stale_handles() {
+        let mut arena = Arena::new(1, 100, 1024);
+        let (first, _) = arena.advanced_alloc(1).unwrap();
+        let (second, _) = arena.advanced_alloc(2).unwrap();
+        assert_ne!(first, second);
+        assert_eq!((second.chunk_index(), second.element_index()), (1, 0));
+
+        *arena.get_mut(second).unwrap() += 10;
+        assert_eq!(*arena.get(second).unwrap(), 12);
+
+        arena.reset();
+        let (reused, _) = arena.advanced_alloc(3).unwrap();
+        assert_eq!(reused.generation(), first.generation() + 1);
+        assert_eq!(arena.get(reused).as_deref(), Some(&3));
+        // The slot of `first` now holds a different item.
+        assert!(arena.get(first).is_none());
+        assert!(arena.get_mut(first).is_none());
+        assert!(arena.get(second).is_none());
+    }
+
+    fn capacities<T>(arena: &Arena<T>) -> Vec<usize> {
+        arena.chunks.borrow().iter().map(Vec::capacity).collect()
+    }
+
+    #[test]
+    fn test_growth_policy() {
+        assert_eq!(GrowthPolicy::Doubling.next_capacity(0), 1);
+        assert_eq!(GrowthPolicy::Capped(3).next_capacity(2), 3);
+
+        let arena = Arena::new(2, 100, 1024).with_growth_policy(GrowthPolicy::Fixed(3));
+        for i in 0..8 {
+            arena.alloc(i).unwrap();
+        }
+        assert_eq!(capacities(&arena), [2, 3, 3]);
+
+        let arena = Arena::new(1, 100, 1024).with_growth_policy(GrowthPolicy::Capped(4));
+        for i in 0..11 {
+            arena.alloc(i).unwrap();
+        }
+        assert_eq!(capacities(&arena), [1, 2, 4, 4]);
+        // A contiguous run larger than the cap still gets a chunk of its own.
+        assert_eq!(arena.alloc_slice(&[0; 6]).unwrap().len(), 6);
+        assert_eq!(capacities(&arena).last(), Some(&6));
+    }
+
+    #[test]
+    fn test_reset_restores_initial_capacity() {
+        let arena = Arena::new(8, 100, 1024);
+        for i in 0..20 {
+            arena.alloc(i).unwrap();
+        }
+        arena.reset();
+        assert_eq!(capacities(&arena), [8]);
+        assert_eq!(arena.iter().count(), 0);
+    }
+
+    #[test]
+    fn test_reset_keep_capacity() {
+        let mut arena = Arena::new(2, 100, 1024);
+        let (handle, _) = arena.advanced_alloc(0).unwrap();
+        for i in 1..10 {
+            arena.alloc(i).unwrap();
+        }
+        assert_eq!(capacities(&arena), [2, 4, 8]);
+
+        arena.reset_keep_capacity();
+        assert!(arena.get(handle).is_none());
+        assert_eq!(arena.iter().count(), 0);
+        assert_eq!(*arena.total_items.borrow(), 0);
+        assert_eq!(*arena.total_memory_used.borrow(), 0);
+        assert_eq!(capacities(&arena), [2]);
+
+        // Refilling reuses the spare chunks instead of allocating.
+        for i in 0..10 {
+            arena.alloc(i).unwrap();
+        }
+        let mut reused = capacities(&arena);
+        reused.sort_unstable();
+        assert_eq!(reused, [2, 4, 8]);
+        assert!(arena.spare_chunks.borrow().is_empty());
+        assert_eq!(
+            arena.iter().copied().collect::<Vec<_>>(),
+            (0..10).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_shrink_to_fit() {
+        let mut arena = Arena::new(2, 100, 1024);
+        for i in 0..5 {
+            arena.alloc(i).unwrap();
+        }
+        arena.reset_keep_capacity();
+        arena.alloc(1).unwrap();
+        arena.shrink_to_fit();
+        assert!(arena.spare_chunks.borrow().is_empty());
+        assert_eq!(capacities(&arena), [1]);
+
+        arena.alloc(2).unwrap();
+        assert_eq!(arena.iter().copied().collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(capacities(&arena), [1, 4]);
+
+        let mut arena = Arena::new(8, 100, 1024);
+        arena.shrink_to_fit();
+        assert_eq!(capacities(&arena), [0]);
+        arena.alloc(1).unwrap();
+        assert_eq!(capacities(&arena), [0, 16]);
+    }
+
+    #[test]
+    fn test_borrow_mut_and_update() {
+        let mut arena: Arena<String> = Arena::new(4, 100, 1024).with_deep_accounting();
+        let (handle, _) = arena.advanced_alloc(String::from("a")).unwrap();
+        let used = arena.total_memory_usage();
+
+        let len = arena.update(handle, |item| {
+            item.reserve_exact(100);
+            item.len()
+        });
+        assert_eq!(len, Some(1));
+        let capacity = arena.get(handle).unwrap().capacity();
+        assert_eq!(arena.total_memory_usage(), used - 1 + capacity);
+
+        // Changes through `borrow_mut` are not charged.
+        arena.borrow_mut(handle).unwrap().push('b');
+        assert_eq!(arena.get(handle).as_deref().map(String::as_str), Some("ab"));
+        assert_eq!(arena.total_memory_usage(), used - 1 + capacity);
+
+        arena.reset();
+        assert!(arena.borrow_mut(handle).is_none());
+        assert!(arena.update(handle, |item| item.clear()).is_none());
+    }
+
+    #[test]
+    fn test_grown_items_release_saturates() {
+        let mut arena: Arena<String> = Arena::new(4, 100, 1024)
+            .with_deep_accounting()
+            .with_free_list();
+        let (handle, _) = arena.advanced_alloc(String::new()).unwrap();
+        arena.get_mut(handle).unwrap().reserve(512);
+        assert!(arena.dealloc(handle));
+        assert_eq!(arena.total_memory_usage(), 0);
+        assert!(arena.alloc(String::new()).is_ok());
+
+        let mut arena: Arena<String> = Arena::new(4, 100, 1024).with_deep_accounting();
+        let snapshot = arena.snapshot();
+        let (handle, _) = arena.advanced_alloc(String::new()).unwrap();
+        arena.borrow_mut(handle).unwrap().reserve(512);
+        arena.rollback_to(snapshot).unwrap();
+        assert_eq!(arena.total_memory_usage(), 0);
+        assert!(arena.alloc(String::new()).is_ok());
+    }
+
+    #[test]
+    fn test_heap_size() {
+        let arena: Arena<String> = Arena::new(2, 100, 1024 * 1024);
+        arena.alloc(String::with_capacity(10)).unwrap();
+        arena.alloc(String::with_capacity(20)).unwrap();
+        arena.alloc(String::with_capacity(30)).unwrap();
+        arena.snapshot();
+
+        // Two chunks with capacity 2 and 4.
+        let strings = 6 * size_of::<String>() + 60;
+        let chunks = size_of::<Vec<String>>() * arena.chunks.borrow().capacity();
+        let snapshots = arena.snapshot_offsets.borrow().capacity() * size_of::<(usize, usize)>();
+        assert_eq!(arena.heap_size(), chunks + strings + snapshots);
+
+        let report = arena.report("arena");
+        assert_eq!(report.bytes(), arena.deep_size());
+        assert_eq!(
+            report.find("chunks").unwrap().bytes(),
+            size_of::<Vec<Vec<String>>>() + chunks + strings
+        );
+    }
 }
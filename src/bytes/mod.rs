@@ -0,0 +1,353 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Byte buffer helpers built on the [`bytes`](::bytes) crate.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use core::ops::Bound;
+use core::ops::RangeBounds;
+
+pub use ::bytes::Buf;
+pub use ::bytes::Bytes;
+
+/// A logical concatenation of [`Bytes`] segments.
+///
+/// Segments are never copied: pushing, slicing and cloning only bump
+/// reference counts. `Chain` implements [`Buf`], so it can be read across
+/// segment boundaries like any contiguous buffer, and under `std` it can be
+/// handed to vectored writes through [`Chain::as_io_slices`].
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::bytes::{Buf, Chain};
+///
+/// let mut chain = Chain::new();
+/// chain.push(&b"\x00\x00"[..]);
+/// chain.push(&b"\x01\x02rest"[..]);
+/// assert_eq!(chain.len(), 8);
+/// assert_eq!(chain.get_u32(), 0x0102);
+/// assert_eq!(chain.to_bytes(), &b"rest"[..]);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct Chain {
+    segments: VecDeque<Bytes>,
+    len: usize,
+}
+
+impl Chain {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates an empty chain with room for `capacity` segments.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            segments: VecDeque::with_capacity(capacity),
+            len: 0,
+        }
+    }
+
+    /// Appends a segment, empty segments are skipped.
+    pub fn push(&mut self, segment: impl Into<Bytes>) {
+        let segment = segment.into();
+        if !segment.is_empty() {
+            self.len += segment.len();
+            self.segments.push_back(segment);
+        }
+    }
+
+    /// Appends all segments of `other`.
+    pub fn append(&mut self, other: Chain) {
+        self.len += other.len;
+        self.segments.extend(other.segments);
+    }
+
+    /// Total number of bytes in the chain.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Iterates over the non-empty segments in order.
+    pub fn segments(&self) -> impl Iterator<Item = &Bytes> {
+        self.segments.iter()
+    }
+
+    pub fn segment_count(&self) -> usize {
+        self.segments.len()
+    }
+
+    /// Returns the byte at logical position `index`.
+    pub fn get(&self, mut index: usize) -> Option<u8> {
+        for segment in &self.segments {
+            if index < segment.len() {
+                return Some(segment[index]);
+            }
+            index -= segment.len();
+        }
+        None
+    }
+
+    /// Returns a chain over the logical `range` of this one, sharing the
+    /// underlying segments.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the range is out of bounds.
+    pub fn slice(&self, range: impl RangeBounds<usize>) -> Chain {
+        let start = match range.start_bound() {
+            Bound::Included(&n) => n,
+            Bound::Excluded(&n) => n + 1,
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(&n) => n + 1,
+            Bound::Excluded(&n) => n,
+            Bound::Unbounded => self.len,
+        };
+        assert!(
+            start <= end && end <= self.len,
+            "range {}..{} out of bounds: {}",
+            start,
+            end,
+            self.len
+        );
+
+        let mut result = Chain::new();
+        let mut segment_start = 0;
+        for segment in &self.segments {
+            let segment_end = segment_start + segment.len();
+            if segment_end > start && segment_start < end {
+                let from = start.saturating_sub(segment_start);
+                let to = end.min(segment_end) - segment_start;
+                result.push(segment.slice(from..to));
+            }
+            if segment_end >= end {
+                break;
+            }
+            segment_start = segment_end;
+        }
+        result
+    }
+
+    /// Returns the content as one contiguous buffer.
+    ///
+    /// This only copies if the chain has more than one segment.
+    pub fn to_bytes(&self) -> Bytes {
+        match self.segments.len() {
+            0 => Bytes::new(),
+            1 => self.segments[0].clone(),
+            _ => Bytes::from(self.to_vec()),
+        }
+    }
+
+    /// Copies the content into a new `Vec`.
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.len);
+        for segment in &self.segments {
+            buf.extend_from_slice(segment);
+        }
+        buf
+    }
+
+    /// Borrows every segment as an [`IoSlice`](std::io::IoSlice), ready for
+    /// [`Write::write_vectored`](std::io::Write::write_vectored).
+    #[cfg(feature = "std")]
+    pub fn as_io_slices(&self) -> Vec<std::io::IoSlice<'_>> {
+        self.segments
+            .iter()
+            .map(|segment| std::io::IoSlice::new(segment))
+            .collect()
+    }
+}
+
+impl Buf for Chain {
+    fn remaining(&self) -> usize {
+        self.len
+    }
+
+    fn chunk(&self) -> &[u8] {
+        self.segments.front().map(|s| &s[..]).unwrap_or_default()
+    }
+
+    fn advance(&mut self, mut cnt: usize) {
+        assert!(
+            cnt <= self.len,
+            "cannot advance past `remaining`: {} <= {}",
+            cnt,
+            self.len
+        );
+        self.len -= cnt;
+        while cnt > 0 {
+            let front = self.segments.front_mut().expect("checked above");
+            if cnt < front.len() {
+                front.advance(cnt);
+                break;
+            }
+            cnt -= front.len();
+            self.segments.pop_front();
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn chunks_vectored<'a>(&'a self, dst: &mut [std::io::IoSlice<'a>]) -> usize {
+        let mut n = 0;
+        for (slot, segment) in dst.iter_mut().zip(&self.segments) {
+            *slot = std::io::IoSlice::new(segment);
+            n += 1;
+        }
+        n
+    }
+
+    fn copy_to_bytes(&mut self, len: usize) -> Bytes {
+        match self.segments.front_mut() {
+            // Avoid copying when the requested bytes live in one segment.
+            Some(front) if len <= front.len() => {
+                let bytes = front.split_to(len);
+                self.len -= len;
+                if front.is_empty() {
+                    self.segments.pop_front();
+                }
+                bytes
+            }
+            _ => {
+                let bytes = self.slice(..len).to_bytes();
+                self.advance(len);
+                bytes
+            }
+        }
+    }
+}
+
+impl<B: Into<Bytes>> FromIterator<B> for Chain {
+    fn from_iter<I: IntoIterator<Item = B>>(iter: I) -> Self {
+        let mut chain = Chain::new();
+        chain.extend(iter);
+        chain
+    }
+}
+
+impl<B: Into<Bytes>> Extend<B> for Chain {
+    fn extend<I: IntoIterator<Item = B>>(&mut self, iter: I) {
+        for segment in iter {
+            self.push(segment);
+        }
+    }
+}
+
+impl From<Bytes> for Chain {
+    fn from(bytes: Bytes) -> Self {
+        let mut chain = Chain::new();
+        chain.push(bytes);
+        chain
+    }
+}
+
+impl PartialEq for Chain {
+    /// Chains are equal if their content is equal, regardless of how it is
+    /// split into segments.
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len
+            && self
+                .segments()
+                .flat_map(|s| s.iter())
+                .eq(other.segments().flat_map(|s| s.iter()))
+    }
+}
+
+impl Eq for Chain {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn sample() -> Chain {
+        [&b"hello"[..], b"", b", ", b"world"].into_iter().collect()
+    }
+
+    #[test]
+    fn test_push_and_get() {
+        let chain = sample();
+        assert_eq!(chain.len(), 12);
+        assert_eq!(chain.segment_count(), 3);
+        assert_eq!(chain.get(0), Some(b'h'));
+        assert_eq!(chain.get(5), Some(b','));
+        assert_eq!(chain.get(11), Some(b'd'));
+        assert_eq!(chain.get(12), None);
+        assert_eq!(chain.to_vec(), b"hello, world");
+    }
+
+    #[test]
+    fn test_read_across_boundaries() {
+        let mut chain: Chain = [vec![0u8, 0, 0], vec![1, 0], vec![0, 0, 2]]
+            .into_iter()
+            .collect();
+        assert_eq!(chain.get_u32(), 1);
+        assert_eq!(chain.remaining(), 4);
+        assert_eq!(chain.chunk(), &[0]);
+        assert_eq!(chain.get_u32(), 2);
+        assert!(chain.is_empty());
+        assert_eq!(chain.segment_count(), 0);
+    }
+
+    #[test]
+    fn test_slice_and_copy_to_bytes() {
+        let chain = sample();
+        assert_eq!(chain.slice(3..9).to_vec(), b"lo, wo");
+        assert_eq!(chain.slice(..5).segment_count(), 1);
+        assert_eq!(chain.slice(12..).len(), 0);
+        assert_eq!(chain.slice(..), chain);
+
+        let mut chain = sample();
+        assert_eq!(chain.copy_to_bytes(2), &b"he"[..]);
+        assert_eq!(chain.copy_to_bytes(6), &b"llo, w"[..]);
+        assert_eq!(chain.to_bytes(), &b"orld"[..]);
+    }
+
+    #[test]
+    fn test_equality_ignores_segmentation() {
+        let one = Chain::from(Bytes::from_static(b"hello, world"));
+        assert_eq!(one, sample());
+        assert_ne!(one, sample().slice(1..));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_vectored_write() {
+        use std::io::Write;
+
+        let chain = sample();
+        let mut out = vec![];
+        let written = out.write_vectored(&chain.as_io_slices()).unwrap();
+        assert_eq!(written, 12);
+        assert_eq!(out, b"hello, world");
+
+        let mut slices = [std::io::IoSlice::new(&[]); 8];
+        assert_eq!(chain.chunks_vectored(&mut slices), 3);
+    }
+}
@@ -0,0 +1,442 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! A growable set of small integers, such as document ids.
+
+use crate::mem::HeapSize;
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::Hash;
+use core::hash::Hasher;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// A set of `usize` values stored as a bitmap of 64-bit words.
+///
+/// Memory is proportional to the largest value ever inserted, so it suits
+/// dense sets such as the matching documents of a segment. The set grows on
+/// insert; [`shrink_to_fit`](BitSet::shrink_to_fit) releases unused words.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::collections::BitSet;
+///
+/// let a: BitSet = [1, 5, 64, 200].into_iter().collect();
+/// let b: BitSet = [5, 64, 65].into_iter().collect();
+/// assert_eq!(a.intersection(&b).iter().collect::<Vec<_>>(), [5, 64]);
+/// assert_eq!(a.union(&b).count_ones(), 5);
+/// assert_eq!(a.difference(&b).iter().collect::<Vec<_>>(), [1, 200]);
+/// ```
+#[derive(Clone, Default)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    rkyv(derive(Debug))
+)]
+pub struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An empty set that holds values below `bits` without reallocating.
+    pub fn with_capacity(bits: usize) -> Self {
+        Self {
+            words: Vec::with_capacity(bits.div_ceil(WORD_BITS)),
+        }
+    }
+
+    /// Adds `value`, returning `true` if it was not present.
+    pub fn insert(&mut self, value: usize) -> bool {
+        let (word, mask) = Self::locate(value);
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        let was_set = self.words[word] & mask != 0;
+        self.words[word] |= mask;
+        !was_set
+    }
+
+    /// Removes `value`, returning `true` if it was present.
+    pub fn remove(&mut self, value: usize) -> bool {
+        let (word, mask) = Self::locate(value);
+        match self.words.get_mut(word) {
+            Some(bits) if *bits & mask != 0 => {
+                *bits &= !mask;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn contains(&self, value: usize) -> bool {
+        let (word, mask) = Self::locate(value);
+        self.words.get(word).is_some_and(|bits| bits & mask != 0)
+    }
+
+    /// The number of values in the set.
+    pub fn count_ones(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&w| w == 0)
+    }
+
+    /// Removes all values, keeping the allocated words.
+    pub fn clear(&mut self) {
+        self.words.clear();
+    }
+
+    /// The smallest value in the set.
+    pub fn min(&self) -> Option<usize> {
+        self.iter().next()
+    }
+
+    /// The largest value in the set.
+    pub fn max(&self) -> Option<usize> {
+        let (index, word) = self.words.iter().enumerate().rev().find(|(_, &w)| w != 0)?;
+        Some(index * WORD_BITS + (WORD_BITS - 1 - word.leading_zeros() as usize))
+    }
+
+    /// Drops trailing empty words and releases the spare capacity.
+    pub fn shrink_to_fit(&mut self) {
+        self.words.truncate(self.used_words());
+        self.words.shrink_to_fit();
+    }
+
+    /// Adds every value of `other`.
+    pub fn union_with(&mut self, other: &BitSet) {
+        if self.words.len() < other.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+        for (a, b) in self.words.iter_mut().zip(&other.words) {
+            *a |= b;
+        }
+    }
+
+    /// Keeps only the values also in `other`.
+    pub fn intersect_with(&mut self, other: &BitSet) {
+        self.words.truncate(other.words.len());
+        for (a, b) in self.words.iter_mut().zip(&other.words) {
+            *a &= b;
+        }
+    }
+
+    /// Removes every value of `other`.
+    pub fn difference_with(&mut self, other: &BitSet) {
+        for (a, b) in self.words.iter_mut().zip(&other.words) {
+            *a &= !b;
+        }
+    }
+
+    /// The values in `self` or `other`.
+    pub fn union(&self, other: &BitSet) -> BitSet {
+        let mut set = self.clone();
+        set.union_with(other);
+        set
+    }
+
+    /// The values in both `self` and `other`.
+    pub fn intersection(&self, other: &BitSet) -> BitSet {
+        let mut set = self.clone();
+        set.intersect_with(other);
+        set
+    }
+
+    /// The values in `self` but not in `other`.
+    pub fn difference(&self, other: &BitSet) -> BitSet {
+        let mut set = self.clone();
+        set.difference_with(other);
+        set
+    }
+
+    /// The values in ascending order.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            words: &self.words,
+            base: 0,
+            current: self.words.first().copied().unwrap_or(0),
+        }
+    }
+
+    fn locate(value: usize) -> (usize, u64) {
+        (value / WORD_BITS, 1 << (value % WORD_BITS))
+    }
+
+    /// The words up to the last non-empty one.
+    fn used_words(&self) -> usize {
+        self.words
+            .iter()
+            .rposition(|&w| w != 0)
+            .map_or(0, |i| i + 1)
+    }
+}
+
+/// Iterator over the values of a [`BitSet`], see [`BitSet::iter`].
+#[derive(Clone)]
+pub struct Iter<'a> {
+    words: &'a [u64],
+    base: usize,
+    current: u64,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.current == 0 {
+            self.words = self.words.get(1..).filter(|rest| !rest.is_empty())?;
+            self.base += WORD_BITS;
+            self.current = self.words[0];
+        }
+        let bit = self.current.trailing_zeros() as usize;
+        // Clear the lowest set bit.
+        self.current &= self.current - 1;
+        Some(self.base + bit)
+    }
+}
+
+impl<'a> IntoIterator for &'a BitSet {
+    type Item = usize;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl FromIterator<usize> for BitSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = BitSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<usize> for BitSet {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+/// Borsh encodes the words like serde, without trailing empty words.
+#[cfg(feature = "borsh")]
+impl borsh::BorshSerialize for BitSet {
+    fn serialize<W: borsh::io::Write>(&self, writer: &mut W) -> borsh::io::Result<()> {
+        borsh::BorshSerialize::serialize(&self.words[..self.used_words()], writer)
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl borsh::BorshDeserialize for BitSet {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        Ok(Self {
+            words: borsh::BorshDeserialize::deserialize_reader(reader)?,
+        })
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl ArchivedBitSet {
+    /// Returns `true` if the archived set contains `value`.
+    pub fn contains(&self, value: usize) -> bool {
+        let (word, mask) = BitSet::locate(value);
+        self.words
+            .get(word)
+            .is_some_and(|w| w.to_native() & mask != 0)
+    }
+}
+
+/// Sets are equal if they hold the same values, whatever their capacity.
+impl PartialEq for BitSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.words[..self.used_words()] == other.words[..other.used_words()]
+    }
+}
+
+impl Eq for BitSet {}
+
+impl Hash for BitSet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.words[..self.used_words()].hash(state)
+    }
+}
+
+impl fmt::Debug for BitSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl HeapSize for BitSet {
+    fn heap_size(&self) -> usize {
+        self.words.heap_size()
+    }
+}
+
+/// Serialized as the little-endian words of the bitmap, without trailing
+/// empty words.
+impl Serialize for BitSet {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.words[..self.used_words()].serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for BitSet {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(Self {
+            words: Vec::deserialize(deserializer)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::collections::BTreeSet;
+    use alloc::format;
+
+    fn values(set: &BitSet) -> Vec<usize> {
+        set.iter().collect()
+    }
+
+    #[test]
+    fn test_insert_remove_contains() {
+        let mut set = BitSet::new();
+        assert!(set.is_empty());
+        assert!(set.insert(0));
+        assert!(set.insert(63));
+        assert!(set.insert(64));
+        assert!(set.insert(1000));
+        assert!(!set.insert(64));
+        assert!(set.contains(1000));
+        assert!(!set.contains(999));
+        assert!(!set.contains(100_000));
+        assert_eq!(set.count_ones(), 4);
+        assert_eq!(values(&set), [0, 63, 64, 1000]);
+        assert_eq!((set.min(), set.max()), (Some(0), Some(1000)));
+
+        assert!(set.remove(1000));
+        assert!(!set.remove(1000));
+        assert!(!set.remove(100_000));
+        assert_eq!(set.max(), Some(64));
+
+        set.clear();
+        assert!(set.is_empty());
+        assert_eq!((set.min(), set.max()), (None, None));
+        assert!(values(&set).is_empty());
+    }
+
+    #[test]
+    fn test_set_operations_match_btreeset() {
+        let cases = [
+            (
+                &[3, 7, 64, 65, 128, 500, 501][..],
+                &[0, 7, 65, 129, 501, 2000][..],
+            ),
+            (&[0, 7, 65, 129, 501, 2000], &[3, 7, 64, 65, 128, 500, 501]),
+            (&[], &[1, 2]),
+            (&[1, 2], &[]),
+        ];
+        for (a, b) in cases {
+            let (x, y): (BitSet, BitSet) =
+                (a.iter().copied().collect(), b.iter().copied().collect());
+            let (l, r): (BTreeSet<usize>, BTreeSet<usize>) =
+                (a.iter().copied().collect(), b.iter().copied().collect());
+            assert_eq!(
+                values(&x.union(&y)),
+                l.union(&r).copied().collect::<Vec<_>>()
+            );
+            assert_eq!(
+                values(&x.intersection(&y)),
+                l.intersection(&r).copied().collect::<Vec<_>>()
+            );
+            assert_eq!(
+                values(&x.difference(&y)),
+                l.difference(&r).copied().collect::<Vec<_>>()
+            );
+        }
+    }
+
+    #[test]
+    fn test_equality_ignores_capacity() {
+        let mut a: BitSet = [1, 2000].into_iter().collect();
+        let b: BitSet = [1].into_iter().collect();
+        assert_ne!(a, b);
+        a.remove(2000);
+        assert_eq!(a, b);
+        assert_eq!(
+            crate::utils::hash::stable_hash(&a),
+            crate::utils::hash::stable_hash(&b)
+        );
+
+        a.shrink_to_fit();
+        assert_eq!(a.heap_size(), size_of::<u64>());
+        assert_eq!(BitSet::new().heap_size(), 0);
+    }
+
+    #[test]
+    fn test_serde_roundtrip() {
+        let mut set: BitSet = [0, 65].into_iter().collect();
+        set.insert(1 << 12);
+        set.remove(1 << 12);
+        let json = serde_json::to_string(&set).unwrap();
+        assert_eq!(json, "[1,2]");
+        assert_eq!(serde_json::from_str::<BitSet>(&json).unwrap(), set);
+        assert_eq!(format!("{:?}", set), "{0, 65}");
+    }
+
+    #[cfg(feature = "borsh")]
+    #[test]
+    fn test_borsh_round_trip() {
+        let mut set: BitSet = [0, 65].into_iter().collect();
+        set.insert(1 << 12);
+        set.remove(1 << 12);
+        let bytes = borsh::to_vec(&set).unwrap();
+        assert_eq!(bytes.len(), 4 + 2 * 8);
+        assert_eq!(borsh::from_slice::<BitSet>(&bytes).unwrap(), set);
+    }
+
+    #[cfg(feature = "rkyv")]
+    #[test]
+    fn test_rkyv_round_trip() {
+        use crate::serde_ext::archive;
+
+        let set: BitSet = [0, 65, 1000].into_iter().collect();
+        let bytes = archive::to_bytes(&set).unwrap();
+        let archived = archive::access::<BitSet>(&bytes).unwrap();
+        assert!(archived.contains(65) && archived.contains(1000));
+        assert!(!archived.contains(64) && !archived.contains(1 << 20));
+        assert_eq!(archive::from_bytes::<BitSet>(&bytes).unwrap(), set);
+    }
+}
@@ -0,0 +1,399 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Approximate membership with a Bloom filter.
+
+use crate::mem::HeapSize;
+use crate::store::PersistStore;
+use crate::store::RecoverableStore;
+use crate::store::StoreError;
+use crate::utils::hash::StableHasher;
+use alloc::format;
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash::Hash;
+use core::hash::Hasher;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Format id of a saved [`BloomFilter`].
+pub const BLOOM_FORMAT: [u8; 4] = *b"PZBF";
+
+const WORD_BITS: u64 = u64::BITS as u64;
+
+/// A probabilistic set: [`contains`](BloomFilter::contains) never misses
+/// an inserted item but may report items that were never inserted.
+///
+/// Items are hashed once with [`StableHasher`] and probed with double
+/// hashing, so a saved filter answers the same on every platform and
+/// process.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::collections::BloomFilter;
+///
+/// let mut terms = BloomFilter::new(1000, 0.01);
+/// terms.insert("pizza");
+/// assert!(terms.contains("pizza"));
+/// assert!(!terms.contains("pasta"));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "BloomFilterData")]
+#[cfg_attr(feature = "borsh", derive(borsh::BorshSerialize))]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    rkyv(derive(Debug), bytecheck(verify))
+)]
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    num_hashes: u32,
+}
+
+#[derive(Deserialize)]
+struct BloomFilterData {
+    bits: Vec<u64>,
+    num_hashes: u32,
+}
+
+impl TryFrom<BloomFilterData> for BloomFilter {
+    type Error = &'static str;
+
+    fn try_from(data: BloomFilterData) -> Result<Self, Self::Error> {
+        check_shape(data.bits.len(), data.num_hashes)?;
+        Ok(Self {
+            bits: data.bits,
+            num_hashes: data.num_hashes,
+        })
+    }
+}
+
+fn check_shape(words: usize, num_hashes: u32) -> Result<(), &'static str> {
+    if words == 0 {
+        return Err("a Bloom filter needs at least one word");
+    }
+    if num_hashes == 0 {
+        return Err("a Bloom filter needs at least one hash");
+    }
+    Ok(())
+}
+
+#[cfg(feature = "borsh")]
+impl borsh::BorshDeserialize for BloomFilter {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        let bits: Vec<u64> = borsh::BorshDeserialize::deserialize_reader(reader)?;
+        let num_hashes = borsh::BorshDeserialize::deserialize_reader(reader)?;
+        check_shape(bits.len(), num_hashes)
+            .map_err(|msg| borsh::io::Error::new(borsh::io::ErrorKind::InvalidData, msg))?;
+        Ok(Self { bits, num_hashes })
+    }
+}
+
+#[cfg(feature = "rkyv")]
+#[derive(Debug)]
+struct InvalidShape(&'static str);
+
+#[cfg(feature = "rkyv")]
+impl fmt::Display for InvalidShape {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl core::error::Error for InvalidShape {}
+
+// SAFETY: `verify` only rejects archives, it never accepts invalid bytes.
+#[cfg(feature = "rkyv")]
+unsafe impl<C> rkyv::bytecheck::Verify<C> for ArchivedBloomFilter
+where
+    C: rkyv::rancor::Fallible + ?Sized,
+    C::Error: rkyv::rancor::Source,
+{
+    fn verify(&self, _context: &mut C) -> Result<(), C::Error> {
+        check_shape(self.bits.len(), self.num_hashes.to_native())
+            .map_err(|msg| rkyv::rancor::Source::new(InvalidShape(msg)))
+    }
+}
+
+/// Filters can only be merged if they have the same number of bits and
+/// hashes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShapeMismatch;
+
+impl fmt::Display for ShapeMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Bloom filters differ in size or number of hashes")
+    }
+}
+
+impl BloomFilter {
+    /// A filter sized to hold `expected_items` with a false positive rate
+    /// of about `false_positive_rate`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `false_positive_rate` is not strictly between 0 and 1.
+    pub fn new(expected_items: usize, false_positive_rate: f64) -> Self {
+        assert!(
+            false_positive_rate > 0.0 && false_positive_rate < 1.0,
+            "false positive rate must be in (0, 1), got {}",
+            false_positive_rate
+        );
+        let items = expected_items.max(1) as f64;
+        let ln2 = core::f64::consts::LN_2;
+        let num_bits = libm::ceil(-items * libm::log(false_positive_rate) / (ln2 * ln2));
+        let num_hashes = libm::round(num_bits / items * ln2).max(1.0);
+        Self::with_params(num_bits as u64, num_hashes as u32)
+    }
+
+    /// A filter of at least `num_bits` bits, rounded up to whole 64-bit
+    /// words, probed `num_hashes` times per item.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `num_hashes` is zero.
+    pub fn with_params(num_bits: u64, num_hashes: u32) -> Self {
+        assert!(num_hashes > 0, "a Bloom filter needs at least one hash");
+        let words = num_bits.div_ceil(WORD_BITS).max(1);
+        Self {
+            bits: alloc::vec![0; words as usize],
+            num_hashes,
+        }
+    }
+
+    /// The size of the filter in bits.
+    pub fn num_bits(&self) -> u64 {
+        self.bits.len() as u64 * WORD_BITS
+    }
+
+    /// The number of bits set per item.
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    /// Adds `item`, returning `true` if it was not already (approximately)
+    /// present.
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) -> bool {
+        let mut added = false;
+        for bit in self.probes(item) {
+            let (word, mask) = ((bit / WORD_BITS) as usize, 1 << (bit % WORD_BITS));
+            added |= self.bits[word] & mask == 0;
+            self.bits[word] |= mask;
+        }
+        added
+    }
+
+    /// Returns `false` if `item` was definitely never inserted.
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        self.probes(item)
+            .all(|bit| self.bits[(bit / WORD_BITS) as usize] & (1 << (bit % WORD_BITS)) != 0)
+    }
+
+    /// Adds every item of `other`, which must have the same shape.
+    pub fn merge(&mut self, other: &BloomFilter) -> Result<(), ShapeMismatch> {
+        if self.bits.len() != other.bits.len() || self.num_hashes != other.num_hashes {
+            return Err(ShapeMismatch);
+        }
+        for (a, b) in self.bits.iter_mut().zip(&other.bits) {
+            *a |= b;
+        }
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.iter().all(|&w| w == 0)
+    }
+
+    /// Removes all items.
+    pub fn clear(&mut self) {
+        self.bits.fill(0);
+    }
+
+    /// The false positive rate given the current fill ratio.
+    pub fn estimated_false_positive_rate(&self) -> f64 {
+        let set: u32 = self.bits.iter().map(|w| w.count_ones()).sum();
+        let ratio = set as f64 / self.num_bits() as f64;
+        libm::pow(ratio, self.num_hashes as f64)
+    }
+
+    fn probes<T: Hash + ?Sized>(&self, item: &T) -> impl Iterator<Item = u64> {
+        let mut hasher = StableHasher::new();
+        item.hash(&mut hasher);
+        // FNV-1a mixes the last bytes poorly, finish with a 64-bit mixer and
+        // derive the second hash from it.
+        let h1 = mix64(hasher.finish());
+        let h2 = mix64(h1 ^ 0x9e37_79b9_7f4a_7c15) | 1;
+        let num_bits = self.num_bits();
+        (0..self.num_hashes as u64).map(move |i| h1.wrapping_add(i.wrapping_mul(h2)) % num_bits)
+    }
+}
+
+/// The SplitMix64 finalizer.
+fn mix64(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+impl HeapSize for BloomFilter {
+    fn heap_size(&self) -> usize {
+        self.bits.heap_size()
+    }
+}
+
+impl PersistStore for BloomFilter {
+    const FORMAT: [u8; 4] = BLOOM_FORMAT;
+    const VERSION: u16 = 1;
+
+    fn encode_payload(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.num_hashes.to_le_bytes());
+        for word in &self.bits {
+            out.extend_from_slice(&word.to_le_bytes());
+        }
+    }
+}
+
+impl RecoverableStore for BloomFilter {
+    fn decode_payload(_version: u16, payload: &[u8]) -> Result<Self, StoreError> {
+        if payload.len() < 12 || !(payload.len() - 4).is_multiple_of(8) {
+            return Err(StoreError::Payload(format!(
+                "invalid Bloom filter length {}",
+                payload.len()
+            )));
+        }
+        let num_hashes = u32::from_le_bytes(payload[..4].try_into().unwrap());
+        let bits = payload[4..]
+            .chunks_exact(8)
+            .map(|word| u64::from_le_bytes(word.try_into().unwrap()))
+            .collect();
+        BloomFilter::try_from(BloomFilterData { bits, num_hashes })
+            .map_err(|reason| StoreError::Payload(reason.into()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sizing() {
+        let filter = BloomFilter::new(1000, 0.01);
+        // m = -n ln(p) / ln(2)^2 = 9586 bits, k = m / n ln(2) = 7
+        assert_eq!(filter.num_bits(), 9600);
+        assert_eq!(filter.num_hashes(), 7);
+        assert!(filter.is_empty());
+
+        let tiny = BloomFilter::new(0, 0.5);
+        assert_eq!((tiny.num_bits(), tiny.num_hashes()), (64, 1));
+    }
+
+    #[test]
+    fn test_no_false_negatives_and_rate() {
+        let mut filter = BloomFilter::new(10_000, 0.01);
+        for i in 0..10_000u32 {
+            filter.insert(&i);
+        }
+        assert!((0..10_000u32).all(|i| filter.contains(&i)));
+
+        let false_positives = (10_000..110_000u32).filter(|i| filter.contains(i)).count();
+        let rate = false_positives as f64 / 100_000.0;
+        assert!(rate < 0.02, "false positive rate {}", rate);
+        assert!((filter.estimated_false_positive_rate() - 0.01).abs() < 0.005);
+    }
+
+    #[test]
+    fn test_insert_reports_new_items() {
+        let mut filter = BloomFilter::with_params(1 << 12, 3);
+        assert!(filter.insert("a"));
+        assert!(!filter.insert("a"));
+        filter.clear();
+        assert!(!filter.contains("a"));
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut a = BloomFilter::new(100, 0.01);
+        let mut b = BloomFilter::new(100, 0.01);
+        a.insert("left");
+        b.insert("right");
+        a.merge(&b).unwrap();
+        assert!(a.contains("left") && a.contains("right"));
+        assert_eq!(a.merge(&BloomFilter::new(1000, 0.01)), Err(ShapeMismatch));
+    }
+
+    #[test]
+    fn test_persistence() {
+        let mut filter = BloomFilter::new(100, 0.01);
+        filter.insert("term");
+
+        let restored = BloomFilter::load(&filter.save()).unwrap();
+        assert_eq!(restored, filter);
+        assert!(restored.contains("term"));
+
+        let json = serde_json::to_string(&filter).unwrap();
+        assert_eq!(serde_json::from_str::<BloomFilter>(&json).unwrap(), filter);
+        assert!(serde_json::from_str::<BloomFilter>(r#"{"bits":[],"num_hashes":1}"#).is_err());
+        assert!(serde_json::from_str::<BloomFilter>(r#"{"bits":[1],"num_hashes":0}"#).is_err());
+    }
+
+    #[cfg(feature = "borsh")]
+    #[test]
+    fn test_borsh_round_trip() {
+        use alloc::vec;
+
+        let mut filter = BloomFilter::new(100, 0.01);
+        filter.insert("term");
+        let bytes = borsh::to_vec(&filter).unwrap();
+        let restored = borsh::from_slice::<BloomFilter>(&bytes).unwrap();
+        assert_eq!(restored, filter);
+        assert!(restored.contains("term"));
+
+        let no_words = borsh::to_vec(&(Vec::<u64>::new(), 1u32)).unwrap();
+        assert!(borsh::from_slice::<BloomFilter>(&no_words).is_err());
+        let no_hashes = borsh::to_vec(&(vec![1u64], 0u32)).unwrap();
+        assert!(borsh::from_slice::<BloomFilter>(&no_hashes).is_err());
+    }
+
+    #[cfg(feature = "rkyv")]
+    #[test]
+    fn test_rkyv_round_trip() {
+        use crate::serde_ext::archive;
+        use alloc::vec;
+
+        let mut filter = BloomFilter::new(100, 0.01);
+        filter.insert("term");
+        let bytes = archive::to_bytes(&filter).unwrap();
+        let restored = archive::from_bytes::<BloomFilter>(&bytes).unwrap();
+        assert_eq!(restored, filter);
+        assert!(restored.contains("term"));
+
+        let invalid = BloomFilter {
+            bits: vec![1],
+            num_hashes: 0,
+        };
+        let bytes = archive::to_bytes(&invalid).unwrap();
+        assert!(archive::access::<BloomFilter>(&bytes).is_err());
+        assert!(archive::from_bytes::<BloomFilter>(&bytes).is_err());
+    }
+}
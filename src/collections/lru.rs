@@ -0,0 +1,554 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! A least recently used cache bounded by entry count and memory.
+
+use crate::mem::HeapSize;
+use crate::mem::MemSize;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::hash::Hash;
+use core::mem::size_of;
+use hashbrown::HashMap;
+
+const NIL: usize = usize::MAX;
+
+struct Node<K, V> {
+    key: K,
+    value: V,
+    charge: usize,
+    prev: usize,
+    next: usize,
+}
+
+/// Hit, miss and eviction counters of an [`LruCache`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+impl CacheStats {
+    /// The fraction of lookups that were hits, zero before any lookup.
+    pub fn hit_rate(&self) -> f64 {
+        match self.hits + self.misses {
+            0 => 0.0,
+            lookups => self.hits as f64 / lookups as f64,
+        }
+    }
+}
+
+/// A map that evicts its least recently used entries once it holds more
+/// than `max_entries` entries, or more than `max_bytes` bytes when a memory
+/// budget was set with [`with_memory_budget`](LruCache::with_memory_budget).
+///
+/// [`get`](LruCache::get) counts as a use and updates the statistics,
+/// [`peek`](LruCache::peek) does neither.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::collections::LruCache;
+///
+/// let mut cache = LruCache::new(2);
+/// cache.put("a", 1);
+/// cache.put("b", 2);
+/// assert_eq!(cache.get("a"), Some(&1));
+/// cache.put("c", 3); // evicts "b", the least recently used
+/// assert_eq!(cache.peek("b"), None);
+/// assert_eq!(cache.stats().evictions, 1);
+/// ```
+pub struct LruCache<K, V> {
+    map: HashMap<K, usize>,
+    nodes: Vec<Option<Node<K, V>>>,
+    free: Vec<usize>,
+    /// Most recently used.
+    head: usize,
+    /// Least recently used.
+    tail: usize,
+    max_entries: usize,
+    max_bytes: usize,
+    used_bytes: usize,
+    charge: fn(&K, &V) -> usize,
+    stats: CacheStats,
+}
+
+fn no_charge<K, V>(_: &K, _: &V) -> usize {
+    0
+}
+
+fn mem_charge<K: MemSize, V: MemSize>(key: &K, value: &V) -> usize {
+    key.mem_size() + value.mem_size()
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    /// A cache holding at most `max_entries` entries.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `max_entries` is zero.
+    pub fn new(max_entries: usize) -> Self {
+        assert!(max_entries > 0, "an LRU cache needs room for one entry");
+        Self {
+            map: HashMap::new(),
+            nodes: Vec::new(),
+            free: Vec::new(),
+            head: NIL,
+            tail: NIL,
+            max_entries,
+            max_bytes: usize::MAX,
+            used_bytes: 0,
+            charge: no_charge,
+            stats: CacheStats::default(),
+        }
+    }
+
+    /// Also bounds the cache by the [`MemSize`] of its keys and values.
+    ///
+    /// An entry larger than the whole budget is not cached.
+    pub fn with_memory_budget(mut self, max_bytes: usize) -> Self
+    where
+        K: MemSize,
+        V: MemSize,
+    {
+        self.charge = mem_charge::<K, V>;
+        self.max_bytes = max_bytes;
+        self.used_bytes = 0;
+        let mut at = self.head;
+        while let Some(node) = self.nodes.get_mut(at).and_then(Option::as_mut) {
+            node.charge = mem_charge(&node.key, &node.value);
+            self.used_bytes += node.charge;
+            at = node.next;
+        }
+        self.evict();
+        self
+    }
+
+    /// Returns the value of `key` and marks it as the most recently used.
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_mut(key).map(|value| &*value)
+    }
+
+    /// Like [`get`](Self::get), with mutable access to the value.
+    ///
+    /// The memory charged for the entry is not updated.
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match self.map.get(key) {
+            Some(&at) => {
+                self.stats.hits += 1;
+                self.unlink(at);
+                self.push_front(at);
+                Some(&mut self.node_mut(at).value)
+            }
+            None => {
+                self.stats.misses += 1;
+                None
+            }
+        }
+    }
+
+    /// Returns the value of `key` without marking it as used.
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let &at = self.map.get(key)?;
+        self.nodes[at].as_ref().map(|node| &node.value)
+    }
+
+    pub fn contains<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.contains_key(key)
+    }
+
+    /// Inserts or replaces the value of `key` as the most recently used
+    /// entry, returning the value it replaced.
+    pub fn put(&mut self, key: K, value: V) -> Option<V> {
+        let charge = (self.charge)(&key, &value);
+        let old = self.pop(&key);
+        if charge > self.max_bytes {
+            self.stats.evictions += 1;
+            return old;
+        }
+
+        let node = Node {
+            key: key.clone(),
+            value,
+            charge,
+            prev: NIL,
+            next: NIL,
+        };
+        let at = match self.free.pop() {
+            Some(at) => {
+                self.nodes[at] = Some(node);
+                at
+            }
+            None => {
+                self.nodes.push(Some(node));
+                self.nodes.len() - 1
+            }
+        };
+        self.map.insert(key, at);
+        self.used_bytes += charge;
+        self.push_front(at);
+        self.evict();
+        old
+    }
+
+    /// Removes `key`, returning its value.
+    pub fn pop<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let at = self.map.remove(key)?;
+        Some(self.remove_node(at).1)
+    }
+
+    /// Removes and returns the least recently used entry.
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        if self.tail == NIL {
+            return None;
+        }
+        let (key, value) = self.remove_node(self.tail);
+        self.map.remove(&key);
+        Some((key, value))
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// The maximum number of entries.
+    pub fn max_entries(&self) -> usize {
+        self.max_entries
+    }
+
+    /// The memory charged for the cached entries, zero without a memory
+    /// budget.
+    pub fn memory_usage(&self) -> usize {
+        self.used_bytes
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = CacheStats::default();
+    }
+
+    /// Removes all entries, keeping the statistics.
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.nodes.clear();
+        self.free.clear();
+        self.head = NIL;
+        self.tail = NIL;
+        self.used_bytes = 0;
+    }
+
+    /// The entries from the most to the least recently used.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        let mut at = self.head;
+        core::iter::from_fn(move || {
+            let node = self.nodes.get(at)?.as_ref()?;
+            at = node.next;
+            Some((&node.key, &node.value))
+        })
+    }
+
+    fn evict(&mut self) {
+        while self.map.len() > self.max_entries || self.used_bytes > self.max_bytes {
+            if self.pop_lru().is_none() {
+                break;
+            }
+            self.stats.evictions += 1;
+        }
+    }
+
+    fn node_mut(&mut self, at: usize) -> &mut Node<K, V> {
+        self.nodes[at].as_mut().expect("linked node is vacant")
+    }
+
+    fn remove_node(&mut self, at: usize) -> (K, V) {
+        self.unlink(at);
+        let node = self.nodes[at].take().expect("linked node is vacant");
+        self.free.push(at);
+        self.used_bytes -= node.charge;
+        (node.key, node.value)
+    }
+
+    fn unlink(&mut self, at: usize) {
+        let (prev, next) = {
+            let node = self.node_mut(at);
+            (node.prev, node.next)
+        };
+        match prev {
+            NIL => self.head = next,
+            prev => self.node_mut(prev).next = next,
+        }
+        match next {
+            NIL => self.tail = prev,
+            next => self.node_mut(next).prev = prev,
+        }
+    }
+
+    fn push_front(&mut self, at: usize) {
+        let head = self.head;
+        {
+            let node = self.node_mut(at);
+            node.prev = NIL;
+            node.next = head;
+        }
+        match head {
+            NIL => self.tail = at,
+            head => self.node_mut(head).prev = at,
+        }
+        self.head = at;
+    }
+}
+
+impl<K: HeapSize, V: HeapSize> HeapSize for LruCache<K, V> {
+    fn heap_size(&self) -> usize {
+        self.map.heap_size()
+            + self.nodes.capacity() * size_of::<Option<Node<K, V>>>()
+            + self
+                .nodes
+                .iter()
+                .flatten()
+                .map(|node| node.key.heap_size() + node.value.heap_size())
+                .sum::<usize>()
+            + self.free.heap_size()
+    }
+}
+
+/// An [`LruCache`] behind a mutex, to share one cache between threads.
+///
+/// Lookups return clones because the value cannot outlive the lock; use
+/// [`lock`](SyncLruCache::lock) for anything else.
+#[cfg(feature = "std")]
+pub struct SyncLruCache<K, V> {
+    inner: std::sync::Mutex<LruCache<K, V>>,
+}
+
+#[cfg(feature = "std")]
+impl<K: Hash + Eq + Clone, V> SyncLruCache<K, V> {
+    pub fn new(cache: LruCache<K, V>) -> Self {
+        Self {
+            inner: std::sync::Mutex::new(cache),
+        }
+    }
+
+    /// Locks the cache. A panic while holding the lock cannot leave the
+    /// cache inconsistent, so a poisoned lock is taken over.
+    pub fn lock(&self) -> std::sync::MutexGuard<'_, LruCache<K, V>> {
+        self.inner
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// See [`LruCache::get`].
+    pub fn get<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Clone,
+    {
+        self.lock().get(key).cloned()
+    }
+
+    /// See [`LruCache::put`].
+    pub fn put(&self, key: K, value: V) -> Option<V> {
+        self.lock().put(key, value)
+    }
+
+    /// See [`LruCache::pop`].
+    pub fn pop<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.lock().pop(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.lock().stats()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::String;
+    use alloc::vec;
+
+    fn keys<V>(cache: &LruCache<u32, V>) -> Vec<u32> {
+        cache.iter().map(|(k, _)| *k).collect()
+    }
+
+    #[test]
+    fn test_recency_order() {
+        let mut cache = LruCache::new(3);
+        for i in 0..3 {
+            assert_eq!(cache.put(i, i * 10), None);
+        }
+        assert_eq!(keys(&cache), [2, 1, 0]);
+        assert_eq!(cache.get(&0), Some(&0));
+        assert_eq!(keys(&cache), [0, 2, 1]);
+        assert_eq!(cache.peek(&1), Some(&10));
+        assert_eq!(keys(&cache), [0, 2, 1]);
+
+        cache.put(3, 30);
+        assert_eq!(keys(&cache), [3, 0, 2]);
+        assert!(!cache.contains(&1));
+
+        assert_eq!(cache.put(2, 21), Some(20));
+        assert_eq!(keys(&cache), [2, 3, 0]);
+        *cache.get_mut(&0).unwrap() += 1;
+        assert_eq!(cache.pop(&0), Some(1));
+        assert_eq!(cache.pop_lru(), Some((3, 30)));
+        assert_eq!(cache.pop_lru(), Some((2, 21)));
+        assert_eq!(cache.pop_lru(), None);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_slots_are_reused() {
+        let mut cache = LruCache::new(4);
+        for i in 0..1000 {
+            cache.put(i, i);
+        }
+        assert_eq!(cache.len(), 4);
+        assert_eq!(cache.nodes.len(), 5);
+        assert_eq!(keys(&cache), [999, 998, 997, 996]);
+        cache.clear();
+        assert!(cache.is_empty());
+        assert!(keys(&cache).is_empty());
+    }
+
+    #[test]
+    fn test_stats() {
+        let mut cache = LruCache::new(1);
+        cache.put("a", 1);
+        cache.get("a");
+        cache.get("b");
+        cache.peek("b");
+        cache.put("b", 2);
+        let stats = cache.stats();
+        assert_eq!(
+            stats,
+            CacheStats {
+                hits: 1,
+                misses: 1,
+                evictions: 1
+            }
+        );
+        assert_eq!(stats.hit_rate(), 0.5);
+        cache.reset_stats();
+        assert_eq!(cache.stats().hit_rate(), 0.0);
+    }
+
+    #[test]
+    fn test_memory_budget() {
+        let entry = |n: usize| 0u32.mem_size() + String::with_capacity(n).mem_size();
+        let budget = entry(100) * 2;
+        let mut cache = LruCache::new(100).with_memory_budget(budget);
+        cache.put(1, String::with_capacity(100));
+        cache.put(2, String::with_capacity(100));
+        assert_eq!(cache.memory_usage(), budget);
+        cache.put(3, String::with_capacity(10));
+        assert_eq!(keys(&cache), [3, 2]);
+        assert_eq!(cache.memory_usage(), entry(100) + entry(10));
+
+        // Too large to cache at all.
+        cache.put(4, String::with_capacity(budget));
+        assert_eq!(keys(&cache), [3, 2]);
+        assert_eq!(cache.stats().evictions, 2);
+
+        cache.pop(&2);
+        assert_eq!(cache.memory_usage(), entry(10));
+
+        let mut unbounded = LruCache::new(10);
+        unbounded.put(1, vec![0u8; 64]);
+        assert_eq!(unbounded.memory_usage(), 0);
+        let bounded = unbounded.with_memory_budget(1024);
+        assert_eq!(
+            bounded.memory_usage(),
+            1u32.mem_size() + vec![0u8; 64].mem_size()
+        );
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_sync_cache() {
+        let cache = std::sync::Arc::new(SyncLruCache::new(LruCache::new(64)));
+        let threads: Vec<_> = (0..4u32)
+            .map(|t| {
+                let cache = cache.clone();
+                std::thread::spawn(move || {
+                    for i in 0..100 {
+                        cache.put(t * 100 + i, i);
+                        cache.get(&(t * 100 + i));
+                    }
+                    // Holding the lock keeps other threads from evicting the
+                    // entry in between.
+                    let mut cache = cache.lock();
+                    cache.put(t, t);
+                    cache.get(&t).copied()
+                })
+            })
+            .collect();
+        for (t, thread) in threads.into_iter().enumerate() {
+            assert_eq!(thread.join().unwrap(), Some(t as u32));
+        }
+        assert_eq!(cache.len(), 64);
+        assert!(!cache.is_empty());
+        cache.put(1000, 1);
+        assert_eq!(cache.pop(&1000), Some(1));
+    }
+}
@@ -0,0 +1,231 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Bounded collection of the greatest items of a stream.
+
+use crate::mem::HeapSize;
+use alloc::collections::BinaryHeap;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+/// Keeps the `k` greatest items pushed into it.
+///
+/// The items sit in a min-heap of size `k`, so each push is `O(log k)` and
+/// an item not greater than the current minimum is rejected in `O(1)`. An
+/// item equal to the minimum does not replace it, so among equal items the
+/// first pushed are kept.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::collections::TopK;
+///
+/// let mut segment_a = TopK::new(3);
+/// segment_a.extend([5, 1, 9, 3]);
+/// let mut segment_b = TopK::new(3);
+/// segment_b.extend([7, 2]);
+///
+/// segment_a.merge(segment_b);
+/// assert_eq!(segment_a.into_sorted_vec(), [9, 7, 5]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TopK<T> {
+    k: usize,
+    heap: BinaryHeap<Reverse<T>>,
+}
+
+impl<T: Ord> TopK<T> {
+    pub fn new(k: usize) -> Self {
+        Self {
+            k,
+            heap: BinaryHeap::with_capacity(k),
+        }
+    }
+
+    /// The number of items kept.
+    pub fn k(&self) -> usize {
+        self.k
+    }
+
+    /// Offers an item, returns `true` if it is currently kept.
+    pub fn push(&mut self, item: T) -> bool {
+        if self.heap.len() < self.k {
+            self.heap.push(Reverse(item));
+            return true;
+        }
+        match self.heap.peek_mut() {
+            Some(mut min) if item > min.0 => {
+                min.0 = item;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// The smallest kept item once `k` items are collected; anything not
+    /// greater than it will be rejected.
+    pub fn threshold(&self) -> Option<&T> {
+        if self.heap.len() < self.k {
+            None
+        } else {
+            self.heap.peek().map(|min| &min.0)
+        }
+    }
+
+    /// Adds the items of another collector, e.g. the results of another
+    /// segment. The result keeps `self`'s `k`.
+    pub fn merge(&mut self, other: TopK<T>) {
+        for Reverse(item) in other.heap {
+            self.push(item);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// The kept items in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.heap.iter().map(|item| &item.0)
+    }
+
+    /// Consumes the collector, returning the kept items greatest first.
+    pub fn into_sorted_vec(self) -> Vec<T> {
+        // Sorted ascending by `Reverse`, that is greatest item first.
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|item| item.0)
+            .collect()
+    }
+}
+
+impl<T: Ord> Extend<T> for TopK<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<T: HeapSize> HeapSize for TopK<T> {
+    fn heap_size(&self) -> usize {
+        self.heap.capacity() * size_of::<T>()
+            + self
+                .heap
+                .iter()
+                .map(|item| item.0.heap_size())
+                .sum::<usize>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_keeps_greatest() {
+        let mut top = TopK::new(3);
+        assert!(top.is_empty());
+        assert!(top.push(4));
+        assert!(top.push(1));
+        assert_eq!(top.threshold(), None);
+        assert!(top.push(8));
+        assert_eq!(top.threshold(), Some(&1));
+        assert!(!top.push(0));
+        assert!(!top.push(1));
+        assert!(top.push(6));
+        assert_eq!(top.len(), 3);
+        let mut kept: Vec<i32> = top.iter().copied().collect();
+        kept.sort();
+        assert_eq!(kept, [4, 6, 8]);
+        assert_eq!(top.into_sorted_vec(), [8, 6, 4]);
+
+        let mut none = TopK::new(0);
+        assert!(!none.push(1));
+        assert!(none.into_sorted_vec().is_empty());
+    }
+
+    #[test]
+    fn test_stable_among_equals() {
+        // Ordered by the key only, so equal keys are told apart by the tag.
+        #[derive(Debug)]
+        struct Keyed(u32, char);
+
+        impl PartialEq for Keyed {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+
+        impl Eq for Keyed {}
+
+        impl PartialOrd for Keyed {
+            fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for Keyed {
+            fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+                self.0.cmp(&other.0)
+            }
+        }
+
+        let mut top = TopK::new(2);
+        top.extend([Keyed(1, 'a'), Keyed(1, 'b'), Keyed(1, 'c'), Keyed(0, 'd')]);
+        let mut tags: Vec<char> = top.into_sorted_vec().into_iter().map(|k| k.1).collect();
+        tags.sort();
+        assert_eq!(tags, ['a', 'b']);
+    }
+
+    #[test]
+    fn test_merge_matches_single_pass() {
+        let values: Vec<u32> = (0..500u32)
+            .map(|i| i.wrapping_mul(2_654_435_761) % 997)
+            .collect();
+        let mut expected = values.clone();
+        expected.sort_unstable_by(|a, b| b.cmp(a));
+        expected.truncate(10);
+
+        let mut merged = TopK::new(10);
+        for chunk in values.chunks(64) {
+            let mut part = TopK::new(10);
+            part.extend(chunk.iter().copied());
+            merged.merge(part);
+        }
+        assert_eq!(merged.into_sorted_vec(), expected);
+
+        let mut small = TopK::new(1);
+        small.merge({
+            let mut big = TopK::new(5);
+            big.extend(vec![3, 9, 4]);
+            big
+        });
+        assert_eq!(small.into_sorted_vec(), [9]);
+    }
+}
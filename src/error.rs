@@ -0,0 +1,193 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! The error type shared by the modules of this crate.
+
+use crate::arena::ArenaCodecError;
+use crate::serde_ext::HeaderError;
+use crate::store::StoreError;
+use crate::utils::units::ParseUnitError;
+use crate::utils::varint::VarintError;
+use alloc::string::String;
+use alloc::string::ToString;
+use core::fmt;
+
+/// What went wrong, for callers that need to tell failures apart.
+///
+/// The message of each variant describes the failure in full; `Display`
+/// prints it as is, with an `invalid format: ` prefix for [`Error::Parse`].
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::arena::Arena;
+/// use pizza_common::error::Error;
+///
+/// let arena = Arena::new(1, 1, 1024);
+/// arena.alloc(1u32).unwrap();
+/// assert!(matches!(arena.alloc(2), Err(Error::CapacityExceeded(_))));
+/// ```
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Error {
+    /// An item count or memory limit would be exceeded.
+    CapacityExceeded(String),
+    /// The requested item does not exist, such as an unknown snapshot.
+    NotFound(String),
+    /// An argument is malformed, such as a JSON Pointer without a leading
+    /// `/`.
+    InvalidArgument(String),
+    /// Text could not be parsed.
+    Parse(String),
+    /// A value could not be encoded or decoded.
+    Serialization(String),
+    #[cfg(feature = "std")]
+    Io(std::io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::CapacityExceeded(message)
+            | Error::NotFound(message)
+            | Error::InvalidArgument(message)
+            | Error::Serialization(message) => f.write_str(message),
+            Error::Parse(message) => write!(f, "invalid format: {}", message),
+            #[cfg(feature = "std")]
+            Error::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl core::error::Error for Error {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            Error::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<std::io::Error> for Error {
+    fn from(e: std::io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<Error> for std::io::Error {
+    fn from(e: Error) -> Self {
+        let kind = match e {
+            Error::Io(e) => return e,
+            Error::NotFound(_) => std::io::ErrorKind::NotFound,
+            Error::InvalidArgument(_) => std::io::ErrorKind::InvalidInput,
+            Error::CapacityExceeded(_) => std::io::ErrorKind::OutOfMemory,
+            Error::Parse(_) | Error::Serialization(_) => std::io::ErrorKind::InvalidData,
+        };
+        std::io::Error::new(kind, e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        if e.is_syntax() || e.is_eof() {
+            Error::Parse(e.to_string())
+        } else {
+            Error::Serialization(e.to_string())
+        }
+    }
+}
+
+impl From<ParseUnitError> for Error {
+    fn from(e: ParseUnitError) -> Self {
+        Error::Parse(e.to_string())
+    }
+}
+
+impl From<VarintError> for Error {
+    fn from(e: VarintError) -> Self {
+        Error::Serialization(e.to_string())
+    }
+}
+
+impl From<HeaderError> for Error {
+    fn from(e: HeaderError) -> Self {
+        Error::Serialization(e.to_string())
+    }
+}
+
+impl From<StoreError> for Error {
+    fn from(e: StoreError) -> Self {
+        Error::Serialization(e.to_string())
+    }
+}
+
+impl From<ArenaCodecError> for Error {
+    fn from(e: ArenaCodecError) -> Self {
+        Error::Serialization(e.to_string())
+    }
+}
+
+/// A `Result` with [`Error`] as the error type.
+pub type Result<T, E = Error> = core::result::Result<T, E>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display_and_conversions() {
+        assert_eq!(
+            Error::Parse("bad".into()).to_string(),
+            "invalid format: bad"
+        );
+        assert_eq!(Error::NotFound("gone".into()).to_string(), "gone");
+
+        let e: Error = serde_json::from_str::<u32>("x").unwrap_err().into();
+        assert!(matches!(e, Error::Parse(_)));
+        let e: Error = serde_json::from_str::<u32>("\"x\"").unwrap_err().into();
+        assert!(matches!(e, Error::Serialization(_)));
+        let e: Error = "12 apples"
+            .parse::<crate::utils::units::ByteSize>()
+            .unwrap_err()
+            .into();
+        assert!(matches!(e, Error::Parse(_)));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_io_roundtrip() {
+        let io = std::io::Error::new(std::io::ErrorKind::BrokenPipe, "pipe");
+        let e = Error::from(io);
+        assert!(core::error::Error::source(&e).is_some());
+        assert_eq!(
+            std::io::Error::from(e).kind(),
+            std::io::ErrorKind::BrokenPipe
+        );
+
+        let io = std::io::Error::from(Error::NotFound("snapshot 3".into()));
+        assert_eq!(io.kind(), std::io::ErrorKind::NotFound);
+        assert_eq!(io.to_string(), "snapshot 3");
+    }
+}
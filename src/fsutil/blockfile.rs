@@ -0,0 +1,405 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! A checksummed container of blocks.
+//!
+//! ```text
+//! +--------+---------+---------+-----+---------+-------+---------+
+//! | header | block 0 | block 1 | ... | block N | index | trailer |
+//! +--------+---------+---------+-----+---------+-------+---------+
+//! ```
+//!
+//! * The header is a streaming [`Header`] carrying the format version and
+//!   flags, followed by the block size when the file uses fixed-size blocks.
+//! * Every block starts with its compression tag, three reserved zero
+//!   bytes, its length and the CRC-32C of its stored bytes.
+//! * The index lists the offset of every block, followed by a fixed-size
+//!   trailer with the index location and its own checksum.
+//!
+//! All integers are little endian. Files are written sequentially through
+//! [`BlockFileWriter`] and read from a byte slice (e.g. an
+//! [`Mmap`](super::Mmap)) through [`BlockFileReader`].
+
+use crate::serde_ext::Header;
+use crate::serde_ext::HEADER_LEN as COMMON_HEADER_LEN;
+use crate::utils::checksum::crc32c;
+use alloc::vec::Vec;
+use std::io;
+use std::io::Write;
+
+/// Format id of block files in their [`Header`], also repeated as the last
+/// bytes of the file.
+pub const FORMAT: [u8; 4] = *b"PZBF";
+/// Version of the layout written by [`BlockFileWriter`].
+pub const VERSION: u16 = 1;
+/// Header flag set when all blocks but the last have the same size.
+pub const FLAG_FIXED_SIZE: u32 = 1;
+
+const HEADER_LEN: usize = COMMON_HEADER_LEN + 8;
+const BLOCK_HEADER_LEN: usize = 12;
+const INDEX_ENTRY_LEN: usize = 8;
+const TRAILER_LEN: usize = 20;
+
+/// How the stored bytes of a block are compressed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Compression {
+    #[default]
+    None = 0,
+}
+
+impl Compression {
+    fn from_tag(tag: u8) -> io::Result<Self> {
+        match tag {
+            0 => Ok(Compression::None),
+            _ => Err(invalid_data("unknown block compression")),
+        }
+    }
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_u32(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())
+}
+
+/// Options of a [`BlockFileWriter`].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlockFileOptions {
+    /// Require every block but the last to be exactly this many bytes.
+    pub fixed_block_size: Option<u32>,
+    /// Application defined flags, stored in the upper byte of the header
+    /// flags.
+    pub user_flags: u8,
+    /// Compression tag recorded for every block.
+    pub compression: Compression,
+}
+
+/// Writes a block file to any [`Write`] sink.
+///
+/// Nothing is readable until [`BlockFileWriter::finish`] wrote the index.
+pub struct BlockFileWriter<W: Write> {
+    writer: W,
+    options: BlockFileOptions,
+    position: u64,
+    offsets: Vec<u64>,
+    last_block_short: bool,
+}
+
+impl<W: Write> BlockFileWriter<W> {
+    pub fn new(mut writer: W, options: BlockFileOptions) -> io::Result<Self> {
+        let mut flags = (options.user_flags as u32) << 8;
+        if options.fixed_block_size.is_some() {
+            flags |= FLAG_FIXED_SIZE;
+        }
+
+        let mut header = [0u8; HEADER_LEN];
+        header[..COMMON_HEADER_LEN].copy_from_slice(
+            &Header::streaming(FORMAT, VERSION)
+                .with_format_flags(flags)
+                .to_bytes(),
+        );
+        header[COMMON_HEADER_LEN..COMMON_HEADER_LEN + 4]
+            .copy_from_slice(&options.fixed_block_size.unwrap_or(0).to_le_bytes());
+        writer.write_all(&header)?;
+
+        Ok(Self {
+            writer,
+            options,
+            position: HEADER_LEN as u64,
+            offsets: Vec::new(),
+            last_block_short: false,
+        })
+    }
+
+    /// Appends a block and returns its index.
+    ///
+    /// With fixed-size blocks, only the last block may be shorter than the
+    /// block size.
+    pub fn write_block(&mut self, data: &[u8]) -> io::Result<usize> {
+        let len = u32::try_from(data.len()).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "block exceeds u32::MAX bytes")
+        })?;
+        if let Some(block_size) = self.options.fixed_block_size {
+            if self.last_block_short || len > block_size {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "only the last fixed-size block may be short",
+                ));
+            }
+            self.last_block_short = len < block_size;
+        }
+
+        let mut header = [0u8; BLOCK_HEADER_LEN];
+        header[0] = self.options.compression as u8;
+        header[4..8].copy_from_slice(&len.to_le_bytes());
+        header[8..12].copy_from_slice(&crc32c(data).to_le_bytes());
+        self.writer.write_all(&header)?;
+        self.writer.write_all(data)?;
+
+        self.offsets.push(self.position);
+        self.position += (BLOCK_HEADER_LEN + data.len()) as u64;
+        Ok(self.offsets.len() - 1)
+    }
+
+    /// Number of blocks written so far.
+    pub fn block_count(&self) -> usize {
+        self.offsets.len()
+    }
+
+    /// Writes the block index and the trailer, returning the inner writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        let block_count = u32::try_from(self.offsets.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "too many blocks"))?;
+
+        let mut index = Vec::with_capacity(self.offsets.len() * INDEX_ENTRY_LEN);
+        for offset in &self.offsets {
+            index.extend_from_slice(&offset.to_le_bytes());
+        }
+        self.writer.write_all(&index)?;
+
+        let mut trailer = [0u8; TRAILER_LEN];
+        trailer[..8].copy_from_slice(&self.position.to_le_bytes());
+        trailer[8..12].copy_from_slice(&block_count.to_le_bytes());
+        trailer[12..16].copy_from_slice(&crc32c(&index).to_le_bytes());
+        trailer[16..].copy_from_slice(&FORMAT);
+        self.writer.write_all(&trailer)?;
+        self.writer.flush()?;
+
+        Ok(self.writer)
+    }
+}
+
+/// A block read from a [`BlockFileReader`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Block<'a> {
+    pub compression: Compression,
+    pub data: &'a [u8],
+}
+
+/// Reads a block file from a byte slice.
+///
+/// The header, trailer and index are validated when the reader is created,
+/// every block is checked against its checksum when it is read.
+#[derive(Debug)]
+pub struct BlockFileReader<'a> {
+    bytes: &'a [u8],
+    flags: u32,
+    block_size: Option<u32>,
+    index: &'a [u8],
+}
+
+impl<'a> BlockFileReader<'a> {
+    pub fn new(bytes: &'a [u8]) -> io::Result<Self> {
+        if bytes.len() < HEADER_LEN + TRAILER_LEN {
+            return Err(invalid_data("block file is too short"));
+        }
+        let header = Header::parse(bytes)?;
+        header.expect(FORMAT, VERSION)?;
+        if bytes[bytes.len() - 4..] != FORMAT {
+            return Err(invalid_data("not a block file"));
+        }
+        let flags = header.format_flags();
+        let block_size = (flags & FLAG_FIXED_SIZE != 0).then(|| read_u32(bytes, COMMON_HEADER_LEN));
+
+        let trailer = bytes.len() - TRAILER_LEN;
+        let index_offset = read_u64(bytes, trailer);
+        let block_count = read_u32(bytes, trailer + 8) as u64;
+        let index_checksum = read_u32(bytes, trailer + 12);
+
+        let index_end = block_count
+            .checked_mul(INDEX_ENTRY_LEN as u64)
+            .and_then(|index_len| index_offset.checked_add(index_len));
+        if index_offset < HEADER_LEN as u64 || index_end != Some(trailer as u64) {
+            return Err(invalid_data("corrupted block file index location"));
+        }
+        let index = &bytes[index_offset as usize..trailer];
+        if crc32c(index) != index_checksum {
+            return Err(invalid_data("block file index checksum mismatch"));
+        }
+
+        Ok(Self {
+            bytes,
+            flags,
+            block_size,
+            index,
+        })
+    }
+
+    /// Number of blocks in the file.
+    pub fn len(&self) -> usize {
+        self.index.len() / INDEX_ENTRY_LEN
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// The block size if the file uses fixed-size blocks.
+    pub fn fixed_block_size(&self) -> Option<u32> {
+        self.block_size
+    }
+
+    /// The application defined flags passed in [`BlockFileOptions`].
+    pub fn user_flags(&self) -> u8 {
+        (self.flags >> 8) as u8
+    }
+
+    /// Reads and verifies the block at `index`.
+    pub fn block(&self, index: usize) -> io::Result<Block<'a>> {
+        if index >= self.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "block index out of bounds",
+            ));
+        }
+        let data_end = self.bytes.len() - TRAILER_LEN - self.index.len();
+        let offset = read_u64(self.index, index * INDEX_ENTRY_LEN);
+        if offset > (data_end - BLOCK_HEADER_LEN) as u64 {
+            return Err(invalid_data("corrupted block offset"));
+        }
+
+        let offset = offset as usize;
+        let compression = Compression::from_tag(self.bytes[offset])?;
+        if self.bytes[offset + 1..offset + 4] != [0; 3] {
+            return Err(invalid_data("reserved block header bytes are set"));
+        }
+        let len = read_u32(self.bytes, offset + 4) as usize;
+        let checksum = read_u32(self.bytes, offset + 8);
+
+        let start = offset + BLOCK_HEADER_LEN;
+        if len > data_end - start {
+            return Err(invalid_data("corrupted block length"));
+        }
+        let data = &self.bytes[start..start + len];
+        if crc32c(data) != checksum {
+            return Err(invalid_data("block checksum mismatch"));
+        }
+
+        Ok(Block { compression, data })
+    }
+
+    /// Iterates over all blocks in order.
+    pub fn blocks(&self) -> impl Iterator<Item = io::Result<Block<'a>>> + '_ {
+        (0..self.len()).map(move |index| self.block(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_file(options: BlockFileOptions, blocks: &[&[u8]]) -> Vec<u8> {
+        let mut writer = BlockFileWriter::new(Vec::new(), options).unwrap();
+        for (i, block) in blocks.iter().enumerate() {
+            assert_eq!(writer.write_block(block).unwrap(), i);
+        }
+        assert_eq!(writer.block_count(), blocks.len());
+        writer.finish().unwrap()
+    }
+
+    #[test]
+    fn test_variable_blocks_round_trip() {
+        let blocks: [&[u8]; 4] = [b"alpha", b"", b"gamma gamma", &[0u8; 1000]];
+        let options = BlockFileOptions {
+            user_flags: 0x5a,
+            ..Default::default()
+        };
+        let bytes = write_file(options, &blocks);
+
+        let reader = BlockFileReader::new(&bytes).unwrap();
+        assert_eq!(reader.len(), 4);
+        assert_eq!(reader.fixed_block_size(), None);
+        assert_eq!(reader.user_flags(), 0x5a);
+        let read: Vec<&[u8]> = reader.blocks().map(|b| b.unwrap().data).collect();
+        assert_eq!(read, blocks);
+        assert!(reader.block(4).is_err());
+    }
+
+    #[test]
+    fn test_fixed_size_blocks() {
+        let options = BlockFileOptions {
+            fixed_block_size: Some(4),
+            ..Default::default()
+        };
+        let bytes = write_file(options, &[b"abcd", b"efgh", b"ij"]);
+        let reader = BlockFileReader::new(&bytes).unwrap();
+        assert_eq!(reader.fixed_block_size(), Some(4));
+        assert_eq!(reader.block(2).unwrap().data, b"ij");
+
+        let mut writer = BlockFileWriter::new(Vec::new(), options).unwrap();
+        assert!(writer.write_block(b"abcde").is_err());
+        writer.write_block(b"ab").unwrap();
+        assert!(writer.write_block(b"abcd").is_err());
+    }
+
+    #[test]
+    fn test_empty_file() {
+        let bytes = write_file(BlockFileOptions::default(), &[]);
+        let reader = BlockFileReader::new(&bytes).unwrap();
+        assert!(reader.is_empty());
+    }
+
+    #[test]
+    fn test_detect_corruption() {
+        let bytes = write_file(BlockFileOptions::default(), &[b"first", b"second"]);
+
+        // Flip a byte in the data of the second block.
+        let mut corrupted = bytes.clone();
+        let at = HEADER_LEN + BLOCK_HEADER_LEN + 5 + BLOCK_HEADER_LEN;
+        corrupted[at] ^= 0xff;
+        let reader = BlockFileReader::new(&corrupted).unwrap();
+        assert_eq!(reader.block(0).unwrap().data, b"first");
+        assert!(reader.block(1).is_err());
+
+        // Flip a byte in the index.
+        let mut corrupted = bytes.clone();
+        let at = bytes.len() - TRAILER_LEN - 1;
+        corrupted[at] ^= 0xff;
+        assert!(BlockFileReader::new(&corrupted).is_err());
+
+        // Set a reserved byte in the header of the first block.
+        let mut corrupted = bytes.clone();
+        corrupted[HEADER_LEN + 2] = 1;
+        let reader = BlockFileReader::new(&corrupted).unwrap();
+        assert!(reader.block(0).is_err());
+        assert_eq!(reader.block(1).unwrap().data, b"second");
+
+        // A trailer whose index location wraps around.
+        let mut corrupted = bytes.clone();
+        let trailer = bytes.len() - TRAILER_LEN;
+        let index_offset = (trailer as u64).wrapping_sub(u32::MAX as u64 * 8);
+        corrupted[trailer..trailer + 8].copy_from_slice(&index_offset.to_le_bytes());
+        corrupted[trailer + 8..trailer + 12].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(BlockFileReader::new(&corrupted).is_err());
+
+        // Truncated file.
+        assert!(BlockFileReader::new(&bytes[..bytes.len() - 1]).is_err());
+        assert!(BlockFileReader::new(&[0u8; 64]).is_err());
+    }
+}
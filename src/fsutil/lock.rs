@@ -0,0 +1,315 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// How a [`FileLock`] is held.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockMode {
+    /// Only one holder at a time.
+    Exclusive,
+    /// Any number of shared holders, but no exclusive one.
+    Shared,
+}
+
+/// An advisory lock on a file, released when dropped.
+///
+/// Locks are held per open file, so two `FileLock`s on the same path conflict
+/// even within a single process. Typical use is locking a marker file inside
+/// a data directory so that two nodes cannot open it at once:
+///
+/// ```no_run
+/// use pizza_common::fsutil::FileLock;
+///
+/// let _lock = FileLock::try_exclusive("data/node.lock")?
+///     .expect("data directory is used by another process");
+/// # Ok::<(), std::io::Error>(())
+/// ```
+///
+/// The lock file is created if it does not exist and is never removed.
+#[derive(Debug)]
+pub struct FileLock {
+    file: File,
+    path: PathBuf,
+    mode: LockMode,
+}
+
+impl FileLock {
+    /// Acquires a lock on `path`, blocking until it is available.
+    pub fn lock(path: impl AsRef<Path>, mode: LockMode) -> io::Result<Self> {
+        let path = path.as_ref();
+        let file = open(path)?;
+        sys::lock(&file, mode)?;
+        Ok(Self {
+            file,
+            path: path.to_path_buf(),
+            mode,
+        })
+    }
+
+    /// Attempts to acquire a lock on `path` without blocking.
+    ///
+    /// Returns `Ok(None)` if a conflicting lock is held by someone else.
+    pub fn try_lock(path: impl AsRef<Path>, mode: LockMode) -> io::Result<Option<Self>> {
+        let path = path.as_ref();
+        let file = open(path)?;
+        if sys::try_lock(&file, mode)? {
+            Ok(Some(Self {
+                file,
+                path: path.to_path_buf(),
+                mode,
+            }))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Blocking exclusive acquisition, see [`FileLock::lock`].
+    pub fn exclusive(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::lock(path, LockMode::Exclusive)
+    }
+
+    /// Blocking shared acquisition, see [`FileLock::lock`].
+    pub fn shared(path: impl AsRef<Path>) -> io::Result<Self> {
+        Self::lock(path, LockMode::Shared)
+    }
+
+    /// Non-blocking exclusive acquisition, see [`FileLock::try_lock`].
+    pub fn try_exclusive(path: impl AsRef<Path>) -> io::Result<Option<Self>> {
+        Self::try_lock(path, LockMode::Exclusive)
+    }
+
+    /// Non-blocking shared acquisition, see [`FileLock::try_lock`].
+    pub fn try_shared(path: impl AsRef<Path>) -> io::Result<Option<Self>> {
+        Self::try_lock(path, LockMode::Shared)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn mode(&self) -> LockMode {
+        self.mode
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        // Closing the file releases the lock anyway, unlocking explicitly just
+        // makes it happen before the handle is gone.
+        let _ = sys::unlock(&self.file);
+    }
+}
+
+fn open(path: &Path) -> io::Result<File> {
+    OpenOptions::new()
+        .read(true)
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(path)
+}
+
+#[cfg(unix)]
+mod sys {
+    use super::LockMode;
+    use std::fs::File;
+    use std::io;
+    use std::os::unix::io::AsRawFd;
+
+    fn flock(file: &File, operation: libc::c_int) -> io::Result<()> {
+        loop {
+            // SAFETY: The descriptor is owned by `file` and stays open.
+            if unsafe { libc::flock(file.as_raw_fd(), operation) } == 0 {
+                return Ok(());
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+    }
+
+    fn operation(mode: LockMode) -> libc::c_int {
+        match mode {
+            LockMode::Exclusive => libc::LOCK_EX,
+            LockMode::Shared => libc::LOCK_SH,
+        }
+    }
+
+    pub(super) fn lock(file: &File, mode: LockMode) -> io::Result<()> {
+        flock(file, operation(mode))
+    }
+
+    pub(super) fn try_lock(file: &File, mode: LockMode) -> io::Result<bool> {
+        match flock(file, operation(mode) | libc::LOCK_NB) {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == io::ErrorKind::WouldBlock => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub(super) fn unlock(file: &File) -> io::Result<()> {
+        flock(file, libc::LOCK_UN)
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use super::LockMode;
+    use core::mem::zeroed;
+    use std::fs::File;
+    use std::io;
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Foundation::ERROR_LOCK_VIOLATION;
+    use windows_sys::Win32::Storage::FileSystem::LockFileEx;
+    use windows_sys::Win32::Storage::FileSystem::UnlockFileEx;
+    use windows_sys::Win32::Storage::FileSystem::LOCKFILE_EXCLUSIVE_LOCK;
+    use windows_sys::Win32::Storage::FileSystem::LOCKFILE_FAIL_IMMEDIATELY;
+    use windows_sys::Win32::Storage::FileSystem::LOCK_FILE_FLAGS;
+
+    fn lock_file(file: &File, flags: LOCK_FILE_FLAGS) -> io::Result<()> {
+        // SAFETY: The handle is owned by `file` and the overlapped structure
+        // lives for the duration of the synchronous call.
+        let ok = unsafe {
+            let mut overlapped = zeroed();
+            LockFileEx(
+                file.as_raw_handle() as _,
+                flags,
+                0,
+                u32::MAX,
+                u32::MAX,
+                &mut overlapped,
+            )
+        };
+        if ok == 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn flags(mode: LockMode) -> LOCK_FILE_FLAGS {
+        match mode {
+            LockMode::Exclusive => LOCKFILE_EXCLUSIVE_LOCK,
+            LockMode::Shared => 0,
+        }
+    }
+
+    pub(super) fn lock(file: &File, mode: LockMode) -> io::Result<()> {
+        lock_file(file, flags(mode))
+    }
+
+    pub(super) fn try_lock(file: &File, mode: LockMode) -> io::Result<bool> {
+        match lock_file(file, flags(mode) | LOCKFILE_FAIL_IMMEDIATELY) {
+            Ok(()) => Ok(true),
+            Err(err) if err.raw_os_error() == Some(ERROR_LOCK_VIOLATION as i32) => Ok(false),
+            Err(err) => Err(err),
+        }
+    }
+
+    pub(super) fn unlock(file: &File) -> io::Result<()> {
+        // SAFETY: Same as in `lock_file`.
+        let ok = unsafe {
+            let mut overlapped = zeroed();
+            UnlockFileEx(
+                file.as_raw_handle() as _,
+                0,
+                u32::MAX,
+                u32::MAX,
+                &mut overlapped,
+            )
+        };
+        if ok == 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Platforms without file locking, such as `wasm32-unknown-unknown`, where
+/// there is no file system to share with other processes anyway.
+#[cfg(not(any(unix, windows)))]
+mod sys {
+    use super::LockMode;
+    use std::fs::File;
+    use std::io;
+
+    fn unsupported() -> io::Error {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            "file locking is not supported on this platform",
+        )
+    }
+
+    pub(super) fn lock(_file: &File, _mode: LockMode) -> io::Result<()> {
+        Err(unsupported())
+    }
+
+    pub(super) fn try_lock(_file: &File, _mode: LockMode) -> io::Result<bool> {
+        Err(unsupported())
+    }
+
+    pub(super) fn unlock(_file: &File) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exclusive_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("node.lock");
+
+        let lock = FileLock::try_exclusive(&path).unwrap().unwrap();
+        assert_eq!(lock.mode(), LockMode::Exclusive);
+        assert_eq!(lock.path(), path);
+        assert!(FileLock::try_exclusive(&path).unwrap().is_none());
+        assert!(FileLock::try_shared(&path).unwrap().is_none());
+
+        drop(lock);
+        assert!(FileLock::try_exclusive(&path).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_shared_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("node.lock");
+
+        let first = FileLock::shared(&path).unwrap();
+        let second = FileLock::try_shared(&path).unwrap().unwrap();
+        assert!(FileLock::try_exclusive(&path).unwrap().is_none());
+
+        drop(first);
+        assert!(FileLock::try_exclusive(&path).unwrap().is_none());
+        drop(second);
+        assert!(FileLock::exclusive(&path).is_ok());
+    }
+}
@@ -0,0 +1,230 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use core::mem::size_of;
+use core::ops::Deref;
+use core::ops::Range;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// Plain-old-data types that can be read from any properly sized byte
+/// sequence.
+///
+/// # Safety
+///
+/// Implementors must be `Copy`, contain no padding and accept every possible
+/// bit pattern as a valid value.
+pub unsafe trait Pod: Copy + 'static {}
+
+macro_rules! impl_pod {
+    ($($ty:ty),*) => {
+        $(unsafe impl Pod for $ty {})*
+    };
+}
+
+impl_pod!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+/// Access pattern hints passed to `madvise(2)`.
+///
+/// Hints are ignored on platforms without `madvise`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Advice {
+    Normal,
+    Sequential,
+    Random,
+    WillNeed,
+}
+
+/// A read-only memory map of a file.
+///
+/// The map derefs to `&[u8]`, so it can be handed to anything that reads from
+/// a byte slice, e.g. the binary decoders of this crate.
+pub struct Mmap {
+    inner: memmap2::Mmap,
+}
+
+impl Mmap {
+    /// Maps the file at `path` read-only.
+    ///
+    /// # Safety
+    ///
+    /// See [`Mmap::map`].
+    pub unsafe fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        Self::map(&file)
+    }
+
+    /// Maps an already opened file read-only.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be truncated or modified in place, by this or any
+    /// other process, for as long as the map is alive. Shrinking a mapped
+    /// file makes reads past its new end fault, and in-place writes change
+    /// bytes behind shared references.
+    ///
+    /// Files replaced by renaming a new file over them, such as
+    /// [`PersistStore::checkpoint`](crate::store::PersistStore::checkpoint)
+    /// writes, are fine to map. WAL segments are not while they may be
+    /// appended to or truncated by [`recover`](super::wal::recover).
+    pub unsafe fn map(file: &File) -> io::Result<Self> {
+        // SAFETY: Upheld by the caller, see above.
+        let inner = unsafe { memmap2::Mmap::map(file)? };
+        Ok(Self { inner })
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// View the whole map as a byte slice.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.inner
+    }
+
+    /// View a sub-range of the map, or `None` if it is out of bounds.
+    pub fn slice(&self, range: Range<usize>) -> Option<&[u8]> {
+        self.inner.get(range)
+    }
+
+    /// Reads a `T` stored at `offset`, or `None` if it does not fit in the map.
+    ///
+    /// The value does not need to be aligned.
+    pub fn read_pod<T: Pod>(&self, offset: usize) -> Option<T> {
+        let end = offset.checked_add(size_of::<T>())?;
+        let bytes = self.slice(offset..end)?;
+        // SAFETY: `bytes` holds exactly `size_of::<T>()` bytes and `T: Pod`
+        // accepts any bit pattern.
+        Some(unsafe { bytes.as_ptr().cast::<T>().read_unaligned() })
+    }
+
+    /// Tells the kernel how the whole map is going to be accessed.
+    pub fn advise(&self, advice: Advice) -> io::Result<()> {
+        #[cfg(unix)]
+        {
+            self.inner.advise(to_memmap_advice(advice))
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = advice;
+            Ok(())
+        }
+    }
+
+    /// Tells the kernel how `range` of the map is going to be accessed.
+    pub fn advise_range(&self, advice: Advice, range: Range<usize>) -> io::Result<()> {
+        if range.start > range.end || range.end > self.len() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "advice range out of bounds",
+            ));
+        }
+        #[cfg(unix)]
+        {
+            self.inner
+                .advise_range(to_memmap_advice(advice), range.start, range.len())
+        }
+        #[cfg(not(unix))]
+        {
+            let _ = advice;
+            Ok(())
+        }
+    }
+}
+
+#[cfg(unix)]
+fn to_memmap_advice(advice: Advice) -> memmap2::Advice {
+    match advice {
+        Advice::Normal => memmap2::Advice::Normal,
+        Advice::Sequential => memmap2::Advice::Sequential,
+        Advice::Random => memmap2::Advice::Random,
+        Advice::WillNeed => memmap2::Advice::WillNeed,
+    }
+}
+
+impl Deref for Mmap {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+impl AsRef<[u8]> for Mmap {
+    fn as_ref(&self) -> &[u8] {
+        self.as_slice()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_typed_views() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data.bin");
+
+        let mut bytes = std::vec::Vec::new();
+        bytes.extend_from_slice(&0xdead_beef_u32.to_le_bytes());
+        bytes.push(7);
+        bytes.extend_from_slice(&1.5f64.to_ne_bytes());
+        std::fs::write(&path, &bytes).unwrap();
+
+        // SAFETY: The file is private to the test and not modified.
+        let mmap = unsafe { Mmap::open(&path) }.unwrap();
+        mmap.advise(Advice::Sequential).unwrap();
+        mmap.advise_range(Advice::WillNeed, 0..4).unwrap();
+        assert!(mmap.advise_range(Advice::WillNeed, 0..100).is_err());
+
+        assert_eq!(mmap.len(), 13);
+        assert_eq!(mmap.as_slice(), &bytes[..]);
+        assert_eq!(mmap.slice(4..5), Some(&[7u8][..]));
+        assert_eq!(mmap.slice(10..20), None);
+
+        assert_eq!(mmap.read_pod::<u32>(0).map(u32::from_le), Some(0xdead_beef));
+        // Unaligned read.
+        assert_eq!(mmap.read_pod::<f64>(5), Some(1.5));
+        assert_eq!(mmap.read_pod::<[u8; 2]>(3), Some([0xde, 7]));
+        assert_eq!(mmap.read_pod::<u64>(6), None);
+        assert_eq!(mmap.read_pod::<u8>(usize::MAX), None);
+    }
+
+    #[test]
+    fn test_empty_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("empty.bin");
+        std::fs::write(&path, b"").unwrap();
+
+        // SAFETY: The file is private to the test and not modified.
+        let mmap = unsafe { Mmap::open(&path) }.unwrap();
+        assert!(mmap.is_empty());
+        assert_eq!(mmap.read_pod::<u8>(0), None);
+    }
+}
@@ -0,0 +1,425 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Append-only write-ahead log segments.
+//!
+//! A segment file starts with a streaming [`Header`], followed by records
+//! that are only ever appended:
+//!
+//! ```text
+//! +----------------+----------------+-------------------+
+//! | length (u32le) | crc32c (u32le) | payload (length)  |
+//! +----------------+----------------+-------------------+
+//! ```
+//!
+//! The checksum covers the length and the payload. After a crash the tail of
+//! a segment may hold a torn or partially written record, [`SegmentReader`]
+//! stops at the last valid record and [`recover`] truncates whatever follows.
+
+use crate::serde_ext::Header;
+use crate::serde_ext::HEADER_LEN;
+use crate::utils::checksum::crc32c;
+use crate::utils::checksum::crc32c_append;
+use alloc::vec;
+use alloc::vec::Vec;
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::BufReader;
+use std::io::BufWriter;
+use std::io::Read;
+use std::io::Seek;
+use std::io::SeekFrom;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Format id of WAL segments in their [`Header`].
+pub const SEGMENT_FORMAT: [u8; 4] = *b"PZWL";
+/// Version of the segment layout written by [`SegmentWriter`].
+pub const SEGMENT_VERSION: u16 = 1;
+/// Length of the segment header in bytes.
+pub const SEGMENT_HEADER_LEN: u64 = HEADER_LEN as u64;
+/// Length of the per-record header (length + checksum) in bytes.
+pub const RECORD_HEADER_LEN: u64 = 8;
+
+/// When a [`SegmentWriter`] calls `fsync` on its file.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SyncPolicy {
+    /// Sync after every appended record.
+    #[default]
+    Always,
+    /// Sync once every `n` appended records.
+    EveryN(usize),
+    /// Only sync when [`SegmentWriter::sync`] is called explicitly.
+    Manual,
+}
+
+/// A record read back from a segment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Record {
+    /// Offset of the record header within the segment file.
+    pub offset: u64,
+    pub payload: Vec<u8>,
+}
+
+fn record_checksum(len: u32, payload: &[u8]) -> u32 {
+    crc32c_append(crc32c(&len.to_le_bytes()), payload)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Appends records to a single segment file.
+pub struct SegmentWriter {
+    path: PathBuf,
+    writer: BufWriter<File>,
+    len: u64,
+    policy: SyncPolicy,
+    unsynced: usize,
+}
+
+impl SegmentWriter {
+    /// Creates a new segment at `path`, failing if the file already exists.
+    pub fn create(path: impl AsRef<Path>, policy: SyncPolicy) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+
+        let mut writer = BufWriter::new(file);
+        Header::streaming(SEGMENT_FORMAT, SEGMENT_VERSION).write_to(&mut writer)?;
+        writer.flush()?;
+        writer.get_ref().sync_all()?;
+
+        Ok(Self {
+            path,
+            writer,
+            len: SEGMENT_HEADER_LEN,
+            policy,
+            unsynced: 0,
+        })
+    }
+
+    /// Opens an existing segment for appending.
+    ///
+    /// A torn tail left behind by a crash is truncated first, so new records
+    /// are appended right after the last valid one.
+    pub fn open(path: impl AsRef<Path>, policy: SyncPolicy) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let recovery = recover(&path)?;
+
+        let mut file = OpenOptions::new().write(true).open(&path)?;
+        file.seek(SeekFrom::Start(recovery.valid_len))?;
+
+        Ok(Self {
+            path,
+            writer: BufWriter::new(file),
+            len: recovery.valid_len,
+            policy,
+            unsynced: 0,
+        })
+    }
+
+    /// Appends a record, returning its offset within the segment.
+    ///
+    /// Whether the record is durable when this returns depends on the
+    /// [`SyncPolicy`] of the writer.
+    pub fn append(&mut self, payload: &[u8]) -> io::Result<u64> {
+        let len = u32::try_from(payload.len()).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "record exceeds u32::MAX bytes")
+        })?;
+
+        let mut header = [0u8; RECORD_HEADER_LEN as usize];
+        header[..4].copy_from_slice(&len.to_le_bytes());
+        header[4..].copy_from_slice(&record_checksum(len, payload).to_le_bytes());
+
+        let offset = self.len;
+        self.writer.write_all(&header)?;
+        self.writer.write_all(payload)?;
+        self.len += RECORD_HEADER_LEN + payload.len() as u64;
+        self.unsynced += 1;
+
+        match self.policy {
+            SyncPolicy::Always => self.sync()?,
+            SyncPolicy::EveryN(n) if self.unsynced >= n => self.sync()?,
+            _ => {}
+        }
+
+        Ok(offset)
+    }
+
+    /// Flushes buffered records to the operating system without syncing.
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Flushes buffered records and syncs them to stable storage.
+    pub fn sync(&mut self) -> io::Result<()> {
+        self.writer.flush()?;
+        self.writer.get_ref().sync_data()?;
+        self.unsynced = 0;
+        Ok(())
+    }
+
+    /// The size of the segment in bytes, including buffered records.
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    /// Returns `true` if no record has been appended to the segment.
+    pub fn is_empty(&self) -> bool {
+        self.len == SEGMENT_HEADER_LEN
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+/// Reads the records of a segment in order.
+///
+/// Iteration ends at the end of the file or at the first torn or corrupted
+/// record, whichever comes first. I/O failures are reported as errors.
+pub struct SegmentReader {
+    reader: BufReader<File>,
+    file_len: u64,
+    valid_len: u64,
+    done: bool,
+}
+
+impl SegmentReader {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        let file_len = file.metadata()?.len();
+        let mut reader = BufReader::new(file);
+
+        if file_len < SEGMENT_HEADER_LEN {
+            return Err(invalid_data("truncated WAL segment header"));
+        }
+        Header::read_from(&mut reader)?.expect(SEGMENT_FORMAT, SEGMENT_VERSION)?;
+
+        Ok(Self {
+            reader,
+            file_len,
+            valid_len: SEGMENT_HEADER_LEN,
+            done: false,
+        })
+    }
+
+    /// Offset right after the last valid record read so far.
+    pub fn valid_len(&self) -> u64 {
+        self.valid_len
+    }
+
+    /// Returns `true` once iteration stopped before the end of the file,
+    /// i.e. the segment has a torn or corrupted tail.
+    pub fn has_torn_tail(&self) -> bool {
+        self.done && self.valid_len < self.file_len
+    }
+
+    /// Reads the next valid record, or `None` at the end of the valid data.
+    pub fn next_record(&mut self) -> io::Result<Option<Record>> {
+        if self.done {
+            return Ok(None);
+        }
+
+        let offset = self.valid_len;
+        let remaining = self.file_len - offset;
+        if remaining < RECORD_HEADER_LEN {
+            self.done = true;
+            return Ok(None);
+        }
+
+        let mut header = [0u8; RECORD_HEADER_LEN as usize];
+        self.reader.read_exact(&mut header)?;
+        let len = u32::from_le_bytes(header[..4].try_into().unwrap());
+        let checksum = u32::from_le_bytes(header[4..].try_into().unwrap());
+
+        if remaining - RECORD_HEADER_LEN < len as u64 {
+            self.done = true;
+            return Ok(None);
+        }
+
+        let mut payload = vec![0u8; len as usize];
+        self.reader.read_exact(&mut payload)?;
+        if record_checksum(len, &payload) != checksum {
+            self.done = true;
+            return Ok(None);
+        }
+
+        self.valid_len += RECORD_HEADER_LEN + len as u64;
+        Ok(Some(Record { offset, payload }))
+    }
+}
+
+impl Iterator for SegmentReader {
+    type Item = io::Result<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_record().transpose()
+    }
+}
+
+/// Outcome of [`recover`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Recovery {
+    /// Number of valid records in the segment.
+    pub records: usize,
+    /// Length of the segment after recovery.
+    pub valid_len: u64,
+    /// Number of bytes cut off the tail of the segment.
+    pub truncated: u64,
+}
+
+/// Scans the segment at `path` and truncates it right after the last valid
+/// record, so that a torn write left by a crash is discarded.
+pub fn recover(path: impl AsRef<Path>) -> io::Result<Recovery> {
+    let path = path.as_ref();
+    let mut reader = SegmentReader::open(path)?;
+
+    let mut records = 0;
+    while reader.next_record()?.is_some() {
+        records += 1;
+    }
+
+    let valid_len = reader.valid_len();
+    let truncated = reader.file_len - valid_len;
+    if truncated > 0 {
+        let file = OpenOptions::new().write(true).open(path)?;
+        file.set_len(valid_len)?;
+        file.sync_all()?;
+    }
+
+    Ok(Recovery {
+        records,
+        valid_len,
+        truncated,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+
+    fn read_all(path: &Path) -> Vec<Vec<u8>> {
+        SegmentReader::open(path)
+            .unwrap()
+            .map(|record| record.unwrap().payload)
+            .collect()
+    }
+
+    #[test]
+    fn test_append_and_read() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("00000001.wal");
+
+        let mut writer = SegmentWriter::create(&path, SyncPolicy::EveryN(2)).unwrap();
+        assert!(writer.is_empty());
+        let mut offsets = Vec::new();
+        for i in 0..10 {
+            offsets.push(writer.append(format!("record-{}", i).as_bytes()).unwrap());
+        }
+        writer.sync().unwrap();
+
+        let records: Vec<Record> = SegmentReader::open(&path)
+            .unwrap()
+            .map(Result::unwrap)
+            .collect();
+        assert_eq!(records.len(), 10);
+        for (i, record) in records.iter().enumerate() {
+            assert_eq!(record.offset, offsets[i]);
+            assert_eq!(record.payload, format!("record-{}", i).as_bytes());
+        }
+        assert!(SegmentWriter::create(&path, SyncPolicy::Always).is_err());
+    }
+
+    #[test]
+    fn test_recover_torn_tail() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("00000001.wal");
+
+        let mut writer = SegmentWriter::create(&path, SyncPolicy::Manual).unwrap();
+        writer.append(b"first").unwrap();
+        writer.append(b"second").unwrap();
+        let third = writer.append(b"third").unwrap();
+        writer.sync().unwrap();
+        let full_len = writer.len();
+        drop(writer);
+
+        // Simulate a crash in the middle of writing the last record.
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(full_len - 2).unwrap();
+        drop(file);
+
+        let mut reader = SegmentReader::open(&path).unwrap();
+        assert_eq!(reader.by_ref().count(), 2);
+        assert!(reader.has_torn_tail());
+
+        let recovery = recover(&path).unwrap();
+        assert_eq!(recovery.records, 2);
+        assert_eq!(recovery.valid_len, third);
+        assert_eq!(recovery.truncated, full_len - 2 - third);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), third);
+
+        // Appending after recovery continues right after the valid records.
+        let mut writer = SegmentWriter::open(&path, SyncPolicy::Always).unwrap();
+        assert_eq!(writer.append(b"fourth").unwrap(), third);
+        drop(writer);
+        assert_eq!(
+            read_all(&path),
+            vec![b"first".to_vec(), b"second".to_vec(), b"fourth".to_vec()]
+        );
+    }
+
+    #[test]
+    fn test_corrupted_record_stops_reading() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("00000001.wal");
+
+        let mut writer = SegmentWriter::create(&path, SyncPolicy::Always).unwrap();
+        writer.append(b"good").unwrap();
+        let bad = writer.append(b"flipped").unwrap();
+        writer.append(b"unreachable").unwrap();
+        drop(writer);
+
+        let mut bytes = std::fs::read(&path).unwrap();
+        bytes[(bad + RECORD_HEADER_LEN) as usize] ^= 0xff;
+        std::fs::write(&path, &bytes).unwrap();
+
+        assert_eq!(read_all(&path), vec![b"good".to_vec()]);
+        assert_eq!(recover(&path).unwrap().valid_len, bad);
+    }
+
+    #[test]
+    fn test_reject_foreign_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("not-a-segment");
+        std::fs::write(&path, b"definitely not a wal").unwrap();
+
+        let err = SegmentReader::open(&path).err().unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
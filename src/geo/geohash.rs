@@ -0,0 +1,322 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! [Geohash](https://en.wikipedia.org/wiki/Geohash) encoding and decoding.
+//!
+//! # Examples
+//!
+//! ```
+//! use pizza_common::geo::geohash;
+//!
+//! let hash = geohash::encode(57.64911, 10.40744, 11).unwrap();
+//! assert_eq!(hash, "u4pruydqqvj");
+//!
+//! let center = geohash::decode(&hash).unwrap();
+//! assert!((center.lat - 57.64911).abs() < 1e-5);
+//! ```
+
+use super::BoundingBox;
+use super::GeoPoint;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// The longest supported geohash, about 3.7cm x 1.9cm per cell.
+pub const MAX_PRECISION: usize = 12;
+
+const BASE32: &[u8; 32] = b"0123456789bcdefghjkmnpqrstuvwxyz";
+
+const DECODE_TABLE: [u8; 128] = {
+    let mut table = [0xff; 128];
+    let mut i = 0;
+    while i < 32 {
+        table[BASE32[i] as usize] = i as u8;
+        i += 1;
+    }
+    table
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum GeohashError {
+    /// The precision is not in `1..=MAX_PRECISION`.
+    InvalidPrecision(usize),
+    /// The latitude or longitude is out of range.
+    InvalidCoordinate(GeoPoint),
+    /// The hash contains a character outside of the geohash alphabet.
+    InvalidCharacter(char),
+    /// The hash is empty or longer than [`MAX_PRECISION`].
+    InvalidLength(usize),
+}
+
+impl fmt::Display for GeohashError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GeohashError::InvalidPrecision(p) => write!(
+                f,
+                "invalid geohash precision {}, expected 1..={}",
+                p, MAX_PRECISION
+            ),
+            GeohashError::InvalidCoordinate(p) => {
+                write!(f, "invalid coordinate: lat {}, lon {}", p.lat, p.lon)
+            }
+            GeohashError::InvalidCharacter(c) => write!(f, "invalid geohash character '{}'", c),
+            GeohashError::InvalidLength(len) => write!(
+                f,
+                "invalid geohash length {}, expected 1..={}",
+                len, MAX_PRECISION
+            ),
+        }
+    }
+}
+
+/// The eight directions of the cells around a geohash cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    North,
+    NorthEast,
+    East,
+    SouthEast,
+    South,
+    SouthWest,
+    West,
+    NorthWest,
+}
+
+impl Direction {
+    /// All directions, clockwise starting from north.
+    pub const ALL: [Direction; 8] = [
+        Direction::North,
+        Direction::NorthEast,
+        Direction::East,
+        Direction::SouthEast,
+        Direction::South,
+        Direction::SouthWest,
+        Direction::West,
+        Direction::NorthWest,
+    ];
+
+    /// Offsets in cells as `(lat, lon)`.
+    fn offsets(self) -> (f64, f64) {
+        match self {
+            Direction::North => (1.0, 0.0),
+            Direction::NorthEast => (1.0, 1.0),
+            Direction::East => (0.0, 1.0),
+            Direction::SouthEast => (-1.0, 1.0),
+            Direction::South => (-1.0, 0.0),
+            Direction::SouthWest => (-1.0, -1.0),
+            Direction::West => (0.0, -1.0),
+            Direction::NorthWest => (1.0, -1.0),
+        }
+    }
+}
+
+/// Encodes a coordinate into a geohash of `precision` characters.
+pub fn encode(lat: f64, lon: f64, precision: usize) -> Result<String, GeohashError> {
+    if precision == 0 || precision > MAX_PRECISION {
+        return Err(GeohashError::InvalidPrecision(precision));
+    }
+    let point = GeoPoint::new(lat, lon);
+    if !point.is_valid() {
+        return Err(GeohashError::InvalidCoordinate(point));
+    }
+
+    let (mut lat_range, mut lon_range) = ((-90.0, 90.0), (-180.0, 180.0));
+    let mut hash = String::with_capacity(precision);
+    let mut even_bit = true;
+
+    for _ in 0..precision {
+        let mut index = 0usize;
+        for _ in 0..5 {
+            let (range, value): (&mut (f64, f64), f64) = if even_bit {
+                (&mut lon_range, lon)
+            } else {
+                (&mut lat_range, lat)
+            };
+            let mid = (range.0 + range.1) / 2.0;
+            index <<= 1;
+            if value >= mid {
+                index |= 1;
+                range.0 = mid;
+            } else {
+                range.1 = mid;
+            }
+            even_bit = !even_bit;
+        }
+        hash.push(BASE32[index] as char);
+    }
+
+    Ok(hash)
+}
+
+/// Returns the cell covered by `hash`.
+pub fn bounding_box(hash: &str) -> Result<BoundingBox, GeohashError> {
+    if hash.is_empty() || hash.len() > MAX_PRECISION {
+        return Err(GeohashError::InvalidLength(hash.len()));
+    }
+
+    let (mut lat_range, mut lon_range) = ((-90.0, 90.0), (-180.0, 180.0));
+    let mut even_bit = true;
+
+    for c in hash.chars() {
+        let index = match DECODE_TABLE.get(c as usize) {
+            Some(&index) if index != 0xff => index,
+            _ => return Err(GeohashError::InvalidCharacter(c)),
+        };
+        for bit in (0..5).rev() {
+            let range: &mut (f64, f64) = if even_bit {
+                &mut lon_range
+            } else {
+                &mut lat_range
+            };
+            let mid = (range.0 + range.1) / 2.0;
+            if (index >> bit) & 1 == 1 {
+                range.0 = mid;
+            } else {
+                range.1 = mid;
+            }
+            even_bit = !even_bit;
+        }
+    }
+
+    Ok(BoundingBox::new(
+        lat_range.0,
+        lon_range.0,
+        lat_range.1,
+        lon_range.1,
+    ))
+}
+
+/// Decodes `hash` into the center of its cell.
+pub fn decode(hash: &str) -> Result<GeoPoint, GeohashError> {
+    bounding_box(hash).map(|bbox| bbox.center())
+}
+
+/// Returns the cell adjacent to `hash` in `direction`, at the same precision.
+///
+/// Longitudes wrap around the antimeridian, there is no cell beyond the poles
+/// so `None` is returned when moving north of the northernmost row or south of
+/// the southernmost one.
+pub fn neighbor(hash: &str, direction: Direction) -> Result<Option<String>, GeohashError> {
+    let bbox = bounding_box(hash)?;
+    let center = bbox.center();
+    let (lat_offset, lon_offset) = direction.offsets();
+
+    let lat = center.lat + lat_offset * (bbox.max_lat - bbox.min_lat);
+    if !(-90.0..=90.0).contains(&lat) {
+        return Ok(None);
+    }
+    let mut lon = center.lon + lon_offset * (bbox.max_lon - bbox.min_lon);
+    if lon > 180.0 {
+        lon -= 360.0;
+    } else if lon < -180.0 {
+        lon += 360.0;
+    }
+
+    encode(lat, lon, hash.len()).map(Some)
+}
+
+/// Returns the existing cells around `hash`, clockwise starting from north.
+///
+/// This is 8 cells everywhere but in the rows touching the poles.
+pub fn neighbors(hash: &str) -> Result<Vec<String>, GeohashError> {
+    let mut cells = Vec::with_capacity(8);
+    for direction in Direction::ALL {
+        if let Some(cell) = neighbor(hash, direction)? {
+            cells.push(cell);
+        }
+    }
+    Ok(cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode(57.64911, 10.40744, 11).unwrap(), "u4pruydqqvj");
+        assert_eq!(encode(42.6, -5.6, 5).unwrap(), "ezs42");
+        assert_eq!(encode(-90.0, -180.0, 4).unwrap(), "0000");
+        assert_eq!(encode(90.0, 180.0, 4).unwrap(), "zzzz");
+
+        assert_eq!(
+            encode(1.0, 1.0, 0).unwrap_err(),
+            GeohashError::InvalidPrecision(0)
+        );
+        assert!(encode(91.0, 1.0, 5).is_err());
+        assert!(encode(1.0, -180.5, 5).is_err());
+    }
+
+    #[test]
+    fn test_decode() {
+        let point = decode("ezs42").unwrap();
+        assert!((point.lat - 42.6).abs() < 0.03);
+        assert!((point.lon - -5.6).abs() < 0.03);
+
+        let bbox = bounding_box("ezs42").unwrap();
+        assert!(bbox.min_lat <= 42.6 && 42.6 <= bbox.max_lat);
+        assert!(bbox.min_lon <= -5.6 && -5.6 <= bbox.max_lon);
+        assert_eq!(bbox.max_lat - bbox.min_lat, 180.0 / 4096.0);
+        assert_eq!(bbox.max_lon - bbox.min_lon, 360.0 / 8192.0);
+
+        assert_eq!(
+            decode("ezsa2").unwrap_err(),
+            GeohashError::InvalidCharacter('a')
+        );
+        assert_eq!(decode("").unwrap_err(), GeohashError::InvalidLength(0));
+        assert!(decode("0123456789bcd").is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for &(lat, lon) in &[(0.0, 0.0), (-33.8688, 151.2093), (39.9042, 116.4074)] {
+            for precision in 1..=MAX_PRECISION {
+                let hash = encode(lat, lon, precision).unwrap();
+                let bbox = bounding_box(&hash).unwrap();
+                assert!(bbox.min_lat <= lat && lat <= bbox.max_lat);
+                assert!(bbox.min_lon <= lon && lon <= bbox.max_lon);
+                assert_eq!(
+                    encode(bbox.center().lat, bbox.center().lon, precision).unwrap(),
+                    hash
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_neighbors() {
+        assert_eq!(
+            neighbors("dqcjq").unwrap(),
+            vec!["dqcjw", "dqcjx", "dqcjr", "dqcjp", "dqcjn", "dqcjj", "dqcjm", "dqcjt"]
+        );
+
+        // Wrapping around the antimeridian.
+        assert_eq!(neighbor("0", Direction::West).unwrap().unwrap(), "p");
+        assert_eq!(neighbor("p", Direction::East).unwrap().unwrap(), "0");
+
+        // Nothing beyond the poles.
+        assert_eq!(neighbor("zzzz", Direction::North).unwrap(), None);
+        assert_eq!(neighbors("zzzz").unwrap().len(), 5);
+        assert_eq!(neighbors("0000").unwrap().len(), 5);
+    }
+}
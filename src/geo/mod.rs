@@ -0,0 +1,288 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Geographic primitives shared by geo aggregations and filters.
+
+pub mod geohash;
+
+use core::f64::consts::FRAC_PI_2;
+use libm::asin;
+use libm::atan2;
+use libm::cos;
+use libm::sin;
+use libm::sqrt;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Mean earth radius in meters, the same value Elasticsearch uses.
+pub const EARTH_MEAN_RADIUS_METERS: f64 = 6_371_008.771_4;
+
+/// Great-circle distance between two points in meters, using the haversine
+/// formula on a spherical earth.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::geo::{haversine_distance, GeoPoint};
+///
+/// let paris = GeoPoint::new(48.8566, 2.3522);
+/// let london = GeoPoint::new(51.5074, -0.1278);
+/// let km = haversine_distance(paris, london) / 1000.0;
+/// assert!((km - 343.5).abs() < 1.0);
+/// ```
+pub fn haversine_distance(a: GeoPoint, b: GeoPoint) -> f64 {
+    let (lat1, lat2) = (a.lat.to_radians(), b.lat.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (b.lon - a.lon).to_radians();
+
+    let sin_lat = sin(d_lat / 2.0);
+    let sin_lon = sin(d_lon / 2.0);
+    let h = sin_lat * sin_lat + cos(lat1) * cos(lat2) * sin_lon * sin_lon;
+
+    2.0 * EARTH_MEAN_RADIUS_METERS * atan2(sqrt(h), sqrt(1.0 - h))
+}
+
+/// Normalizes a longitude into `[-180, 180]`.
+fn normalize_lon(lon: f64) -> f64 {
+    if lon > 180.0 {
+        lon - 360.0
+    } else if lon < -180.0 {
+        lon + 360.0
+    } else {
+        lon
+    }
+}
+
+/// A point on the globe, in degrees.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct GeoPoint {
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl GeoPoint {
+    pub const fn new(lat: f64, lon: f64) -> Self {
+        Self { lat, lon }
+    }
+
+    /// Returns `true` if the latitude is within `[-90, 90]` and the
+    /// longitude within `[-180, 180]`.
+    pub fn is_valid(&self) -> bool {
+        (-90.0..=90.0).contains(&self.lat) && (-180.0..=180.0).contains(&self.lon)
+    }
+}
+
+/// An axis-aligned box on the globe, in degrees.
+///
+/// A box with `min_lon > max_lon` crosses the antimeridian, e.g. the box
+/// from `170` to `-170` covers 20 degrees of longitude around `180`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Deserialize, Serialize)]
+pub struct BoundingBox {
+    pub min_lat: f64,
+    pub min_lon: f64,
+    pub max_lat: f64,
+    pub max_lon: f64,
+}
+
+impl BoundingBox {
+    pub const fn new(min_lat: f64, min_lon: f64, max_lat: f64, max_lon: f64) -> Self {
+        Self {
+            min_lat,
+            min_lon,
+            max_lat,
+            max_lon,
+        }
+    }
+
+    /// The smallest box containing all points within `radius_meters` of
+    /// `center`.
+    pub fn from_point_radius(center: GeoPoint, radius_meters: f64) -> Self {
+        BoundingBox::new(center.lat, center.lon, center.lat, center.lon)
+            .expand_by_radius(radius_meters)
+    }
+
+    /// Returns `true` if the box crosses the antimeridian.
+    pub fn crosses_antimeridian(&self) -> bool {
+        self.min_lon > self.max_lon
+    }
+
+    /// The center of the box.
+    pub fn center(&self) -> GeoPoint {
+        let mut max_lon = self.max_lon;
+        if self.crosses_antimeridian() {
+            max_lon += 360.0;
+        }
+        GeoPoint::new(
+            (self.min_lat + self.max_lat) / 2.0,
+            normalize_lon((self.min_lon + max_lon) / 2.0),
+        )
+    }
+
+    fn contains_lon(&self, lon: f64) -> bool {
+        if self.crosses_antimeridian() {
+            lon >= self.min_lon || lon <= self.max_lon
+        } else {
+            self.min_lon <= lon && lon <= self.max_lon
+        }
+    }
+
+    /// Returns `true` if `point` lies within the box, edges included.
+    pub fn contains(&self, point: GeoPoint) -> bool {
+        self.min_lat <= point.lat && point.lat <= self.max_lat && self.contains_lon(point.lon)
+    }
+
+    /// Returns `true` if the two boxes share at least one point.
+    pub fn intersects(&self, other: &BoundingBox) -> bool {
+        if self.max_lat < other.min_lat || other.max_lat < self.min_lat {
+            return false;
+        }
+        self.contains_lon(other.min_lon)
+            || self.contains_lon(other.max_lon)
+            || other.contains_lon(self.min_lon)
+            || other.contains_lon(self.max_lon)
+    }
+
+    /// Grows the box so that it contains every point within `radius_meters`
+    /// of the original box.
+    ///
+    /// The result is conservative: it may contain points slightly farther
+    /// away, but never misses a point within the radius. Boxes reaching a
+    /// pole cover all longitudes.
+    pub fn expand_by_radius(&self, radius_meters: f64) -> BoundingBox {
+        let radius = radius_meters / EARTH_MEAN_RADIUS_METERS;
+        let min_lat = self.min_lat.to_radians() - radius;
+        let max_lat = self.max_lat.to_radians() + radius;
+
+        if min_lat <= -FRAC_PI_2 || max_lat >= FRAC_PI_2 {
+            return BoundingBox::new(
+                min_lat.max(-FRAC_PI_2).to_degrees(),
+                -180.0,
+                max_lat.min(FRAC_PI_2).to_degrees(),
+                180.0,
+            );
+        }
+
+        // The longitude delta is the widest at the latitude closest to a pole.
+        let widest_lat = self.min_lat.abs().max(self.max_lat.abs()).to_radians();
+        let ratio = sin(radius) / cos(widest_lat);
+        let mut span = self.max_lon - self.min_lon;
+        if self.crosses_antimeridian() {
+            span += 360.0;
+        }
+        if ratio >= 1.0 {
+            return BoundingBox::new(min_lat.to_degrees(), -180.0, max_lat.to_degrees(), 180.0);
+        }
+        let d_lon = asin(ratio).to_degrees();
+        if span + 2.0 * d_lon >= 360.0 {
+            return BoundingBox::new(min_lat.to_degrees(), -180.0, max_lat.to_degrees(), 180.0);
+        }
+
+        BoundingBox::new(
+            min_lat.to_degrees(),
+            normalize_lon(self.min_lon - d_lon),
+            max_lat.to_degrees(),
+            normalize_lon(self.max_lon + d_lon),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_haversine_distance() {
+        let a = GeoPoint::new(40.7486, -73.9864);
+        assert_eq!(haversine_distance(a, a), 0.0);
+
+        // One degree of latitude.
+        let d = haversine_distance(GeoPoint::new(0.0, 0.0), GeoPoint::new(1.0, 0.0));
+        assert!((d - 111_195.08).abs() < 1.0);
+
+        // Across the antimeridian is short.
+        let d = haversine_distance(GeoPoint::new(0.0, 179.5), GeoPoint::new(0.0, -179.5));
+        assert!((d - 111_195.08).abs() < 1.0);
+
+        // Antipodes.
+        let d = haversine_distance(GeoPoint::new(0.0, 0.0), GeoPoint::new(0.0, 180.0));
+        assert!((d - core::f64::consts::PI * EARTH_MEAN_RADIUS_METERS).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_contains_and_intersects() {
+        let bbox = BoundingBox::new(10.0, 20.0, 30.0, 40.0);
+        assert!(bbox.contains(GeoPoint::new(10.0, 40.0)));
+        assert!(!bbox.contains(GeoPoint::new(31.0, 30.0)));
+        assert!(bbox.intersects(&BoundingBox::new(25.0, 35.0, 50.0, 50.0)));
+        assert!(bbox.intersects(&BoundingBox::new(0.0, 0.0, 50.0, 50.0)));
+        assert!(!bbox.intersects(&BoundingBox::new(31.0, 20.0, 50.0, 40.0)));
+        assert!(!bbox.intersects(&BoundingBox::new(10.0, 41.0, 30.0, 50.0)));
+
+        let pacific = BoundingBox::new(-10.0, 170.0, 10.0, -170.0);
+        assert!(pacific.crosses_antimeridian());
+        assert!(pacific.contains(GeoPoint::new(0.0, 180.0)));
+        assert!(pacific.contains(GeoPoint::new(0.0, -175.0)));
+        assert!(!pacific.contains(GeoPoint::new(0.0, 0.0)));
+        assert_eq!(pacific.center(), GeoPoint::new(0.0, 180.0));
+        assert!(pacific.intersects(&BoundingBox::new(-5.0, -175.0, 5.0, -160.0)));
+        assert!(!pacific.intersects(&bbox));
+    }
+
+    #[test]
+    fn test_expand_by_radius() {
+        let center = GeoPoint::new(45.0, 10.0);
+        let bbox = BoundingBox::from_point_radius(center, 10_000.0);
+        assert!(bbox.contains(center));
+
+        // Every point 10km away in any direction lies within the box.
+        for bearing in 0..360 {
+            let bearing = (bearing as f64).to_radians();
+            let point = GeoPoint::new(
+                center.lat + 0.0899 * cos(bearing),
+                center.lon + 0.0899 * sin(bearing) / cos(center.lat.to_radians()),
+            );
+            assert!(haversine_distance(center, point) <= 10_000.0);
+            assert!(bbox.contains(point), "{:?} not in {:?}", point, bbox);
+        }
+
+        let polar = BoundingBox::from_point_radius(GeoPoint::new(89.99, 0.0), 10_000.0);
+        assert_eq!(
+            (polar.min_lon, polar.max_lon, polar.max_lat),
+            (-180.0, 180.0, 90.0)
+        );
+
+        let dateline = BoundingBox::from_point_radius(GeoPoint::new(0.0, 179.99), 10_000.0);
+        assert!(dateline.crosses_antimeridian());
+        assert!(dateline.contains(GeoPoint::new(0.0, -179.99)));
+    }
+
+    #[test]
+    fn test_serde() {
+        let bbox = BoundingBox::new(1.5, 2.0, 3.0, 4.0);
+        let json = serde_json::to_string(&bbox).unwrap();
+        assert_eq!(
+            json,
+            r#"{"min_lat":1.5,"min_lon":2.0,"max_lat":3.0,"max_lon":4.0}"#
+        );
+        assert_eq!(serde_json::from_str::<BoundingBox>(&json).unwrap(), bbox);
+    }
+}
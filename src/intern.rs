@@ -0,0 +1,388 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! String interning on top of [`ByteArena`].
+
+use crate::arena::ByteArena;
+use crate::mem::HeapSize;
+use alloc::vec::Vec;
+use core::fmt;
+use hashbrown::HashMap;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// A compact id for a string in a [`StringInterner`].
+///
+/// Symbols are handed out sequentially from zero, so they can double as
+/// indices into side tables.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    rkyv(derive(Debug, Hash, PartialEq, Eq), compare(PartialEq))
+)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    pub fn from_u32(id: u32) -> Self {
+        Symbol(id)
+    }
+
+    pub fn as_u32(self) -> u32 {
+        self.0
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// Deduplicates strings and maps each distinct one to a [`Symbol`].
+///
+/// The strings are stored back to back in a [`ByteArena`], so interning
+/// many short terms costs one copy of each instead of a `String` apiece.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::intern::StringInterner;
+///
+/// let mut interner = StringInterner::new();
+/// let apple = interner.intern("apple");
+/// let pear = interner.intern("pear");
+/// assert_eq!(interner.intern("apple"), apple);
+/// assert_ne!(apple, pear);
+/// assert_eq!(interner.resolve(pear), "pear");
+/// assert_eq!(interner.len(), 2);
+/// ```
+pub struct StringInterner {
+    // The `'static` lifetimes are a lie, the strings live in `arena`, which
+    // never moves them and is only reset together with these tables.
+    symbols: HashMap<&'static str, Symbol>,
+    strings: Vec<&'static str>,
+    arena: ByteArena,
+}
+
+impl fmt::Debug for StringInterner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.strings.iter()).finish()
+    }
+}
+
+impl Default for StringInterner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StringInterner {
+    pub fn new() -> Self {
+        Self::with_chunk_capacity(4096)
+    }
+
+    /// Creates an interner whose first arena chunk holds `capacity` bytes.
+    pub fn with_chunk_capacity(capacity: usize) -> Self {
+        Self {
+            symbols: HashMap::new(),
+            strings: Vec::new(),
+            arena: ByteArena::new(capacity, usize::MAX),
+        }
+    }
+
+    /// Returns the symbol of `s`, storing it on first use.
+    ///
+    /// # Panics
+    ///
+    /// Panics if more than `u32::MAX` distinct strings are interned.
+    pub fn intern(&mut self, s: &str) -> Symbol {
+        if let Some(&symbol) = self.symbols.get(s) {
+            return symbol;
+        }
+        let symbol = Symbol(u32::try_from(self.strings.len()).expect("too many interned strings"));
+        let stored: &str = self.arena.alloc_str(s).unwrap();
+        // SAFETY: See the field comment, the string is never handed out for
+        // longer than a borrow of `self`.
+        let stored: &'static str = unsafe { &*(stored as *const str) };
+        self.symbols.insert(stored, symbol);
+        self.strings.push(stored);
+        symbol
+    }
+
+    /// Returns the symbol of `s` if it has been interned.
+    pub fn get(&self, s: &str) -> Option<Symbol> {
+        self.symbols.get(s).copied()
+    }
+
+    /// Returns the string of `symbol`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the symbol was not handed out by this interner.
+    pub fn resolve(&self, symbol: Symbol) -> &str {
+        self.strings[symbol.0 as usize]
+    }
+
+    /// Returns the string of `symbol`, or `None` for an unknown symbol.
+    pub fn try_resolve(&self, symbol: Symbol) -> Option<&str> {
+        self.strings.get(symbol.0 as usize).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strings.is_empty()
+    }
+
+    /// Iterates over all symbols and their strings in symbol order.
+    pub fn iter(&self) -> impl Iterator<Item = (Symbol, &str)> + '_ {
+        self.strings
+            .iter()
+            .enumerate()
+            .map(|(i, s)| (Symbol(i as u32), *s))
+    }
+
+    /// Forgets all strings, symbols are handed out from zero again.
+    pub fn clear(&mut self) {
+        self.symbols.clear();
+        self.strings.clear();
+        self.arena.reset();
+    }
+}
+
+impl HeapSize for StringInterner {
+    fn heap_size(&self) -> usize {
+        // The map and list only point into the arena.
+        self.symbols.capacity() * size_of::<(&str, Symbol)>()
+            + self.strings.capacity() * size_of::<&str>()
+            + self.arena.heap_size()
+    }
+}
+
+/// Borsh encodes the strings in symbol order, the same layout as a
+/// `Vec<String>`. Deserializing re-interns them, so symbols are preserved.
+#[cfg(feature = "borsh")]
+impl borsh::BorshSerialize for StringInterner {
+    fn serialize<W: borsh::io::Write>(&self, writer: &mut W) -> borsh::io::Result<()> {
+        // Fits, symbols are `u32`s.
+        borsh::BorshSerialize::serialize(&(self.strings.len() as u32), writer)?;
+        for s in &self.strings {
+            borsh::BorshSerialize::serialize(*s, writer)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "borsh")]
+impl borsh::BorshDeserialize for StringInterner {
+    fn deserialize_reader<R: borsh::io::Read>(reader: &mut R) -> borsh::io::Result<Self> {
+        let count: u32 = borsh::BorshDeserialize::deserialize_reader(reader)?;
+        let mut interner = StringInterner::new();
+        for _ in 0..count {
+            let s: alloc::string::String = borsh::BorshDeserialize::deserialize_reader(reader)?;
+            let len = interner.len();
+            interner.intern(&s);
+            if interner.len() == len {
+                return Err(borsh::io::Error::new(
+                    borsh::io::ErrorKind::InvalidData,
+                    "an interned string appears twice",
+                ));
+            }
+        }
+        Ok(interner)
+    }
+}
+
+/// Archived as the strings in symbol order, the same layout as a
+/// `Vec<String>`. Deserializing re-interns them, so symbols are preserved.
+#[cfg(feature = "rkyv")]
+impl rkyv::Archive for StringInterner {
+    type Archived = rkyv::vec::ArchivedVec<rkyv::string::ArchivedString>;
+    type Resolver = rkyv::vec::VecResolver;
+
+    fn resolve(&self, resolver: Self::Resolver, out: rkyv::Place<Self::Archived>) {
+        rkyv::vec::ArchivedVec::resolve_from_len(self.strings.len(), resolver, out);
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<S> rkyv::Serialize<S> for StringInterner
+where
+    S: rkyv::rancor::Fallible + rkyv::ser::Allocator + rkyv::ser::Writer + ?Sized,
+    S::Error: rkyv::rancor::Source,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        rkyv::vec::ArchivedVec::serialize_from_iter::<ArchivedStr, _, _>(
+            self.strings.iter().copied().map(ArchivedStr),
+            serializer,
+        )
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<D> rkyv::Deserialize<StringInterner, D>
+    for rkyv::vec::ArchivedVec<rkyv::string::ArchivedString>
+where
+    D: rkyv::rancor::Fallible + ?Sized,
+    D::Error: rkyv::rancor::Source,
+{
+    fn deserialize(&self, _deserializer: &mut D) -> Result<StringInterner, D::Error> {
+        let mut interner = StringInterner::new();
+        for s in self.iter() {
+            let len = interner.len();
+            interner.intern(s.as_str());
+            if interner.len() == len {
+                return Err(rkyv::rancor::Source::new(DuplicateString));
+            }
+        }
+        Ok(interner)
+    }
+}
+
+/// Serializes an interned string as an `ArchivedString`.
+#[cfg(feature = "rkyv")]
+struct ArchivedStr(&'static str);
+
+#[cfg(feature = "rkyv")]
+impl rkyv::Archive for ArchivedStr {
+    type Archived = rkyv::string::ArchivedString;
+    type Resolver = rkyv::string::StringResolver;
+
+    fn resolve(&self, resolver: Self::Resolver, out: rkyv::Place<Self::Archived>) {
+        rkyv::string::ArchivedString::resolve_from_str(self.0, resolver, out);
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl<S> rkyv::Serialize<S> for ArchivedStr
+where
+    S: rkyv::rancor::Fallible + rkyv::ser::Writer + ?Sized,
+    S::Error: rkyv::rancor::Source,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<Self::Resolver, S::Error> {
+        rkyv::string::ArchivedString::serialize_from_str(self.0, serializer)
+    }
+}
+
+#[cfg(feature = "rkyv")]
+#[derive(Debug)]
+struct DuplicateString;
+
+#[cfg(feature = "rkyv")]
+impl fmt::Display for DuplicateString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an interned string appears twice")
+    }
+}
+
+#[cfg(feature = "rkyv")]
+impl core::error::Error for DuplicateString {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+    use alloc::string::String;
+
+    #[test]
+    fn test_intern_and_resolve() {
+        let mut interner = StringInterner::with_chunk_capacity(8);
+        let words: Vec<String> = (0..100).map(|i| format!("term-{}", i % 40)).collect();
+        let symbols: Vec<Symbol> = words.iter().map(|w| interner.intern(w)).collect();
+
+        assert_eq!(interner.len(), 40);
+        assert_eq!(symbols[0], symbols[40]);
+        assert_eq!(symbols[39].as_u32(), 39);
+        for (word, symbol) in words.iter().zip(&symbols) {
+            assert_eq!(interner.resolve(*symbol), word);
+            assert_eq!(interner.get(word), Some(*symbol));
+        }
+        assert_eq!(interner.get("missing"), None);
+        assert_eq!(interner.try_resolve(Symbol::from_u32(40)), None);
+        assert_eq!(interner.iter().nth(3), Some((Symbol(3), "term-3")));
+
+        let empty = interner.intern("");
+        assert_eq!(interner.resolve(empty), "");
+
+        interner.clear();
+        assert!(interner.is_empty());
+        assert_eq!(interner.intern("term-5"), Symbol(0));
+    }
+
+    #[cfg(feature = "borsh")]
+    #[test]
+    fn test_borsh_round_trip() {
+        use alloc::vec;
+
+        let mut interner = StringInterner::new();
+        for s in ["apple", "", "pear"] {
+            interner.intern(s);
+        }
+        let bytes = borsh::to_vec(&interner).unwrap();
+        assert_eq!(
+            borsh::from_slice::<Vec<String>>(&bytes).unwrap(),
+            ["apple", "", "pear"]
+        );
+        let restored = borsh::from_slice::<StringInterner>(&bytes).unwrap();
+        assert_eq!(restored.len(), 3);
+        for (symbol, s) in interner.iter() {
+            assert_eq!(restored.resolve(symbol), s);
+        }
+
+        let duplicates = borsh::to_vec(&vec!["apple", "apple"]).unwrap();
+        assert!(borsh::from_slice::<StringInterner>(&duplicates).is_err());
+    }
+
+    #[cfg(feature = "rkyv")]
+    #[test]
+    fn test_rkyv_round_trip() {
+        use crate::serde_ext::archive;
+        use alloc::vec;
+
+        let mut interner = StringInterner::new();
+        let long = "a string too long to be stored inline";
+        for s in ["apple", "", long, "pear"] {
+            interner.intern(s);
+        }
+        let bytes = archive::to_bytes(&interner).unwrap();
+        let archived = archive::access::<StringInterner>(&bytes).unwrap();
+        assert_eq!(archived.len(), 4);
+        assert_eq!(archived[2].as_str(), long);
+
+        let restored = archive::from_bytes::<StringInterner>(&bytes).unwrap();
+        assert_eq!(restored.len(), 4);
+        for (symbol, s) in interner.iter() {
+            assert_eq!(restored.resolve(symbol), s);
+        }
+
+        let duplicates: Vec<String> = vec!["apple".into(), "apple".into()];
+        let bytes = archive::to_bytes(&duplicates).unwrap();
+        assert!(archive::from_bytes::<StringInterner>(&bytes).is_err());
+    }
+}
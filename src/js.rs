@@ -0,0 +1,54 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Shims for `wasm32-unknown-unknown` hosted by a JavaScript runtime, where
+//! the standard library has no clock. Randomness comes from the `js`
+//! features of `getrandom` and `uuid`.
+
+use js_sys::Function;
+use js_sys::Reflect;
+use wasm_bindgen::JsCast;
+use wasm_bindgen::JsValue;
+
+fn property(target: &JsValue, name: &str) -> Option<JsValue> {
+    Reflect::get(target, &JsValue::from_str(name))
+        .ok()
+        .filter(|value| !value.is_undefined() && !value.is_null())
+}
+
+/// Milliseconds since the Unix epoch from `performance.timeOrigin +
+/// performance.now()`, which browsers and Node.js provide with sub-millisecond
+/// resolution, falling back to `Date.now()`.
+pub(crate) fn now_millis() -> i64 {
+    let from_performance = || {
+        let performance = property(&js_sys::global(), "performance")?;
+        let origin = property(&performance, "timeOrigin")?.as_f64()?;
+        let now = property(&performance, "now")?
+            .dyn_into::<Function>()
+            .ok()?
+            .call0(&performance)
+            .ok()?
+            .as_f64()?;
+        Some(origin + now)
+    };
+    from_performance().unwrap_or_else(js_sys::Date::now) as i64
+}
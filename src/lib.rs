@@ -22,7 +22,44 @@
 
 #![no_std]
 extern crate alloc;
-#[cfg(test)]
+// Lets derive macros refer to `::pizza_common` from within this crate.
+extern crate self as pizza_common;
+#[cfg(any(test, feature = "std"))]
 extern crate std; // use the standard library for tests
 pub mod arena;
+pub mod bytes;
+pub mod collections;
+pub mod error;
+#[cfg(feature = "std")]
+pub mod fsutil;
+pub mod geo;
+pub mod intern;
+#[cfg(all(target_arch = "wasm32", target_os = "unknown", feature = "js"))]
+mod js;
+pub mod mem;
+pub mod net;
+pub mod routing;
+pub mod serde_ext;
+pub mod store;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod text;
 pub mod utils;
+pub mod vector;
+
+#[cfg(all(target_arch = "wasm32", target_os = "unknown", not(feature = "js")))]
+compile_error!(
+    "pizza-common needs the `js` feature on wasm32-unknown-unknown for randomness and time"
+);
+
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::collections::BTreeMap;
+    pub use alloc::collections::BTreeSet;
+    pub use alloc::string::String;
+    pub use hashbrown::HashMap;
+    pub use hashbrown::HashSet;
+    pub use serde;
+    pub use serde_json::Map;
+    pub use serde_json::Value;
+}
@@ -0,0 +1,500 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Memory introspection.
+//!
+//! [`HeapSize`] reports how many bytes a value owns on the heap, and
+//! [`MemoryReport`] breaks a structure down into a serializable tree of
+//! per-component sizes, to answer "where is my memory" from a stats
+//! endpoint. With the `derive` feature, `#[derive(HeapSize)]` implements
+//! the trait for structs and enums field by field. [`MemSize`] is the
+//! deep size used for memory budgets.
+//!
+//! Sizes of hash tables and B-trees are estimates derived from their
+//! capacity or length, as their exact layout is private to the standard
+//! library. Values behind `Rc` and `Arc` are counted in full by every
+//! owner.
+
+mod report;
+
+#[cfg(feature = "derive")]
+pub use pizza_common_derive::HeapSize;
+pub use report::MemoryReport;
+
+use alloc::borrow::Cow;
+use alloc::borrow::ToOwned;
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::collections::BTreeSet;
+use alloc::collections::BinaryHeap;
+use alloc::collections::VecDeque;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::cell::RefCell;
+use core::marker::PhantomData;
+use core::mem::size_of;
+use core::mem::size_of_val;
+
+/// Memory owned by a value.
+///
+/// Implementations only provide [`heap_size`](HeapSize::heap_size), the
+/// bytes allocated on behalf of the value. The size of the value itself is
+/// its [`shallow_size`](HeapSize::shallow_size) and both add up to the
+/// [`deep_size`](HeapSize::deep_size).
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::mem::HeapSize;
+///
+/// let names = vec![String::from("margherita"), String::with_capacity(32)];
+/// assert_eq!(names.shallow_size(), 3 * std::mem::size_of::<usize>());
+/// assert_eq!(names.heap_size(), 2 * std::mem::size_of::<String>() + 10 + 32);
+/// ```
+pub trait HeapSize {
+    /// Bytes allocated on the heap and owned by this value.
+    fn heap_size(&self) -> usize;
+
+    /// Bytes of the value itself, not counting what it points to.
+    fn shallow_size(&self) -> usize {
+        size_of_val(self)
+    }
+
+    /// The shallow size plus the heap size.
+    fn deep_size(&self) -> usize {
+        self.shallow_size() + self.heap_size()
+    }
+
+    /// Describes the memory of this value, broken down by component where
+    /// the implementation knows its structure.
+    fn report(&self, name: &str) -> MemoryReport {
+        MemoryReport::new(name, self.deep_size())
+    }
+}
+
+/// Total memory charged for a value against a memory budget, such as the
+/// `max_memory_bytes` of an [`Arena`](crate::arena::Arena).
+///
+/// Every [`HeapSize`] type implements it with its
+/// [`deep_size`](HeapSize::deep_size), so `#[derive(HeapSize)]` is the way
+/// to make a type budgetable.
+pub trait MemSize {
+    fn mem_size(&self) -> usize;
+}
+
+impl<T: HeapSize + ?Sized> MemSize for T {
+    fn mem_size(&self) -> usize {
+        self.deep_size()
+    }
+}
+
+macro_rules! impl_no_heap {
+    ($($ty:ty),* $(,)?) => {
+        $(
+            impl HeapSize for $ty {
+                fn heap_size(&self) -> usize {
+                    0
+                }
+            }
+        )*
+    };
+}
+
+impl_no_heap!(
+    (),
+    bool,
+    char,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+    str,
+    core::time::Duration,
+    crate::utils::uuid::Uuid,
+    crate::utils::sequencer::Sequencer,
+    crate::utils::sequencer::Sequencer64,
+    crate::utils::sequencer::AtomicSequencer,
+);
+
+impl<T: ?Sized> HeapSize for PhantomData<T> {
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
+/// References do not own what they point to.
+impl<T: ?Sized> HeapSize for &T {
+    fn heap_size(&self) -> usize {
+        0
+    }
+}
+
+impl<T: HeapSize> HeapSize for [T] {
+    fn heap_size(&self) -> usize {
+        self.iter().map(HeapSize::heap_size).sum()
+    }
+}
+
+impl<T: HeapSize, const N: usize> HeapSize for [T; N] {
+    fn heap_size(&self) -> usize {
+        self.as_slice().heap_size()
+    }
+}
+
+impl<T: HeapSize + ?Sized> HeapSize for Box<T> {
+    fn heap_size(&self) -> usize {
+        (**self).deep_size()
+    }
+}
+
+impl HeapSize for String {
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Vec<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<T>() + self.as_slice().heap_size()
+    }
+}
+
+impl<T: HeapSize> HeapSize for VecDeque<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<T>() + self.iter().map(HeapSize::heap_size).sum::<usize>()
+    }
+}
+
+impl<T: HeapSize> HeapSize for BinaryHeap<T> {
+    fn heap_size(&self) -> usize {
+        self.capacity() * size_of::<T>() + self.iter().map(HeapSize::heap_size).sum::<usize>()
+    }
+}
+
+/// Counts the entries, not the overhead of B-tree nodes.
+impl<K: HeapSize, V: HeapSize> HeapSize for BTreeMap<K, V> {
+    fn heap_size(&self) -> usize {
+        self.iter()
+            .map(|(k, v)| size_of::<(K, V)>() + k.heap_size() + v.heap_size())
+            .sum()
+    }
+}
+
+/// Counts the entries, not the overhead of B-tree nodes.
+impl<T: HeapSize> HeapSize for BTreeSet<T> {
+    fn heap_size(&self) -> usize {
+        self.iter().map(|v| size_of::<T>() + v.heap_size()).sum()
+    }
+}
+
+/// Bytes of a SwissTable with `capacity` usable slots of `entry_size`
+/// bytes: the buckets plus one control byte per bucket and a trailing
+/// group of control bytes.
+fn hash_table_size(capacity: usize, entry_size: usize) -> usize {
+    if capacity == 0 {
+        return 0;
+    }
+    let buckets = if capacity < 8 {
+        (capacity + 1).next_power_of_two()
+    } else {
+        (capacity * 8 / 7).next_power_of_two()
+    };
+    buckets * (entry_size + 1) + 16
+}
+
+impl<K: HeapSize, V: HeapSize, S> HeapSize for hashbrown::HashMap<K, V, S> {
+    fn heap_size(&self) -> usize {
+        hash_table_size(self.capacity(), size_of::<(K, V)>())
+            + self
+                .iter()
+                .map(|(k, v)| k.heap_size() + v.heap_size())
+                .sum::<usize>()
+    }
+}
+
+impl<T: HeapSize, S> HeapSize for hashbrown::HashSet<T, S> {
+    fn heap_size(&self) -> usize {
+        hash_table_size(self.capacity(), size_of::<T>())
+            + self.iter().map(HeapSize::heap_size).sum::<usize>()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: HeapSize, V: HeapSize, S> HeapSize for std::collections::HashMap<K, V, S> {
+    fn heap_size(&self) -> usize {
+        hash_table_size(self.capacity(), size_of::<(K, V)>())
+            + self
+                .iter()
+                .map(|(k, v)| k.heap_size() + v.heap_size())
+                .sum::<usize>()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<T: HeapSize, S> HeapSize for std::collections::HashSet<T, S> {
+    fn heap_size(&self) -> usize {
+        hash_table_size(self.capacity(), size_of::<T>())
+            + self.iter().map(HeapSize::heap_size).sum::<usize>()
+    }
+}
+
+/// The allocation holds both reference counts next to the value.
+impl<T: HeapSize + ?Sized> HeapSize for Rc<T> {
+    fn heap_size(&self) -> usize {
+        2 * size_of::<usize>() + (**self).deep_size()
+    }
+}
+
+/// The allocation holds both reference counts next to the value.
+impl<T: HeapSize + ?Sized> HeapSize for Arc<T> {
+    fn heap_size(&self) -> usize {
+        2 * size_of::<usize>() + (**self).deep_size()
+    }
+}
+
+impl<T: HeapSize> HeapSize for Option<T> {
+    fn heap_size(&self) -> usize {
+        self.as_ref().map_or(0, HeapSize::heap_size)
+    }
+}
+
+impl<T: HeapSize, E: HeapSize> HeapSize for Result<T, E> {
+    fn heap_size(&self) -> usize {
+        match self {
+            Ok(value) => value.heap_size(),
+            Err(error) => error.heap_size(),
+        }
+    }
+}
+
+impl<T: HeapSize + Copy> HeapSize for Cell<T> {
+    fn heap_size(&self) -> usize {
+        self.get().heap_size()
+    }
+}
+
+impl<T: HeapSize> HeapSize for RefCell<T> {
+    fn heap_size(&self) -> usize {
+        self.borrow().heap_size()
+    }
+}
+
+impl<B> HeapSize for Cow<'_, B>
+where
+    B: ToOwned + ?Sized,
+    B::Owned: HeapSize,
+{
+    fn heap_size(&self) -> usize {
+        match self {
+            Cow::Borrowed(_) => 0,
+            Cow::Owned(owned) => owned.heap_size(),
+        }
+    }
+}
+
+/// Counts the viewed bytes; buffers shared between clones are counted by
+/// each of them.
+impl HeapSize for bytes::Bytes {
+    fn heap_size(&self) -> usize {
+        self.len()
+    }
+}
+
+impl HeapSize for bytes::BytesMut {
+    fn heap_size(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl HeapSize for serde_json::Value {
+    fn heap_size(&self) -> usize {
+        use serde_json::Value;
+        match self {
+            Value::Null | Value::Bool(_) | Value::Number(_) => 0,
+            Value::String(s) => s.heap_size(),
+            Value::Array(values) => values.heap_size(),
+            // serde_json's map is a B-tree without the preserve_order
+            // feature.
+            Value::Object(map) => map
+                .iter()
+                .map(|(k, v)| size_of::<(String, Value)>() + k.heap_size() + v.heap_size())
+                .sum(),
+        }
+    }
+}
+
+macro_rules! impl_tuple {
+    ($($name:ident)+) => {
+        impl<$($name: HeapSize),+> HeapSize for ($($name,)+) {
+            #[allow(non_snake_case)]
+            fn heap_size(&self) -> usize {
+                let ($($name,)+) = self;
+                0 $(+ $name.heap_size())+
+            }
+        }
+    };
+}
+
+impl_tuple!(A);
+impl_tuple!(A B);
+impl_tuple!(A B C);
+impl_tuple!(A B C D);
+impl_tuple!(A B C D E);
+impl_tuple!(A B C D E F);
+impl_tuple!(A B C D E F G);
+impl_tuple!(A B C D E F G H);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    #[test]
+    fn test_primitives_and_strings() {
+        assert_eq!(42u64.heap_size(), 0);
+        assert_eq!(42u64.deep_size(), 8);
+        assert_eq!("abc".heap_size(), 0);
+        assert_eq!("abc".shallow_size(), 3);
+        let s = String::with_capacity(100);
+        assert_eq!(s.heap_size(), 100);
+        assert_eq!(s.deep_size(), size_of::<String>() + 100);
+        assert_eq!(Box::<str>::from("abcd").heap_size(), 4);
+        assert_eq!(Box::new(7u32).heap_size(), 4);
+    }
+
+    #[test]
+    fn test_collections() {
+        let mut v: Vec<String> = Vec::with_capacity(4);
+        v.push(String::with_capacity(10));
+        assert_eq!(v.heap_size(), 4 * size_of::<String>() + 10);
+        assert_eq!(vec![[1u16; 3]; 2].heap_size(), 12);
+
+        let tuple = (
+            1u8,
+            String::with_capacity(3),
+            Some(String::with_capacity(5)),
+        );
+        assert_eq!(tuple.heap_size(), 8);
+
+        let mut map = BTreeMap::new();
+        map.insert(1u32, String::with_capacity(8));
+        assert_eq!(map.heap_size(), size_of::<(u32, String)>() + 8);
+
+        let mut table: hashbrown::HashMap<u64, u64> = hashbrown::HashMap::new();
+        assert_eq!(table.heap_size(), 0);
+        table.insert(1, 2);
+        assert!(table.heap_size() >= table.capacity() * 16);
+
+        let shared = Arc::new(String::with_capacity(16));
+        assert_eq!(
+            shared.heap_size(),
+            2 * size_of::<usize>() + size_of::<String>() + 16
+        );
+
+        let borrowed: Cow<str> = Cow::Borrowed("abc");
+        assert_eq!(borrowed.heap_size(), 0);
+        let json = serde_json::json!({"a": [1, 2], "b": "xyz"});
+        assert!(json.heap_size() > 0);
+    }
+
+    #[test]
+    fn test_hash_table_size() {
+        assert_eq!(hash_table_size(0, 8), 0);
+        assert_eq!(hash_table_size(3, 8), 4 * 9 + 16);
+        assert_eq!(hash_table_size(7, 8), 8 * 9 + 16);
+        assert_eq!(hash_table_size(14, 8), 16 * 9 + 16);
+        assert_eq!(hash_table_size(28, 8), 32 * 9 + 16);
+    }
+
+    #[cfg(feature = "derive")]
+    mod derive {
+        use super::*;
+
+        #[derive(HeapSize)]
+        struct Segment<T> {
+            name: String,
+            docs: Vec<T>,
+            #[heap_size(skip)]
+            #[allow(dead_code)]
+            shared: Arc<Vec<u8>>,
+        }
+
+        #[derive(HeapSize)]
+        enum Posting {
+            Empty,
+            Single(u32),
+            Many { ids: Vec<u32>, label: String },
+        }
+
+        #[test]
+        fn test_derive_struct() {
+            let segment = Segment {
+                name: String::with_capacity(8),
+                docs: vec![Posting::Single(1), Posting::Empty],
+                shared: Arc::new(vec![0; 1024]),
+            };
+            let docs_size = 2 * size_of::<Posting>();
+            assert_eq!(segment.heap_size(), 8 + docs_size);
+
+            let report = segment.report("segment");
+            assert_eq!(report.name(), "segment");
+            assert_eq!(report.bytes(), segment.deep_size());
+            let children: Vec<(&str, usize)> = report
+                .children()
+                .iter()
+                .map(|child| (child.name(), child.bytes()))
+                .collect();
+            assert_eq!(
+                children,
+                [
+                    ("name", size_of::<String>() + 8),
+                    ("docs", size_of::<Vec<Posting>>() + docs_size),
+                ]
+            );
+        }
+
+        #[test]
+        fn test_derive_enum() {
+            assert_eq!(Posting::Empty.heap_size(), 0);
+            assert_eq!(Posting::Single(3).heap_size(), 0);
+            let many = Posting::Many {
+                ids: Vec::with_capacity(4),
+                label: String::with_capacity(2),
+            };
+            assert_eq!(many.heap_size(), 18);
+            assert_eq!(many.report("posting").children().len(), 2);
+        }
+    }
+}
@@ -0,0 +1,125 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// A tree of memory usage by component.
+///
+/// `bytes` is the total of the component including its children. Children
+/// do not have to add up to it exactly, as padding and untracked fields are
+/// only counted in the parent.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::mem::HeapSize;
+/// use pizza_common::mem::MemoryReport;
+///
+/// let terms = vec![String::from("pizza")];
+/// let report = MemoryReport::new("index", 0)
+///     .child(terms.report("terms"))
+///     .child(MemoryReport::new("cache", 4096));
+/// assert_eq!(report.bytes(), terms.deep_size() + 4096);
+/// assert_eq!(report.find("cache").unwrap().bytes(), 4096);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemoryReport {
+    name: String,
+    bytes: usize,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    children: Vec<MemoryReport>,
+}
+
+impl MemoryReport {
+    pub fn new(name: impl Into<String>, bytes: usize) -> Self {
+        Self {
+            name: name.into(),
+            bytes,
+            children: Vec::new(),
+        }
+    }
+
+    /// Adds a child. If the children add up to more than the current total,
+    /// the total grows to match, so a report can be assembled bottom-up
+    /// starting from zero bytes.
+    pub fn child(mut self, child: MemoryReport) -> Self {
+        self.children.push(child);
+        let children_bytes = self.children.iter().map(|c| c.bytes).sum();
+        self.bytes = self.bytes.max(children_bytes);
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn bytes(&self) -> usize {
+        self.bytes
+    }
+
+    pub fn children(&self) -> &[MemoryReport] {
+        &self.children
+    }
+
+    /// Finds a descendant by a `/`-separated path of names, such as
+    /// `"segments/postings"`.
+    pub fn find(&self, path: &str) -> Option<&MemoryReport> {
+        path.split('/')
+            .filter(|name| !name.is_empty())
+            .try_fold(self, |report, name| {
+                report.children.iter().find(|child| child.name == name)
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_tree() {
+        let report = MemoryReport::new("root", 10)
+            .child(MemoryReport::new("a", 4).child(MemoryReport::new("b", 3)))
+            .child(MemoryReport::new("c", 2));
+        assert_eq!(report.bytes(), 10);
+        assert_eq!(report.find("a/b").map(MemoryReport::bytes), Some(3));
+        assert_eq!(report.find(""), Some(&report));
+        assert!(report.find("a/x").is_none());
+
+        let grown = MemoryReport::new("root", 1).child(MemoryReport::new("a", 5));
+        assert_eq!(grown.bytes(), 5);
+    }
+
+    #[test]
+    fn test_report_serde() {
+        let report = MemoryReport::new("root", 3).child(MemoryReport::new("leaf", 3));
+        let json = serde_json::to_string(&report).unwrap();
+        assert_eq!(
+            json,
+            r#"{"name":"root","bytes":3,"children":[{"name":"leaf","bytes":3}]}"#
+        );
+        assert_eq!(serde_json::from_str::<MemoryReport>(&json).unwrap(), report);
+    }
+}
@@ -0,0 +1,278 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use alloc::string::String;
+use alloc::string::ToString;
+use core::fmt;
+use core::net::IpAddr;
+use core::net::Ipv4Addr;
+use core::net::Ipv6Addr;
+use core::str::FromStr;
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CidrError {
+    InvalidAddress(String),
+    InvalidPrefix(String),
+}
+
+impl fmt::Display for CidrError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CidrError::InvalidAddress(addr) => write!(f, "invalid CIDR address '{}'", addr),
+            CidrError::InvalidPrefix(prefix) => {
+                write!(f, "invalid CIDR prefix length '{}'", prefix)
+            }
+        }
+    }
+}
+
+/// An IPv4 or IPv6 network in CIDR notation, e.g. `10.0.0.0/8` or
+/// `2001:db8::/32`.
+///
+/// Host bits are cleared when parsing, so `10.1.2.3/8` is `10.0.0.0/8`. A
+/// bare address is a network of a single host. IPv4-mapped IPv6 addresses
+/// such as `::ffff:10.0.0.1` match IPv4 networks.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::net::Cidr;
+///
+/// let private: Cidr = "192.168.0.0/16".parse().unwrap();
+/// assert!(private.contains("192.168.10.1".parse().unwrap()));
+/// assert!(!private.contains("10.0.0.1".parse().unwrap()));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cidr {
+    network: IpAddr,
+    prefix_len: u8,
+}
+
+fn mask_v4(prefix_len: u8) -> u32 {
+    u32::MAX.checked_shl(32 - prefix_len as u32).unwrap_or(0)
+}
+
+fn mask_v6(prefix_len: u8) -> u128 {
+    u128::MAX.checked_shl(128 - prefix_len as u32).unwrap_or(0)
+}
+
+impl Cidr {
+    /// Creates a network, clearing the host bits of `addr`.
+    ///
+    /// Returns `None` if `prefix_len` is longer than the address.
+    pub fn new(addr: IpAddr, prefix_len: u8) -> Option<Self> {
+        let network = match addr {
+            IpAddr::V4(ip) if prefix_len <= 32 => {
+                IpAddr::V4(Ipv4Addr::from(u32::from(ip) & mask_v4(prefix_len)))
+            }
+            IpAddr::V6(ip) if prefix_len <= 128 => {
+                IpAddr::V6(Ipv6Addr::from(u128::from(ip) & mask_v6(prefix_len)))
+            }
+            _ => return None,
+        };
+        Some(Self {
+            network,
+            prefix_len,
+        })
+    }
+
+    /// The network address, with all host bits cleared.
+    pub fn network(&self) -> IpAddr {
+        self.network
+    }
+
+    pub fn prefix_len(&self) -> u8 {
+        self.prefix_len
+    }
+
+    pub fn is_ipv4(&self) -> bool {
+        self.network.is_ipv4()
+    }
+
+    /// Returns `true` if `ip` belongs to this network.
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                u32::from(ip) & mask_v4(self.prefix_len) == u32::from(network)
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                u128::from(ip) & mask_v6(self.prefix_len) == u128::from(network)
+            }
+            (IpAddr::V4(_), IpAddr::V6(ip)) => ip
+                .to_ipv4_mapped()
+                .is_some_and(|ip| self.contains(IpAddr::V4(ip))),
+            (IpAddr::V6(_), IpAddr::V4(_)) => false,
+        }
+    }
+
+    /// Returns `true` if every address of `other` belongs to this network.
+    pub fn contains_cidr(&self, other: &Cidr) -> bool {
+        self.is_ipv4() == other.is_ipv4()
+            && self.prefix_len <= other.prefix_len
+            && self.contains(other.network)
+    }
+
+    /// Returns `true` if the two networks share at least one address.
+    pub fn overlaps(&self, other: &Cidr) -> bool {
+        self.contains_cidr(other) || other.contains_cidr(self)
+    }
+}
+
+impl FromStr for Cidr {
+    type Err = CidrError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (addr, prefix) = match s.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (s, None),
+        };
+        let addr = addr
+            .parse::<IpAddr>()
+            .map_err(|_| CidrError::InvalidAddress(addr.to_string()))?;
+        let max = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix {
+            Some(prefix) => prefix
+                .parse::<u8>()
+                .ok()
+                .filter(|len| *len <= max && prefix.bytes().all(|b| b.is_ascii_digit()))
+                .ok_or_else(|| CidrError::InvalidPrefix(prefix.to_string()))?,
+            None => max,
+        };
+        Ok(Cidr::new(addr, prefix_len).expect("prefix length checked above"))
+    }
+}
+
+impl fmt::Display for Cidr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
+}
+
+impl Serialize for Cidr {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cidr {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct CidrVisitor;
+        impl serde::de::Visitor<'_> for CidrVisitor {
+            type Value = Cidr;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a CIDR network such as \"10.0.0.0/8\"")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Cidr::from_str(v).map_err(E::custom)
+            }
+        }
+        deserializer.deserialize_str(CidrVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cidr(s: &str) -> Cidr {
+        s.parse().unwrap()
+    }
+
+    fn ip(s: &str) -> IpAddr {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(cidr("10.1.2.3/8").to_string(), "10.0.0.0/8");
+        assert_eq!(cidr("10.1.2.3").to_string(), "10.1.2.3/32");
+        assert_eq!(cidr("0.0.0.0/0").prefix_len(), 0);
+        assert_eq!(cidr("2001:db8:1::1/32").to_string(), "2001:db8::/32");
+        assert_eq!(cidr("::1").prefix_len(), 128);
+
+        assert_eq!(
+            "10.0.0.0/33".parse::<Cidr>().unwrap_err(),
+            CidrError::InvalidPrefix("33".into())
+        );
+        assert_eq!(
+            "10.0.0/8".parse::<Cidr>().unwrap_err(),
+            CidrError::InvalidAddress("10.0.0".into())
+        );
+        assert!("::/129".parse::<Cidr>().is_err());
+        assert!("10.0.0.0/".parse::<Cidr>().is_err());
+        assert_eq!(
+            "10.0.0.0/+8".parse::<Cidr>().unwrap_err(),
+            CidrError::InvalidPrefix("+8".into())
+        );
+        assert!(Cidr::new(ip("10.0.0.0"), 33).is_none());
+    }
+
+    #[test]
+    fn test_contains() {
+        let net = cidr("172.16.0.0/12");
+        assert!(net.contains(ip("172.16.0.0")));
+        assert!(net.contains(ip("172.31.255.255")));
+        assert!(!net.contains(ip("172.32.0.0")));
+        assert!(net.contains(ip("::ffff:172.20.1.1")));
+        assert!(!net.contains(ip("2001:db8::1")));
+
+        assert!(cidr("0.0.0.0/0").contains(ip("8.8.8.8")));
+        assert!(!cidr("::/0").contains(ip("8.8.8.8")));
+
+        let net = cidr("fd00::/8");
+        assert!(net.contains(ip("fd12:3456::1")));
+        assert!(!net.contains(ip("fe80::1")));
+    }
+
+    #[test]
+    fn test_overlaps() {
+        assert!(cidr("10.0.0.0/8").overlaps(&cidr("10.20.0.0/16")));
+        assert!(cidr("10.20.0.0/16").overlaps(&cidr("10.0.0.0/8")));
+        assert!(!cidr("10.0.0.0/16").overlaps(&cidr("10.1.0.0/16")));
+        assert!(!cidr("0.0.0.0/0").overlaps(&cidr("::/0")));
+        assert!(cidr("10.0.0.0/8").contains_cidr(&cidr("10.1.0.0/16")));
+        assert!(!cidr("10.1.0.0/16").contains_cidr(&cidr("10.0.0.0/8")));
+    }
+
+    #[test]
+    fn test_serde() {
+        let net = cidr("192.168.0.0/16");
+        let json = serde_json::to_string(&net).unwrap();
+        assert_eq!(json, r#""192.168.0.0/16""#);
+        assert_eq!(serde_json::from_str::<Cidr>(&json).unwrap(), net);
+        assert!(serde_json::from_str::<Cidr>(r#""nope""#).is_err());
+    }
+}
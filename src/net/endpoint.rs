@@ -0,0 +1,372 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use alloc::string::String;
+use alloc::string::ToString;
+use core::fmt;
+use core::net::IpAddr;
+use core::net::Ipv6Addr;
+use core::net::SocketAddr;
+use core::str::FromStr;
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EndpointError {
+    Empty,
+    MissingPort,
+    InvalidPort(String),
+    InvalidHost(String),
+}
+
+impl fmt::Display for EndpointError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EndpointError::Empty => f.write_str("empty endpoint"),
+            EndpointError::MissingPort => f.write_str("missing port in endpoint"),
+            EndpointError::InvalidPort(port) => {
+                write!(f, "invalid port '{}', expected 1-65535", port)
+            }
+            EndpointError::InvalidHost(host) => write!(f, "invalid host '{}'", host),
+        }
+    }
+}
+
+/// The host part of an [`Endpoint`].
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Host {
+    Ip(IpAddr),
+    /// A DNS name, stored lowercase.
+    Domain(String),
+}
+
+impl Host {
+    fn parse(host: &str) -> Result<Host, EndpointError> {
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            return Ok(Host::Ip(ip));
+        }
+
+        let name = host.strip_suffix('.').unwrap_or(host);
+        let valid = !name.is_empty()
+            && !ends_in_number(name)
+            && name.len() <= 253
+            && name.split('.').all(|label| {
+                !label.is_empty()
+                    && label.len() <= 63
+                    && !label.starts_with('-')
+                    && !label.ends_with('-')
+                    && label
+                        .bytes()
+                        .all(|b| b.is_ascii_alphanumeric() || b == b'-' || b == b'_')
+            });
+
+        if valid {
+            Ok(Host::Domain(name.to_ascii_lowercase()))
+        } else {
+            Err(EndpointError::InvalidHost(host.to_string()))
+        }
+    }
+}
+
+/// Whether the last label of `name` is a decimal or `0x` hex number. Like
+/// URL host parsers, such names are taken for malformed IPv4 addresses,
+/// e.g. `1.2.3`, rather than domains.
+fn ends_in_number(name: &str) -> bool {
+    let last = name.rsplit('.').next().unwrap_or(name);
+    match last.strip_prefix("0x").or_else(|| last.strip_prefix("0X")) {
+        Some(hex) => hex.bytes().all(|b| b.is_ascii_hexdigit()),
+        None => !last.is_empty() && last.bytes().all(|b| b.is_ascii_digit()),
+    }
+}
+
+impl fmt::Display for Host {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Host::Ip(IpAddr::V6(ip)) => write!(f, "[{}]", ip),
+            Host::Ip(IpAddr::V4(ip)) => write!(f, "{}", ip),
+            Host::Domain(name) => f.write_str(name),
+        }
+    }
+}
+
+/// A `host:port` pair, e.g. `node-1.example.com:9300`, `10.0.0.1:9200` or
+/// `[::1]:9300`.
+///
+/// Names are kept as they are and only resolved when
+/// [`Endpoint::resolve`] is called. Endpoints serialize as plain strings.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::net::{Endpoint, Host};
+///
+/// let endpoint: Endpoint = "[::1]:9300".parse().unwrap();
+/// assert_eq!(endpoint.port(), 9300);
+/// assert_eq!(endpoint.to_string(), "[::1]:9300");
+///
+/// let endpoint = Endpoint::parse_with_default_port("localhost", 9200).unwrap();
+/// assert_eq!(endpoint.host(), &Host::Domain("localhost".into()));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Endpoint {
+    host: Host,
+    port: u16,
+}
+
+impl Endpoint {
+    pub fn new(host: Host, port: u16) -> Self {
+        Self { host, port }
+    }
+
+    /// Parses `s`, using `default_port` when it has no port.
+    pub fn parse_with_default_port(s: &str, default_port: u16) -> Result<Self, EndpointError> {
+        Self::parse(s, Some(default_port))
+    }
+
+    fn parse(s: &str, default_port: Option<u16>) -> Result<Self, EndpointError> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(EndpointError::Empty);
+        }
+
+        let (host, port) = if let Some(rest) = s.strip_prefix('[') {
+            let (ip, rest) = rest
+                .split_once(']')
+                .ok_or_else(|| EndpointError::InvalidHost(s.to_string()))?;
+            let ip = ip
+                .parse::<Ipv6Addr>()
+                .map_err(|_| EndpointError::InvalidHost(ip.to_string()))?;
+            let port = match rest {
+                "" => None,
+                _ => Some(
+                    rest.strip_prefix(':')
+                        .ok_or_else(|| EndpointError::InvalidHost(s.to_string()))?,
+                ),
+            };
+            (Host::Ip(IpAddr::V6(ip)), port)
+        } else if s.matches(':').count() > 1 {
+            // A bare IPv6 address, which cannot carry a port.
+            let ip = s
+                .parse::<Ipv6Addr>()
+                .map_err(|_| EndpointError::InvalidHost(s.to_string()))?;
+            (Host::Ip(IpAddr::V6(ip)), None)
+        } else {
+            match s.split_once(':') {
+                Some((host, port)) => (Host::parse(host)?, Some(port)),
+                None => (Host::parse(s)?, None),
+            }
+        };
+
+        let port = match port {
+            Some(port) => match port.parse::<u16>() {
+                Ok(p) if p != 0 && port.bytes().all(|b| b.is_ascii_digit()) => p,
+                _ => return Err(EndpointError::InvalidPort(port.to_string())),
+            },
+            None => default_port.ok_or(EndpointError::MissingPort)?,
+        };
+
+        Ok(Self { host, port })
+    }
+
+    pub fn host(&self) -> &Host {
+        &self.host
+    }
+
+    pub fn port(&self) -> u16 {
+        self.port
+    }
+
+    /// The socket address, if the host is an IP address.
+    pub fn socket_addr(&self) -> Option<SocketAddr> {
+        match self.host {
+            Host::Ip(ip) => Some(SocketAddr::new(ip, self.port)),
+            Host::Domain(_) => None,
+        }
+    }
+
+    /// Resolves the endpoint into socket addresses, querying DNS for names.
+    #[cfg(feature = "std")]
+    pub fn resolve(&self) -> std::io::Result<alloc::vec::Vec<SocketAddr>> {
+        use std::net::ToSocketAddrs;
+
+        match &self.host {
+            Host::Ip(ip) => Ok(alloc::vec![SocketAddr::new(*ip, self.port)]),
+            Host::Domain(name) => Ok((name.as_str(), self.port).to_socket_addrs()?.collect()),
+        }
+    }
+}
+
+impl From<SocketAddr> for Endpoint {
+    fn from(addr: SocketAddr) -> Self {
+        Self::new(Host::Ip(addr.ip()), addr.port())
+    }
+}
+
+impl FromStr for Endpoint {
+    type Err = EndpointError;
+
+    /// Parses `host:port`, the port is mandatory.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s, None)
+    }
+}
+
+impl fmt::Display for Endpoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.host, self.port)
+    }
+}
+
+impl Serialize for Endpoint {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Endpoint {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct EndpointVisitor;
+        impl serde::de::Visitor<'_> for EndpointVisitor {
+            type Value = Endpoint;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("an endpoint such as \"host:port\"")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Endpoint::from_str(v).map_err(E::custom)
+            }
+        }
+        deserializer.deserialize_str(EndpointVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::net::Ipv4Addr;
+
+    #[test]
+    fn test_parse() {
+        let endpoint: Endpoint = "10.0.0.1:9200".parse().unwrap();
+        assert_eq!(
+            endpoint.host(),
+            &Host::Ip(Ipv4Addr::new(10, 0, 0, 1).into())
+        );
+        assert_eq!(endpoint.port(), 9200);
+        assert_eq!(
+            endpoint.socket_addr(),
+            Some("10.0.0.1:9200".parse().unwrap())
+        );
+
+        let endpoint: Endpoint = "[::1]:9300".parse().unwrap();
+        assert_eq!(endpoint.host(), &Host::Ip(Ipv6Addr::LOCALHOST.into()));
+
+        let endpoint: Endpoint = " Node-1.Example.com:9300 ".parse().unwrap();
+        assert_eq!(endpoint.host(), &Host::Domain("node-1.example.com".into()));
+        assert_eq!(endpoint.to_string(), "node-1.example.com:9300");
+        assert_eq!(endpoint.socket_addr(), None);
+
+        let endpoint: Endpoint = "1.example.0x1g:9300".parse().unwrap();
+        assert_eq!(endpoint.host(), &Host::Domain("1.example.0x1g".into()));
+    }
+
+    #[test]
+    fn test_default_port() {
+        let endpoint = Endpoint::parse_with_default_port("::1", 9300).unwrap();
+        assert_eq!(endpoint.to_string(), "[::1]:9300");
+        let endpoint = Endpoint::parse_with_default_port("[fe80::1]", 9300).unwrap();
+        assert_eq!(endpoint.port(), 9300);
+        let endpoint = Endpoint::parse_with_default_port("es:9201", 9300).unwrap();
+        assert_eq!(endpoint.port(), 9201);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let cases = [
+            ("", EndpointError::Empty),
+            ("localhost", EndpointError::MissingPort),
+            ("::1", EndpointError::MissingPort),
+            ("localhost:0", EndpointError::InvalidPort("0".into())),
+            (
+                "localhost:65536",
+                EndpointError::InvalidPort("65536".into()),
+            ),
+            ("localhost:", EndpointError::InvalidPort("".into())),
+            ("bad host:80", EndpointError::InvalidHost("bad host".into())),
+            ("-bad:80", EndpointError::InvalidHost("-bad".into())),
+            ("a..b:80", EndpointError::InvalidHost("a..b".into())),
+            ("[::1:80", EndpointError::InvalidHost("[::1:80".into())),
+            ("[::1]80", EndpointError::InvalidHost("[::1]80".into())),
+            (
+                "[10.0.0.1]:80",
+                EndpointError::InvalidHost("10.0.0.1".into()),
+            ),
+            ("1.2.3:80", EndpointError::InvalidHost("1.2.3".into())),
+            (
+                "1.2.3.4.5:80",
+                EndpointError::InvalidHost("1.2.3.4.5".into()),
+            ),
+            (
+                "node.0x1f:80",
+                EndpointError::InvalidHost("node.0x1f".into()),
+            ),
+            ("9200:80", EndpointError::InvalidHost("9200".into())),
+            ("host:+9200", EndpointError::InvalidPort("+9200".into())),
+        ];
+        for (input, err) in cases {
+            assert_eq!(input.parse::<Endpoint>().unwrap_err(), err, "{}", input);
+        }
+    }
+
+    #[test]
+    fn test_serde_as_string() {
+        let endpoint: Endpoint = "[2001:db8::1]:9300".parse().unwrap();
+        let json = serde_json::to_string(&endpoint).unwrap();
+        assert_eq!(json, r#""[2001:db8::1]:9300""#);
+        assert_eq!(serde_json::from_str::<Endpoint>(&json).unwrap(), endpoint);
+        assert!(serde_json::from_str::<Endpoint>(r#""localhost""#).is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_resolve() {
+        let endpoint: Endpoint = "127.0.0.1:9200".parse().unwrap();
+        assert_eq!(
+            endpoint.resolve().unwrap(),
+            [SocketAddr::from(([127, 0, 0, 1], 9200))]
+        );
+
+        let endpoint: Endpoint = "localhost:9200".parse().unwrap();
+        let addrs = endpoint.resolve().unwrap();
+        assert!(addrs.iter().all(|addr| addr.ip().is_loopback()));
+    }
+}
@@ -0,0 +1,224 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! `application/x-www-form-urlencoded` query strings.
+//!
+//! Parsing keeps every pair in input order, including repeated keys, and
+//! borrows from the input unless a component contains escapes. Malformed
+//! percent escapes are kept literally and invalid UTF-8 is replaced, so
+//! parsing never fails.
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Parses a query string, with or without the leading `?`.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::net::query_string;
+///
+/// let pairs = query_string::parse("?q=pizza+margherita&tag=a&tag=b&pretty");
+/// assert_eq!(query_string::get(&pairs, "q"), Some("pizza margherita"));
+/// assert_eq!(query_string::get_all(&pairs, "tag").collect::<Vec<_>>(), ["a", "b"]);
+/// assert_eq!(query_string::get(&pairs, "pretty"), Some(""));
+/// ```
+pub fn parse(query: &str) -> Vec<(Cow<'_, str>, Cow<'_, str>)> {
+    let query = query.strip_prefix('?').unwrap_or(query);
+    query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode_component(key), decode_component(value))
+        })
+        .collect()
+}
+
+/// Encodes pairs into a query string without the leading `?`, in the order
+/// given.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::net::query_string;
+///
+/// let query = query_string::encode([("q", "pizza margherita"), ("size", "10")]);
+/// assert_eq!(query, "q=pizza+margherita&size=10");
+/// ```
+pub fn encode<I, K, V>(pairs: I) -> String
+where
+    I: IntoIterator<Item = (K, V)>,
+    K: AsRef<str>,
+    V: AsRef<str>,
+{
+    let mut query = String::new();
+    for (key, value) in pairs {
+        if !query.is_empty() {
+            query.push('&');
+        }
+        query.push_str(&encode_component(key.as_ref()));
+        query.push('=');
+        query.push_str(&encode_component(value.as_ref()));
+    }
+    query
+}
+
+/// Returns the first value of a key.
+pub fn get<'a>(pairs: &'a [(Cow<'_, str>, Cow<'_, str>)], key: &str) -> Option<&'a str> {
+    get_all(pairs, key).next()
+}
+
+/// Returns every value of a key, in input order.
+pub fn get_all<'a, 'k>(
+    pairs: &'a [(Cow<'_, str>, Cow<'_, str>)],
+    key: &'k str,
+) -> impl Iterator<Item = &'a str> + use<'a, 'k> {
+    pairs
+        .iter()
+        .filter(move |(k, _)| k == key)
+        .map(|(_, value)| value.as_ref())
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
+/// Decodes `+` as a space and `%XX` escapes.
+pub fn decode_component(s: &str) -> Cow<'_, str> {
+    if !s.contains(['+', '%']) {
+        return Cow::Borrowed(s);
+    }
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => {
+                let escaped = bytes
+                    .get(i + 1..i + 3)
+                    .and_then(|hex| Some(hex_value(hex[0])? << 4 | hex_value(hex[1])?));
+                match escaped {
+                    Some(byte) => {
+                        decoded.push(byte);
+                        i += 2;
+                    }
+                    None => decoded.push(b'%'),
+                }
+            }
+            byte => decoded.push(byte),
+        }
+        i += 1;
+    }
+    match String::from_utf8_lossy(&decoded) {
+        Cow::Borrowed(valid) => Cow::Owned(valid.into()),
+        Cow::Owned(replaced) => Cow::Owned(replaced),
+    }
+}
+
+fn is_unreserved(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || matches!(byte, b'*' | b'-' | b'.' | b'_')
+}
+
+/// Escapes everything but ASCII alphanumerics and `*-._`, writing spaces as
+/// `+`.
+pub fn encode_component(s: &str) -> Cow<'_, str> {
+    if s.bytes().all(is_unreserved) {
+        return Cow::Borrowed(s);
+    }
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+    let mut encoded = String::with_capacity(s.len() * 3);
+    for byte in s.bytes() {
+        if is_unreserved(byte) {
+            encoded.push(byte as char);
+        } else if byte == b' ' {
+            encoded.push('+');
+        } else {
+            encoded.push('%');
+            encoded.push(HEX[(byte >> 4) as usize] as char);
+            encoded.push(HEX[(byte & 0xf) as usize] as char);
+        }
+    }
+    Cow::Owned(encoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let pairs = parse("a=1&b=&c&&=d&a=2&e=x=y");
+        assert_eq!(
+            pairs,
+            [
+                ("a".into(), "1".into()),
+                ("b".into(), "".into()),
+                ("c".into(), "".into()),
+                ("".into(), "d".into()),
+                ("a".into(), "2".into()),
+                ("e".into(), "x=y".into()),
+            ]
+        );
+        assert!(matches!(pairs[0].0, Cow::Borrowed(_)));
+        assert!(parse("").is_empty());
+        assert!(parse("?").is_empty());
+        assert_eq!(get_all(&pairs, "a").collect::<Vec<_>>(), ["1", "2"]);
+        assert_eq!(get(&pairs, "missing"), None);
+    }
+
+    #[test]
+    fn test_decode_component() {
+        assert_eq!(decode_component("a+b%20c"), "a b c");
+        assert_eq!(decode_component("%E6%90%9C%e7%b4%a2"), "搜索");
+        assert_eq!(decode_component("%2B%26%3D"), "+&=");
+        assert_eq!(decode_component("100%"), "100%");
+        assert_eq!(decode_component("%zz%4"), "%zz%4");
+        assert_eq!(decode_component("%FF"), "\u{fffd}");
+        assert!(matches!(decode_component("plain"), Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode_component("a b&c=d+e"), "a+b%26c%3Dd%2Be");
+        assert_eq!(encode_component("搜索"), "%E6%90%9C%E7%B4%A2");
+        assert_eq!(encode_component("safe-._*~"), "safe-._*%7E");
+        assert_eq!(encode(Vec::<(&str, &str)>::new()), "");
+        assert_eq!(encode([("k", ""), ("k", "v")]), "k=&k=v");
+
+        let original = [("q", "a&b = c"), ("q", "100%"), ("π", "3.14")];
+        let query = encode(original);
+        let pairs = parse(&query);
+        let decoded: Vec<(&str, &str)> = pairs
+            .iter()
+            .map(|(k, v)| (k.as_ref(), v.as_ref()))
+            .collect();
+        assert_eq!(decoded, original);
+    }
+}
@@ -0,0 +1,205 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Shard selection compatible with Elasticsearch's `_routing`, so documents
+//! written by an Elasticsearch or OpenSearch cluster can be located when the
+//! data is migrated.
+
+use alloc::vec::Vec;
+
+use crate::utils::hash::murmur3_x86_32;
+
+/// The hash Elasticsearch computes for a routing value: murmur3 (x86, 32-bit,
+/// seed 0) over the UTF-16LE code units of the string.
+pub fn es_routing_hash(key: &str) -> i32 {
+    let bytes: Vec<u8> = key.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    murmur3_x86_32(&bytes, 0) as i32
+}
+
+/// The `index.number_of_routing_shards` Elasticsearch 7+ picks when it is
+/// not set: `num_shards` doubled until reaching 1024, at least once.
+///
+/// # Panics
+///
+/// Panics if `num_shards` is zero.
+pub fn default_routing_num_shards(num_shards: u32) -> u32 {
+    assert!(num_shards > 0, "num_shards must be positive");
+    let log2_num_shards = 32 - (num_shards - 1).leading_zeros();
+    let num_splits = 10_u32.saturating_sub(log2_num_shards).max(1);
+    num_shards << num_splits
+}
+
+/// The shard Elasticsearch stores a document with the given routing key in,
+/// which is the document `_id` unless a custom `_routing` was given.
+///
+/// `num_routing_partitions` is the index's `number_of_routing_shards`, see
+/// [`default_routing_num_shards`] for indices that never set it explicitly.
+///
+/// # Panics
+///
+/// Panics if `num_shards` is zero or does not divide
+/// `num_routing_partitions`.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::routing::default_routing_num_shards;
+/// use pizza_common::routing::shard_for;
+///
+/// assert_eq!(shard_for("hello", 5, default_routing_num_shards(5)), 4);
+/// ```
+pub fn shard_for(routing_key: &str, num_shards: u32, num_routing_partitions: u32) -> u32 {
+    scaled_shard(
+        es_routing_hash(routing_key),
+        num_shards,
+        num_routing_partitions,
+    )
+}
+
+/// Like [`shard_for`] for an index with `routing_partition_size` greater
+/// than one, where a custom routing value selects a group of shards and the
+/// document `id` picks one within it.
+///
+/// # Panics
+///
+/// Panics under the same conditions as [`shard_for`], or if
+/// `routing_partition_size` is zero.
+pub fn shard_for_partitioned(
+    routing_key: &str,
+    id: &str,
+    num_shards: u32,
+    num_routing_partitions: u32,
+    routing_partition_size: u32,
+) -> u32 {
+    assert!(
+        routing_partition_size > 0,
+        "routing_partition_size must be positive"
+    );
+    let offset = es_routing_hash(id).rem_euclid(routing_partition_size as i32);
+    scaled_shard(
+        es_routing_hash(routing_key).wrapping_add(offset),
+        num_shards,
+        num_routing_partitions,
+    )
+}
+
+fn scaled_shard(hash: i32, num_shards: u32, num_routing_partitions: u32) -> u32 {
+    assert!(num_shards > 0, "num_shards must be positive");
+    assert!(
+        num_routing_partitions >= num_shards && num_routing_partitions.is_multiple_of(num_shards),
+        "num_routing_partitions must be a multiple of num_shards"
+    );
+    let routing_factor = num_routing_partitions / num_shards;
+    let bucket = (hash as i64).rem_euclid(num_routing_partitions as i64) as u32;
+    bucket / routing_factor
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_routing_hash_vectors() {
+        // From Elasticsearch's Murmur3HashFunctionTests.
+        let vectors = [
+            ("hell", 0x5a0c_b7c3_u32),
+            ("hello", 0xd7c3_1989),
+            ("hello w", 0x22ab_2984),
+            ("hello wo", 0xdf0c_a123),
+            ("hello wor", 0xe774_4d61),
+            ("The quick brown fox jumps over the lazy dog", 0xe07d_b09c),
+            ("The quick brown fox jumps over the lazy cog", 0x4e63_d2ad),
+            // Computed with an independent implementation, covering UTF-16
+            // code units above 0xff and surrogate pairs.
+            ("", 0),
+            ("a", 0x6f49_d11a),
+            ("ö", 0xf5a8_898c),
+            ("日本", 0xa4a1_f0f3),
+            ("😀", 0x5606_5e39),
+        ];
+        for (key, hash) in vectors {
+            assert_eq!(es_routing_hash(key), hash as i32, "{}", key);
+        }
+    }
+
+    #[test]
+    fn test_default_routing_num_shards() {
+        let expected = [
+            (1, 1024),
+            (2, 1024),
+            (3, 768),
+            (5, 640),
+            (7, 896),
+            (10, 640),
+            (16, 1024),
+            (512, 1024),
+            (513, 1026),
+            (1024, 2048),
+        ];
+        for (num_shards, routing_shards) in expected {
+            assert_eq!(default_routing_num_shards(num_shards), routing_shards);
+        }
+    }
+
+    #[test]
+    fn test_shard_for() {
+        let expected: [(&str, [u32; 6]); 7] = [
+            ("hello", [0, 0, 2, 4, 8, 26]),
+            ("user-42", [0, 0, 0, 1, 3, 27]),
+            ("0", [0, 1, 1, 3, 7, 15]),
+            ("1", [0, 0, 2, 4, 8, 17]),
+            ("2", [0, 0, 1, 3, 7, 4]),
+            ("doc#1", [0, 0, 0, 3, 6, 13]),
+            ("😀", [0, 1, 0, 2, 4, 19]),
+        ];
+        for (key, shards) in expected {
+            for (num_shards, shard) in [1, 2, 3, 5, 10, 30].into_iter().zip(shards) {
+                let routing_shards = default_routing_num_shards(num_shards);
+                assert_eq!(shard_for(key, num_shards, routing_shards), shard, "{}", key);
+            }
+        }
+
+        // Indices created before 7.0 or with explicit routing shards.
+        assert_eq!(shard_for("doc#1", 4, 8), 3);
+        assert_eq!(shard_for("2", 4, 8), 1);
+        assert_eq!(shard_for("hello", 4, 4), 1);
+    }
+
+    #[test]
+    fn test_shard_for_partitioned() {
+        let expected = [("doc-1", 3), ("doc-2", 4), ("doc-3", 3), ("doc-5", 4)];
+        for (id, shard) in expected {
+            assert_eq!(shard_for_partitioned("user-42", id, 10, 10, 3), shard);
+        }
+        assert_eq!(shard_for_partitioned("user-42", "doc-2", 5, 640, 3), 1);
+        assert_eq!(
+            shard_for_partitioned("user-42", "doc-2", 5, 640, 1),
+            shard_for("user-42", 5, 640)
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "multiple of num_shards")]
+    fn test_shard_for_invalid_routing_shards() {
+        shard_for("hello", 3, 8);
+    }
+}
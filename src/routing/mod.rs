@@ -0,0 +1,124 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Placement of keys onto shards and of shards onto nodes.
+
+mod elasticsearch;
+mod rebalance;
+
+use core::hash::Hash;
+
+pub use elasticsearch::default_routing_num_shards;
+pub use elasticsearch::es_routing_hash;
+pub use elasticsearch::shard_for;
+pub use elasticsearch::shard_for_partitioned;
+pub use rebalance::Assignment;
+pub use rebalance::RebalanceConstraints;
+pub use rebalance::RebalanceError;
+pub use rebalance::RebalancePlan;
+pub use rebalance::ShardMove;
+
+use crate::utils::hash::stable_hash;
+
+/// Maps `key` onto one of `buckets` buckets with the jump consistent hash of
+/// Lamping and Veach.
+///
+/// Unlike a hash ring it needs no memory, but buckets can only be added or
+/// removed at the end. When the bucket count grows from `n` to `n + 1`, a
+/// key either stays where it was or moves to the new bucket `n`.
+///
+/// # Panics
+///
+/// Panics if `buckets` is zero.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::routing::jump_hash;
+///
+/// let shard = jump_hash(42, 16);
+/// assert!(shard < 16);
+/// let grown = jump_hash(42, 17);
+/// assert!(grown == shard || grown == 16);
+/// ```
+pub fn jump_hash(mut key: u64, buckets: u32) -> u32 {
+    assert!(buckets > 0, "buckets must be positive");
+
+    let mut b: i64 = -1;
+    let mut j: i64 = 0;
+    while j < buckets as i64 {
+        b = j;
+        key = key.wrapping_mul(2_862_933_555_777_941_757).wrapping_add(1);
+        j = ((b + 1) as f64 * ((1_i64 << 31) as f64 / ((key >> 33) + 1) as f64)) as i64;
+    }
+    b as u32
+}
+
+/// [`jump_hash`] for any hashable key, hashed first with the platform
+/// independent [`StableHasher`](crate::utils::hash::StableHasher).
+pub fn jump_hash_key<K: Hash + ?Sized>(key: &K, buckets: u32) -> u32 {
+    jump_hash(stable_hash(key), buckets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_jump_hash_reference_values() {
+        assert_eq!(jump_hash(1, 1), 0);
+        assert_eq!(jump_hash(42, 57), 43);
+        assert_eq!(jump_hash(0xDEAD_10CC, 1), 0);
+        assert_eq!(jump_hash(0xDEAD_10CC, 666), 361);
+        assert_eq!(jump_hash(256, 1024), 520);
+    }
+
+    #[test]
+    fn test_jump_hash_moves_only_to_new_bucket() {
+        for key in 0..1000_u64 {
+            let key = key.wrapping_mul(0x9E37_79B9_7F4A_7C15);
+            let mut previous = jump_hash(key, 1);
+            for buckets in 2..64 {
+                let current = jump_hash(key, buckets);
+                assert!(current == previous || current == buckets - 1);
+                previous = current;
+            }
+        }
+    }
+
+    #[test]
+    fn test_jump_hash_balance() {
+        let mut counts = [0_u32; 10];
+        for key in 0..100_000_u64 {
+            counts[jump_hash_key(&key, 10) as usize] += 1;
+        }
+        for count in counts {
+            assert!((9_000..11_000).contains(&count), "{:?}", counts);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "buckets must be positive")]
+    fn test_jump_hash_zero_buckets() {
+        jump_hash(1, 0);
+    }
+}
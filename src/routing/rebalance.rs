@@ -0,0 +1,531 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Planning shard moves when the set of nodes changes.
+
+use alloc::collections::BTreeMap;
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+/// The nodes holding each shard, primary first. Every copy of a shard must
+/// live on a different node.
+pub type Assignment = BTreeMap<u32, Vec<String>>;
+
+/// Limits the planner has to honor besides replica anti-affinity.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RebalanceConstraints {
+    weights: BTreeMap<String, u32>,
+}
+
+impl RebalanceConstraints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the relative capacity of `node`, nodes default to `1`. A node
+    /// with weight `0` is drained.
+    pub fn weight(mut self, node: impl Into<String>, weight: u32) -> Self {
+        self.weights.insert(node.into(), weight);
+        self
+    }
+
+    fn weight_of(&self, node: &str) -> u32 {
+        self.weights.get(node).copied().unwrap_or(1)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RebalanceError {
+    /// There is no node with a positive weight.
+    NoNodes,
+    /// A shard has more copies than there are nodes to spread them over.
+    InsufficientNodes {
+        shard: u32,
+        copies: usize,
+        nodes: usize,
+    },
+}
+
+impl fmt::Display for RebalanceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RebalanceError::NoNodes => f.write_str("no node can hold shards"),
+            RebalanceError::InsufficientNodes {
+                shard,
+                copies,
+                nodes,
+            } => write!(
+                f,
+                "shard {} has {} copies but only {} nodes are available",
+                shard, copies, nodes
+            ),
+        }
+    }
+}
+
+/// Relocation of one shard copy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShardMove {
+    pub shard: u32,
+    /// Position of the copy, `0` being the primary.
+    pub copy: usize,
+    pub from: String,
+    pub to: String,
+}
+
+/// The moves turning an assignment into a balanced one over a new node set.
+///
+/// Each node gets a number of copies proportional to its weight. Copies on
+/// nodes that are still present stay where they are unless their node holds
+/// more than its share, so growing or shrinking the cluster by one node only
+/// moves the copies that have to move. Planning is deterministic: the same
+/// inputs always produce the same plan.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RebalancePlan {
+    moves: Vec<ShardMove>,
+    assignment: Assignment,
+}
+
+impl RebalancePlan {
+    /// Plans the moves from `old_assignment` onto `new_nodes`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pizza_common::routing::Assignment;
+    /// use pizza_common::routing::RebalanceConstraints;
+    /// use pizza_common::routing::RebalancePlan;
+    ///
+    /// let mut old = Assignment::new();
+    /// for shard in 0..4 {
+    ///     old.insert(shard, vec!["a".to_string()]);
+    /// }
+    /// let plan =
+    ///     RebalancePlan::compute(&old, &["a", "b"], &RebalanceConstraints::new()).unwrap();
+    /// assert_eq!(plan.moves().len(), 2);
+    /// assert!(plan.moves().iter().all(|m| m.from == "a" && m.to == "b"));
+    /// ```
+    pub fn compute<S: AsRef<str>>(
+        old_assignment: &Assignment,
+        new_nodes: &[S],
+        constraints: &RebalanceConstraints,
+    ) -> Result<Self, RebalanceError> {
+        let nodes: BTreeSet<&str> = new_nodes
+            .iter()
+            .map(AsRef::as_ref)
+            .filter(|node| constraints.weight_of(node) > 0)
+            .collect();
+        if nodes.is_empty() {
+            return if old_assignment.values().all(Vec::is_empty) {
+                Ok(Self {
+                    moves: Vec::new(),
+                    assignment: old_assignment.clone(),
+                })
+            } else {
+                Err(RebalanceError::NoNodes)
+            };
+        }
+        for (&shard, copies) in old_assignment {
+            if copies.len() > nodes.len() {
+                return Err(RebalanceError::InsufficientNodes {
+                    shard,
+                    copies: copies.len(),
+                    nodes: nodes.len(),
+                });
+            }
+        }
+
+        let mut state = State::new(old_assignment, &nodes);
+        let targets = targets(&state, constraints, old_assignment.len());
+
+        // Copies on departed nodes, or duplicated on one node, must move.
+        let mut pending: Vec<(u32, usize)> = Vec::new();
+        for (&shard, copies) in old_assignment {
+            let mut seen = BTreeSet::new();
+            for (copy, node) in copies.iter().enumerate() {
+                if !nodes.contains(node.as_str()) || !seen.insert(node.as_str()) {
+                    pending.push((shard, copy));
+                } else {
+                    state.place(shard, node);
+                }
+            }
+        }
+
+        // Overloaded nodes shed replicas before primaries, highest shard
+        // first.
+        for &node in &nodes {
+            let excess = state.load(node).saturating_sub(targets[node]);
+            if excess == 0 {
+                continue;
+            }
+            let mut held: Vec<(usize, u32)> = state.held[node]
+                .iter()
+                .map(|&shard| (state.copy_index(shard, node), shard))
+                .collect();
+            held.sort_by(|a, b| (a.0 == 0).cmp(&(b.0 == 0)).then(b.1.cmp(&a.1)));
+            for &(copy, shard) in held.iter().take(excess) {
+                state.unplace(shard, node);
+                pending.push((shard, copy));
+            }
+        }
+
+        for (shard, copy) in pending {
+            state.relocate(shard, copy, &targets);
+        }
+
+        Ok(Self {
+            moves: state.moves,
+            assignment: state.assignment,
+        })
+    }
+
+    /// The moves to perform, in a deterministic order.
+    pub fn moves(&self) -> &[ShardMove] {
+        &self.moves
+    }
+
+    /// The assignment once every move is done.
+    pub fn assignment(&self) -> &Assignment {
+        &self.assignment
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+}
+
+struct State<'a> {
+    nodes: &'a BTreeSet<&'a str>,
+    assignment: Assignment,
+    held: BTreeMap<&'a str, BTreeSet<u32>>,
+    moves: Vec<ShardMove>,
+}
+
+impl<'a> State<'a> {
+    fn new(old_assignment: &Assignment, nodes: &'a BTreeSet<&'a str>) -> Self {
+        Self {
+            nodes,
+            assignment: old_assignment.clone(),
+            held: nodes.iter().map(|&node| (node, BTreeSet::new())).collect(),
+            moves: Vec::new(),
+        }
+    }
+
+    fn load(&self, node: &str) -> usize {
+        self.held[node].len()
+    }
+
+    fn holds(&self, node: &str, shard: u32) -> bool {
+        self.held[node].contains(&shard)
+    }
+
+    fn place(&mut self, shard: u32, node: &str) {
+        let node = *self.nodes.get(node).unwrap();
+        self.held.get_mut(node).unwrap().insert(shard);
+    }
+
+    fn unplace(&mut self, shard: u32, node: &str) {
+        self.held.get_mut(node).unwrap().remove(&shard);
+    }
+
+    fn copy_index(&self, shard: u32, node: &str) -> usize {
+        self.assignment[&shard]
+            .iter()
+            .position(|n| n == node)
+            .unwrap()
+    }
+
+    fn move_copy(&mut self, shard: u32, copy: usize, to: &str) {
+        let slot = &mut self.assignment.get_mut(&shard).unwrap()[copy];
+        let from = core::mem::replace(slot, String::from(to));
+        self.place(shard, to);
+        self.moves.push(ShardMove {
+            shard,
+            copy,
+            from,
+            to: String::from(to),
+        });
+    }
+
+    /// Places a pending copy on the node furthest below its target that does
+    /// not hold the shard yet. When anti-affinity rules out every such node,
+    /// a copy of another shard is first moved out of the way.
+    fn relocate(&mut self, shard: u32, copy: usize, targets: &BTreeMap<&str, usize>) {
+        let underloaded = |state: &Self, shard: u32| {
+            state
+                .nodes
+                .iter()
+                .copied()
+                .filter(|&node| state.load(node) < targets[node] && !state.holds(node, shard))
+                .max_by(|a, b| {
+                    let deficit = |node: &str| targets[node] - state.load(node);
+                    deficit(a).cmp(&deficit(b)).then(b.cmp(a))
+                })
+        };
+
+        if let Some(node) = underloaded(self, shard) {
+            self.move_copy(shard, copy, node);
+            return;
+        }
+
+        let nodes = self.nodes;
+        for &via in nodes {
+            if self.holds(via, shard) {
+                continue;
+            }
+            let swap = self.held[via]
+                .iter()
+                .find_map(|&other| underloaded(self, other).map(|to| (other, to)));
+            if let Some((other, to)) = swap {
+                let other_copy = self.copy_index(other, via);
+                self.unplace(other, via);
+                self.move_copy(other, other_copy, to);
+                self.move_copy(shard, copy, via);
+                return;
+            }
+        }
+
+        // Every node without the shard is at its target, accept a small
+        // imbalance rather than breaking anti-affinity.
+        let node = nodes
+            .iter()
+            .copied()
+            .filter(|&node| !self.holds(node, shard))
+            .min_by_key(|&node| self.load(node))
+            .expect("a shard never has more copies than nodes");
+        self.move_copy(shard, copy, node);
+    }
+}
+
+/// Splits the copies over the nodes proportionally to their weights. A node
+/// never gets more than one copy per shard, any share above that is spread
+/// over the other nodes.
+fn targets<'a>(
+    state: &State<'a>,
+    constraints: &RebalanceConstraints,
+    num_shards: usize,
+) -> BTreeMap<&'a str, usize> {
+    let total: usize = state.assignment.values().map(Vec::len).sum();
+    let current = |node: &str| {
+        state
+            .assignment
+            .values()
+            .filter(|copies| copies.iter().any(|n| n == node))
+            .count()
+    };
+
+    let mut targets = BTreeMap::new();
+    let mut active: Vec<&str> = state.nodes.iter().copied().collect();
+    let mut remaining = total;
+    loop {
+        let weight_sum: u64 = active
+            .iter()
+            .map(|node| constraints.weight_of(node) as u64)
+            .sum();
+        let mut shares: Vec<(&str, usize, u64)> = active
+            .iter()
+            .map(|&node| {
+                let scaled = remaining as u64 * constraints.weight_of(node) as u64;
+                (node, (scaled / weight_sum) as usize, scaled % weight_sum)
+            })
+            .collect();
+
+        // Hand the leftover copies to the largest remainders, preferring
+        // nodes that already hold that many so nothing has to move.
+        let leftover = remaining - shares.iter().map(|share| share.1).sum::<usize>();
+        let mut order: Vec<usize> = (0..shares.len()).collect();
+        order.sort_by(|&a, &b| {
+            let keeps = |i: usize| current(shares[i].0) > shares[i].1;
+            keeps(b)
+                .cmp(&keeps(a))
+                .then(shares[b].2.cmp(&shares[a].2))
+                .then(shares[a].0.cmp(shares[b].0))
+        });
+        order.retain(|&i| shares[i].2 > 0);
+        for &i in order.iter().take(leftover) {
+            shares[i].1 += 1;
+        }
+
+        let capped: Vec<&str> = shares
+            .iter()
+            .filter(|share| share.1 > num_shards)
+            .map(|share| share.0)
+            .collect();
+        if capped.is_empty() {
+            targets.extend(shares.into_iter().map(|(node, share, _)| (node, share)));
+            return targets;
+        }
+        for node in capped {
+            targets.insert(node, num_shards);
+            remaining -= num_shards;
+            active.retain(|&n| n != node);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    fn assignment(shards: &[&[&str]]) -> Assignment {
+        shards
+            .iter()
+            .enumerate()
+            .map(|(shard, nodes)| {
+                (
+                    shard as u32,
+                    nodes.iter().map(|node| node.to_string()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    fn node_loads(assignment: &Assignment) -> BTreeMap<String, usize> {
+        let mut loads = BTreeMap::new();
+        for copies in assignment.values() {
+            let unique: BTreeSet<&String> = copies.iter().collect();
+            assert_eq!(unique.len(), copies.len(), "anti-affinity violated");
+            for node in copies {
+                *loads.entry(node.clone()).or_default() += 1;
+            }
+        }
+        loads
+    }
+
+    #[test]
+    fn test_balanced_assignment_is_kept() {
+        let old = assignment(&[&["a", "b"], &["b", "c"], &["c", "a"]]);
+        let plan =
+            RebalancePlan::compute(&old, &["a", "b", "c"], &RebalanceConstraints::new()).unwrap();
+        assert!(plan.is_empty());
+        assert_eq!(plan.assignment(), &old);
+    }
+
+    #[test]
+    fn test_add_node_moves_only_its_share() {
+        let old = assignment(&[
+            &["a", "b"],
+            &["b", "c"],
+            &["c", "a"],
+            &["a", "b"],
+            &["b", "c"],
+            &["c", "a"],
+        ]);
+        let plan =
+            RebalancePlan::compute(&old, &["a", "b", "c", "d"], &RebalanceConstraints::new())
+                .unwrap();
+        assert_eq!(plan.moves().len(), 3);
+        assert!(plan.moves().iter().all(|m| m.to == "d"));
+        let loads = node_loads(plan.assignment());
+        assert_eq!(loads.values().copied().collect::<Vec<_>>(), vec![3; 4]);
+    }
+
+    #[test]
+    fn test_remove_node_moves_only_its_copies() {
+        let old = assignment(&[&["a", "b"], &["b", "c"], &["c", "a"], &["a", "c"]]);
+        let plan = RebalancePlan::compute(&old, &["a", "b"], &RebalanceConstraints::new()).unwrap();
+        assert_eq!(plan.moves().len(), 3);
+        assert!(plan.moves().iter().all(|m| m.from == "c"));
+        let loads = node_loads(plan.assignment());
+        assert_eq!(loads["a"], 4);
+        assert_eq!(loads["b"], 4);
+    }
+
+    #[test]
+    fn test_weights() {
+        let old = assignment(&[&["a"] as &[&str]; 8]);
+        let constraints = RebalanceConstraints::new().weight("b", 3);
+        let plan = RebalancePlan::compute(&old, &["a", "b"], &constraints).unwrap();
+        let loads = node_loads(plan.assignment());
+        assert_eq!(loads["a"], 2);
+        assert_eq!(loads["b"], 6);
+
+        // Draining a node moves everything away from it.
+        let constraints = RebalanceConstraints::new().weight("a", 0);
+        let plan = RebalancePlan::compute(&old, &["a", "b"], &constraints).unwrap();
+        assert_eq!(plan.moves().len(), 8);
+        assert_eq!(node_loads(plan.assignment())["b"], 8);
+    }
+
+    #[test]
+    fn test_weight_capped_by_anti_affinity() {
+        // "a" would deserve 4 copies but can hold one per shard only.
+        let old = assignment(&[&["b", "c"], &["c", "b"]]);
+        let constraints = RebalanceConstraints::new().weight("a", 100);
+        let plan = RebalancePlan::compute(&old, &["a", "b", "c"], &constraints).unwrap();
+        let loads = node_loads(plan.assignment());
+        assert_eq!(loads["a"], 2);
+        assert_eq!(loads["b"] + loads["c"], 2);
+    }
+
+    #[test]
+    fn test_anti_affinity_swap() {
+        // The copy leaving "x" can only go to "c", which is full, so the plan
+        // first frees a slot on "c" by moving shard 1 to "a".
+        let old = assignment(&[&["x", "a", "b"], &["c", "b"]]);
+        let plan =
+            RebalancePlan::compute(&old, &["a", "b", "c"], &RebalanceConstraints::new()).unwrap();
+        let loads = node_loads(plan.assignment());
+        assert!(loads.values().all(|&load| (1..=2).contains(&load)));
+        assert!(plan.moves().iter().any(|m| m.shard == 0 && m.to == "c"));
+    }
+
+    #[test]
+    fn test_errors() {
+        let old = assignment(&[&["a", "b", "c"]]);
+        assert_eq!(
+            RebalancePlan::compute(&old, &["a", "b"], &RebalanceConstraints::new()),
+            Err(RebalanceError::InsufficientNodes {
+                shard: 0,
+                copies: 3,
+                nodes: 2
+            })
+        );
+        assert_eq!(
+            RebalancePlan::compute::<&str>(&old, &[], &RebalanceConstraints::new()),
+            Err(RebalanceError::NoNodes)
+        );
+    }
+
+    #[test]
+    fn test_deterministic() {
+        let old = assignment(&[
+            &["a", "b"],
+            &["b", "c"],
+            &["c", "d"],
+            &["d", "a"],
+            &["a", "c"],
+        ]);
+        let nodes = ["e", "b", "a", "f"];
+        let first = RebalancePlan::compute(&old, &nodes, &RebalanceConstraints::new()).unwrap();
+        for _ in 0..3 {
+            let again = RebalancePlan::compute(&old, &nodes, &RebalanceConstraints::new()).unwrap();
+            assert_eq!(first, again);
+        }
+        let loads = node_loads(first.assignment());
+        assert!(loads.values().all(|&load| (2..=3).contains(&load)));
+    }
+}
@@ -0,0 +1,143 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Validated zero-copy archives built on [`rkyv`].
+//!
+//! Archives read from disk or the network are untrusted: these helpers
+//! always check the bytes before handing out a reference, so a corrupted
+//! file results in an error instead of undefined behavior.
+
+use rkyv::api::high::HighSerializer;
+use rkyv::api::high::HighValidator;
+use rkyv::bytecheck::CheckBytes;
+use rkyv::de::Pool;
+use rkyv::rancor::Strategy;
+use rkyv::ser::allocator::ArenaHandle;
+use rkyv::util::AlignedVec;
+use rkyv::Archive;
+use rkyv::Deserialize;
+use rkyv::Portable;
+use rkyv::Serialize;
+
+/// The error returned by the archive helpers.
+pub type ArchiveError = rkyv::rancor::Error;
+
+/// Alignment of the buffers returned by [`to_bytes`].
+pub const ARCHIVE_ALIGNMENT: usize = 16;
+
+/// Serializes `value` into an aligned archive.
+pub fn to_bytes<T>(value: &T) -> Result<AlignedVec, ArchiveError>
+where
+    T: for<'a> Serialize<HighSerializer<AlignedVec, ArenaHandle<'a>, ArchiveError>>,
+{
+    rkyv::to_bytes(value)
+}
+
+/// Validates `bytes` and returns a reference to the archived `T` inside,
+/// without copying or deserializing anything.
+///
+/// `bytes` must be aligned to [`ARCHIVE_ALIGNMENT`], which is the case for
+/// buffers from [`to_bytes`] and for memory maps; misaligned input is
+/// rejected.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::serde_ext::archive;
+/// use pizza_common::utils::uuid::Uuid;
+///
+/// let id = Uuid::from(42);
+/// let bytes = archive::to_bytes(&id).unwrap();
+/// let archived = archive::access::<Uuid>(&bytes).unwrap();
+/// assert_eq!(*archived, id);
+/// ```
+pub fn access<T>(bytes: &[u8]) -> Result<&T::Archived, ArchiveError>
+where
+    T: Archive,
+    T::Archived: Portable + for<'a> CheckBytes<HighValidator<'a, ArchiveError>>,
+{
+    rkyv::access::<T::Archived, ArchiveError>(bytes)
+}
+
+/// Validates `bytes` and deserializes the archived `T`. Unlike [`access`],
+/// misaligned input is copied into an aligned buffer first.
+pub fn from_bytes<T>(bytes: &[u8]) -> Result<T, ArchiveError>
+where
+    T: Archive,
+    T::Archived: for<'a> CheckBytes<HighValidator<'a, ArchiveError>>
+        + Deserialize<T, Strategy<Pool, ArchiveError>>,
+{
+    if (bytes.as_ptr() as usize).is_multiple_of(ARCHIVE_ALIGNMENT) {
+        return rkyv::from_bytes::<T, ArchiveError>(bytes);
+    }
+    let mut aligned = AlignedVec::<ARCHIVE_ALIGNMENT>::with_capacity(bytes.len());
+    aligned.extend_from_slice(bytes);
+    rkyv::from_bytes::<T, ArchiveError>(&aligned)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::sequencer::Sequencer;
+    use crate::utils::uuid::Uuid;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_uuid_round_trip() {
+        let ids: Vec<Uuid> = (0..4).map(|_| Uuid::new()).collect();
+        let bytes = to_bytes(&ids).unwrap();
+        let archived = access::<Vec<Uuid>>(&bytes).unwrap();
+        assert_eq!(archived.len(), 4);
+        for (archived, id) in archived.iter().zip(&ids) {
+            assert_eq!(archived, id);
+        }
+        assert_eq!(from_bytes::<Vec<Uuid>>(&bytes).unwrap(), ids);
+    }
+
+    #[test]
+    fn test_sequencer_round_trip() {
+        let mut sequencer = Sequencer::new(0, 5, 100);
+        sequencer.next();
+        let bytes = to_bytes(&sequencer).unwrap();
+
+        // Misaligned input still deserializes through a copy.
+        let mut shifted = AlignedVec::<ARCHIVE_ALIGNMENT>::new();
+        shifted.push(0);
+        shifted.extend_from_slice(&bytes);
+        let restored = from_bytes::<Sequencer>(&shifted[1..]).unwrap();
+        assert_eq!(restored.current(), 5);
+        assert!(access::<Sequencer>(&shifted[1..]).is_err());
+    }
+
+    #[test]
+    fn test_rejects_corrupted_archive() {
+        let names = alloc::vec![alloc::string::String::from("pizza")];
+        let bytes = to_bytes(&names).unwrap();
+        let mut corrupted = AlignedVec::<ARCHIVE_ALIGNMENT>::new();
+        corrupted.extend_from_slice(&bytes);
+        let len = corrupted.len();
+        // Point the relative pointer of the vector far outside the buffer.
+        corrupted[len - 8..len - 4].copy_from_slice(&0x7fff_0000_i32.to_le_bytes());
+        assert!(access::<Vec<alloc::string::String>>(&corrupted).is_err());
+        assert!(access::<Vec<alloc::string::String>>(&[]).is_err());
+    }
+}
@@ -0,0 +1,201 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! CBOR encoding, with an optional deterministic mode for signing and
+//! content hashing.
+//!
+//! [`to_vec_deterministic`] follows the core deterministic encoding
+//! requirements of RFC 8949 (section 4.2.1): integers, lengths and floats
+//! use their shortest form, lengths are always definite and map entries are
+//! sorted by the bytewise order of their encoded keys. For text keys this
+//! means shorter keys sort first, unlike the plain lexicographic order of
+//! JSON object keys.
+
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt;
+
+use ciborium::Value;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CborError {
+    Encode(String),
+    Decode(String),
+    /// Two entries of a map have the same key, which deterministic encoding
+    /// forbids.
+    DuplicateKey,
+}
+
+impl fmt::Display for CborError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CborError::Encode(reason) => write!(f, "CBOR encoding failed: {}", reason),
+            CborError::Decode(reason) => write!(f, "CBOR decoding failed: {}", reason),
+            CborError::DuplicateKey => f.write_str("duplicate key in CBOR map"),
+        }
+    }
+}
+
+/// Encodes `value`, keeping map entries in the order serde produces them.
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, CborError> {
+    let mut bytes = Vec::new();
+    ciborium::into_writer(value, &mut bytes).map_err(|e| CborError::Encode(e.to_string()))?;
+    Ok(bytes)
+}
+
+/// Encodes `value` deterministically: equal values always produce the same
+/// bytes, whatever the iteration order of the maps they contain.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use pizza_common::serde_ext::cbor;
+///
+/// let a: HashMap<&str, u32> = [("bb", 1), ("a", 2), ("c", 3)].into_iter().collect();
+/// let b: HashMap<&str, u32> = [("c", 3), ("bb", 1), ("a", 2)].into_iter().collect();
+/// assert_eq!(
+///     cbor::to_vec_deterministic(&a).unwrap(),
+///     cbor::to_vec_deterministic(&b).unwrap()
+/// );
+/// ```
+pub fn to_vec_deterministic<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, CborError> {
+    let value = Value::serialized(value).map_err(|e| CborError::Encode(e.to_string()))?;
+    to_vec(&canonicalize(value)?)
+}
+
+/// Decodes a value from `bytes`.
+pub fn from_slice<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, CborError> {
+    ciborium::from_reader(bytes).map_err(|e| CborError::Decode(e.to_string()))
+}
+
+fn canonicalize(value: Value) -> Result<Value, CborError> {
+    Ok(match value {
+        Value::Map(entries) => {
+            let mut keyed = entries
+                .into_iter()
+                .map(|(key, value)| {
+                    let key = canonicalize(key)?;
+                    Ok((to_vec(&key)?, key, canonicalize(value)?))
+                })
+                .collect::<Result<Vec<_>, CborError>>()?;
+            keyed.sort_by(|a, b| a.0.cmp(&b.0));
+            if keyed.windows(2).any(|pair| pair[0].0 == pair[1].0) {
+                return Err(CborError::DuplicateKey);
+            }
+            Value::Map(
+                keyed
+                    .into_iter()
+                    .map(|(_, key, value)| (key, value))
+                    .collect(),
+            )
+        }
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .map(canonicalize)
+                .collect::<Result<_, _>>()?,
+        ),
+        Value::Tag(tag, inner) => Value::Tag(tag, Box::new(canonicalize(*inner)?)),
+        other => other,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Doc {
+        zeta: u32,
+        id: String,
+        alpha: Vec<f64>,
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let doc = Doc {
+            zeta: 7,
+            id: "d1".into(),
+            alpha: vec![1.5, -0.25],
+        };
+        assert_eq!(from_slice::<Doc>(&to_vec(&doc).unwrap()).unwrap(), doc);
+        assert_eq!(
+            from_slice::<Doc>(&to_vec_deterministic(&doc).unwrap()).unwrap(),
+            doc
+        );
+        assert!(matches!(
+            from_slice::<Doc>(&[0xa1]),
+            Err(CborError::Decode(_))
+        ));
+    }
+
+    #[test]
+    fn test_deterministic_key_order() {
+        let doc = Doc {
+            zeta: 1,
+            id: "x".into(),
+            alpha: vec![],
+        };
+        // Keys sort by their encoding: "id" (0x62 ...) before "zeta" and
+        // "alpha" (0x64 ..., 0x65 ...), so shorter keys come first.
+        let expected = [
+            0xa3, // map(3)
+            0x62, b'i', b'd', 0x61, b'x', // "id": "x"
+            0x64, b'z', b'e', b't', b'a', 0x01, // "zeta": 1
+            0x65, b'a', b'l', b'p', b'h', b'a', 0x80, // "alpha": []
+        ];
+        assert_eq!(to_vec_deterministic(&doc).unwrap(), expected);
+
+        // Integer keys sort before text keys, nested maps are sorted too.
+        let value = Value::Array(vec![Value::Map(vec![
+            (Value::Text("b".into()), Value::Integer(1.into())),
+            (Value::Integer(10.into()), Value::Integer(2.into())),
+        ])]);
+        assert_eq!(
+            to_vec_deterministic(&value).unwrap(),
+            [0x81, 0xa2, 0x0a, 0x02, 0x61, b'b', 0x01]
+        );
+    }
+
+    #[test]
+    fn test_shortest_encodings() {
+        assert_eq!(to_vec_deterministic(&1.5_f64).unwrap(), [0xf9, 0x3e, 0x00]);
+        assert_eq!(to_vec_deterministic(&500_u64).unwrap(), [0x19, 0x01, 0xf4]);
+    }
+
+    #[test]
+    fn test_duplicate_keys() {
+        let value = Value::Map(vec![
+            (Value::Text("a".into()), Value::Null),
+            (Value::Text("a".into()), Value::Bool(true)),
+        ]);
+        assert_eq!(to_vec_deterministic(&value), Err(CborError::DuplicateKey));
+    }
+}
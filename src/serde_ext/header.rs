@@ -0,0 +1,391 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! The preamble shared by every file pizza writes to disk.
+//!
+//! ```text
+//! +-------+--------+---------+----------+-------+-------------+-------------+------------+
+//! | magic | format | version | reserved | flags | payload len | payload crc | header crc |
+//! |  4    |   4    |  u16    |   u16    |  u32  |    u64      |    u32      |    u32     |
+//! +-------+--------+---------+----------+-------+-------------+-------------+------------+
+//! ```
+//!
+//! All integers are little endian and both checksums are CRC-32C. The header
+//! checksum covers the 28 bytes before it. Files that are written
+//! incrementally, such as WAL segments, cannot know their payload upfront:
+//! they set [`FLAG_STREAMING`] and leave the payload length and checksum at
+//! zero, relying on their own framing instead. The low 16 flag bits are
+//! left to each format, bits 16 to 30 are reserved and must be zero.
+
+use core::fmt;
+
+use crate::utils::checksum::crc32c;
+
+/// Magic bytes at the beginning of every pizza file.
+pub const HEADER_MAGIC: [u8; 4] = *b"PIZZ";
+/// Length of an encoded [`Header`] in bytes.
+pub const HEADER_LEN: usize = 32;
+/// Flag set when the payload length and checksum are not recorded.
+pub const FLAG_STREAMING: u32 = 1 << 31;
+/// Flags in this mask are left to the individual formats.
+pub const FORMAT_FLAGS_MASK: u32 = 0xffff;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeaderError {
+    /// Fewer than [`HEADER_LEN`] bytes were available.
+    TooShort(usize),
+    BadMagic([u8; 4]),
+    /// The header checksum does not match, or reserved bits are set.
+    Corrupted,
+    UnexpectedFormat {
+        expected: [u8; 4],
+        found: [u8; 4],
+    },
+    UnsupportedVersion {
+        found: u16,
+        max: u16,
+    },
+    PayloadLength {
+        expected: u64,
+        found: u64,
+    },
+    PayloadChecksum,
+}
+
+impl fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HeaderError::TooShort(len) => {
+                write!(f, "header needs {} bytes, got {}", HEADER_LEN, len)
+            }
+            HeaderError::BadMagic(magic) => write!(f, "bad magic bytes {:02x?}", magic),
+            HeaderError::Corrupted => f.write_str("header checksum mismatch or reserved bits set"),
+            HeaderError::UnexpectedFormat { expected, found } => write!(
+                f,
+                "expected format {}, found {}",
+                FormatId(expected),
+                FormatId(found)
+            ),
+            HeaderError::UnsupportedVersion { found, max } => write!(
+                f,
+                "format version {} is newer than the supported version {}",
+                found, max
+            ),
+            HeaderError::PayloadLength { expected, found } => write!(
+                f,
+                "payload length mismatch: header says {} bytes, found {}",
+                expected, found
+            ),
+            HeaderError::PayloadChecksum => f.write_str("payload checksum mismatch"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<HeaderError> for std::io::Error {
+    fn from(err: HeaderError) -> Self {
+        std::io::Error::new(
+            std::io::ErrorKind::InvalidData,
+            alloc::string::ToString::to_string(&err),
+        )
+    }
+}
+
+struct FormatId<'a>(&'a [u8; 4]);
+
+impl fmt::Display for FormatId<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for &b in self.0 {
+            if b.is_ascii_graphic() {
+                write!(f, "{}", b as char)?;
+            } else {
+                write!(f, "\\x{:02x}", b)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The self-describing header at the start of pizza files.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::serde_ext::Header;
+///
+/// let payload = b"some state";
+/// let mut file = Header::new(*b"DEMO", 1, payload).to_bytes().to_vec();
+/// file.extend_from_slice(payload);
+///
+/// let (header, body) = Header::parse_with_payload(&file).unwrap();
+/// header.expect(*b"DEMO", 1).unwrap();
+/// assert_eq!(body, payload);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    pub format: [u8; 4],
+    pub version: u16,
+    pub flags: u32,
+    pub payload_len: u64,
+    pub payload_crc: u32,
+}
+
+impl Header {
+    /// A header describing `payload`.
+    pub fn new(format: [u8; 4], version: u16, payload: &[u8]) -> Self {
+        Self {
+            format,
+            version,
+            flags: 0,
+            payload_len: payload.len() as u64,
+            payload_crc: crc32c(payload),
+        }
+    }
+
+    /// A header for a payload that is written incrementally.
+    pub fn streaming(format: [u8; 4], version: u16) -> Self {
+        Self {
+            format,
+            version,
+            flags: FLAG_STREAMING,
+            payload_len: 0,
+            payload_crc: 0,
+        }
+    }
+
+    /// Sets the format specific flags, see [`FORMAT_FLAGS_MASK`].
+    ///
+    /// # Panics
+    ///
+    /// Panics if `flags` has bits outside of [`FORMAT_FLAGS_MASK`].
+    pub fn with_format_flags(mut self, flags: u32) -> Self {
+        assert_eq!(flags & !FORMAT_FLAGS_MASK, 0, "reserved header flags");
+        self.flags = (self.flags & !FORMAT_FLAGS_MASK) | flags;
+        self
+    }
+
+    pub fn format_flags(&self) -> u32 {
+        self.flags & FORMAT_FLAGS_MASK
+    }
+
+    pub fn is_streaming(&self) -> bool {
+        self.flags & FLAG_STREAMING != 0
+    }
+
+    pub fn to_bytes(&self) -> [u8; HEADER_LEN] {
+        let mut bytes = [0u8; HEADER_LEN];
+        bytes[..4].copy_from_slice(&HEADER_MAGIC);
+        bytes[4..8].copy_from_slice(&self.format);
+        bytes[8..10].copy_from_slice(&self.version.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.flags.to_le_bytes());
+        bytes[16..24].copy_from_slice(&self.payload_len.to_le_bytes());
+        bytes[24..28].copy_from_slice(&self.payload_crc.to_le_bytes());
+        let checksum = crc32c(&bytes[..28]);
+        bytes[28..].copy_from_slice(&checksum.to_le_bytes());
+        bytes
+    }
+
+    /// Parses and validates the header at the start of `bytes`. Trailing
+    /// bytes are ignored.
+    pub fn parse(bytes: &[u8]) -> Result<Self, HeaderError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(HeaderError::TooShort(bytes.len()));
+        }
+        let bytes = &bytes[..HEADER_LEN];
+        let field = |at: usize| -> [u8; 4] { bytes[at..at + 4].try_into().unwrap() };
+
+        if field(0) != HEADER_MAGIC {
+            return Err(HeaderError::BadMagic(field(0)));
+        }
+        if crc32c(&bytes[..28]) != u32::from_le_bytes(field(28)) || bytes[10..12] != [0, 0] {
+            return Err(HeaderError::Corrupted);
+        }
+
+        let header = Self {
+            format: field(4),
+            version: u16::from_le_bytes([bytes[8], bytes[9]]),
+            flags: u32::from_le_bytes(field(12)),
+            payload_len: u64::from_le_bytes(bytes[16..24].try_into().unwrap()),
+            payload_crc: u32::from_le_bytes(field(24)),
+        };
+        if header.flags & !(FLAG_STREAMING | FORMAT_FLAGS_MASK) != 0 {
+            return Err(HeaderError::Corrupted);
+        }
+        if header.is_streaming() && (header.payload_len != 0 || header.payload_crc != 0) {
+            return Err(HeaderError::Corrupted);
+        }
+        Ok(header)
+    }
+
+    /// Parses the header and checks the payload following it. The payload
+    /// of a streaming header is returned unchecked.
+    pub fn parse_with_payload(bytes: &[u8]) -> Result<(Self, &[u8]), HeaderError> {
+        let header = Self::parse(bytes)?;
+        let payload = &bytes[HEADER_LEN..];
+        header.verify_payload(payload)?;
+        Ok((header, payload))
+    }
+
+    /// Checks the format id and that the version is at most `max_version`.
+    pub fn expect(&self, format: [u8; 4], max_version: u16) -> Result<(), HeaderError> {
+        if self.format != format {
+            return Err(HeaderError::UnexpectedFormat {
+                expected: format,
+                found: self.format,
+            });
+        }
+        if self.version > max_version {
+            return Err(HeaderError::UnsupportedVersion {
+                found: self.version,
+                max: max_version,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks the length and checksum of `payload`, a no-op for streaming
+    /// headers.
+    pub fn verify_payload(&self, payload: &[u8]) -> Result<(), HeaderError> {
+        if self.is_streaming() {
+            return Ok(());
+        }
+        if payload.len() as u64 != self.payload_len {
+            return Err(HeaderError::PayloadLength {
+                expected: self.payload_len,
+                found: payload.len() as u64,
+            });
+        }
+        if crc32c(payload) != self.payload_crc {
+            return Err(HeaderError::PayloadChecksum);
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    pub fn write_to(&self, writer: &mut impl std::io::Write) -> std::io::Result<()> {
+        writer.write_all(&self.to_bytes())
+    }
+
+    /// Reads and validates a header, see [`Header::parse`].
+    #[cfg(feature = "std")]
+    pub fn read_from(reader: &mut impl std::io::Read) -> std::io::Result<Self> {
+        let mut bytes = [0u8; HEADER_LEN];
+        reader.read_exact(&mut bytes)?;
+        Ok(Self::parse(&bytes)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+
+    fn file(header: Header, payload: &[u8]) -> Vec<u8> {
+        let mut bytes = header.to_bytes().to_vec();
+        bytes.extend_from_slice(payload);
+        bytes
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let header = Header::new(*b"TEST", 3, b"payload").with_format_flags(0x0101);
+        let bytes = header.to_bytes();
+        assert_eq!(&bytes[..8], b"PIZZTEST");
+        assert_eq!(Header::parse(&bytes).unwrap(), header);
+        assert_eq!(header.format_flags(), 0x0101);
+        assert!(!header.is_streaming());
+
+        let streaming = Header::streaming(*b"TEST", 1);
+        assert!(streaming.is_streaming());
+        let bytes = file(streaming, b"anything");
+        let (parsed, payload) = Header::parse_with_payload(&bytes).unwrap();
+        assert_eq!(parsed, streaming);
+        assert_eq!(payload, b"anything");
+    }
+
+    #[test]
+    fn test_validation_errors() {
+        let header = Header::new(*b"TEST", 2, b"payload");
+        let bytes = file(header, b"payload");
+
+        assert_eq!(Header::parse(&bytes[..31]), Err(HeaderError::TooShort(31)));
+
+        let mut corrupted = bytes.clone();
+        corrupted[0] = b'X';
+        assert_eq!(
+            Header::parse(&corrupted),
+            Err(HeaderError::BadMagic(*b"XIZZ"))
+        );
+
+        for at in [4, 8, 10, 16, 28] {
+            let mut corrupted = bytes.clone();
+            corrupted[at] ^= 1;
+            assert_eq!(Header::parse(&corrupted), Err(HeaderError::Corrupted));
+        }
+
+        // Reserved flags with a valid checksum.
+        for flag in [1 << 16, 1 << 30] {
+            let mut reserved = header;
+            reserved.flags = flag;
+            assert_eq!(
+                Header::parse(&reserved.to_bytes()),
+                Err(HeaderError::Corrupted)
+            );
+        }
+
+        assert_eq!(
+            header.expect(*b"ELSE", 2),
+            Err(HeaderError::UnexpectedFormat {
+                expected: *b"ELSE",
+                found: *b"TEST"
+            })
+        );
+        assert_eq!(
+            header.expect(*b"TEST", 1),
+            Err(HeaderError::UnsupportedVersion { found: 2, max: 1 })
+        );
+        assert_eq!(header.expect(*b"TEST", 2), Ok(()));
+
+        assert_eq!(
+            Header::parse_with_payload(&bytes[..bytes.len() - 1]),
+            Err(HeaderError::PayloadLength {
+                expected: 7,
+                found: 6
+            })
+        );
+        let mut corrupted = bytes.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            Header::parse_with_payload(&corrupted),
+            Err(HeaderError::PayloadChecksum)
+        );
+        assert_eq!(
+            HeaderError::UnexpectedFormat {
+                expected: *b"ELSE",
+                found: [b'T', 0, b'S', b'T']
+            }
+            .to_string(),
+            "expected format ELSE, found T\\x00ST"
+        );
+    }
+}
@@ -0,0 +1,42 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Serialization helpers shared by everything pizza persists or exchanges.
+
+#[cfg(feature = "rkyv")]
+pub mod archive;
+#[cfg(feature = "cbor")]
+pub mod cbor;
+mod header;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+mod versioned;
+
+pub use header::Header;
+pub use header::HeaderError;
+pub use header::FLAG_STREAMING;
+pub use header::FORMAT_FLAGS_MASK;
+pub use header::HEADER_LEN;
+pub use header::HEADER_MAGIC;
+pub use versioned::MigrationError;
+pub use versioned::Migrations;
+pub use versioned::Versioned;
@@ -0,0 +1,153 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! MessagePack encoding configured the same way across pizza services.
+//!
+//! Structs are encoded as maps keyed by field name, so fields can be added
+//! or reordered without breaking existing data, and
+//! [`Uuid`](crate::utils::uuid::Uuid)s use their compact byte
+//! representation. Other binary formats keep the hex string, so data they
+//! already stored stays readable.
+
+use alloc::vec::Vec;
+use serde::Deserialize;
+use serde::Serialize;
+use std::io;
+
+pub use rmp_serde::decode::Error as DecodeError;
+pub use rmp_serde::encode::Error as EncodeError;
+
+/// Encodes `value` into a new buffer.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::serde_ext::msgpack;
+/// use pizza_common::utils::uuid::Uuid;
+///
+/// let id = Uuid::new();
+/// let bytes = msgpack::to_vec(&id).unwrap();
+/// assert_eq!(bytes.len(), 12);
+/// assert_eq!(msgpack::from_slice::<Uuid>(&bytes).unwrap(), id);
+/// ```
+pub fn to_vec<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, EncodeError> {
+    let mut bytes = Vec::new();
+    write(&mut bytes, value)?;
+    Ok(bytes)
+}
+
+/// Encodes `value` into `writer`.
+pub fn write<W, T>(writer: &mut W, value: &T) -> Result<(), EncodeError>
+where
+    W: io::Write + ?Sized,
+    T: Serialize + ?Sized,
+{
+    let mut serializer = rmp_serde::Serializer::new(writer)
+        .with_struct_map()
+        .with_binary();
+    crate::utils::uuid::with_binary_repr(|| value.serialize(&mut serializer))
+}
+
+/// Decodes a value from `bytes`, borrowing strings and bytes from it where
+/// the type allows.
+pub fn from_slice<'a, T: Deserialize<'a>>(bytes: &'a [u8]) -> Result<T, DecodeError> {
+    let mut deserializer = rmp_serde::Deserializer::from_read_ref(bytes).with_binary();
+    crate::utils::uuid::with_binary_repr(|| T::deserialize(&mut deserializer))
+}
+
+/// The number of bytes [`to_vec`] would produce for `value`, computed
+/// without allocating the output.
+pub fn encoded_len<T: Serialize + ?Sized>(value: &T) -> Result<usize, EncodeError> {
+    struct Counter(usize);
+
+    impl io::Write for Counter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0 += buf.len();
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let mut counter = Counter(0);
+    write(&mut counter, value)?;
+    Ok(counter.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::uuid::Uuid;
+    use alloc::string::String;
+    use alloc::string::ToString;
+    use alloc::vec;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Doc {
+        id: Uuid,
+        title: String,
+        tags: Vec<String>,
+    }
+
+    #[derive(Debug, PartialEq, Deserialize)]
+    struct DocV2 {
+        tags: Vec<String>,
+        id: Uuid,
+        #[serde(default)]
+        score: f32,
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let doc = Doc {
+            id: Uuid::new(),
+            title: "pizza".into(),
+            tags: vec!["food".into()],
+        };
+        let bytes = to_vec(&doc).unwrap();
+        assert_eq!(encoded_len(&doc).unwrap(), bytes.len());
+        assert_eq!(from_slice::<Doc>(&bytes).unwrap(), doc);
+
+        // Field names are encoded, so a reordered struct with new fields can
+        // still read the data.
+        let v2: DocV2 = from_slice(&bytes).unwrap();
+        assert_eq!(v2.id, doc.id);
+        assert_eq!(v2.tags, doc.tags);
+        assert_eq!(v2.score, 0.0);
+    }
+
+    #[test]
+    fn test_uuid_is_binary() {
+        let id = Uuid::from(7);
+        let bytes = to_vec(&id).unwrap();
+        assert_eq!(bytes[..2], [0xc4, 10]);
+        assert_eq!(&bytes[2..], id.as_bytes());
+
+        // The string form written by other encoders is accepted too.
+        let bytes = rmp_serde::to_vec(&id.to_string()).unwrap();
+        assert_eq!(from_slice::<Uuid>(&bytes).unwrap(), id);
+
+        assert!(from_slice::<Uuid>(&to_vec(&[1u8; 3][..]).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,305 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::string::ToString;
+use core::fmt;
+
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+
+/// A payload tagged with the schema version it was written with.
+///
+/// Serializes as `{"version": <u32>, "data": <payload>}`. Persist values
+/// through this envelope and load them through [`Migrations`], so that
+/// state written before a struct changed can still be read.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Versioned<T> {
+    pub version: u32,
+    pub data: T,
+}
+
+impl<T> Versioned<T> {
+    pub fn new(version: u32, data: T) -> Self {
+        Self { version, data }
+    }
+
+    pub fn into_inner(self) -> T {
+        self.data
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MigrationError {
+    /// The input is not a valid envelope, or the migrated payload does not
+    /// match the target type.
+    Malformed(String),
+    /// The payload was written by a newer schema than this build knows.
+    FutureVersion { found: u32, current: u32 },
+    /// No migration is registered from this version to the next.
+    MissingMigration { from: u32 },
+    /// A migration function rejected the payload.
+    Failed { from: u32, reason: String },
+}
+
+impl fmt::Display for MigrationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MigrationError::Malformed(reason) => {
+                write!(f, "malformed versioned payload: {}", reason)
+            }
+            MigrationError::FutureVersion { found, current } => write!(
+                f,
+                "payload version {} is newer than the supported version {}",
+                found, current
+            ),
+            MigrationError::MissingMigration { from } => {
+                write!(f, "no migration registered from version {}", from)
+            }
+            MigrationError::Failed { from, reason } => {
+                write!(f, "migration from version {} failed: {}", from, reason)
+            }
+        }
+    }
+}
+
+type MigrationFn = Box<dyn Fn(Value) -> Result<Value, String> + Send + Sync>;
+
+/// A registry of the functions upgrading a payload from one schema version
+/// to the next, working on the payload as a JSON [`Value`].
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::serde_ext::Migrations;
+/// use serde::Deserialize;
+/// use serde_json::json;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     name: String,
+///     replicas: u32,
+/// }
+///
+/// // Version 2 renamed `copies` to `replicas`.
+/// let migrations = Migrations::new(2).register(1, |mut value| {
+///     let copies = value["copies"].take();
+///     value["replicas"] = copies;
+///     Ok(value)
+/// });
+///
+/// let stored = json!({"version": 1, "data": {"name": "logs", "copies": 3}});
+/// let config: Config = migrations.load(stored).unwrap();
+/// assert_eq!(config.name, "logs");
+/// assert_eq!(config.replicas, 3);
+/// ```
+pub struct Migrations {
+    current: u32,
+    steps: BTreeMap<u32, MigrationFn>,
+}
+
+impl Migrations {
+    /// Creates an empty registry for the given current schema version.
+    pub fn new(current_version: u32) -> Self {
+        Self {
+            current: current_version,
+            steps: BTreeMap::new(),
+        }
+    }
+
+    /// Registers the migration from version `from` to `from + 1`, replacing
+    /// any previous one.
+    pub fn register<F>(mut self, from: u32, migration: F) -> Self
+    where
+        F: Fn(Value) -> Result<Value, String> + Send + Sync + 'static,
+    {
+        self.steps.insert(from, Box::new(migration));
+        self
+    }
+
+    pub fn current_version(&self) -> u32 {
+        self.current
+    }
+
+    /// Wraps `data` in an envelope tagged with the current version.
+    pub fn wrap<T>(&self, data: T) -> Versioned<T> {
+        Versioned::new(self.current, data)
+    }
+
+    /// Upgrades a payload to the current version.
+    pub fn migrate(&self, versioned: Versioned<Value>) -> Result<Value, MigrationError> {
+        let Versioned {
+            mut version,
+            mut data,
+        } = versioned;
+        if version > self.current {
+            return Err(MigrationError::FutureVersion {
+                found: version,
+                current: self.current,
+            });
+        }
+        while version < self.current {
+            let step = self
+                .steps
+                .get(&version)
+                .ok_or(MigrationError::MissingMigration { from: version })?;
+            data = step(data).map_err(|reason| MigrationError::Failed {
+                from: version,
+                reason,
+            })?;
+            version += 1;
+        }
+        Ok(data)
+    }
+
+    /// Loads a value from a serialized envelope, migrating it first if it
+    /// was written by an older version.
+    pub fn load<T: DeserializeOwned>(&self, envelope: Value) -> Result<T, MigrationError> {
+        let versioned: Versioned<Value> = serde_json::from_value(envelope)
+            .map_err(|e| MigrationError::Malformed(e.to_string()))?;
+        let data = self.migrate(versioned)?;
+        serde_json::from_value(data).map_err(|e| MigrationError::Malformed(e.to_string()))
+    }
+
+    /// Like [`Migrations::load`] for an envelope stored as JSON text.
+    pub fn load_str<T: DeserializeOwned>(&self, json: &str) -> Result<T, MigrationError> {
+        let envelope: Value =
+            serde_json::from_str(json).map_err(|e| MigrationError::Malformed(e.to_string()))?;
+        self.load(envelope)
+    }
+}
+
+impl fmt::Debug for Migrations {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Migrations")
+            .field("current", &self.current)
+            .field("steps", &self.steps.keys())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Settings {
+        name: String,
+        shards: u32,
+        #[serde(default)]
+        tags: alloc::vec::Vec<String>,
+    }
+
+    fn migrations() -> Migrations {
+        Migrations::new(3)
+            .register(1, |mut value| {
+                let count = value["shard_count"].take();
+                value.as_object_mut().unwrap().remove("shard_count");
+                value["shards"] = count;
+                Ok(value)
+            })
+            .register(2, |mut value| {
+                match value["tags"].take() {
+                    Value::String(tags) => {
+                        value["tags"] = tags.split(',').map(|tag| json!(tag)).collect();
+                    }
+                    Value::Null => {
+                        value.as_object_mut().unwrap().remove("tags");
+                    }
+                    other => return Err(alloc::format!("unexpected tags {}", other)),
+                }
+                Ok(value)
+            })
+    }
+
+    #[test]
+    fn test_round_trip_current_version() {
+        let migrations = migrations();
+        let settings = Settings {
+            name: "logs".into(),
+            shards: 2,
+            tags: alloc::vec!["hot".into()],
+        };
+        let json = serde_json::to_string(&migrations.wrap(&settings)).unwrap();
+        assert_eq!(
+            json,
+            r#"{"version":3,"data":{"name":"logs","shards":2,"tags":["hot"]}}"#
+        );
+        assert_eq!(migrations.load_str::<Settings>(&json).unwrap(), settings);
+    }
+
+    #[test]
+    fn test_migrates_old_versions() {
+        let migrations = migrations();
+        let v1 = json!({"version": 1, "data": {"name": "a", "shard_count": 5, "tags": "x,y"}});
+        let settings: Settings = migrations.load(v1).unwrap();
+        assert_eq!(settings.shards, 5);
+        assert_eq!(settings.tags, ["x", "y"]);
+
+        let v2 = json!({"version": 2, "data": {"name": "b", "shards": 1}});
+        let settings: Settings = migrations.load(v2).unwrap();
+        assert_eq!(settings.shards, 1);
+        assert!(settings.tags.is_empty());
+    }
+
+    #[test]
+    fn test_errors() {
+        let migrations = migrations();
+        assert_eq!(
+            migrations
+                .load::<Settings>(json!({"version": 4, "data": {}}))
+                .unwrap_err(),
+            MigrationError::FutureVersion {
+                found: 4,
+                current: 3
+            }
+        );
+        assert_eq!(
+            migrations
+                .load::<Settings>(json!({"version": 0, "data": {}}))
+                .unwrap_err(),
+            MigrationError::MissingMigration { from: 0 }
+        );
+        assert_eq!(
+            migrations
+                .load::<Settings>(json!({"version": 2, "data": {"tags": 1}}))
+                .unwrap_err(),
+            MigrationError::Failed {
+                from: 2,
+                reason: "unexpected tags 1".into()
+            }
+        );
+        assert!(matches!(
+            migrations.load::<Settings>(json!({"data": {}})),
+            Err(MigrationError::Malformed(_))
+        ));
+        assert!(matches!(
+            migrations.load_str::<Settings>(r#"{"version":3,"data":{"name":"x"}}"#),
+            Err(MigrationError::Malformed(_))
+        ));
+    }
+}
@@ -0,0 +1,273 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::serde_ext::Header;
+use crate::serde_ext::HeaderError;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::fs;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "std")]
+use std::io::Write;
+#[cfg(feature = "std")]
+use std::path::Path;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoreError {
+    /// The header is missing, corrupted, of another format, or the payload
+    /// checksum does not match.
+    Header(HeaderError),
+    /// The payload passed its checksum but could not be decoded.
+    Payload(String),
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::Header(err) => write!(f, "{}", err),
+            StoreError::Payload(reason) => write!(f, "invalid payload: {}", reason),
+        }
+    }
+}
+
+impl From<HeaderError> for StoreError {
+    fn from(err: HeaderError) -> Self {
+        StoreError::Header(err)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<StoreError> for io::Error {
+    fn from(err: StoreError) -> Self {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            alloc::string::ToString::to_string(&err),
+        )
+    }
+}
+
+/// State that can be saved as a self-describing blob.
+///
+/// Implementations only encode their payload, the provided methods add the
+/// [`Header`] with [`FORMAT`](Self::FORMAT), [`VERSION`](Self::VERSION)
+/// and the payload checksum.
+pub trait PersistStore {
+    /// Format id written into the header.
+    const FORMAT: [u8; 4];
+    /// Version of the payload layout written by
+    /// [`encode_payload`](Self::encode_payload).
+    const VERSION: u16;
+
+    fn encode_payload(&self, out: &mut Vec<u8>);
+
+    /// Encodes the header and the payload.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`encode_payload`](Self::encode_payload) does, like an
+    /// [`Arena`](crate::arena::Arena) with deallocated slots.
+    fn save(&self) -> Vec<u8> {
+        let mut payload = Vec::new();
+        self.encode_payload(&mut payload);
+        let mut bytes = Header::new(Self::FORMAT, Self::VERSION, &payload)
+            .to_bytes()
+            .to_vec();
+        bytes.extend_from_slice(&payload);
+        bytes
+    }
+
+    /// Atomically replaces the file at `path` with the saved state.
+    ///
+    /// The state is written and synced to a temporary file next to `path`,
+    /// which is then renamed over it, so a crash leaves either the previous
+    /// or the new checkpoint behind, never a torn one.
+    #[cfg(feature = "std")]
+    fn checkpoint(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        write_atomically(path.as_ref(), &self.save())
+    }
+}
+
+/// The body of [`PersistStore::checkpoint`], for implementations that
+/// override it to check their state first.
+#[cfg(feature = "std")]
+pub(crate) fn write_atomically(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut tmp = std::ffi::OsString::from(path.as_os_str());
+    tmp.push(".tmp");
+    let tmp = std::path::PathBuf::from(tmp);
+
+    {
+        let mut file = fs::File::create(&tmp)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+    }
+    fs::rename(&tmp, path)?;
+
+    // Persist the rename itself, not supported on every platform.
+    #[cfg(unix)]
+    if let Some(dir) = path.parent() {
+        let dir = if dir.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            dir
+        };
+        fs::File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+/// State that can be rebuilt from what [`PersistStore::save`] wrote.
+pub trait RecoverableStore: PersistStore + Sized {
+    /// Decodes a payload of the given version, which is never newer than
+    /// [`VERSION`](PersistStore::VERSION). Older versions should be migrated.
+    fn decode_payload(version: u16, payload: &[u8]) -> Result<Self, StoreError>;
+
+    /// Validates the header and checksum, then decodes the payload.
+    fn load(bytes: &[u8]) -> Result<Self, StoreError> {
+        let (header, payload) = Header::parse_with_payload(bytes)?;
+        header.expect(Self::FORMAT, Self::VERSION)?;
+        Self::decode_payload(header.version, payload)
+    }
+
+    /// Loads the checkpoint written to `path` by
+    /// [`PersistStore::checkpoint`].
+    #[cfg(feature = "std")]
+    fn recover(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Self::load(&fs::read(path)?)?)
+    }
+
+    /// Like [`recover`](Self::recover), but decodes straight from a memory
+    /// map of the checkpoint instead of reading it into memory first.
+    ///
+    /// # Safety
+    ///
+    /// The file must not be modified in place while it is decoded, see
+    /// [`Mmap::map`](crate::fsutil::Mmap::map). Checkpoints only ever
+    /// replace it atomically.
+    #[cfg(feature = "std")]
+    unsafe fn recover_mapped(path: impl AsRef<Path>) -> io::Result<Self> {
+        // SAFETY: Upheld by the caller.
+        let mmap = unsafe { crate::fsutil::Mmap::open(path)? };
+        Ok(Self::load(&mmap)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::arena::Arena;
+    use crate::utils::sequencer::Sequencer;
+
+    #[test]
+    fn test_sequencer_round_trip() {
+        let mut sequencer = Sequencer::new(0, 5, 100);
+        sequencer.nth(3);
+        let bytes = sequencer.save();
+        assert_eq!(bytes.len(), crate::serde_ext::HEADER_LEN + 12);
+
+        let mut restored = Sequencer::load(&bytes).unwrap();
+        assert_eq!(restored.current(), 20);
+        assert_eq!(restored.next(), sequencer.next());
+        assert_eq!(restored.free(), sequencer.free());
+    }
+
+    #[test]
+    fn test_load_errors() {
+        let bytes = Sequencer::new(0, 1, 10).save();
+        let mut corrupted = bytes.clone();
+        *corrupted.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            Sequencer::load(&corrupted).unwrap_err(),
+            StoreError::Header(HeaderError::PayloadChecksum)
+        );
+        assert!(matches!(
+            Arena::<u32>::load(&bytes),
+            Err(StoreError::Header(HeaderError::UnexpectedFormat { .. }))
+        ));
+
+        // A valid header around a payload that is too short.
+        let mut short = Header::new(Sequencer::FORMAT, Sequencer::VERSION, b"1234")
+            .to_bytes()
+            .to_vec();
+        short.extend_from_slice(b"1234");
+        assert!(matches!(
+            Sequencer::load(&short),
+            Err(StoreError::Payload(_))
+        ));
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_checkpoint_and_recover() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("arena.bin");
+
+        let arena = Arena::new(2, 100, 1024);
+        arena.alloc_slice(&[1u32, 2, 3]).unwrap();
+        arena.snapshot();
+        arena.checkpoint(&path).unwrap();
+        arena.alloc(4).unwrap();
+        arena.checkpoint(&path).unwrap();
+
+        let recovered = Arena::<u32>::recover(&path).unwrap();
+        assert_eq!(recovered.iter().copied().collect::<Vec<_>>(), [1, 2, 3, 4]);
+        assert_eq!(recovered.get_snapshot(0), [&1, &2, &3]);
+        assert!(!dir.path().join("arena.bin.tmp").exists());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_checkpoint_with_vacant_slots() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("arena.bin");
+
+        let mut arena = Arena::new(2, 100, 1024).with_free_list();
+        let (first, _) = arena.advanced_alloc(1u32).unwrap();
+        arena.alloc(2).unwrap();
+        assert!(arena.dealloc(first));
+        let err = arena.checkpoint(&path).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+        assert!(!path.exists());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_recover_mapped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("arena.bin");
+
+        let arena = Arena::new(2, 100, 1024);
+        arena.alloc_slice(&[1u32, 2, 3]).unwrap();
+        arena.checkpoint(&path).unwrap();
+
+        // SAFETY: Nothing else touches the checkpoint.
+        let recovered = unsafe { Arena::<u32>::recover_mapped(&path) }.unwrap();
+        assert_eq!(recovered.iter().copied().collect::<Vec<_>>(), [1, 2, 3]);
+
+        std::fs::write(&path, b"garbage").unwrap();
+        // SAFETY: As above.
+        let err = unsafe { Arena::<u32>::recover_mapped(&path) }.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
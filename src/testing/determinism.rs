@@ -0,0 +1,254 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use alloc::sync::Arc;
+use core::cell::RefCell;
+use core::marker::PhantomData;
+use rand_chacha::ChaCha8Rng;
+use rand_core::RngCore;
+use rand_core::SeedableRng;
+use std::eprintln;
+
+use crate::utils::time::Clock;
+use crate::utils::time::ManualClock;
+
+/// Environment variable overriding the seed of [`Determinism::new`].
+pub const SEED_ENV: &str = "PIZZA_TEST_SEED";
+
+/// Time the [`ManualClock`] of a [`Determinism`] guard starts at:
+/// 2023-11-14T22:13:20Z.
+pub const START_MILLIS: i64 = 1_700_000_000_000;
+
+struct Installed {
+    seed: u64,
+    rng: ChaCha8Rng,
+    uuids: ChaCha8Rng,
+    clock: Arc<ManualClock>,
+}
+
+std::thread_local! {
+    static INSTALLED: RefCell<Option<Installed>> = const { RefCell::new(None) };
+}
+
+pub(crate) fn next_rng_seed() -> Option<u64> {
+    INSTALLED.with(|installed| {
+        installed
+            .borrow_mut()
+            .as_mut()
+            .map(|installed| installed.rng.next_u64())
+    })
+}
+
+pub(crate) fn uuid_bytes() -> Option<[u8; 10]> {
+    INSTALLED.with(|installed| {
+        installed.borrow_mut().as_mut().map(|installed| {
+            let mut bytes = [0u8; 10];
+            installed.uuids.fill_bytes(&mut bytes);
+            bytes
+        })
+    })
+}
+
+pub(crate) fn now_millis() -> Option<i64> {
+    INSTALLED.with(|installed| {
+        installed
+            .borrow()
+            .as_ref()
+            .map(|installed| installed.clock.now_millis())
+    })
+}
+
+/// Makes the hidden sources of entropy of this crate reproducible for the
+/// current thread while the guard is alive:
+///
+/// * the helpers in [`utils::rand`](crate::utils::rand) draw from an RNG
+///   seeded with [`Determinism::seed`],
+/// * [`Uuid::new`](crate::utils::uuid::Uuid::new) returns a fixed sequence
+///   of ids derived from the same seed,
+/// * [`utils::time::now_millis`](crate::utils::time::now_millis) reads a
+///   [`ManualClock`] starting at [`START_MILLIS`].
+///
+/// Dropping the guard restores whatever was installed before. If the test
+/// panics, the seed is printed so the failure can be replayed by setting
+/// [`SEED_ENV`].
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::testing::Determinism;
+/// use pizza_common::utils::time::now_millis;
+/// use pizza_common::utils::uuid::Uuid;
+///
+/// let first = {
+///     let _guard = Determinism::with_seed(7);
+///     Uuid::new()
+/// };
+/// let guard = Determinism::with_seed(7);
+/// assert_eq!(Uuid::new(), first);
+///
+/// let start = now_millis();
+/// guard.clock().advance(1_000);
+/// assert_eq!(now_millis(), start + 1_000);
+/// ```
+pub struct Determinism {
+    seed: u64,
+    clock: Arc<ManualClock>,
+    previous: Option<Installed>,
+    // The installed state is thread-local, the guard must stay on its thread.
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Determinism {
+    /// Installs a guard seeded from [`SEED_ENV`], or from fresh entropy when
+    /// the variable is not set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if [`SEED_ENV`] is set but is not a `u64`.
+    pub fn new() -> Self {
+        let seed = match std::env::var(SEED_ENV) {
+            Ok(seed) => seed
+                .parse()
+                .unwrap_or_else(|_| panic!("{} must be a u64, got {:?}", SEED_ENV, seed)),
+            Err(_) => {
+                let mut bytes = [0u8; 8];
+                getrandom::getrandom(&mut bytes).expect("failed to gather entropy");
+                u64::from_le_bytes(bytes)
+            }
+        };
+        Self::with_seed(seed)
+    }
+
+    /// Installs a guard with an explicit seed.
+    pub fn with_seed(seed: u64) -> Self {
+        let clock = Arc::new(ManualClock::new(START_MILLIS));
+        let installed = Installed {
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            uuids: ChaCha8Rng::seed_from_u64(seed ^ 0x9e37_79b9_7f4a_7c15),
+            clock: clock.clone(),
+        };
+        let previous = INSTALLED.with(|cell| cell.borrow_mut().replace(installed));
+        Self {
+            seed,
+            clock,
+            previous,
+            _not_send: PhantomData,
+        }
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The clock behind [`utils::time::now_millis`](crate::utils::time::now_millis)
+    /// while this guard is installed.
+    pub fn clock(&self) -> &Arc<ManualClock> {
+        &self.clock
+    }
+
+    /// A new RNG for the test itself, derived from the seed.
+    pub fn rng(&self) -> ChaCha8Rng {
+        ChaCha8Rng::seed_from_u64(next_rng_seed().unwrap_or(self.seed))
+    }
+}
+
+impl Default for Determinism {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for Determinism {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        let restored = INSTALLED.with(|cell| core::mem::replace(&mut *cell.borrow_mut(), previous));
+        debug_assert_eq!(
+            restored.map(|installed| installed.seed),
+            Some(self.seed),
+            "Determinism guards dropped out of order"
+        );
+        if std::thread::panicking() {
+            eprintln!(
+                "test failed with determinism seed {0}, rerun with {1}={0}",
+                self.seed, SEED_ENV
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::rand::generate_random_string;
+    use crate::utils::time::now_millis as clock_now;
+    use crate::utils::uuid::Uuid;
+    use alloc::vec::Vec;
+
+    fn sample() -> (Vec<Uuid>, alloc::string::String, i64) {
+        let uuids = (0..3).map(|_| Uuid::new()).collect();
+        (uuids, generate_random_string((2, 4), (3, 6)), clock_now())
+    }
+
+    #[test]
+    fn test_same_seed_same_values() {
+        let first = {
+            let _guard = Determinism::with_seed(42);
+            sample()
+        };
+        let second = {
+            let _guard = Determinism::with_seed(42);
+            sample()
+        };
+        assert_eq!(first, second);
+        assert_eq!(first.2, START_MILLIS);
+
+        let other = {
+            let _guard = Determinism::with_seed(43);
+            sample()
+        };
+        assert_ne!(first.0, other.0);
+    }
+
+    #[test]
+    fn test_guard_restores_previous_state() {
+        assert_eq!(now_millis(), None);
+        let outer = Determinism::with_seed(1);
+        outer.clock().advance(5);
+        {
+            let inner = Determinism::with_seed(2);
+            assert_eq!(inner.seed(), 2);
+            assert_eq!(clock_now(), START_MILLIS);
+        }
+        assert_eq!(clock_now(), START_MILLIS + 5);
+        drop(outer);
+        assert_eq!(now_millis(), None);
+        assert_ne!(Uuid::new(), Uuid::new());
+    }
+
+    #[test]
+    fn test_rng_is_reproducible() {
+        let a = Determinism::with_seed(9).rng().next_u64();
+        let b = Determinism::with_seed(9).rng().next_u64();
+        assert_eq!(a, b);
+    }
+}
@@ -0,0 +1,280 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use serde_json::Value;
+use std::fs;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Set this environment variable to rewrite the fixtures with the actual
+/// values instead of comparing against them.
+pub const UPDATE_GOLDEN_ENV: &str = "PIZZA_UPDATE_GOLDEN";
+
+/// A value compared by [`assert_golden`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum GoldenValue {
+    /// Compared byte for byte.
+    Bytes(Vec<u8>),
+    /// Compared line by line.
+    Text(String),
+    /// Compared structurally, so key order and whitespace in the fixture do
+    /// not matter.
+    Json(Value),
+}
+
+impl From<&[u8]> for GoldenValue {
+    fn from(bytes: &[u8]) -> Self {
+        GoldenValue::Bytes(bytes.to_vec())
+    }
+}
+
+impl<const N: usize> From<&[u8; N]> for GoldenValue {
+    fn from(bytes: &[u8; N]) -> Self {
+        GoldenValue::Bytes(bytes.to_vec())
+    }
+}
+
+impl From<Vec<u8>> for GoldenValue {
+    fn from(bytes: Vec<u8>) -> Self {
+        GoldenValue::Bytes(bytes)
+    }
+}
+
+impl From<&str> for GoldenValue {
+    fn from(text: &str) -> Self {
+        GoldenValue::Text(text.to_string())
+    }
+}
+
+impl From<String> for GoldenValue {
+    fn from(text: String) -> Self {
+        GoldenValue::Text(text)
+    }
+}
+
+impl From<Value> for GoldenValue {
+    fn from(value: Value) -> Self {
+        GoldenValue::Json(value)
+    }
+}
+
+impl From<&Value> for GoldenValue {
+    fn from(value: &Value) -> Self {
+        GoldenValue::Json(value.clone())
+    }
+}
+
+/// Where [`assert_golden`] stores the fixture called `name`:
+/// `tests/golden/<name>` under the package being tested.
+pub fn golden_path(name: &str) -> PathBuf {
+    let root = std::env::var_os("CARGO_MANIFEST_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_default();
+    root.join("tests").join("golden").join(name)
+}
+
+/// Asserts that `actual` matches the fixture stored at [`golden_path`].
+///
+/// When [`UPDATE_GOLDEN_ENV`] is set, the fixture is (re)written instead,
+/// so intended changes are accepted by rerunning the tests once with it.
+///
+/// # Panics
+///
+/// Panics with a readable difference if the fixture does not match, or if
+/// it does not exist yet.
+///
+/// # Examples
+///
+/// ```no_run
+/// use pizza_common::testing::assert_golden;
+/// use serde_json::json;
+///
+/// assert_golden("mapping.json", json!({"properties": {"title": {"type": "text"}}}));
+/// assert_golden("header.bin", &[0x50, 0x49, 0x5a, 0x5a]);
+/// ```
+#[track_caller]
+pub fn assert_golden(name: &str, actual: impl Into<GoldenValue>) {
+    let update = std::env::var_os(UPDATE_GOLDEN_ENV).is_some();
+    if let Err(message) = check_golden(&golden_path(name), &actual.into(), update) {
+        panic!("golden file {} mismatch\n{}", name, message);
+    }
+}
+
+fn check_golden(path: &Path, actual: &GoldenValue, update: bool) -> Result<(), String> {
+    let encoded = match actual {
+        GoldenValue::Bytes(bytes) => bytes.clone(),
+        GoldenValue::Text(text) => text.clone().into_bytes(),
+        GoldenValue::Json(value) => {
+            let mut text = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+            text.push('\n');
+            text.into_bytes()
+        }
+    };
+
+    if update {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        return fs::write(path, encoded).map_err(|e| e.to_string());
+    }
+
+    let expected = fs::read(path).map_err(|e| {
+        format!(
+            "cannot read {}: {}\nrun the test with {}=1 to create it",
+            path.display(),
+            e,
+            UPDATE_GOLDEN_ENV
+        )
+    })?;
+
+    let difference = match actual {
+        GoldenValue::Bytes(bytes) => diff_bytes(&expected, bytes),
+        GoldenValue::Text(text) => diff_text(&String::from_utf8_lossy(&expected), text),
+        GoldenValue::Json(value) => {
+            let expected: Value = serde_json::from_slice(&expected)
+                .map_err(|e| format!("fixture is not valid JSON: {}", e))?;
+            diff_json(&expected, value)
+        }
+    };
+    match difference {
+        None => Ok(()),
+        Some(difference) => Err(format!(
+            "{}\nrun the test with {}=1 to accept the new output",
+            difference, UPDATE_GOLDEN_ENV
+        )),
+    }
+}
+
+fn diff_bytes(expected: &[u8], actual: &[u8]) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+    let at = expected
+        .iter()
+        .zip(actual)
+        .position(|(a, b)| a != b)
+        .unwrap_or(expected.len().min(actual.len()));
+    let window = |bytes: &[u8]| {
+        let end = bytes.len().min(at + 16);
+        format!("{:02x?}", &bytes[at.min(end)..end])
+    };
+    Some(format!(
+        "bytes differ at offset {} (expected {} bytes, got {})\n- {}\n+ {}",
+        at,
+        expected.len(),
+        actual.len(),
+        window(expected),
+        window(actual)
+    ))
+}
+
+fn diff_text(expected: &str, actual: &str) -> Option<String> {
+    if expected == actual {
+        return None;
+    }
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+    let mut out = String::new();
+    for line in 0..expected.len().max(actual.len()) {
+        let (e, a) = (expected.get(line), actual.get(line));
+        if e != a {
+            if let Some(e) = e {
+                let _ = writeln!(out, "{:>4} - {}", line + 1, e);
+            }
+            if let Some(a) = a {
+                let _ = writeln!(out, "{:>4} + {}", line + 1, a);
+            }
+        }
+    }
+    if out.is_empty() {
+        out.push_str("texts differ in line endings or trailing newline");
+    }
+    Some(out)
+}
+
+fn diff_json(expected: &Value, actual: &Value) -> Option<String> {
+    let patch = crate::utils::json::diff_json(expected, actual);
+    (!patch.is_empty()).then(|| patch.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_create_then_compare() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("nested").join("doc.json");
+        let doc = GoldenValue::from(json!({"b": [1, 2], "a": "x"}));
+
+        let missing = check_golden(&path, &doc, false).unwrap_err();
+        assert!(missing.contains(UPDATE_GOLDEN_ENV));
+
+        check_golden(&path, &doc, true).unwrap();
+        check_golden(&path, &doc, false).unwrap();
+
+        // Key order and formatting of the fixture do not matter.
+        fs::write(&path, r#"{"a":"x","b":[1,2]}"#).unwrap();
+        check_golden(&path, &doc, false).unwrap();
+
+        let changed = GoldenValue::from(json!({"b": [1, 3], "c": null}));
+        let message = check_golden(&path, &changed, false).unwrap_err();
+        assert!(
+            message.contains("/a: missing, expected \"x\""),
+            "{}",
+            message
+        );
+        assert!(message.contains("/b/1: expected 2, got 3"), "{}", message);
+        assert!(message.contains("/c: unexpected null"), "{}", message);
+    }
+
+    #[test]
+    fn test_text_and_bytes() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("out.txt");
+        check_golden(&path, &"one\ntwo\n".into(), true).unwrap();
+        let message = check_golden(&path, &"one\nthree\n".into(), false).unwrap_err();
+        assert!(message.contains("   2 - two\n   2 + three"), "{}", message);
+
+        let path = dir.path().join("out.bin");
+        check_golden(&path, &b"\x00\x01\x02".into(), true).unwrap();
+        check_golden(&path, &b"\x00\x01\x02".into(), false).unwrap();
+        let message = check_golden(&path, &b"\x00\x01".into(), false).unwrap_err();
+        assert!(
+            message.contains("offset 2 (expected 3 bytes, got 2)"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn test_golden_path() {
+        let path = golden_path("a/b.json");
+        assert!(path.ends_with("tests/golden/a/b.json"));
+    }
+}
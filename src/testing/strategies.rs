@@ -0,0 +1,155 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! [`proptest`](mod@proptest) strategies producing realistic pizza-common values.
+//!
+//! # Examples
+//!
+//! ```
+//! use pizza_common::testing::strategies;
+//! use pizza_common::utils::uuid::Uuid;
+//! use proptest::prelude::*;
+//! use proptest::test_runner::TestRunner;
+//!
+//! let mut runner = TestRunner::default();
+//! runner
+//!     .run(&strategies::uuid(), |id| {
+//!         prop_assert_eq!(id.to_string().parse::<Uuid>().unwrap(), id);
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//! ```
+
+use alloc::string::String;
+use core::fmt;
+use core::ops::Range;
+
+use proptest::collection::btree_map;
+use proptest::collection::vec;
+use proptest::prelude::*;
+use serde_json::Value;
+
+use crate::arena::Arena;
+use crate::utils::sequencer::Sequencer;
+use crate::utils::uuid::Uuid;
+
+/// Any [`Uuid`].
+pub fn uuid() -> impl Strategy<Value = Uuid> {
+    // SAFETY: every 10-byte array is a valid Uuid.
+    any::<[u8; 10]>().prop_map(|bytes| unsafe { Uuid::from_bytes(bytes) })
+}
+
+/// A [`Sequencer`] with a positive step whose offset does not exceed its
+/// maximum.
+pub fn sequencer() -> impl Strategy<Value = Sequencer> {
+    (0..u32::MAX / 2, 1..1024_u32, 0..u32::MAX / 2).prop_map(|(a, step, b)| {
+        let (offset, max) = if a <= b { (a, b) } else { (b, a) };
+        Sequencer::new(offset, step, max)
+    })
+}
+
+fn json_leaf() -> impl Strategy<Value = Value> {
+    prop_oneof![
+        Just(Value::Null),
+        any::<bool>().prop_map(Value::Bool),
+        any::<i64>().prop_map(Value::from),
+        // Quarters survive a JSON round trip exactly.
+        (-1_000_000..1_000_000_i32).prop_map(|n| Value::from(n as f64 / 4.0)),
+        "[a-zA-Z0-9 _\\-\u{e9}\u{4e2d}]{0,16}".prop_map(Value::String),
+    ]
+}
+
+/// JSON trees nested at most `depth` levels deep. Numbers are always
+/// finite, as JSON requires.
+pub fn json_value(depth: u32) -> impl Strategy<Value = Value> {
+    json_leaf().prop_recursive(depth, 64, 8, |inner| {
+        prop_oneof![
+            vec(inner.clone(), 0..8).prop_map(Value::Array),
+            btree_map("[a-z_]{1,8}", inner, 0..8)
+                .prop_map(|map| Value::Object(map.into_iter().collect())),
+        ]
+    })
+}
+
+/// An [`Arena`] holding `len` values drawn from `element`.
+///
+/// The initial chunk capacity is randomized as well, so the items span
+/// anywhere from one to many chunks.
+pub fn arena<T, S>(element: S, len: Range<usize>) -> impl Strategy<Value = Arena<T>>
+where
+    T: fmt::Debug + Clone,
+    S: Strategy<Value = T>,
+{
+    (vec(element, len), 1..16_usize).prop_map(|(items, initial_capacity)| {
+        let arena = Arena::new(initial_capacity, usize::MAX, usize::MAX);
+        for item in items {
+            arena.must_alloc(item);
+        }
+        arena
+    })
+}
+
+/// Short lowercase ASCII words, handy as arena payloads or map keys.
+pub fn word() -> impl Strategy<Value = String> {
+    "[a-z]{1,12}"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn depth(value: &Value) -> u32 {
+        match value {
+            Value::Array(items) => 1 + items.iter().map(depth).max().unwrap_or(0),
+            Value::Object(map) => 1 + map.values().map(depth).max().unwrap_or(0),
+            _ => 0,
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_uuid_round_trip(id in uuid()) {
+            let encoded = serde_json::to_string(&id).unwrap();
+            prop_assert_eq!(serde_json::from_str::<Uuid>(&encoded).unwrap(), id);
+        }
+
+        #[test]
+        fn test_sequencer_is_bounded(sequencer in sequencer()) {
+            prop_assert!(sequencer.current() <= sequencer.current() + sequencer.free());
+            let max = sequencer.current() + sequencer.free();
+            prop_assert!(sequencer.take(8).all(|value| value <= max));
+        }
+
+        #[test]
+        fn test_json_depth(value in json_value(3)) {
+            prop_assert!(depth(&value) <= 3);
+            let encoded = serde_json::to_string(&value).unwrap();
+            prop_assert_eq!(serde_json::from_str::<Value>(&encoded).unwrap(), value);
+        }
+
+        #[test]
+        fn test_arena_len(arena in arena(word(), 0..64)) {
+            prop_assert!(arena.total_items() < 64);
+            prop_assert_eq!(arena.iter().count(), arena.total_items());
+        }
+    }
+}
@@ -0,0 +1,442 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! A minimal RFC 4180 CSV reader and writer.
+//!
+//! The [`Reader`] iterates over the records of an in-memory `&str` and
+//! borrows every field from it, only allocating for quoted fields that
+//! contain `""` escapes. The [`Writer`] quotes fields when needed and writes
+//! to any [`core::fmt::Write`], such as a `String`.
+
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Index;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CsvError {
+    /// A quoted field is not closed before the end of the input.
+    UnterminatedQuote { line: usize },
+    /// A closing quote is followed by something other than a delimiter or
+    /// the end of the record.
+    UnexpectedCharacter { line: usize },
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::UnterminatedQuote { line } => {
+                write!(f, "unterminated quoted field starting on line {}", line)
+            }
+            CsvError::UnexpectedCharacter { line } => {
+                write!(
+                    f,
+                    "unexpected character after closing quote on line {}",
+                    line
+                )
+            }
+        }
+    }
+}
+
+/// A record of fields borrowed from the input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record<'a> {
+    fields: Vec<Cow<'a, str>>,
+    line: usize,
+}
+
+impl<'a> Record<'a> {
+    /// The 1-based line the record starts on.
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn len(&self) -> usize {
+        self.fields.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&str> {
+        self.fields.get(index).map(|field| field.as_ref())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.fields.iter().map(|field| field.as_ref())
+    }
+
+    pub fn into_fields(self) -> Vec<Cow<'a, str>> {
+        self.fields
+    }
+}
+
+impl Index<usize> for Record<'_> {
+    type Output = str;
+
+    fn index(&self, index: usize) -> &str {
+        &self.fields[index]
+    }
+}
+
+/// An iterator over the records of CSV text.
+///
+/// Records end at `\n` or `\r\n`, and empty lines are skipped. Quotes in the
+/// middle of an unquoted field are kept literally.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::text::csv::Reader;
+///
+/// let input = "name,toppings\nmargherita,\"tomato, mozzarella\"\n";
+/// let records: Vec<_> = Reader::new(input).collect::<Result<_, _>>().unwrap();
+/// assert_eq!(records[1].get(1), Some("tomato, mozzarella"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Reader<'a> {
+    input: &'a str,
+    pos: usize,
+    line: usize,
+    delimiter: u8,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            input,
+            pos: 0,
+            line: 1,
+            delimiter: b',',
+        }
+    }
+
+    /// Sets the field delimiter, which must be an ASCII character other
+    /// than a quote or a line break.
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        assert!(
+            delimiter.is_ascii() && !matches!(delimiter, b'"' | b'\r' | b'\n'),
+            "invalid CSV delimiter"
+        );
+        self.delimiter = delimiter;
+        self
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.input.as_bytes().get(self.pos).copied()
+    }
+
+    /// Consumes a line break, returning whether there was one.
+    fn eat_line_break(&mut self) -> bool {
+        let rest = &self.input.as_bytes()[self.pos..];
+        let len = if rest.starts_with(b"\r\n") {
+            2
+        } else if rest.starts_with(b"\n") {
+            1
+        } else {
+            return false;
+        };
+        self.pos += len;
+        self.line += 1;
+        true
+    }
+
+    fn read_quoted(&mut self) -> Result<Cow<'a, str>, CsvError> {
+        let start_line = self.line;
+        let bytes = self.input.as_bytes();
+        self.pos += 1;
+        let start = self.pos;
+        let mut owned: Option<String> = None;
+        loop {
+            let Some(offset) = bytes[self.pos..].iter().position(|&b| b == b'"') else {
+                return Err(CsvError::UnterminatedQuote { line: start_line });
+            };
+            let chunk = &self.input[self.pos..self.pos + offset];
+            self.line += chunk.matches('\n').count();
+            self.pos += offset + 1;
+            if self.peek() == Some(b'"') {
+                // An escaped quote: copy what we have so far and go on.
+                let owned = owned.get_or_insert_with(String::new);
+                owned.push_str(chunk);
+                owned.push('"');
+                self.pos += 1;
+                continue;
+            }
+            return Ok(match owned {
+                Some(mut owned) => {
+                    owned.push_str(chunk);
+                    Cow::Owned(owned)
+                }
+                None => Cow::Borrowed(&self.input[start..self.pos - 1]),
+            });
+        }
+    }
+
+    fn read_unquoted(&mut self) -> Cow<'a, str> {
+        let bytes = self.input.as_bytes();
+        let start = self.pos;
+        while let Some(&byte) = bytes.get(self.pos) {
+            if byte == self.delimiter
+                || byte == b'\n'
+                || (byte == b'\r' && bytes.get(self.pos + 1) == Some(&b'\n'))
+            {
+                break;
+            }
+            self.pos += 1;
+        }
+        Cow::Borrowed(&self.input[start..self.pos])
+    }
+
+    fn read_record(&mut self) -> Result<Record<'a>, CsvError> {
+        let line = self.line;
+        let mut fields = Vec::new();
+        loop {
+            let field = if self.peek() == Some(b'"') {
+                self.read_quoted()?
+            } else {
+                self.read_unquoted()
+            };
+            fields.push(field);
+            match self.peek() {
+                Some(byte) if byte == self.delimiter => self.pos += 1,
+                None => break,
+                Some(_) if self.eat_line_break() => break,
+                Some(_) => return Err(CsvError::UnexpectedCharacter { line: self.line }),
+            }
+        }
+        Ok(Record { fields, line })
+    }
+}
+
+impl<'a> Iterator for Reader<'a> {
+    type Item = Result<Record<'a>, CsvError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.eat_line_break() {}
+        if self.pos == self.input.len() {
+            return None;
+        }
+        let record = self.read_record();
+        if record.is_err() {
+            // Stop after the first error rather than resynchronizing.
+            self.pos = self.input.len();
+        }
+        Some(record)
+    }
+}
+
+/// Writes CSV records to a [`fmt::Write`].
+///
+/// Fields are quoted when they contain the delimiter, a quote or a line
+/// break, and records end with `\n` unless [`Writer::crlf`] is set.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::text::csv::Writer;
+///
+/// let mut writer = Writer::new(String::new());
+/// writer.write_record(["term", "count"]).unwrap();
+/// writer.write_record(["say \"cheese\"", "42"]).unwrap();
+/// assert_eq!(writer.into_inner(), "term,count\n\"say \"\"cheese\"\"\",42\n");
+/// ```
+#[derive(Debug)]
+pub struct Writer<W> {
+    out: W,
+    delimiter: char,
+    crlf: bool,
+}
+
+impl<W: fmt::Write> Writer<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            delimiter: ',',
+            crlf: false,
+        }
+    }
+
+    /// Sets the field delimiter, which must be an ASCII character other
+    /// than a quote or a line break.
+    pub fn delimiter(mut self, delimiter: u8) -> Self {
+        assert!(
+            delimiter.is_ascii() && !matches!(delimiter, b'"' | b'\r' | b'\n'),
+            "invalid CSV delimiter"
+        );
+        self.delimiter = delimiter as char;
+        self
+    }
+
+    /// Ends records with `\r\n` as RFC 4180 specifies.
+    pub fn crlf(mut self, crlf: bool) -> Self {
+        self.crlf = crlf;
+        self
+    }
+
+    pub fn write_record<I, F>(&mut self, fields: I) -> fmt::Result
+    where
+        I: IntoIterator<Item = F>,
+        F: AsRef<str>,
+    {
+        let mut fields = fields.into_iter().peekable();
+        let mut first = true;
+        while let Some(field) = fields.next() {
+            let field = field.as_ref();
+            if !first {
+                self.out.write_char(self.delimiter)?;
+            }
+            // A lone empty field would be an empty line, which readers skip.
+            let lone_empty = first && field.is_empty() && fields.peek().is_none();
+            self.write_field(field, lone_empty)?;
+            first = false;
+        }
+        self.out.write_str(if self.crlf { "\r\n" } else { "\n" })
+    }
+
+    fn write_field(&mut self, field: &str, force_quotes: bool) -> fmt::Result {
+        if !force_quotes && !field.contains([self.delimiter, '"', '\r', '\n']) {
+            return self.out.write_str(field);
+        }
+        self.out.write_char('"')?;
+        for (i, part) in field.split('"').enumerate() {
+            if i > 0 {
+                self.out.write_str("\"\"")?;
+            }
+            self.out.write_str(part)?;
+        }
+        self.out.write_char('"')
+    }
+
+    pub fn get_ref(&self) -> &W {
+        &self.out
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(input: &str) -> Vec<Vec<&str>> {
+        Reader::new(input)
+            .map(|record| {
+                record
+                    .unwrap()
+                    .fields
+                    .iter()
+                    .map(|f| match f {
+                        Cow::Borrowed(s) => *s,
+                        Cow::Owned(_) => "<owned>",
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_read_simple() {
+        assert_eq!(read("a,b,c\n1,2,3"), [["a", "b", "c"], ["1", "2", "3"]]);
+        assert_eq!(read("a,b\r\n\r\n\n1,2\r\n"), [["a", "b"], ["1", "2"]]);
+        assert_eq!(read(",\n"), [["", ""]]);
+        assert_eq!(read("a\rb,c"), [["a\rb", "c"]]);
+        assert_eq!(read("5\" pizza,x"), [["5\" pizza", "x"]]);
+        assert!(read("").is_empty());
+        assert!(read("\n\r\n").is_empty());
+    }
+
+    #[test]
+    fn test_read_quoted() {
+        let input = "\"a,b\",\"multi\nline\",\"\"\n\"say \"\"hi\"\"\",x\n";
+        let records: Vec<Record> = Reader::new(input).collect::<Result<_, _>>().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(
+            records[0].iter().collect::<Vec<_>>(),
+            ["a,b", "multi\nline", ""]
+        );
+        assert_eq!(records[0].line(), 1);
+        assert_eq!(&records[1][0], "say \"hi\"");
+        assert_eq!(records[1].line(), 3);
+        assert_eq!(read("\"a,b\",c"), [["a,b", "c"]]);
+        assert_eq!(read("\"a\"\"b\""), [["<owned>"]]);
+    }
+
+    #[test]
+    fn test_read_errors() {
+        let mut reader = Reader::new("a\n\"open,\nstill open");
+        assert!(reader.next().unwrap().is_ok());
+        assert_eq!(
+            reader.next(),
+            Some(Err(CsvError::UnterminatedQuote { line: 2 }))
+        );
+        assert_eq!(reader.next(), None);
+
+        let mut reader = Reader::new("a\n\"x\"y,z");
+        assert!(reader.next().unwrap().is_ok());
+        assert_eq!(
+            reader.next(),
+            Some(Err(CsvError::UnexpectedCharacter { line: 2 }))
+        );
+    }
+
+    #[test]
+    fn test_delimiter() {
+        let records: Vec<Record> = Reader::new("a,b;\"c;d\";e\tf")
+            .delimiter(b';')
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(
+            records[0].iter().collect::<Vec<_>>(),
+            ["a,b", "c;d", "e\tf"]
+        );
+
+        let mut writer = Writer::new(String::new()).delimiter(b'\t').crlf(true);
+        writer.write_record(["a\tb", "c,d"]).unwrap();
+        assert_eq!(writer.get_ref(), "\"a\tb\"\tc,d\r\n");
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let rows: [&[&str]; 5] = [
+            &["id", "name", "notes"],
+            &["1", "quote \" inside", "line\nbreak"],
+            &["2", "", "comma, here"],
+            &[""],
+            &["\r\n", "\"\"", " spaced "],
+        ];
+        let mut writer = Writer::new(String::new());
+        for row in rows {
+            writer.write_record(row).unwrap();
+        }
+        let output = writer.into_inner();
+        let records: Vec<Record> = Reader::new(&output).collect::<Result<_, _>>().unwrap();
+        assert_eq!(records.len(), rows.len());
+        for (record, row) in records.iter().zip(rows) {
+            assert_eq!(record.iter().collect::<Vec<_>>(), row);
+        }
+    }
+}
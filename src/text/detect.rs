@@ -0,0 +1,418 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Language detection by script and character trigram profiles.
+//!
+//! Cyrillic, Han and Kana text is recognized by its script alone. Latin text
+//! is compared against a ranked trigram profile per language with the
+//! out-of-place measure of Cavnar and Trenkle, "N-Gram-Based Text
+//! Categorization" (1994). The profiles were derived from a few hundred
+//! characters of parallel text per language, enough to tell languages apart
+//! on sentences but not on single words.
+
+use super::Language;
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Number of input trigrams compared with each profile.
+const MAX_INPUT_TRIGRAMS: usize = 300;
+
+#[derive(Default)]
+struct ScriptCounts {
+    latin: usize,
+    cyrillic: usize,
+    han: usize,
+    kana: usize,
+    other: usize,
+}
+
+impl ScriptCounts {
+    fn of(text: &str) -> Self {
+        let mut counts = Self::default();
+        for c in text.chars().filter(|c| c.is_alphabetic()) {
+            match c as u32 {
+                0x0041..=0x024f | 0x1e00..=0x1eff => counts.latin += 1,
+                0x0400..=0x052f => counts.cyrillic += 1,
+                0x3040..=0x30ff | 0x31f0..=0x31ff | 0xff66..=0xff9f => counts.kana += 1,
+                0x3400..=0x4dbf | 0x4e00..=0x9fff | 0xf900..=0xfaff => counts.han += 1,
+                _ => counts.other += 1,
+            }
+        }
+        counts
+    }
+}
+
+/// Guesses the language of a text, returning `None` for text without
+/// letters or in a script none of the supported languages use.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::text::detect_language;
+/// use pizza_common::text::Language;
+///
+/// let text = "Le chat dort sur le canapé pendant que les enfants jouent.";
+/// assert_eq!(detect_language(text), Some(Language::French));
+/// assert_eq!(detect_language("日本語のテキストです"), Some(Language::Japanese));
+/// assert_eq!(detect_language("42 + 17"), None);
+/// ```
+pub fn detect_language(text: &str) -> Option<Language> {
+    let counts = ScriptCounts::of(text);
+    let cjk = counts.han + counts.kana;
+    let dominant = counts.latin.max(counts.cyrillic).max(cjk).max(counts.other);
+    if dominant == 0 || dominant == counts.other {
+        None
+    } else if dominant == cjk {
+        // Japanese mixes kanji with kana, Chinese has no kana at all.
+        Some(if counts.kana > 0 {
+            Language::Japanese
+        } else {
+            Language::Chinese
+        })
+    } else if dominant == counts.cyrillic {
+        Some(Language::Russian)
+    } else {
+        detect_latin(text)
+    }
+}
+
+fn detect_latin(text: &str) -> Option<Language> {
+    let trigrams = ranked_trigrams(text);
+    PROFILES
+        .iter()
+        .map(|(language, profile)| (distance(&trigrams, profile), *language))
+        .min()
+        .map(|(_, language)| language)
+}
+
+/// Counts the trigrams of the lowercased words of a text, each word padded
+/// with a space on both sides, and returns them by descending frequency.
+fn ranked_trigrams(text: &str) -> Vec<String> {
+    let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+    let mut word: Vec<char> = alloc::vec![' '];
+    for c in text.chars().chain(core::iter::once(' ')) {
+        if c.is_alphabetic() {
+            word.extend(c.to_lowercase());
+            continue;
+        }
+        if word.len() > 1 {
+            word.push(' ');
+            for trigram in word.windows(3) {
+                *counts.entry(trigram.iter().collect()).or_default() += 1;
+            }
+        }
+        word.truncate(1);
+    }
+    let mut ranked: Vec<(String, usize)> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked
+        .into_iter()
+        .take(MAX_INPUT_TRIGRAMS)
+        .map(|(trigram, _)| trigram)
+        .collect()
+}
+
+/// The out-of-place distance between ranked input trigrams and a profile.
+fn distance(trigrams: &[String], profile: &[&str]) -> usize {
+    let ranks: BTreeMap<&str, usize> = profile
+        .iter()
+        .enumerate()
+        .map(|(rank, trigram)| (*trigram, rank))
+        .collect();
+    trigrams
+        .iter()
+        .enumerate()
+        .map(|(rank, trigram)| match ranks.get(trigram.as_str()) {
+            Some(profile_rank) => rank.abs_diff(*profile_rank),
+            None => profile.len(),
+        })
+        .sum()
+}
+
+const PROFILES: [(Language, &[&str]); 7] = [
+    (Language::English, ENGLISH),
+    (Language::French, FRENCH),
+    (Language::German, GERMAN),
+    (Language::Spanish, SPANISH),
+    (Language::Italian, ITALIAN),
+    (Language::Portuguese, PORTUGUESE),
+    (Language::Dutch, DUTCH),
+];
+
+const ENGLISH: &[&str] = &[
+    " th", "the", " an", "he ", "nd ", "and", "re ", "ds ", " ar", " of", " to", "are", "it ",
+    "of ", " in", " it", " re", "an ", "at ", "hat", "her", "ind", "ing", "ll ", "ne ", "rit",
+    "tha", "to ", "ts ", "ty ", " al", " be", " bu", " en", " fi", " li", " pe", " ri", " se",
+    " so", " us", " wh", " wi", "all", "ch ", "eas", "ere", "ers", "fin", "ght", "igh", "in ",
+    "ity", "man", "ng ", "not", "on ", "one", "oth", "rds", "rea", "rig", "son", "use", " a ",
+    " ac", " bo", " br", " by", " ca", " co", " di", " do", " ea", " eq", " ev", " fo", " fr",
+    " ha", " hu", " is", " kn", " lo", " ma", " mo", " no", " on", " qu", " ra", " sh", " sp",
+    " wa", " we", " wo", " wr", "act", "ads", "al ", "ank", "ano", "any", "arc", "ard", "as ",
+    "aso", "asy", "ays", "be ", "bei", "ber", "bor", "bro", "bui", "but", "by ", "can", "ce ",
+    "cie", "ckl", "con", "ct ", "cum", "cur", "dex", "dig", "doc", "dow", "ead", "ear", "ecu",
+    "ed ", "ee ", "efu", "ein", "em ", "en ", "enc", "end", "eng", "ent", "eop", "equ", "er ",
+    "erh", "ert", "ery", "ese", "esu", "eth", "eve", "ex ", "ey ", "fe ", "for", "fre", "ful",
+    "gin", "gni", "gs ", "has", "hem", "hes", "hey", "hic", "hin", "ho ", "hoo", "hou", "ht ",
+    "hts", "hum", "ibe", "ich", "ick", "ien", "ife", "ign", "ild", "ill", "ine", "iri", "is ",
+    "ith", "itt", "kin", "kly", "kno", "ld ", "lds", "le ", "lib", "lif", "loo", "lts", "ly ",
+    "men", "met", "mos", "nce", "nde", "ndo", "nds", "ngi", "ngs", "nit", "nk ", "now", "nsc",
+    "nts", "ny ", "ocu", "od ", "oki", "ome", "ons", "ood", "ook", "opl", "or ", "ord", "orn",
+    "ost", "ot ", "oul", "ow ", "owa", "owe", "peo", "per", "pir", "ple", "qua", "qui", "ran",
+    "rch", "ree", "res", "rho", "rn ", "rot", "rs ", "rso", "rty", "ryo", "sci", "se ", "sea",
+    "sec", "sef", "ser", "sho", "so ", "som", "spi", "st ", "sul", "sy ", "ten", "th ", "thi",
+    "tow", "tte", "ual", "uic", "uil", "ul ", "uld", "ult", "uma", "ume", "uri", "ut ", "ver",
+    "war", "way", "wed", "wer", "whi", "who", "wil", "wit", "wor", "wri", "yon", "ys ",
+];
+
+const FRENCH: &[&str] = &[
+    "es ", " le", " de", "de ", "les", "nt ", " et", "ent", "et ", " qu", "le ", "ts ", "té ",
+    " il", "che", "it ", "ns ", "que", "son", " do", " en", " la", " li", " to", " un", " à ",
+    "ais", "ers", "il ", "la ", "ons", "res", "sse", "tou", "us ", " ch", " co", " dr", " es",
+    " fa", " in", " mo", " pa", " pe", " ra", " sa", " tr", " ut", "ate", "bre", "con", "dro",
+    "en ", "er ", "erc", "eur", "her", "ile", "ind", "ir ", "iss", "its", "ité", "lib", "mai",
+    "men", "mot", "nit", "nne", "oit", "onn", "ont", "ous", "ouv", "per", "qui", "rch", "rit",
+    "roi", "rou", "rs ", "rso", "sen", "ser", "teu", "til", "tre", "tro", "ue ", "uel", "ui ",
+    "un ", "uti", "uve", "ver", " a ", " af", " ag", " au", " ce", " cl", " da", " di", " ex",
+    " fr", " hu", " ma", " n ", " na", " no", " on", " pl", " pu", " re", " ré", " se", " so",
+    " sû", " vi", " éc", " ég", " ét", " êt", "aci", "afi", "agi", "ain", "ans", "api", "ar ",
+    "as ", "ass", "ats", "aut", "aux", "avo", "aço", "ber", "ce ", "ces", "cho", "cie", "cil",
+    "cla", "cri", "cum", "dan", "dem", "dex", "dig", "div", "doc", "doi", "dou", "du ", "ech",
+    "el ", "elq", "eme", "enc", "env", "equ", "era", "ern", "ert", "esp", "est", "eté", "eus",
+    "ex ", "exi", "fac", "faç", "fin", "fra", "gau", "gir", "gni", "he ", "hen", "hos", "hum",
+    "ibe", "ibr", "ide", "idu", "ie ", "ien", "ign", "ili", "ils", "in ", "ins", "is ", "isa",
+    "iso", "ist", "ive", "ivi", "las", "leq", "lis", "lit", "lqu", "ls ", "lta", "lus", "mbr",
+    "nai", "nce", "nde", "ndi", "ne ", "nes", "nom", "nsc", "nst", "nts", "nve", "ocu", "oir",
+    "oiv", "omb", "on ", "ose", "ote", "ots", "out", "oué", "par", "pas", "pid", "plu", "pri",
+    "pui", "qu ", "ra ", "rai", "rap", "rat", "rec", "ret", "reu", "rni", "rté", "rui", "rés",
+    "sa ", "sat", "sav", "sci", "se ", "ses", "spr", "st ", "ste", "str", "sul", "sûr", "tat",
+    "te ", "ter", "tru", "uis", "uit", "ult", "uma", "ume", "uns", "ur ", "urs", "use", "ut ",
+    "utr", "ux ", "ués", "ve ", "ven", "vid", "vie", "voi", "xis", "çon", "écr", "éga", "és ",
+    "ésu", "été", "êtr", "ûre",
+];
+
+const GERMAN: &[&str] = &[
+    "en ", "ie ", "er ", "nd ", " di", "die", " si", " un", "ich", "und", "che", "der", "ind",
+    "it ", "sch", " ge", "ein", "eit", "es ", "nde", "nen", " be", " de", " ei", " es", "ben",
+    "cht", "den", "hen", "iss", "lic", "lle", "men", "sie", "sse", "st ", "ten", " al", " da",
+    " er", " fi", " fr", " me", " re", " su", " wi", " zu", "all", "as ", "beg", "bt ", "ch ",
+    "chk", "ebe", "ech", "ell", "ens", "ern", "ers", "fin", "fre", "geb", "hei", "hke", "ht ",
+    "ies", "ine", "ist", "kei", "le ", "mit", "nsc", "on ", "rde", "rec", "rei", "sen", "sin",
+    "ste", "suc", "uch", "wis", "zu ", " ab", " am", " an", " au", " br", " do", " et", " gi",
+    " gl", " ha", " im", " in", " is", " je", " kö", " le", " li", " mi", " mö", " ni", " nü",
+    " or", " pe", " sc", " se", " so", " ve", " vi", " vo", " we", " wu", " wö", " wü", "abe",
+    "abt", "ach", "am ", "ami", "an ", "and", "asc", "at ", "auf", "ber", "bni", "bor", "brü",
+    "chi", "chm", "chn", "chr", "chs", "dam", "das", "de ", "det", "dex", "dne", "dok", "ebn",
+    "ebo", "ede", "ega", "ege", "egn", "ei ", "eic", "eih", "eis", "elc", "ele", "ent", "enu",
+    "erg", "erh", "erl", "esc", "ese", "est", "et ", "etw", "ewi", "ex ", "fac", "ft ", "gab",
+    "geg", "gei", "ges", "gew", "gib", "gle", "gli", "gne", "hat", "her", "hes", "hin", "hma",
+    "hne", "hri", "hst", "hte", "ibt", "ieb", "iel", "ihe", "im ", "in ", "ina", "inf", "ird",
+    "ite", "jed", "kum", "kön", "lch", "leb", "lei", "len", "ler", "lie", "ll ", "llt", "lt ",
+    "mas", "mög", "nan", "ne ", "nel", "nfa", "nft", "nic", "nis", "nne", "nte", "nun", "nut",
+    "nüt", "oku", "oll", "ord", "ore", "per", "rd ", "rdn", "ren", "rge", "rhe", "rie", "rli",
+    "rn ", "rnu", "rso", "rst", "rte", "rüd", "se ", "sei", "ser", "sic", "sol", "son", "te ",
+    "tel", "ter", "twa", "tze", "tzl", "uf ", "ume", "unf", "urd", "utz", "ver", "vie", "von",
+    "was", "wel", "wir", "wur", "wör", "wür", "zer", "zli", "ögl", "önn", "ört", "üde", "ürd",
+    "ütz",
+];
+
+const SPANISH: &[&str] = &[
+    "os ", " de", "as ", "de ", "los", " co", " la", " lo", " y ", "que", " es", " qu", "con",
+    "ent", "es ", "ida", "ue ", " a ", " en", " pe", " se", " to", "ad ", "dos", "en ", "enc",
+    "ere", "la ", "men", "per", "res", "tad", "tod", "tos", " do", " el", " li", " pa", " un",
+    "ado", "an ", "arl", "ber", "cho", "com", "da ", "dad", "der", "ech", "eda", "el ", "ero",
+    "ers", "est", "ien", "il ", "las", "lib", "man", "nci", "ndi", "nos", "nte", "ntr", "odo",
+    "on ", "ona", "or ", "por", "ra ", "ras", "rec", "rlo", "rso", "rta", "ser", "son", "te ",
+    "tra", "ued", "vid", " al", " bu", " bú", " cu", " di", " e ", " fr", " fu", " fá", " ha",
+    " hu", " ig", " in", " le", " ma", " mo", " mu", " má", " na", " no", " or", " ot", " po",
+    " pu", " ra", " re", " rá", " sa", " su", " ti", " us", " vi", " ín", " út", "abe", "abr",
+    "ace", "ala", "ale", "alg", "alm", "ame", "ane", "ano", "ara", "ari", "ars", "ate", "ay ",
+    "azó", "ben", "bra", "bre", "bus", "bús", "can", "ce ", "cen", "cha", "cia", "cie", "cil",
+    "cri", "cue", "cum", "cuá", "dam", "dan", "das", "deb", "den", "dic", "dig", "div", "do ",
+    "doc", "dot", "duo", "ebe", "ee ", "egu", "ena", "ene", "er ", "era", "ern", "ert", "erá",
+    "esc", "esu", "fra", "fue", "fác", "gni", "go ", "gua", "gur", "has", "hay", "ho ", "hos",
+    "hum", "ia ", "ibe", "ibr", "ice", "idu", "ign", "igu", "ind", "ios", "ito", "ivi", "lab",
+    "lee", "les", "lgo", "lme", "lo ", "lta", "mo ", "mot", "mpo", "muc", "más", "na ", "nac",
+    "nal", "nar", "nas", "nco", "ncu", "ne ", "ner", "nid", "no ", "nst", "nto", "ocu", "oda",
+    "omo", "omp", "onc", "ons", "ont", "ord", "ort", "ota", "oto", "otr", "pal", "par", "pid",
+    "pue", "rar", "rat", "raz", "rde", "rid", "rio", "rit", "rna", "ro ", "ron", "ros", "rse",
+    "ruy", "rá ", "ráp", "sab", "sca", "scr", "se ", "seg", "squ", "sto", "str", "stá", "su ",
+    "sua", "sul", "tar", "ter", "tie", "til", "tor", "tro", "tru", "tán", "ual", "uar", "uch",
+    "uen", "uer", "ult", "uma", "ume", "un ", "uno", "uo ", "uri", "usc", "usu", "uye", "uál",
+    "ye ", "zón", "áci", "ál ", "án ", "ápi", "ás ", "índ", "ón ", "úsq", "úti",
+];
+
+const ITALIAN: &[&str] = &[
+    "ti ", " di", "di ", "no ", "le ", "li ", " in", "la ", "lla", "ono", "son", " al", " ch",
+    " e ", " mo", "che", "gli", "he ", "per", "rit", " gl", " le", " pe", " qu", " so", "all",
+    "ati", "cos", "ent", "ers", "in ", "iri", "itt", "ni ", "re ", "ri ", "rso", "tat", "tti",
+    "ual", "za ", " co", " de", " do", " ed", " es", " il", " li", " ra", " ri", " sa", " tr",
+    " tu", " un", " ut", "agi", "ano", "arl", "ber", "ce ", "cer", "dir", "ed ", "ell", "erc",
+    "eri", "ess", "gni", "ibe", "ice", "il ", "ile", "ind", "lib", "men", "mod", "ndi", "ne ",
+    "nti", "nza", "one", "ova", "qua", "rca", "rov", "te ", "to ", "tro", "tut", "tà ", "utt",
+    " ag", " ce", " ci", " da", " eg", " fa", " fr", " ha", " i ", " ma", " na", " no", " og",
+    " or", " pa", " pi", " po", " pr", " sc", " si", " sp", " st", " um", " ve", " vi", " è ",
+    "aci", "agl", "alc", "ale", "ali", "alt", "ame", "ani", "anz", "ape", "api", "aro", "arà",
+    "asc", "ate", "ca ", "can", "ci ", "cie", "cil", "con", "cri", "cum", "cur", "dag", "dam",
+    "del", "dev", "dic", "dig", "din", "div", "do ", "doc", "dot", "duo", "egg", "egu", "enz",
+    "er ", "ere", "ert", "est", "evo", "ezz", "fac", "fra", "ge ", "gge", "gio", "gir", "gua",
+    "ha ", "ia ", "icu", "ida", "idu", "ien", "ign", "ina", "ion", "ire", "isc", "isu", "ita",
+    "ito", "ità", "ivi", "iù ", "lan", "lco", "leg", "lta", "lti", "ltr", "ma ", "man", "mol",
+    "mot", "na ", "nar", "nas", "nit", "non", "nte", "ocu", "odi", "odo", "ogn", "ole", "olt",
+    "on ", "ona", "opr", "ord", "ore", "osa", "osc", "oss", "ost", "ota", "oto", "par", "pid",
+    "pir", "più", "pos", "pri", "pro", "que", "rag", "rap", "rat", "rdi", "rez", "ria", "ric",
+    "ris", "rla", "rli", "rol", "rop", "rtà", "rui", "rà ", "sa ", "san", "sap", "sar", "sce",
+    "sci", "sco", "scr", "ser", "si ", "sic", "so ", "spi", "ssa", "sse", "ssi", "sta", "sti",
+    "str", "sul", "ta ", "tel", "ten", "til", "tor", "tri", "tru", "tte", "tto", "ues", "uis",
+    "ult", "uma", "ume", "un ", "uni", "uo ", "ure", "ute", "uti", "va ", "var", "ver", "vid",
+    "vit", "von", "zza",
+];
+
+const PORTUGUESE: &[&str] = &[
+    "os ", "de ", " de", "as ", "em ", " e ", " qu", " co", " os", "con", "es ", "ida", "ito",
+    "que", "res", "ue ", " di", " em", " ma", " o ", " pa", " pe", " se", " to", " à ", "ade",
+    "ado", "am ", "dad", "dos", "ra ", "tod", "tos", " as", " do", " en", " es", " li", " ra",
+    "ais", "al ", "ara", "ber", "dir", "eit", "enc", "ent", "ess", "il ", "ire", "is ", "man",
+    "men", "nco", "ndi", "nid", "ntr", "ocu", "odo", "ons", "ont", "par", "pes", "ram", "ras",
+    "rei", "rit", "rá ", "ser", "soa", "sso", "ste", "tad", "tem", "til", "to ", "uma", "ura",
+    "ão ", " ag", " al", " bu", " ex", " fo", " fr", " fá", " hu", " ig", " in", " la", " lê",
+    " mo", " mu", " na", " nã", " or", " ou", " po", " pr", " re", " sa", " te", " um", " un",
+    " ut", " vi", " é ", " ín", " út", "abe", "agi", "ala", "alg", "ame", "ane", "ano", "anç",
+    "api", "ar ", "asc", "ate", "avr", "azã", "bus", "ca ", "ce ", "cem", "cia", "cil", "ciê",
+    "coi", "com", "cri", "cum", "cur", "da ", "dam", "das", "den", "des", "dev", "dic", "dig",
+    "div", "do ", "doc", "dor", "dot", "duo", "egu", "eir", "elo", "ena", "er ", "erd", "ere",
+    "ern", "erá", "esc", "esp", "est", "esu", "eve", "exi", "for", "fra", "fác", "gir", "gni",
+    "gua", "gum", "gur", "hum", "ia ", "ibe", "ice", "ign", "igu", "ili", "ind", "ir ", "ira",
+    "isa", "ist", "ita", "ivr", "iví", "iza", "iên", "la ", "lav", "lgu", "lib", "liv", "liz",
+    "los", "lta", "lê ", "ma ", "mai", "mas", "mot", "mui", "nar", "nas", "nci", "nei", "nos",
+    "ns ", "nsc", "nst", "nte", "nto", "não", "nça", "oal", "oas", "oda", "ois", "om ", "or ",
+    "ora", "ord", "ore", "oss", "ota", "oto", "out", "pal", "pel", "pid", "pos", "pro", "pír",
+    "qua", "ran", "rap", "rat", "raz", "rda", "rde", "rni", "roc", "ros", "rói", "sa ", "sab",
+    "sam", "sca", "sce", "sci", "scr", "seg", "spí", "ssa", "str", "sul", "tas", "te ", "ter",
+    "tes", "tor", "tra", "tro", "trá", "tró", "uai", "ual", "uit", "ult", "um ", "ume", "uns",
+    "uo ", "usc", "uti", "utr", "vem", "vid", "vra", "vre", "víd", "xis", "zad", "zão", "áci",
+    "ça ", "ênc", "ídu", "índ", "íri", "ói ", "úti",
+];
+
+const DUTCH: &[&str] = &[
+    "en ", " zi", " ge", "zij", " en", " de", "et ", "ijn", "jn ", "ten", " ee", " he", " me",
+    " va", "an ", "de ", "den", "een", "ers", "van", " di", " in", " re", " te", " zo", "aar",
+    "der", "die", "ees", "eid", "ens", "er ", "est", "hei", "het", "id ", "ie ", "ij ", "ind",
+    "men", "nde", "ord", "rde", "ren", "sch", "st ", "te ", " al", " be", " do", " ie", " le",
+    " ma", " om", " ve", " vi", " vr", " we", " wo", "all", "and", "cht", "dig", "ech", "ede",
+    "el ", "elk", "ete", "eve", "geb", "gen", "iet", "ig ", "in ", "le ", "lle", "nen", "nie",
+    "nse", "oek", "om ", "oor", "ore", "rec", "rij", "sen", "tig", "ven", "vin", "vri", "wet",
+    "ze ", "zoe", " bo", " br", " el", " er", " is", " je", " ku", " ni", " nu", " on", " op",
+    " or", " pe", " sn", " wa", " za", " ze", "ach", "age", "al ", "ani", "ap ", "ar ", "ard",
+    "arh", "at ", "ate", "baa", "beg", "beh", "bor", "bou", "bro", "bru", "ch ", "cha", "che",
+    "chi", "chr", "cum", "dat", "dba", "dex", "dez", "doc", "doo", "dra", "dt ", "ebo", "ebr",
+    "edr", "eef", "eel", "eft", "ege", "egi", "eho", "eke", "ekm", "eli", "end", "ene", "ent",
+    "env", "ere", "esc", "esu", "ets", "ewe", "ex ", "eze", "ft ", "fti", "gd ", "ged", "gee",
+    "gel", "ges", "gew", "ghe", "gif", "hap", "hee", "hen", "hin", "hor", "hre", "ht ", "hte",
+    "ich", "ied", "ier", "ift", "igd", "igh", "ijh", "ijk", "ike", "ine", "is ", "jeg", "jhe",
+    "jk ", "kan", "ke ", "ken", "ker", "kma", "kun", "lee", "lev", "lij", "lka", "lke", "lta",
+    "maa", "mac", "man", "mee", "met", "nd ", "ndb", "ndt", "ne ", "nel", "nne", "ns ", "nsc",
+    "nte", "nut", "nvo", "ocu", "oda", "oed", "on ", "ons", "oon", "op ", "or ", "oud", "ouw",
+    "per", "rag", "rdi", "res", "rev", "rhe", "roe", "rs ", "rsc", "rso", "rst", "rui", "sne",
+    "soo", "sta", "sul", "tan", "tat", "ts ", "tti", "udi", "uik", "ult", "ume", "unn", "utt",
+    "uwt", "vee", "ver", "vou", "waa", "wel", "woo", "wor", "wt ", "zal", "zic", "zod",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_latin() {
+        let samples = [
+            (
+                Language::English,
+                "Please make sure that the cluster is healthy before you upgrade the nodes.",
+            ),
+            (
+                Language::French,
+                "Veuillez vérifier que le cluster est en bonne santé avant de mettre à jour les nœuds.",
+            ),
+            (
+                Language::German,
+                "Bitte stellen Sie sicher, dass der Cluster gesund ist, bevor Sie die Knoten aktualisieren.",
+            ),
+            (
+                Language::Spanish,
+                "Por favor, asegúrese de que el clúster esté sano antes de actualizar los nodos.",
+            ),
+            (
+                Language::Italian,
+                "Per favore assicurati che il cluster sia sano prima di aggiornare i nodi.",
+            ),
+            (
+                Language::Portuguese,
+                "O gato dorme no sofá enquanto as crianças brincam no jardim.",
+            ),
+            (
+                Language::Dutch,
+                "Zorg ervoor dat het cluster gezond is voordat je de knooppunten bijwerkt.",
+            ),
+        ];
+        for (language, text) in samples {
+            assert_eq!(detect_language(text), Some(language), "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_detect_by_script() {
+        assert_eq!(
+            detect_language("Быстрая коричневая лиса прыгает через ленивую собаку."),
+            Some(Language::Russian)
+        );
+        assert_eq!(
+            detect_language("我们的搜索引擎很快"),
+            Some(Language::Chinese)
+        );
+        assert_eq!(
+            detect_language("検索エンジンはとても速い"),
+            Some(Language::Japanese)
+        );
+        assert_eq!(detect_language("빠른 검색 엔진"), None);
+        assert_eq!(detect_language(""), None);
+        assert_eq!(detect_language("2024-01-01 12:00"), None);
+    }
+
+    #[test]
+    fn test_ranked_trigrams() {
+        assert_eq!(
+            ranked_trigrams("Aba, ab!"),
+            [" ab", "ab ", "aba", "ba "].map(String::from)
+        );
+    }
+
+    #[test]
+    fn test_profiles_are_unique() {
+        for (_, profile) in PROFILES {
+            let mut sorted = profile.to_vec();
+            sorted.sort_unstable();
+            sorted.dedup();
+            assert_eq!(sorted.len(), profile.len());
+            assert!(profile.iter().all(|trigram| trigram.chars().count() == 3));
+        }
+    }
+}
@@ -0,0 +1,364 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use super::Language;
+
+/// Returns the stopwords of a language, sorted by their UTF-8 bytes.
+///
+/// The lists are deliberately short and cover the function words that
+/// dominate term frequencies, lowercased. Chinese and Japanese entries are
+/// single particles, matching what a character or bigram tokenizer emits.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::text::stopwords;
+/// use pizza_common::text::Language;
+///
+/// assert!(stopwords(Language::English).contains(&"the"));
+/// ```
+pub fn stopwords(language: Language) -> &'static [&'static str] {
+    match language {
+        Language::English => ENGLISH,
+        Language::French => FRENCH,
+        Language::German => GERMAN,
+        Language::Spanish => SPANISH,
+        Language::Italian => ITALIAN,
+        Language::Portuguese => PORTUGUESE,
+        Language::Dutch => DUTCH,
+        Language::Russian => RUSSIAN,
+        Language::Chinese => CHINESE,
+        Language::Japanese => JAPANESE,
+    }
+}
+
+/// Returns whether an already lowercased token is a stopword.
+pub fn is_stopword(language: Language, token: &str) -> bool {
+    stopwords(language).binary_search(&token).is_ok()
+}
+
+const ENGLISH: &[&str] = &[
+    "a",
+    "about",
+    "above",
+    "after",
+    "again",
+    "against",
+    "all",
+    "am",
+    "an",
+    "and",
+    "any",
+    "are",
+    "as",
+    "at",
+    "be",
+    "because",
+    "been",
+    "before",
+    "being",
+    "below",
+    "between",
+    "both",
+    "but",
+    "by",
+    "can",
+    "could",
+    "did",
+    "do",
+    "does",
+    "doing",
+    "down",
+    "during",
+    "each",
+    "few",
+    "for",
+    "from",
+    "further",
+    "had",
+    "has",
+    "have",
+    "having",
+    "he",
+    "her",
+    "here",
+    "hers",
+    "herself",
+    "him",
+    "himself",
+    "his",
+    "how",
+    "i",
+    "if",
+    "in",
+    "into",
+    "is",
+    "it",
+    "its",
+    "itself",
+    "just",
+    "me",
+    "more",
+    "most",
+    "my",
+    "myself",
+    "no",
+    "nor",
+    "not",
+    "now",
+    "of",
+    "off",
+    "on",
+    "once",
+    "only",
+    "or",
+    "other",
+    "our",
+    "ours",
+    "ourselves",
+    "out",
+    "over",
+    "own",
+    "same",
+    "she",
+    "should",
+    "so",
+    "some",
+    "such",
+    "than",
+    "that",
+    "the",
+    "their",
+    "theirs",
+    "them",
+    "themselves",
+    "then",
+    "there",
+    "these",
+    "they",
+    "this",
+    "those",
+    "through",
+    "to",
+    "too",
+    "under",
+    "until",
+    "up",
+    "very",
+    "was",
+    "we",
+    "were",
+    "what",
+    "when",
+    "where",
+    "which",
+    "while",
+    "who",
+    "whom",
+    "why",
+    "will",
+    "with",
+    "would",
+    "you",
+    "your",
+    "yours",
+    "yourself",
+    "yourselves",
+];
+
+const FRENCH: &[&str] = &[
+    "au", "aux", "avec", "avoir", "ce", "ces", "cette", "dans", "de", "des", "du", "elle", "elles",
+    "en", "est", "et", "eux", "il", "ils", "je", "la", "le", "les", "leur", "leurs", "lui", "ma",
+    "mais", "me", "mes", "moi", "mon", "même", "ne", "nos", "notre", "nous", "on", "ont", "ou",
+    "où", "par", "pas", "pour", "qu", "que", "qui", "sa", "se", "ses", "son", "sont", "sur", "ta",
+    "te", "tes", "toi", "ton", "tu", "un", "une", "vos", "votre", "vous", "y", "à", "était", "été",
+    "être",
+];
+
+const GERMAN: &[&str] = &[
+    "aber", "alle", "als", "also", "am", "an", "auch", "auf", "aus", "bei", "bin", "bis", "bist",
+    "da", "damit", "dann", "das", "dass", "dein", "deine", "dem", "den", "der", "des", "dich",
+    "die", "dir", "doch", "du", "durch", "ein", "eine", "einem", "einen", "einer", "eines", "er",
+    "es", "für", "hat", "hatte", "ich", "ihm", "ihn", "ihr", "ihre", "im", "in", "ist", "ja",
+    "jede", "jeder", "kein", "keine", "man", "mein", "meine", "mich", "mir", "mit", "nach",
+    "nicht", "noch", "nun", "nur", "ob", "oder", "ohne", "sein", "seine", "sich", "sie", "sind",
+    "so", "um", "und", "uns", "unser", "unter", "vom", "von", "vor", "war", "waren", "was", "weil",
+    "wenn", "wer", "wie", "wir", "wird", "zu", "zum", "zur", "über",
+];
+
+const SPANISH: &[&str] = &[
+    "a", "al", "algo", "como", "con", "cual", "de", "del", "donde", "el", "ella", "ellas", "ellos",
+    "en", "entre", "era", "es", "esa", "ese", "eso", "esta", "este", "esto", "fue", "ha", "hay",
+    "la", "las", "le", "les", "lo", "los", "me", "mi", "muy", "más", "ni", "no", "nos", "o",
+    "para", "pero", "por", "porque", "que", "quien", "se", "sin", "sobre", "su", "sus", "sí",
+    "también", "te", "tu", "un", "una", "unas", "uno", "unos", "y", "ya", "yo", "él",
+];
+
+const ITALIAN: &[&str] = &[
+    "a", "ad", "al", "alla", "alle", "anche", "che", "chi", "ci", "come", "con", "da", "dal",
+    "dalla", "degli", "dei", "del", "della", "delle", "di", "e", "ed", "era", "gli", "ha", "hanno",
+    "i", "il", "in", "io", "la", "le", "lei", "lo", "loro", "lui", "ma", "mi", "ne", "nel",
+    "nella", "noi", "non", "o", "per", "più", "quale", "quando", "questa", "questo", "se", "si",
+    "sono", "su", "sua", "suo", "tra", "tu", "un", "una", "uno", "voi", "è",
+];
+
+const PORTUGUESE: &[&str] = &[
+    "a", "ao", "aos", "as", "até", "com", "como", "da", "das", "de", "dela", "dele", "do", "dos",
+    "e", "ela", "ele", "eles", "em", "entre", "era", "essa", "esse", "esta", "este", "eu", "foi",
+    "há", "isso", "já", "lhe", "mais", "mas", "me", "meu", "minha", "muito", "na", "nas", "nem",
+    "no", "nos", "não", "o", "os", "ou", "para", "pela", "pelo", "por", "quando", "que", "quem",
+    "se", "sem", "ser", "seu", "sua", "são", "também", "te", "tem", "um", "uma", "você", "à", "é",
+];
+
+const DUTCH: &[&str] = &[
+    "aan", "al", "als", "bij", "dan", "dat", "de", "der", "des", "deze", "die", "dit", "door",
+    "dus", "een", "en", "er", "ge", "geen", "had", "heb", "heeft", "het", "hij", "hoe", "hun",
+    "ik", "in", "is", "ja", "je", "kan", "kon", "maar", "me", "met", "mij", "mijn", "na", "naar",
+    "niet", "nog", "nu", "of", "om", "omdat", "ons", "ook", "op", "over", "te", "tot", "u", "uit",
+    "van", "veel", "voor", "want", "was", "wat", "we", "wel", "werd", "wie", "wij", "worden",
+    "zal", "ze", "zich", "zij", "zijn", "zo", "zou",
+];
+
+const RUSSIAN: &[&str] = &[
+    "а",
+    "без",
+    "бы",
+    "был",
+    "была",
+    "были",
+    "было",
+    "в",
+    "вам",
+    "вас",
+    "весь",
+    "во",
+    "вот",
+    "все",
+    "всё",
+    "вы",
+    "где",
+    "да",
+    "даже",
+    "для",
+    "до",
+    "его",
+    "ее",
+    "если",
+    "есть",
+    "еще",
+    "ещё",
+    "её",
+    "же",
+    "за",
+    "и",
+    "из",
+    "или",
+    "им",
+    "их",
+    "к",
+    "как",
+    "ко",
+    "когда",
+    "кто",
+    "ли",
+    "между",
+    "мне",
+    "мой",
+    "мы",
+    "на",
+    "над",
+    "не",
+    "него",
+    "нет",
+    "ни",
+    "них",
+    "но",
+    "ну",
+    "о",
+    "об",
+    "он",
+    "она",
+    "они",
+    "оно",
+    "от",
+    "по",
+    "под",
+    "при",
+    "с",
+    "со",
+    "так",
+    "также",
+    "там",
+    "то",
+    "тоже",
+    "только",
+    "том",
+    "ты",
+    "у",
+    "уже",
+    "чем",
+    "что",
+    "чтобы",
+    "эта",
+    "эти",
+    "это",
+    "я",
+];
+
+const CHINESE: &[&str] = &[
+    "不", "与", "个", "之", "也", "了", "于", "从", "他", "以", "们", "你", "及", "吗", "吧", "呢",
+    "和", "啊", "在", "她", "它", "对", "就", "我", "或", "把", "是", "有", "没", "的", "给", "而",
+    "被", "让", "这", "那", "都",
+];
+
+const JAPANESE: &[&str] = &[
+    "あの", "ある", "あれ", "いる", "か", "から", "が", "こと", "この", "これ", "する", "その",
+    "それ", "で", "です", "と", "な", "に", "ね", "の", "は", "へ", "ます", "まで", "も", "や",
+    "よ", "より", "を",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lists_are_sorted_and_lowercase() {
+        for language in Language::ALL {
+            let words = stopwords(language);
+            assert!(!words.is_empty());
+            assert!(words.windows(2).all(|w| w[0] < w[1]), "{}", language);
+            for word in words {
+                assert!(word.chars().all(|c| !c.is_uppercase()), "{}", word);
+            }
+        }
+    }
+
+    #[test]
+    fn test_is_stopword() {
+        assert!(is_stopword(Language::English, "the"));
+        assert!(!is_stopword(Language::English, "The"));
+        assert!(!is_stopword(Language::English, "pizza"));
+        assert!(is_stopword(Language::German, "über"));
+        assert!(is_stopword(Language::Russian, "это"));
+        assert!(is_stopword(Language::Japanese, "です"));
+    }
+}
@@ -0,0 +1,509 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+/// Declares a transparent flags newtype over an unsigned integer.
+///
+/// The generated type has a `const` per flag, set operations as methods and
+/// operators, an `iter()` over the contained flags and a `Debug`
+/// output listing flag names. With serde it is written as a list of flag
+/// names in human-readable formats and as the bare integer otherwise, and
+/// it reads either form back. Unknown bits or names are rejected when
+/// deserializing.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::bitflags;
+///
+/// bitflags! {
+///     /// Options of an index field.
+///     pub struct FieldFlags: u32 {
+///         const INDEXED = 1 << 0;
+///         const STORED = 1 << 1;
+///         const DOC_VALUES = 1 << 2;
+///         const DEFAULT = Self::INDEXED.bits() | Self::DOC_VALUES.bits();
+///     }
+/// }
+///
+/// let mut flags = FieldFlags::INDEXED | FieldFlags::STORED;
+/// assert!(flags.contains(FieldFlags::STORED));
+/// flags.remove(FieldFlags::STORED);
+/// assert_eq!(flags, FieldFlags::INDEXED);
+/// assert_eq!(format!("{:?}", FieldFlags::DEFAULT), "FieldFlags(INDEXED | DOC_VALUES)");
+/// assert_eq!(serde_json::to_string(&FieldFlags::DEFAULT).unwrap(), r#"["INDEXED","DOC_VALUES"]"#);
+/// assert_eq!(serde_json::from_str::<FieldFlags>("5").unwrap(), FieldFlags::DEFAULT);
+/// ```
+///
+/// `iter()` and `Debug` go through the flags in declaration order and skip
+/// those whose bits were already covered, so a combined flag like `DEFAULT`
+/// only shows up when it is declared before its parts.
+#[macro_export]
+macro_rules! bitflags {
+    (
+        $(#[$outer:meta])*
+        $vis:vis struct $name:ident: $bits:ty {
+            $(
+                $(#[$inner:meta])*
+                const $flag:ident = $value:expr;
+            )*
+        }
+    ) => {
+        $(#[$outer])*
+        #[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default)]
+        #[repr(transparent)]
+        $vis struct $name($bits);
+
+        #[allow(dead_code)]
+        impl $name {
+            $(
+                $(#[$inner])*
+                pub const $flag: Self = Self($value);
+            )*
+
+            /// Every named flag with its name, in declaration order.
+            pub const FLAGS: &'static [(&'static str, Self)] = &[$((stringify!($flag), Self::$flag)),*];
+
+            pub const fn empty() -> Self {
+                Self(0)
+            }
+
+            /// The union of all named flags.
+            pub const fn all() -> Self {
+                Self(0 $(| Self::$flag.0)*)
+            }
+
+            pub const fn bits(&self) -> $bits {
+                self.0
+            }
+
+            /// Returns `None` if `bits` has bits no flag defines.
+            pub const fn from_bits(bits: $bits) -> ::core::option::Option<Self> {
+                if bits & !Self::all().0 == 0 {
+                    ::core::option::Option::Some(Self(bits))
+                } else {
+                    ::core::option::Option::None
+                }
+            }
+
+            /// Drops bits no flag defines.
+            pub const fn from_bits_truncate(bits: $bits) -> Self {
+                Self(bits & Self::all().0)
+            }
+
+            /// Keeps bits no flag defines, for forward compatibility with
+            /// flags written by newer versions.
+            pub const fn from_bits_retain(bits: $bits) -> Self {
+                Self(bits)
+            }
+
+            pub fn from_name(name: &str) -> ::core::option::Option<Self> {
+                Self::FLAGS
+                    .iter()
+                    .find(|(flag_name, _)| *flag_name == name)
+                    .map(|(_, flag)| *flag)
+            }
+
+            pub const fn is_empty(&self) -> bool {
+                self.0 == 0
+            }
+
+            pub const fn is_all(&self) -> bool {
+                self.0 & Self::all().0 == Self::all().0
+            }
+
+            pub const fn contains(&self, other: Self) -> bool {
+                self.0 & other.0 == other.0
+            }
+
+            pub const fn intersects(&self, other: Self) -> bool {
+                self.0 & other.0 != 0
+            }
+
+            pub fn insert(&mut self, other: Self) {
+                self.0 |= other.0;
+            }
+
+            pub fn remove(&mut self, other: Self) {
+                self.0 &= !other.0;
+            }
+
+            pub fn toggle(&mut self, other: Self) {
+                self.0 ^= other.0;
+            }
+
+            pub fn set(&mut self, other: Self, value: bool) {
+                if value {
+                    self.insert(other);
+                } else {
+                    self.remove(other);
+                }
+            }
+
+            #[must_use]
+            pub const fn union(self, other: Self) -> Self {
+                Self(self.0 | other.0)
+            }
+
+            #[must_use]
+            pub const fn intersection(self, other: Self) -> Self {
+                Self(self.0 & other.0)
+            }
+
+            #[must_use]
+            pub const fn difference(self, other: Self) -> Self {
+                Self(self.0 & !other.0)
+            }
+
+            #[must_use]
+            pub const fn symmetric_difference(self, other: Self) -> Self {
+                Self(self.0 ^ other.0)
+            }
+
+            /// The named flags not in `self`.
+            #[must_use]
+            pub const fn complement(self) -> Self {
+                Self(!self.0 & Self::all().0)
+            }
+
+            /// Iterates over the named flags contained in `self` with their
+            /// names, in declaration order, skipping flags whose bits were
+            /// already yielded.
+            pub fn iter_names(&self) -> impl Iterator<Item = (&'static str, Self)> {
+                let flags = *self;
+                let mut remaining = flags;
+                Self::FLAGS.iter().filter_map(move |(name, flag)| {
+                    if flag.is_empty() || !flags.contains(*flag) || !remaining.intersects(*flag) {
+                        return ::core::option::Option::None;
+                    }
+                    remaining.remove(*flag);
+                    ::core::option::Option::Some((*name, *flag))
+                })
+            }
+
+            /// Iterates over the contained flags like
+            /// [`iter_names`](Self::iter_names), followed by any bits no
+            /// flag defines, so the union of the items is always `self`.
+            pub fn iter(&self) -> impl Iterator<Item = Self> {
+                let unknown = Self(self.0 & !Self::all().0);
+                self.iter_names()
+                    .map(|(_, flag)| flag)
+                    .chain((!unknown.is_empty()).then_some(unknown))
+            }
+        }
+
+        impl ::core::fmt::Debug for $name {
+            fn fmt(&self, f: &mut ::core::fmt::Formatter<'_>) -> ::core::fmt::Result {
+                f.write_str(concat!(stringify!($name), "("))?;
+                if self.is_empty() {
+                    f.write_str("empty")?;
+                }
+                let mut first = true;
+                for flag in self.iter() {
+                    if !::core::mem::take(&mut first) {
+                        f.write_str(" | ")?;
+                    }
+                    match Self::FLAGS.iter().find(|(_, named)| *named == flag) {
+                        ::core::option::Option::Some((name, _)) => f.write_str(name)?,
+                        ::core::option::Option::None => write!(f, "{:#x}", flag.0)?,
+                    }
+                }
+                f.write_str(")")
+            }
+        }
+
+        impl ::core::ops::BitOr for $name {
+            type Output = Self;
+            fn bitor(self, other: Self) -> Self {
+                self.union(other)
+            }
+        }
+
+        impl ::core::ops::BitOrAssign for $name {
+            fn bitor_assign(&mut self, other: Self) {
+                self.insert(other);
+            }
+        }
+
+        impl ::core::ops::BitAnd for $name {
+            type Output = Self;
+            fn bitand(self, other: Self) -> Self {
+                self.intersection(other)
+            }
+        }
+
+        impl ::core::ops::BitAndAssign for $name {
+            fn bitand_assign(&mut self, other: Self) {
+                *self = self.intersection(other);
+            }
+        }
+
+        impl ::core::ops::BitXor for $name {
+            type Output = Self;
+            fn bitxor(self, other: Self) -> Self {
+                self.symmetric_difference(other)
+            }
+        }
+
+        impl ::core::ops::BitXorAssign for $name {
+            fn bitxor_assign(&mut self, other: Self) {
+                self.toggle(other);
+            }
+        }
+
+        impl ::core::ops::Sub for $name {
+            type Output = Self;
+            fn sub(self, other: Self) -> Self {
+                self.difference(other)
+            }
+        }
+
+        impl ::core::ops::SubAssign for $name {
+            fn sub_assign(&mut self, other: Self) {
+                self.remove(other);
+            }
+        }
+
+        impl ::core::ops::Not for $name {
+            type Output = Self;
+            fn not(self) -> Self {
+                self.complement()
+            }
+        }
+
+        impl ::core::iter::FromIterator<$name> for $name {
+            fn from_iter<I: IntoIterator<Item = Self>>(iter: I) -> Self {
+                iter.into_iter().fold(Self::empty(), Self::union)
+            }
+        }
+
+        impl ::core::iter::Extend<$name> for $name {
+            fn extend<I: IntoIterator<Item = Self>>(&mut self, iter: I) {
+                for flag in iter {
+                    self.insert(flag);
+                }
+            }
+        }
+
+        impl $crate::__private::serde::Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> ::core::result::Result<S::Ok, S::Error>
+            where
+                S: $crate::__private::serde::Serializer,
+            {
+                use $crate::__private::serde::ser::SerializeSeq;
+                if !serializer.is_human_readable() || !self.iter().all(|flag| flag.iter_names().next().is_some()) {
+                    return <$bits as $crate::__private::serde::Serialize>::serialize(&self.0, serializer);
+                }
+                let mut seq = serializer.serialize_seq(::core::option::Option::None)?;
+                for (name, _) in self.iter_names() {
+                    seq.serialize_element(name)?;
+                }
+                seq.end()
+            }
+        }
+
+        impl<'de> $crate::__private::serde::Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> ::core::result::Result<Self, D::Error>
+            where
+                D: $crate::__private::serde::Deserializer<'de>,
+            {
+                use $crate::__private::serde::de;
+
+                fn from_bits<E: de::Error>(bits: u64) -> ::core::result::Result<$name, E> {
+                    <$bits>::try_from(bits)
+                        .ok()
+                        .and_then($name::from_bits)
+                        .ok_or_else(|| E::custom(::core::format_args!(
+                            "unknown bits {:#x} for {}", bits, stringify!($name)
+                        )))
+                }
+
+                fn from_name<E: de::Error>(name: &str) -> ::core::result::Result<$name, E> {
+                    $name::from_name(name.trim()).ok_or_else(|| E::custom(::core::format_args!(
+                        "unknown flag '{}' for {}", name, stringify!($name)
+                    )))
+                }
+
+                struct FlagsVisitor;
+
+                impl<'de> de::Visitor<'de> for FlagsVisitor {
+                    type Value = $name;
+
+                    fn expecting(&self, f: &mut ::core::fmt::Formatter) -> ::core::fmt::Result {
+                        f.write_str("an integer or a list of flag names")
+                    }
+
+                    fn visit_u64<E: de::Error>(self, v: u64) -> ::core::result::Result<$name, E> {
+                        from_bits(v)
+                    }
+
+                    fn visit_i64<E: de::Error>(self, v: i64) -> ::core::result::Result<$name, E> {
+                        let bits = u64::try_from(v).map_err(|_| E::invalid_value(de::Unexpected::Signed(v), &self))?;
+                        from_bits(bits)
+                    }
+
+                    /// Accepts names separated by `|`, such as `"A | B"`.
+                    fn visit_str<E: de::Error>(self, v: &str) -> ::core::result::Result<$name, E> {
+                        v.split('|')
+                            .filter(|name| !name.trim().is_empty())
+                            .map(from_name)
+                            .collect()
+                    }
+
+                    fn visit_seq<A>(self, mut seq: A) -> ::core::result::Result<$name, A::Error>
+                    where
+                        A: de::SeqAccess<'de>,
+                    {
+                        let mut flags = $name::empty();
+                        while let ::core::option::Option::Some(name) = seq.next_element::<&str>()? {
+                            flags.insert(from_name(name)?);
+                        }
+                        ::core::result::Result::Ok(flags)
+                    }
+                }
+
+                if deserializer.is_human_readable() {
+                    deserializer.deserialize_any(FlagsVisitor)
+                } else {
+                    let bits = <$bits as $crate::__private::serde::Deserialize>::deserialize(deserializer)?;
+                    from_bits(bits as u64)
+                }
+            }
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::format;
+    use alloc::vec::Vec;
+
+    bitflags! {
+        /// Test flags.
+        pub struct Flags: u8 {
+            const A = 1;
+            const B = 1 << 1;
+            const C = 1 << 2;
+            const AB = Self::A.bits() | Self::B.bits();
+        }
+    }
+
+    #[test]
+    fn test_set_operations() {
+        let mut flags = Flags::A | Flags::C;
+        assert!(flags.contains(Flags::A));
+        assert!(!flags.contains(Flags::AB));
+        assert!(flags.intersects(Flags::AB));
+        flags.insert(Flags::B);
+        assert!(flags.is_all());
+        flags.remove(Flags::AB);
+        assert_eq!(flags, Flags::C);
+        flags.toggle(Flags::A);
+        assert_eq!(flags.bits(), 0b101);
+        flags.set(Flags::C, false);
+        assert_eq!(flags, Flags::A);
+
+        assert_eq!(Flags::all().bits(), 0b111);
+        assert!(Flags::empty().is_empty());
+        assert_eq!(!Flags::A, Flags::B | Flags::C);
+        assert_eq!(Flags::AB - Flags::A, Flags::B);
+        assert_eq!(Flags::AB & Flags::B, Flags::B);
+        assert_eq!(Flags::AB ^ Flags::all(), Flags::C);
+        let mut flags = Flags::empty();
+        flags |= Flags::C;
+        flags ^= Flags::A;
+        flags -= Flags::C;
+        flags &= Flags::AB;
+        assert_eq!(flags, Flags::A);
+        assert_eq!(
+            [Flags::A, Flags::C].into_iter().collect::<Flags>().bits(),
+            0b101
+        );
+    }
+
+    #[test]
+    fn test_from_bits() {
+        assert_eq!(Flags::from_bits(0b011), Some(Flags::AB));
+        assert_eq!(Flags::from_bits(0b1000), None);
+        assert_eq!(Flags::from_bits_truncate(0b1001), Flags::A);
+        assert_eq!(Flags::from_bits_retain(0b1001).bits(), 0b1001);
+        assert_eq!(Flags::from_name("AB"), Some(Flags::AB));
+        assert_eq!(Flags::from_name("D"), None);
+    }
+
+    #[test]
+    fn test_iter() {
+        let names: Vec<&str> = Flags::all().iter_names().map(|(name, _)| name).collect();
+        assert_eq!(names, ["A", "B", "C"]);
+        let flags = Flags::from_bits_retain(0b1100_0100);
+        assert_eq!(
+            flags.iter().collect::<Vec<_>>(),
+            [Flags::C, Flags::from_bits_retain(0b1100_0000)]
+        );
+        assert_eq!(flags.iter().collect::<Flags>(), flags);
+        assert_eq!(Flags::empty().iter().count(), 0);
+        assert_eq!(Flags::FLAGS.len(), 4);
+    }
+
+    #[test]
+    fn test_debug() {
+        assert_eq!(format!("{:?}", Flags::empty()), "Flags(empty)");
+        assert_eq!(format!("{:?}", Flags::AB | Flags::C), "Flags(A | B | C)");
+        assert_eq!(
+            format!("{:?}", Flags::from_bits_retain(0x41)),
+            "Flags(A | 0x40)"
+        );
+    }
+
+    #[test]
+    fn test_serde() {
+        let flags = Flags::A | Flags::C;
+        assert_eq!(serde_json::to_string(&flags).unwrap(), r#"["A","C"]"#);
+        assert_eq!(serde_json::to_string(&Flags::empty()).unwrap(), "[]");
+        assert_eq!(
+            serde_json::from_str::<Flags>(r#"["A","C"]"#).unwrap(),
+            flags
+        );
+        assert_eq!(serde_json::from_str::<Flags>("5").unwrap(), flags);
+        assert_eq!(serde_json::from_str::<Flags>(r#""A | C""#).unwrap(), flags);
+        assert_eq!(
+            serde_json::from_str::<Flags>(r#""""#).unwrap(),
+            Flags::empty()
+        );
+        assert!(serde_json::from_str::<Flags>("8").is_err());
+        assert!(serde_json::from_str::<Flags>("-1").is_err());
+        assert!(serde_json::from_str::<Flags>("256").is_err());
+        assert!(serde_json::from_str::<Flags>(r#"["A","D"]"#).is_err());
+
+        // Unknown bits cannot be named, so they are kept as an integer.
+        let retained = Flags::from_bits_retain(0x41);
+        assert_eq!(serde_json::to_string(&retained).unwrap(), "65");
+    }
+
+    #[cfg(feature = "msgpack")]
+    #[test]
+    fn test_serde_binary() {
+        let flags = Flags::A | Flags::C;
+        let bytes = rmp_serde::to_vec(&flags).unwrap();
+        assert_eq!(bytes, [5]);
+        assert_eq!(rmp_serde::from_slice::<Flags>(&bytes).unwrap(), flags);
+    }
+}
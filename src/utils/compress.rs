@@ -0,0 +1,266 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//! Compression of persisted buffers, such as encoded arenas and filters.
+//!
+//! [`compress`] wraps the compressed bytes in a small frame recording the
+//! codec, the uncompressed length and a CRC-32C of the uncompressed bytes,
+//! so [`decompress`] needs no other input and detects corruption:
+//!
+//! ```text
+//! codec: u8 | uncompressed length: varint | checksum: u32 LE | payload
+//! ```
+//!
+//! [`Codec::Lz4`] is always available. [`Codec::Zstd`] compresses better at
+//! a higher cost and needs the `zstd` feature.
+
+use crate::error::Error;
+use crate::utils::checksum::crc32c;
+use crate::utils::varint;
+use alloc::format;
+use alloc::vec::Vec;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Compressed bytes never expand to more than this many times their size
+/// with LZ4, which bounds the allocation for a corrupted length.
+const LZ4_MAX_RATIO: usize = 255;
+
+/// Compression codec of a frame, written as its first byte.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+#[repr(u8)]
+pub enum Codec {
+    /// Stores the bytes as they are.
+    None = 0,
+    /// The LZ4 block format, fast with a moderate ratio.
+    #[default]
+    Lz4 = 1,
+    /// Zstandard at its default level.
+    #[cfg(feature = "zstd")]
+    Zstd = 2,
+}
+
+impl Codec {
+    fn from_tag(tag: u8) -> Result<Self, Error> {
+        match tag {
+            0 => Ok(Codec::None),
+            1 => Ok(Codec::Lz4),
+            #[cfg(feature = "zstd")]
+            2 => Ok(Codec::Zstd),
+            #[cfg(not(feature = "zstd"))]
+            2 => Err(Error::Serialization(
+                "zstd compressed buffers need the `zstd` feature".into(),
+            )),
+            tag => Err(Error::Serialization(format!(
+                "unknown compression codec {}",
+                tag
+            ))),
+        }
+    }
+}
+
+/// Compresses `data` into a frame that [`decompress`] reverses.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::compress::compress;
+/// use pizza_common::utils::compress::decompress;
+/// use pizza_common::utils::compress::Codec;
+///
+/// let data = b"pizza ".repeat(100);
+/// let frame = compress(&data, Codec::Lz4);
+/// assert!(frame.len() < data.len() / 10);
+/// assert_eq!(decompress(&frame).unwrap(), data);
+/// ```
+pub fn compress(data: &[u8], codec: Codec) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(16 + data.len() / 2);
+    frame.push(codec as u8);
+    varint::encode_u64(data.len() as u64, &mut frame);
+    frame.extend_from_slice(&crc32c(data).to_le_bytes());
+    match codec {
+        Codec::None => frame.extend_from_slice(data),
+        Codec::Lz4 => frame.extend_from_slice(&lz4_flex::block::compress(data)),
+        #[cfg(feature = "zstd")]
+        Codec::Zstd => {
+            let compressed = zstd::bulk::compress(data, zstd::DEFAULT_COMPRESSION_LEVEL)
+                .expect("compressing an in-memory buffer cannot fail");
+            frame.extend_from_slice(&compressed);
+        }
+    }
+    frame
+}
+
+/// Decompresses a frame written by [`compress`], checking the length and
+/// checksum of the result.
+pub fn decompress(frame: &[u8]) -> Result<Vec<u8>, Error> {
+    let (codec, len, checksum, payload) = read_header(frame)?;
+    let data = match codec {
+        Codec::None => payload.to_vec(),
+        Codec::Lz4 => {
+            if len > payload.len().saturating_mul(LZ4_MAX_RATIO) {
+                return Err(Error::Serialization(format!(
+                    "compressed length {} cannot hold {} bytes",
+                    payload.len(),
+                    len
+                )));
+            }
+            lz4_flex::block::decompress(payload, len)
+                .map_err(|e| Error::Serialization(format!("corrupted lz4 payload: {}", e)))?
+        }
+        #[cfg(feature = "zstd")]
+        Codec::Zstd => {
+            // zstd has no useful ratio bound, so rather than allocating `len`
+            // up front, grow with the output and stop just past `len`.
+            use std::io::Read;
+            let mut data = Vec::new();
+            zstd::stream::read::Decoder::with_buffer(payload)
+                .and_then(|decoder| {
+                    decoder
+                        .take((len as u64).saturating_add(1))
+                        .read_to_end(&mut data)
+                })
+                .map_err(|e| Error::Serialization(format!("corrupted zstd payload: {}", e)))?;
+            data
+        }
+    };
+    if data.len() != len {
+        return Err(Error::Serialization(format!(
+            "expected {} uncompressed bytes, got {}",
+            len,
+            data.len()
+        )));
+    }
+    if crc32c(&data) != checksum {
+        return Err(Error::Serialization(
+            "checksum mismatch in compressed buffer".into(),
+        ));
+    }
+    Ok(data)
+}
+
+/// The codec and uncompressed length of a frame, without decompressing it.
+pub fn frame_info(frame: &[u8]) -> Result<(Codec, usize), Error> {
+    let (codec, len, _, _) = read_header(frame)?;
+    Ok((codec, len))
+}
+
+fn read_header(frame: &[u8]) -> Result<(Codec, usize, u32, &[u8]), Error> {
+    let truncated = || Error::Serialization("truncated compression header".into());
+    let (&tag, rest) = frame.split_first().ok_or_else(truncated)?;
+    let codec = Codec::from_tag(tag)?;
+    let (len, read) = varint::decode_u64(rest)?;
+    let len = usize::try_from(len)
+        .map_err(|_| Error::Serialization(format!("uncompressed length {} is too large", len)))?;
+    let rest = &rest[read..];
+    if rest.len() < 4 {
+        return Err(truncated());
+    }
+    let (checksum, payload) = rest.split_at(4);
+    let checksum = u32::from_le_bytes(checksum.try_into().unwrap());
+    Ok((codec, len, checksum, payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn codecs() -> Vec<Codec> {
+        vec![
+            Codec::None,
+            Codec::Lz4,
+            #[cfg(feature = "zstd")]
+            Codec::Zstd,
+        ]
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let inputs: [Vec<u8>; 4] = [
+            Vec::new(),
+            b"a".to_vec(),
+            b"the quick brown fox ".repeat(50),
+            (0..4096u32)
+                .map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8)
+                .collect(),
+        ];
+        for codec in codecs() {
+            for input in &inputs {
+                let frame = compress(input, codec);
+                assert_eq!(frame_info(&frame).unwrap(), (codec, input.len()));
+                assert_eq!(decompress(&frame).unwrap(), *input, "{:?}", codec);
+            }
+        }
+    }
+
+    #[test]
+    fn test_corruption_is_detected() {
+        let data = b"pizza margherita ".repeat(20);
+        for codec in codecs() {
+            let frame = compress(&data, codec);
+            for at in [0, 1, 3, frame.len() / 2, frame.len() - 1] {
+                let mut corrupted = frame.clone();
+                corrupted[at] ^= 0x55;
+                assert!(decompress(&corrupted).is_err(), "{:?} at {}", codec, at);
+            }
+            assert!(decompress(&frame[..frame.len() - 1]).is_err());
+        }
+        assert!(decompress(&[]).is_err());
+        assert!(decompress(&[1, 5, 0]).is_err());
+        assert!(matches!(
+            decompress(&[9, 0, 0, 0, 0, 0]),
+            Err(Error::Serialization(message)) if message == "unknown compression codec 9"
+        ));
+    }
+
+    #[test]
+    fn test_lz4_length_bound() {
+        let mut frame = compress(b"abc", Codec::Lz4);
+        // Claim a length no LZ4 payload of this size can expand to.
+        frame.splice(1..2, [0xff, 0xff, 0xff, 0x7f]);
+        assert!(decompress(&frame).is_err());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd_length_bound() {
+        let data = b"pizza ".repeat(1000);
+        let frame = compress(&data, Codec::Zstd);
+        for claimed in [u64::MAX >> 1, data.len() as u64 - 1, data.len() as u64 + 1] {
+            let mut corrupted = vec![Codec::Zstd as u8];
+            varint::encode_u64(claimed, &mut corrupted);
+            // Skip the codec and the two byte length, keep the checksum.
+            corrupted.extend_from_slice(&frame[3..]);
+            assert!(decompress(&corrupted).is_err(), "{}", claimed);
+        }
+    }
+
+    #[test]
+    fn test_codec_serde() {
+        assert_eq!(serde_json::to_string(&Codec::Lz4).unwrap(), r#""lz4""#);
+        assert_eq!(
+            serde_json::from_str::<Codec>(r#""none""#).unwrap(),
+            Codec::None
+        );
+    }
+}
@@ -0,0 +1,450 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Hashing that is stable across platforms, processes and releases.
+//!
+//! The std `DefaultHasher` is randomly seeded and its algorithm may change
+//! between Rust versions, so it must not be used for anything persisted or
+//! shared between nodes, such as shard assignment.
+//!
+//! Byte hashes come in two families: FNV-1a, which is tiny and fast on short
+//! keys, and xxHash (XXH32/XXH64), which is much faster on long inputs and
+//! takes a seed. All of them match the reference implementations.
+
+use core::hash::BuildHasher;
+use core::hash::Hash;
+use core::hash::Hasher;
+
+pub const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+pub const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+pub const FNV_OFFSET_BASIS_32: u32 = 0x811c_9dc5;
+pub const FNV_PRIME_32: u32 = 0x0100_0193;
+
+/// 32-bit FNV-1a of `bytes`.
+pub fn fnv1a_32(bytes: &[u8]) -> u32 {
+    bytes.iter().fold(FNV_OFFSET_BASIS_32, |hash, &b| {
+        (hash ^ b as u32).wrapping_mul(FNV_PRIME_32)
+    })
+}
+
+/// 64-bit FNV-1a of `bytes`.
+pub fn fnv1a_64(bytes: &[u8]) -> u64 {
+    fnv1a_64_append(FNV_OFFSET_BASIS, bytes)
+}
+
+fn fnv1a_64_append(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+/// A [`Hasher`] based on FNV-1a whose output does not depend on the
+/// platform: integers are fed in little-endian order and `usize`/`isize`
+/// are widened to 64 bits.
+#[derive(Debug, Clone, Copy)]
+pub struct StableHasher {
+    state: u64,
+}
+
+impl StableHasher {
+    pub fn new() -> Self {
+        Self {
+            state: FNV_OFFSET_BASIS,
+        }
+    }
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.state
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        self.state = fnv1a_64_append(self.state, bytes);
+    }
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes());
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16);
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32);
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64);
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i as u128);
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as i64 as u64);
+    }
+}
+
+/// Builds [`StableHasher`]s, for hash maps whose iteration order and
+/// hashes must not change between processes.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StableBuildHasher;
+
+impl BuildHasher for StableBuildHasher {
+    type Hasher = StableHasher;
+
+    fn build_hasher(&self) -> StableHasher {
+        StableHasher::new()
+    }
+}
+
+/// A [`hashbrown::HashMap`] hashed with [`StableHasher`].
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::hash::StableHashMap;
+///
+/// let mut shards = StableHashMap::default();
+/// shards.insert("index-a", 3);
+/// assert_eq!(shards["index-a"], 3);
+/// ```
+pub type StableHashMap<K, V> = hashbrown::HashMap<K, V, StableBuildHasher>;
+
+/// A [`hashbrown::HashSet`] hashed with [`StableHasher`].
+pub type StableHashSet<T> = hashbrown::HashSet<T, StableBuildHasher>;
+
+const XXH32_PRIME_1: u32 = 0x9e37_79b1;
+const XXH32_PRIME_2: u32 = 0x85eb_ca77;
+const XXH32_PRIME_3: u32 = 0xc2b2_ae3d;
+const XXH32_PRIME_4: u32 = 0x27d4_eb2f;
+const XXH32_PRIME_5: u32 = 0x1656_67b1;
+
+const XXH64_PRIME_1: u64 = 0x9e37_79b1_85eb_ca87;
+const XXH64_PRIME_2: u64 = 0xc2b2_ae3d_27d4_eb4f;
+const XXH64_PRIME_3: u64 = 0x1656_67b1_9e37_79f9;
+const XXH64_PRIME_4: u64 = 0x85eb_ca77_c2b2_ae63;
+const XXH64_PRIME_5: u64 = 0x27d4_eb2f_1656_67c5;
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_le_bytes(bytes[..4].try_into().unwrap())
+}
+
+fn read_u64(bytes: &[u8]) -> u64 {
+    u64::from_le_bytes(bytes[..8].try_into().unwrap())
+}
+
+fn xxh32_round(acc: u32, lane: u32) -> u32 {
+    acc.wrapping_add(lane.wrapping_mul(XXH32_PRIME_2))
+        .rotate_left(13)
+        .wrapping_mul(XXH32_PRIME_1)
+}
+
+/// 32-bit xxHash (XXH32) of `bytes`.
+pub fn xxh32(bytes: &[u8], seed: u32) -> u32 {
+    let mut stripes = bytes.chunks_exact(16);
+    let mut h = if bytes.len() >= 16 {
+        let mut acc = [
+            seed.wrapping_add(XXH32_PRIME_1).wrapping_add(XXH32_PRIME_2),
+            seed.wrapping_add(XXH32_PRIME_2),
+            seed,
+            seed.wrapping_sub(XXH32_PRIME_1),
+        ];
+        for stripe in &mut stripes {
+            for (i, acc) in acc.iter_mut().enumerate() {
+                *acc = xxh32_round(*acc, read_u32(&stripe[i * 4..]));
+            }
+        }
+        acc[0]
+            .rotate_left(1)
+            .wrapping_add(acc[1].rotate_left(7))
+            .wrapping_add(acc[2].rotate_left(12))
+            .wrapping_add(acc[3].rotate_left(18))
+    } else {
+        seed.wrapping_add(XXH32_PRIME_5)
+    };
+    h = h.wrapping_add(bytes.len() as u32);
+
+    let mut words = stripes.remainder().chunks_exact(4);
+    for word in &mut words {
+        h = h
+            .wrapping_add(read_u32(word).wrapping_mul(XXH32_PRIME_3))
+            .rotate_left(17)
+            .wrapping_mul(XXH32_PRIME_4);
+    }
+    for &b in words.remainder() {
+        h = h
+            .wrapping_add((b as u32).wrapping_mul(XXH32_PRIME_5))
+            .rotate_left(11)
+            .wrapping_mul(XXH32_PRIME_1);
+    }
+
+    h ^= h >> 15;
+    h = h.wrapping_mul(XXH32_PRIME_2);
+    h ^= h >> 13;
+    h = h.wrapping_mul(XXH32_PRIME_3);
+    h ^ (h >> 16)
+}
+
+fn xxh64_round(acc: u64, lane: u64) -> u64 {
+    acc.wrapping_add(lane.wrapping_mul(XXH64_PRIME_2))
+        .rotate_left(31)
+        .wrapping_mul(XXH64_PRIME_1)
+}
+
+fn xxh64_merge_round(h: u64, acc: u64) -> u64 {
+    (h ^ xxh64_round(0, acc))
+        .wrapping_mul(XXH64_PRIME_1)
+        .wrapping_add(XXH64_PRIME_4)
+}
+
+/// 64-bit xxHash (XXH64) of `bytes`.
+pub fn xxh64(bytes: &[u8], seed: u64) -> u64 {
+    let mut stripes = bytes.chunks_exact(32);
+    let mut h = if bytes.len() >= 32 {
+        let mut acc = [
+            seed.wrapping_add(XXH64_PRIME_1).wrapping_add(XXH64_PRIME_2),
+            seed.wrapping_add(XXH64_PRIME_2),
+            seed,
+            seed.wrapping_sub(XXH64_PRIME_1),
+        ];
+        for stripe in &mut stripes {
+            for (i, acc) in acc.iter_mut().enumerate() {
+                *acc = xxh64_round(*acc, read_u64(&stripe[i * 8..]));
+            }
+        }
+        let h = acc[0]
+            .rotate_left(1)
+            .wrapping_add(acc[1].rotate_left(7))
+            .wrapping_add(acc[2].rotate_left(12))
+            .wrapping_add(acc[3].rotate_left(18));
+        acc.iter().fold(h, |h, &acc| xxh64_merge_round(h, acc))
+    } else {
+        seed.wrapping_add(XXH64_PRIME_5)
+    };
+    h = h.wrapping_add(bytes.len() as u64);
+
+    let mut words = stripes.remainder().chunks_exact(8);
+    for word in &mut words {
+        h ^= xxh64_round(0, read_u64(word));
+        h = h
+            .rotate_left(27)
+            .wrapping_mul(XXH64_PRIME_1)
+            .wrapping_add(XXH64_PRIME_4);
+    }
+    let mut half_words = words.remainder().chunks_exact(4);
+    for word in &mut half_words {
+        h ^= (read_u32(word) as u64).wrapping_mul(XXH64_PRIME_1);
+        h = h
+            .rotate_left(23)
+            .wrapping_mul(XXH64_PRIME_2)
+            .wrapping_add(XXH64_PRIME_3);
+    }
+    for &b in half_words.remainder() {
+        h ^= (b as u64).wrapping_mul(XXH64_PRIME_5);
+        h = h.rotate_left(11).wrapping_mul(XXH64_PRIME_1);
+    }
+
+    h ^= h >> 33;
+    h = h.wrapping_mul(XXH64_PRIME_2);
+    h ^= h >> 29;
+    h = h.wrapping_mul(XXH64_PRIME_3);
+    h ^ (h >> 32)
+}
+
+/// Stable 64-bit hash of `bytes` under `seed`, currently [`xxh64`].
+pub fn hash_bytes_with_seed(bytes: &[u8], seed: u64) -> u64 {
+    xxh64(bytes, seed)
+}
+
+/// Stable 64-bit hash of a string's UTF-8 bytes, the unseeded
+/// [`hash_bytes_with_seed`].
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::hash::hash_bytes_with_seed;
+/// use pizza_common::utils::hash::hash_str;
+///
+/// assert_eq!(hash_str("pizza"), hash_bytes_with_seed(b"pizza", 0));
+/// ```
+pub fn hash_str(s: &str) -> u64 {
+    hash_bytes_with_seed(s.as_bytes(), 0)
+}
+
+/// 32-bit MurmurHash3 (x86 variant) of `bytes`.
+pub fn murmur3_x86_32(bytes: &[u8], seed: u32) -> u32 {
+    const C1: u32 = 0xcc9e_2d51;
+    const C2: u32 = 0x1b87_3593;
+
+    let mut h = seed;
+    let mut blocks = bytes.chunks_exact(4);
+    for block in &mut blocks {
+        let mut k = u32::from_le_bytes(block.try_into().unwrap());
+        k = k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+        h ^= k;
+        h = h.rotate_left(13).wrapping_mul(5).wrapping_add(0xe654_6b64);
+    }
+
+    let tail = blocks.remainder();
+    if !tail.is_empty() {
+        let mut k = 0_u32;
+        for (i, &b) in tail.iter().enumerate() {
+            k |= (b as u32) << (8 * i);
+        }
+        h ^= k.wrapping_mul(C1).rotate_left(15).wrapping_mul(C2);
+    }
+
+    h ^= bytes.len() as u32;
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x85eb_ca6b);
+    h ^= h >> 13;
+    h = h.wrapping_mul(0xc2b2_ae35);
+    h ^= h >> 16;
+    h
+}
+
+/// Hashes `value` with a fresh [`StableHasher`].
+pub fn stable_hash<T: Hash + ?Sized>(value: &T) -> u64 {
+    let mut hasher = StableHasher::new();
+    value.hash(&mut hasher);
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fnv1a_vectors() {
+        assert_eq!(fnv1a_64(b""), 0xcbf2_9ce4_8422_2325);
+        assert_eq!(fnv1a_64(b"a"), 0xaf63_dc4c_8601_ec8c);
+        assert_eq!(fnv1a_64(b"foobar"), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn test_fnv1a_32_vectors() {
+        assert_eq!(fnv1a_32(b""), 0x811c_9dc5);
+        assert_eq!(fnv1a_32(b"a"), 0xe40c_292c);
+        assert_eq!(fnv1a_32(b"foobar"), 0xbf9c_f968);
+    }
+
+    #[test]
+    fn test_xxhash_vectors() {
+        const SPAM: &[u8] = b"Nobody inspects the spammish repetition";
+        assert_eq!(xxh32(b"", 0), 0x02cc_5d05);
+        assert_eq!(xxh32(b"a", 0), 0x550d_7456);
+        assert_eq!(xxh32(b"abc", 0), 0x32d1_53ff);
+        assert_eq!(xxh32(SPAM, 0), 0xe229_3b2f);
+
+        assert_eq!(xxh64(b"", 0), 0xef46_db37_51d8_e999);
+        assert_eq!(xxh64(b"a", 0), 0xd24e_c4f1_a98c_6e5b);
+        assert_eq!(xxh64(b"abc", 0), 0x44bc_2cf5_ad77_0999);
+        assert_eq!(xxh64(SPAM, 0), 0xfbce_a83c_8a37_8bf1);
+        assert_eq!(
+            xxh64(b"I want an unsigned 64-bit seed!", 0),
+            0xd4cb_0a70_a2b8_c7c1
+        );
+        assert_eq!(
+            xxh64(b"I want an unsigned 64-bit seed!", 1),
+            0xce50_87f1_2470_d961
+        );
+    }
+
+    #[test]
+    fn test_seeds_and_lengths() {
+        // Cover every tail length around the stripe sizes.
+        let data: alloc::vec::Vec<u8> = (0..100u8).collect();
+        for len in 0..data.len() {
+            let bytes = &data[..len];
+            assert_ne!(
+                hash_bytes_with_seed(bytes, 1),
+                hash_bytes_with_seed(bytes, 2)
+            );
+            assert_ne!(xxh32(bytes, 1), xxh32(bytes, 2));
+            if len > 0 {
+                assert_ne!(xxh64(bytes, 0), xxh64(&data[..len - 1], 0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_stable_hash_map() {
+        let mut a = StableHashMap::default();
+        let mut b = StableHashMap::default();
+        for i in 0..100u32 {
+            a.insert(i, i);
+            b.insert(99 - i, 99 - i);
+        }
+        assert_eq!(
+            a.hasher().hash_one("key"),
+            StableBuildHasher.hash_one("key")
+        );
+        assert_eq!(a, b);
+        let set: StableHashSet<&str> = ["x", "y"].into_iter().collect();
+        assert!(set.contains("x"));
+    }
+
+    #[test]
+    fn test_murmur3_vectors() {
+        assert_eq!(murmur3_x86_32(b"", 0), 0);
+        assert_eq!(murmur3_x86_32(b"", 1), 0x514e_28b7);
+        assert_eq!(murmur3_x86_32(b"hello", 0), 0x248b_fa47);
+        assert_eq!(murmur3_x86_32(b"hello", 42), 0xe2db_d2e1);
+    }
+
+    #[test]
+    fn test_stable_hash_is_platform_independent() {
+        // 7usize must hash like 7u64 on every target.
+        assert_eq!(stable_hash(&7usize), stable_hash(&7u64));
+        assert_eq!(stable_hash(&7u64), fnv1a_64(&7u64.to_le_bytes()));
+        assert_eq!(stable_hash("pizza"), stable_hash("pizza"));
+        assert_ne!(stable_hash("pizza"), stable_hash("pasta"));
+    }
+}
@@ -19,6 +19,15 @@
 // LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
+use crate::error::Error;
+use alloc::borrow::Cow;
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::Write;
+use serde_json::Map;
 use serde_json::Value;
 
 /// Compares two JSON strings for equality.
@@ -41,17 +50,644 @@ use serde_json::Value;
 ///
 /// assert_eq!(compare_json(json1, json2), true);
 /// ```
+///
+/// # Panics
+///
+/// Panics if either string is not valid JSON, see [`try_compare_json`].
 pub fn compare_json(json1: &str, json2: &str) -> bool {
     let value1: Value = serde_json::from_str(json1).unwrap();
     let value2: Value = serde_json::from_str(json2).unwrap();
     value1 == value2
 }
 
+/// Options of [`try_compare_json`] and [`compare_json_values`].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CompareOptions {
+    /// Numbers are equal if they differ by at most this much. Integers that
+    /// fit `i64` or `u64` are still compared exactly.
+    pub float_epsilon: f64,
+    /// Compare arrays as multisets instead of sequences.
+    pub ignore_array_order: bool,
+    /// JSON Pointers of values to skip on both sides; a `*` token matches
+    /// any object member or array index.
+    pub ignored_paths: Vec<String>,
+}
+
+/// Like [`compare_json`], but reports malformed input as an
+/// [`Error::Parse`] and compares as `options` describes.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::json::try_compare_json;
+/// use pizza_common::utils::json::CompareOptions;
+///
+/// let options = CompareOptions {
+///     float_epsilon: 1e-6,
+///     ignore_array_order: true,
+///     ignored_paths: vec!["/took".into()],
+/// };
+/// let expected = r#"{"took": 3, "score": 1.0, "ids": [1, 2]}"#;
+/// let actual = r#"{"took": 5, "score": 1.00000001, "ids": [2, 1]}"#;
+/// assert!(try_compare_json(expected, actual, &options).unwrap());
+/// assert!(try_compare_json(expected, "{", &options).is_err());
+/// ```
+pub fn try_compare_json(json1: &str, json2: &str, options: &CompareOptions) -> Result<bool, Error> {
+    let parse = |json, which| {
+        serde_json::from_str::<Value>(json)
+            .map_err(|e| Error::Parse(format!("{} document is not valid JSON: {}", which, e)))
+    };
+    let value1 = parse(json1, "first")?;
+    let value2 = parse(json2, "second")?;
+    Ok(compare_json_values(&value1, &value2, options))
+}
+
+/// Compares two parsed documents as `options` describes.
+///
+/// With [`CompareOptions::ignore_array_order`], elements are paired
+/// greedily, which can miss a match when a tolerance makes one element
+/// equal to several others.
+pub fn compare_json_values(value1: &Value, value2: &Value, options: &CompareOptions) -> bool {
+    let ignored: Vec<Vec<Cow<'_, str>>> = options
+        .ignored_paths
+        .iter()
+        .filter_map(|path| Some(pointer_tokens(path)?.collect()))
+        .collect();
+    let comparer = Comparer { options, ignored };
+    comparer.eq(&mut Vec::new(), value1, value2)
+}
+
+struct Comparer<'a> {
+    options: &'a CompareOptions,
+    ignored: Vec<Vec<Cow<'a, str>>>,
+}
+
+impl Comparer<'_> {
+    fn is_ignored(&self, path: &[String]) -> bool {
+        self.ignored.iter().any(|pattern| {
+            pattern.len() == path.len()
+                && pattern
+                    .iter()
+                    .zip(path)
+                    .all(|(pattern, token)| pattern == "*" || pattern == token)
+        })
+    }
+
+    fn child_eq(&self, path: &mut Vec<String>, token: String, a: &Value, b: &Value) -> bool {
+        path.push(token);
+        let eq = self.is_ignored(path) || self.eq(path, a, b);
+        path.pop();
+        eq
+    }
+
+    fn eq(&self, path: &mut Vec<String>, a: &Value, b: &Value) -> bool {
+        match (a, b) {
+            (Value::Number(x), Value::Number(y)) => {
+                if x == y {
+                    return true;
+                }
+                let exact = |n: &serde_json::Number| n.is_i64() || n.is_u64();
+                if exact(x) && exact(y) {
+                    return false;
+                }
+                match (x.as_f64(), y.as_f64()) {
+                    (Some(x), Some(y)) => (x - y).abs() <= self.options.float_epsilon,
+                    _ => false,
+                }
+            }
+            (Value::Object(x), Value::Object(y)) => {
+                x.iter().all(|(key, a)| match y.get(key) {
+                    Some(b) => self.child_eq(path, key.clone(), a, b),
+                    None => self.is_member_ignored(path, key),
+                }) && y
+                    .keys()
+                    .filter(|key| !x.contains_key(*key))
+                    .all(|key| self.is_member_ignored(path, key))
+            }
+            (Value::Array(x), Value::Array(y)) if x.len() == y.len() => {
+                if !self.options.ignore_array_order {
+                    return x
+                        .iter()
+                        .zip(y)
+                        .enumerate()
+                        .all(|(i, (a, b))| self.child_eq(path, i.to_string(), a, b));
+                }
+                let mut used = alloc::vec![false; y.len()];
+                x.iter().enumerate().all(|(i, a)| {
+                    let found = y
+                        .iter()
+                        .enumerate()
+                        .position(|(j, b)| !used[j] && self.child_eq(path, i.to_string(), a, b));
+                    found.map(|j| used[j] = true).is_some()
+                })
+            }
+            _ => a == b,
+        }
+    }
+
+    fn is_member_ignored(&self, path: &mut Vec<String>, key: &str) -> bool {
+        path.push(key.into());
+        let ignored = self.is_ignored(path);
+        path.pop();
+        ignored
+    }
+}
+
+/// One difference found by [`diff_json`], located by a JSON Pointer
+/// (RFC 6901) path; the root is the empty path.
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonChange {
+    /// `path` only exists in the new document.
+    Added { path: String, value: Value },
+    /// `path` only exists in the old document.
+    Removed { path: String, value: Value },
+    /// `path` holds a different value in each document.
+    Replaced {
+        path: String,
+        old: Value,
+        new: Value,
+    },
+}
+
+impl JsonChange {
+    /// The JSON Pointer of the changed value.
+    pub fn path(&self) -> &str {
+        match self {
+            JsonChange::Added { path, .. }
+            | JsonChange::Removed { path, .. }
+            | JsonChange::Replaced { path, .. } => path,
+        }
+    }
+}
+
+impl fmt::Display for JsonChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let at = if self.path().is_empty() {
+            "/"
+        } else {
+            self.path()
+        };
+        match self {
+            JsonChange::Added { value, .. } => write!(f, "{}: unexpected {}", at, value),
+            JsonChange::Removed { value, .. } => write!(f, "{}: missing, expected {}", at, value),
+            JsonChange::Replaced { old, new, .. } => {
+                write!(f, "{}: expected {}, got {}", at, old, new)
+            }
+        }
+    }
+}
+
+/// The differences between two JSON documents, see [`diff_json`].
+///
+/// Its `Display` prints one change per line.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JsonPatch {
+    changes: Vec<JsonChange>,
+}
+
+impl JsonPatch {
+    /// Returns `true` if the documents are equal.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// The number of changes.
+    pub fn len(&self) -> usize {
+        self.changes.len()
+    }
+
+    /// The changes in document order.
+    pub fn changes(&self) -> &[JsonChange] {
+        &self.changes
+    }
+
+    /// Consumes the patch, returning its changes.
+    pub fn into_changes(self) -> Vec<JsonChange> {
+        self.changes
+    }
+}
+
+impl fmt::Display for JsonPatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for change in &self.changes {
+            writeln!(f, "{}", change)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> IntoIterator for &'a JsonPatch {
+    type Item = &'a JsonChange;
+    type IntoIter = core::slice::Iter<'a, JsonChange>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.changes.iter()
+    }
+}
+
+/// Computes the changes turning `old` into `new`.
+///
+/// Objects are compared key by key and arrays index by index, so a change is
+/// reported at the deepest path where the documents disagree. Object keys
+/// are escaped as JSON Pointer tokens (`~` as `~0`, `/` as `~1`).
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::json::diff_json;
+/// use pizza_common::utils::json::JsonChange;
+/// use serde_json::json;
+///
+/// let patch = diff_json(&json!({"a": 1, "b": [1, 2]}), &json!({"a": 1, "b": [1, 3]}));
+/// assert_eq!(
+///     patch.changes(),
+///     [JsonChange::Replaced { path: "/b/1".into(), old: json!(2), new: json!(3) }]
+/// );
+/// ```
+pub fn diff_json(old: &Value, new: &Value) -> JsonPatch {
+    fn walk(path: &mut String, old: &Value, new: &Value, out: &mut Vec<JsonChange>) {
+        match (old, new) {
+            (Value::Object(o), Value::Object(n)) => {
+                for (key, o_value) in o {
+                    let len = path.len();
+                    push_token(path, key);
+                    match n.get(key) {
+                        Some(n_value) => walk(path, o_value, n_value, out),
+                        None => out.push(JsonChange::Removed {
+                            path: path.clone(),
+                            value: o_value.clone(),
+                        }),
+                    }
+                    path.truncate(len);
+                }
+                for (key, n_value) in n.iter().filter(|(key, _)| !o.contains_key(*key)) {
+                    let len = path.len();
+                    push_token(path, key);
+                    out.push(JsonChange::Added {
+                        path: path.clone(),
+                        value: n_value.clone(),
+                    });
+                    path.truncate(len);
+                }
+            }
+            (Value::Array(o), Value::Array(n)) => {
+                for i in 0..o.len().max(n.len()) {
+                    let len = path.len();
+                    let _ = write!(path, "/{}", i);
+                    match (o.get(i), n.get(i)) {
+                        (Some(o), Some(n)) => walk(path, o, n, out),
+                        (Some(o), None) => out.push(JsonChange::Removed {
+                            path: path.clone(),
+                            value: o.clone(),
+                        }),
+                        (None, Some(n)) => out.push(JsonChange::Added {
+                            path: path.clone(),
+                            value: n.clone(),
+                        }),
+                        (None, None) => unreachable!(),
+                    }
+                    path.truncate(len);
+                }
+            }
+            _ if old != new => out.push(JsonChange::Replaced {
+                path: path.clone(),
+                old: old.clone(),
+                new: new.clone(),
+            }),
+            _ => {}
+        }
+    }
+
+    let mut changes = Vec::new();
+    walk(&mut String::new(), old, new, &mut changes);
+    JsonPatch { changes }
+}
+
+fn push_token(path: &mut String, key: &str) {
+    path.push('/');
+    for c in key.chars() {
+        match c {
+            '~' => path.push_str("~0"),
+            '/' => path.push_str("~1"),
+            c => path.push(c),
+        }
+    }
+}
+
+/// How [`merge_json_with`] treats an array in the patch.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ArrayMerge {
+    /// The patch array replaces the target, as RFC 7386 specifies.
+    #[default]
+    Replace,
+    /// Elements are merged index by index into a target array; extra patch
+    /// elements are appended and extra target elements are kept.
+    Deep,
+}
+
+/// Applies `patch` to `target` with RFC 7386 JSON merge-patch semantics.
+///
+/// Object members of the patch are merged recursively, `null` members remove
+/// the key from the target, and any other value replaces it.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::json::merge_json;
+/// use serde_json::json;
+///
+/// let mut config = json!({"host": "localhost", "tls": {"enabled": false, "cert": "a.pem"}});
+/// merge_json(&mut config, &json!({"tls": {"enabled": true, "cert": null}}));
+/// assert_eq!(config, json!({"host": "localhost", "tls": {"enabled": true}}));
+/// ```
+pub fn merge_json(target: &mut Value, patch: &Value) {
+    merge_json_with(target, patch, ArrayMerge::Replace)
+}
+
+/// Like [`merge_json`], with `arrays` choosing how arrays are merged.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::json::merge_json_with;
+/// use pizza_common::utils::json::ArrayMerge;
+/// use serde_json::json;
+///
+/// let mut nodes = json!([{"name": "a", "port": 1}, {"name": "b", "port": 2}]);
+/// merge_json_with(&mut nodes, &json!([{"port": 10}]), ArrayMerge::Deep);
+/// assert_eq!(nodes, json!([{"name": "a", "port": 10}, {"name": "b", "port": 2}]));
+/// ```
+pub fn merge_json_with(target: &mut Value, patch: &Value, arrays: ArrayMerge) {
+    match (target, patch) {
+        (target, Value::Object(patch)) => {
+            if !target.is_object() {
+                *target = Value::Object(Map::new());
+            }
+            let Value::Object(target) = target else {
+                unreachable!()
+            };
+            for (key, value) in patch {
+                if value.is_null() {
+                    target.remove(key);
+                } else {
+                    merge_json_with(
+                        target.entry(key.clone()).or_insert(Value::Null),
+                        value,
+                        arrays,
+                    );
+                }
+            }
+        }
+        (Value::Array(target), Value::Array(patch)) if arrays == ArrayMerge::Deep => {
+            for (i, value) in patch.iter().enumerate() {
+                match target.get_mut(i) {
+                    Some(slot) => merge_json_with(slot, value, arrays),
+                    None => {
+                        let mut slot = Value::Null;
+                        merge_json_with(&mut slot, value, arrays);
+                        target.push(slot);
+                    }
+                }
+            }
+        }
+        (target, patch) => *target = patch.clone(),
+    }
+}
+
+fn pointer_tokens(pointer: &str) -> Option<impl Iterator<Item = Cow<'_, str>>> {
+    let rest = match pointer {
+        "" => None,
+        _ => Some(pointer.strip_prefix('/')?),
+    };
+    Some(
+        rest.into_iter()
+            .flat_map(|rest| rest.split('/'))
+            .map(|token| {
+                if token.contains('~') {
+                    Cow::Owned(token.replace("~1", "/").replace("~0", "~"))
+                } else {
+                    Cow::Borrowed(token)
+                }
+            }),
+    )
+}
+
+fn array_index(token: &str, len: usize) -> Option<usize> {
+    if token == "-" {
+        return Some(len);
+    }
+    if token.len() > 1 && token.starts_with('0') || !token.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    token.parse().ok()
+}
+
+/// Returns the value at a JSON Pointer (RFC 6901) path, the empty pointer
+/// being the whole document.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::json::get_path;
+/// use serde_json::json;
+///
+/// let doc = json!({"user": {"tags": ["a", "b"]}});
+/// assert_eq!(get_path(&doc, "/user/tags/1"), Some(&json!("b")));
+/// assert_eq!(get_path(&doc, "/user/name"), None);
+/// ```
+pub fn get_path<'a>(value: &'a Value, pointer: &str) -> Option<&'a Value> {
+    pointer_tokens(pointer)?.try_fold(value, |value, token| match value {
+        Value::Object(map) => map.get(token.as_ref()),
+        Value::Array(list) => list.get(array_index(&token, usize::MAX)?),
+        _ => None,
+    })
+}
+
+/// Stores `new` at a JSON Pointer path, returning the value it replaced.
+///
+/// Missing object members along the path are created, and so is an object
+/// in place of a `null`. An array index must name an existing element, or
+/// be the array length or `-` to append; anything else, a pointer without a
+/// leading `/` or a path through a scalar fails with
+/// [`Error::InvalidArgument`].
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::json::set_path;
+/// use serde_json::json;
+///
+/// let mut doc = json!({"tags": ["a"]});
+/// set_path(&mut doc, "/user/name", json!("bob")).unwrap();
+/// set_path(&mut doc, "/tags/-", json!("b")).unwrap();
+/// assert_eq!(doc, json!({"tags": ["a", "b"], "user": {"name": "bob"}}));
+/// assert!(set_path(&mut doc, "/tags/5", json!("c")).is_err());
+/// ```
+pub fn set_path(value: &mut Value, pointer: &str, new: Value) -> Result<Option<Value>, Error> {
+    let tokens = pointer_tokens(pointer)
+        .ok_or_else(|| Error::InvalidArgument("JSON pointer must start with '/'".into()))?;
+    let mut path = String::new();
+    let mut slot = value;
+    for token in tokens {
+        if slot.is_null() {
+            *slot = Value::Object(Map::new());
+        }
+        slot = match slot {
+            Value::Object(map) => map.entry(token.as_ref()).or_insert(Value::Null),
+            Value::Array(list) => {
+                let len = list.len();
+                match array_index(&token, len) {
+                    Some(i) if i == len => {
+                        list.push(Value::Null);
+                        &mut list[i]
+                    }
+                    Some(i) if i < len => &mut list[i],
+                    _ => {
+                        push_token(&mut path, &token);
+                        return Err(Error::InvalidArgument(format!(
+                            "invalid array index at '{}'",
+                            path
+                        )));
+                    }
+                }
+            }
+            _ => {
+                return Err(Error::InvalidArgument(format!(
+                    "value at '{}' is not an object or array",
+                    path
+                )))
+            }
+        };
+        push_token(&mut path, &token);
+    }
+    let old = core::mem::replace(slot, new);
+    Ok((!old.is_null()).then_some(old))
+}
+
+/// Flattens nested objects and arrays into `(dotted key, leaf)` pairs.
+///
+/// Keys join object members and array indices with `.`; scalars and empty
+/// objects or arrays are leaves. A scalar document flattens to one pair with
+/// an empty key.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::json::flatten;
+/// use serde_json::json;
+///
+/// let flat = flatten(&json!({"a": {"b": [1, 2]}, "c": {}}));
+/// assert_eq!(
+///     flat,
+///     [("a.b.0".into(), json!(1)), ("a.b.1".into(), json!(2)), ("c".into(), json!({}))]
+/// );
+/// ```
+pub fn flatten(value: &Value) -> Vec<(String, Value)> {
+    fn walk(prefix: &mut String, value: &Value, out: &mut Vec<(String, Value)>) {
+        let len = prefix.len();
+        let mut descend = |key: &str, value: &Value, prefix: &mut String| {
+            if len > 0 {
+                prefix.push('.');
+            }
+            prefix.push_str(key);
+            walk(prefix, value, out);
+            prefix.truncate(len);
+        };
+        match value {
+            Value::Object(map) if !map.is_empty() => {
+                for (key, value) in map {
+                    descend(key, value, prefix);
+                }
+            }
+            Value::Array(list) if !list.is_empty() => {
+                for (i, value) in list.iter().enumerate() {
+                    descend(&i.to_string(), value, prefix);
+                }
+            }
+            _ => out.push((prefix.clone(), value.clone())),
+        }
+    }
+
+    let mut out = Vec::new();
+    walk(&mut String::new(), value, &mut out);
+    out
+}
+
+/// Rebuilds a document from [`flatten`]ed pairs.
+///
+/// A key segment made of digits appends to an array if it equals the array's
+/// length or indexes into it if it is smaller, as in [`flatten`]ed order.
+/// Any other segment is an object key, so objects with numeric or dotted
+/// keys do not round-trip.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::json::flatten;
+/// use pizza_common::utils::json::unflatten;
+/// use serde_json::json;
+///
+/// let doc = json!({"a": {"b": [1, {"c": true}]}, "d": []});
+/// assert_eq!(unflatten(flatten(&doc)), doc);
+/// ```
+pub fn unflatten<K, I>(entries: I) -> Value
+where
+    K: AsRef<str>,
+    I: IntoIterator<Item = (K, Value)>,
+{
+    let mut root = Value::Null;
+    for (key, leaf) in entries {
+        let key = key.as_ref();
+        if key.is_empty() {
+            root = leaf;
+            continue;
+        }
+        let mut slot = &mut root;
+        for segment in key.split('.') {
+            // Never pad, a large index must not allocate a large array.
+            let len = match slot {
+                Value::Array(list) => list.len(),
+                _ => 0,
+            };
+            let index = array_index(segment, len).filter(|&i| segment != "-" && i <= len);
+            slot = match (slot, index) {
+                (slot @ Value::Array(_), Some(i)) | (slot @ Value::Null, Some(i)) => {
+                    if slot.is_null() {
+                        *slot = Value::Array(Vec::new());
+                    }
+                    let Value::Array(list) = slot else {
+                        unreachable!()
+                    };
+                    if i == list.len() {
+                        list.push(Value::Null);
+                    }
+                    &mut list[i]
+                }
+                (slot, _) => {
+                    if !slot.is_object() {
+                        *slot = Value::Object(Map::new());
+                    }
+                    let Value::Object(map) = slot else {
+                        unreachable!()
+                    };
+                    map.entry(segment).or_insert(Value::Null)
+                }
+            };
+        }
+        *slot = leaf;
+    }
+    root
+}
+
 #[cfg(test)]
 mod test {
-    use crate::utils::json::compare_json;
+    use crate::utils::json::*;
+    use alloc::string::ToString;
+    use alloc::vec;
+    use serde_json::json;
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_compare_json_equal() {
         let json1 = r#"{"name":"John","age":30}"#;
         let json2 = r#"{"age":30,"name":"John"}"#;
@@ -59,9 +695,267 @@ mod test {
     }
 
     #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_compare_json_not_equal() {
         let json1 = r#"{"name":"John","age":30}"#;
         let json2 = r#"{"name":"Jane","age":25}"#;
         assert_eq!(compare_json(json1, json2), false);
     }
+
+    #[test]
+    fn test_diff_json() {
+        let old = json!({"a": 1, "b": [1, 2, 3], "c": {"d": true}, "e/f": 0});
+        let new = json!({"a": 1, "b": [1, 4], "c": {"d": false, "g": null}, "e/f": 0});
+        assert_eq!(
+            diff_json(&old, &new).into_changes(),
+            [
+                JsonChange::Replaced {
+                    path: "/b/1".into(),
+                    old: json!(2),
+                    new: json!(4)
+                },
+                JsonChange::Removed {
+                    path: "/b/2".into(),
+                    value: json!(3)
+                },
+                JsonChange::Replaced {
+                    path: "/c/d".into(),
+                    old: json!(true),
+                    new: json!(false)
+                },
+                JsonChange::Added {
+                    path: "/c/g".into(),
+                    value: json!(null)
+                },
+            ]
+        );
+        assert!(diff_json(&old, &old).is_empty());
+
+        let patch = diff_json(&json!({"a~b": 1, "x/y": 1}), &json!({"a~b": 2}));
+        let paths: Vec<&str> = patch.changes().iter().map(JsonChange::path).collect();
+        assert_eq!(paths, ["/a~0b", "/x~1y"]);
+
+        let root = diff_json(&json!(1), &json!("1"));
+        assert_eq!(root.len(), 1);
+        assert_eq!(root.changes()[0].path(), "");
+        assert_eq!(root.to_string(), "/: expected 1, got \"1\"\n");
+    }
+
+    #[test]
+    fn test_merge_json_rfc7386() {
+        // Examples from RFC 7386, appendix A.
+        let cases = [
+            (json!({"a": "b"}), json!({"a": "c"}), json!({"a": "c"})),
+            (
+                json!({"a": "b"}),
+                json!({"b": "c"}),
+                json!({"a": "b", "b": "c"}),
+            ),
+            (json!({"a": "b"}), json!({"a": null}), json!({})),
+            (
+                json!({"a": "b", "b": "c"}),
+                json!({"a": null}),
+                json!({"b": "c"}),
+            ),
+            (json!({"a": ["b"]}), json!({"a": "c"}), json!({"a": "c"})),
+            (json!({"a": "c"}), json!({"a": ["b"]}), json!({"a": ["b"]})),
+            (
+                json!({"a": {"b": "c"}}),
+                json!({"a": {"b": "d", "c": null}}),
+                json!({"a": {"b": "d"}}),
+            ),
+            (
+                json!({"a": [{"b": "c"}]}),
+                json!({"a": [1]}),
+                json!({"a": [1]}),
+            ),
+            (json!(["a", "b"]), json!(["c", "d"]), json!(["c", "d"])),
+            (json!({"a": "b"}), json!(["c"]), json!(["c"])),
+            (json!({"a": "foo"}), json!(null), json!(null)),
+            (json!({"a": "foo"}), json!("bar"), json!("bar")),
+            (
+                json!({"e": null}),
+                json!({"a": 1}),
+                json!({"e": null, "a": 1}),
+            ),
+            (
+                json!([1, 2]),
+                json!({"a": "b", "c": null}),
+                json!({"a": "b"}),
+            ),
+            (
+                json!({}),
+                json!({"a": {"bb": {"ccc": null}}}),
+                json!({"a": {"bb": {}}}),
+            ),
+        ];
+        for (mut target, patch, expected) in cases {
+            merge_json(&mut target, &patch);
+            assert_eq!(target, expected, "patch {}", patch);
+        }
+    }
+
+    #[test]
+    fn test_merge_json_deep_arrays() {
+        let mut target = json!({"list": [{"a": 1, "b": 2}, 5], "other": [1]});
+        let patch = json!({"list": [{"b": null, "c": 3}, 6, {"d": null}], "other": {"x": 1}});
+        merge_json_with(&mut target, &patch, ArrayMerge::Deep);
+        assert_eq!(
+            target,
+            json!({"list": [{"a": 1, "c": 3}, 6, {}], "other": {"x": 1}})
+        );
+
+        let mut shorter = json!([1, 2, 3]);
+        merge_json_with(&mut shorter, &json!([0]), ArrayMerge::Deep);
+        assert_eq!(shorter, json!([0, 2, 3]));
+    }
+
+    #[test]
+    fn test_get_path() {
+        let doc = json!({"a": {"b": [10, 20]}, "x/y": {"~": 1}, "": 2});
+        assert_eq!(get_path(&doc, ""), Some(&doc));
+        assert_eq!(get_path(&doc, "/a/b/0"), Some(&json!(10)));
+        assert_eq!(get_path(&doc, "/x~1y/~0"), Some(&json!(1)));
+        assert_eq!(get_path(&doc, "/"), Some(&json!(2)));
+        assert_eq!(get_path(&doc, "/a/b/2"), None);
+        assert_eq!(get_path(&doc, "/a/b/01"), None);
+        assert_eq!(get_path(&doc, "/a/b/0/c"), None);
+        assert_eq!(get_path(&doc, "a"), None);
+    }
+
+    #[test]
+    fn test_set_path() {
+        let mut doc = json!({"a": [1], "s": "text"});
+        assert_eq!(
+            set_path(&mut doc, "/a/0", json!(5)).unwrap(),
+            Some(json!(1))
+        );
+        assert_eq!(set_path(&mut doc, "/a/1", json!(6)).unwrap(), None);
+        assert_eq!(set_path(&mut doc, "/b/c~1d", json!(true)).unwrap(), None);
+        assert_eq!(doc, json!({"a": [5, 6], "s": "text", "b": {"c/d": true}}));
+
+        let error = |pointer| match set_path(&mut doc.clone(), pointer, json!(0)) {
+            Err(Error::InvalidArgument(message)) => message,
+            other => panic!("unexpected {:?}", other),
+        };
+        assert_eq!(error("/a/9"), "invalid array index at '/a/9'");
+        assert_eq!(error("/s/x"), "value at '/s' is not an object or array");
+        assert_eq!(error("s"), "JSON pointer must start with '/'");
+
+        assert_eq!(
+            set_path(&mut doc, "", json!(1)).unwrap().unwrap()["s"],
+            "text"
+        );
+        assert_eq!(doc, json!(1));
+    }
+
+    #[test]
+    fn test_flatten_roundtrip() {
+        let doc = json!({
+            "title": "pizza",
+            "meta": {"tags": ["a", "b"], "empty": {}, "none": null},
+            "nested": [[1, 2], {"k": []}]
+        });
+        let flat = flatten(&doc);
+        let keys: Vec<&str> = flat.iter().map(|(key, _)| key.as_str()).collect();
+        assert_eq!(
+            keys,
+            [
+                "meta.empty",
+                "meta.none",
+                "meta.tags.0",
+                "meta.tags.1",
+                "nested.0.0",
+                "nested.0.1",
+                "nested.1.k",
+                "title"
+            ]
+        );
+        assert_eq!(unflatten(flat), doc);
+
+        assert_eq!(flatten(&json!(3)), [(String::new(), json!(3))]);
+        assert_eq!(unflatten([("", json!(3))]), json!(3));
+        assert_eq!(unflatten([("a.2", json!(1))]), json!({"a": {"2": 1}}));
+        assert_eq!(
+            unflatten([("a.0", json!(1)), ("a.1", json!(2)), ("a.0", json!(3))]),
+            json!({"a": [3, 2]})
+        );
+        assert_eq!(
+            unflatten([("a.18446744073709551615", json!(1))]),
+            json!({"a": {"18446744073709551615": 1}})
+        );
+        assert_eq!(unflatten(Vec::<(String, Value)>::new()), Value::Null);
+    }
+
+    #[test]
+    fn test_try_compare_json() {
+        let strict = CompareOptions::default();
+        assert!(try_compare_json(r#"{"a":[1,2]}"#, r#"{"a":[1,2]}"#, &strict).unwrap());
+        assert!(!try_compare_json("1.0", "1.00000001", &strict).unwrap());
+        match try_compare_json("{", "1", &strict) {
+            Err(Error::Parse(message)) => assert!(message.starts_with("first document")),
+            other => panic!("unexpected {:?}", other),
+        }
+        match try_compare_json("1", "[", &strict) {
+            Err(Error::Parse(message)) => assert!(message.starts_with("second document")),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let tolerant = CompareOptions {
+            float_epsilon: 1e-6,
+            ..Default::default()
+        };
+        assert!(try_compare_json("1.0", "1.00000001", &tolerant).unwrap());
+        assert!(try_compare_json("1", "1.0000001", &tolerant).unwrap());
+        assert!(!try_compare_json("1.0", "1.1", &tolerant).unwrap());
+        assert!(!try_compare_json("1", "2", &tolerant).unwrap());
+    }
+
+    #[test]
+    fn test_compare_array_order() {
+        let unordered = CompareOptions {
+            ignore_array_order: true,
+            ..Default::default()
+        };
+        let a = json!([{"id": 1}, {"id": 2}, {"id": 2}]);
+        assert!(compare_json_values(
+            &a,
+            &json!([{"id": 2}, {"id": 1}, {"id": 2}]),
+            &unordered
+        ));
+        assert!(!compare_json_values(
+            &a,
+            &json!([{"id": 2}, {"id": 1}, {"id": 1}]),
+            &unordered
+        ));
+        assert!(!compare_json_values(
+            &a,
+            &json!([{"id": 2}, {"id": 1}]),
+            &unordered
+        ));
+        assert!(!compare_json_values(
+            &a,
+            &json!([{"id": 2}, {"id": 1}, {"id": 2}]),
+            &CompareOptions::default()
+        ));
+    }
+
+    #[test]
+    fn test_compare_ignored_paths() {
+        let options = CompareOptions {
+            ignored_paths: vec!["/took".into(), "/hits/*/_score".into()],
+            ..Default::default()
+        };
+        let expected = json!({"took": 1, "hits": [{"_id": "a", "_score": 1.5}]});
+        let actual = json!({"took": 9, "hits": [{"_id": "a", "_score": 0.5}]});
+        assert!(compare_json_values(&expected, &actual, &options));
+
+        // An ignored member may be missing on either side.
+        let missing = json!({"hits": [{"_id": "a"}]});
+        assert!(compare_json_values(&expected, &missing, &options));
+        assert!(compare_json_values(&missing, &expected, &options));
+
+        let other_id = json!({"took": 1, "hits": [{"_id": "b", "_score": 1.5}]});
+        assert!(!compare_json_values(&expected, &other_id, &options));
+    }
 }
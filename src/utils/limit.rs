@@ -0,0 +1,464 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+//! Throttling and retry helpers.
+//!
+//! Both read the time from an injected [`Clock`], so they work without an
+//! operating system and can be driven by a
+//! [`ManualClock`](crate::utils::time::ManualClock) in tests. Their options
+//! are serde types, with durations written like `"250ms"`.
+
+use crate::utils::rand::Rng;
+use crate::utils::time::Clock;
+use crate::utils::units::Duration;
+use rand::Rng as _;
+use serde::Deserialize;
+use serde::Serialize;
+
+/// Options of a [`RateLimiter`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimiterOptions {
+    /// Most tokens the bucket holds, and so the largest burst.
+    pub burst: u64,
+    /// Tokens added every `refill_interval`.
+    pub refill_tokens: u64,
+    /// Time between refills, with millisecond resolution.
+    pub refill_interval: Duration,
+}
+
+impl Default for RateLimiterOptions {
+    fn default() -> Self {
+        Self {
+            burst: 1,
+            refill_tokens: 1,
+            refill_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+/// A token bucket rate limiter.
+///
+/// The bucket starts full and is refilled with whole batches of
+/// `refill_tokens`, so the rate is exact over time regardless of how often
+/// it is polled.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::limit::RateLimiter;
+/// use pizza_common::utils::limit::RateLimiterOptions;
+/// use pizza_common::utils::time::ManualClock;
+/// use pizza_common::utils::units::Duration;
+///
+/// let clock = ManualClock::new(0);
+/// let options = RateLimiterOptions {
+///     burst: 10,
+///     refill_tokens: 5,
+///     refill_interval: Duration::from_secs(1),
+/// };
+/// let mut limiter = RateLimiter::new(options, &clock);
+/// assert!(limiter.try_acquire(10));
+/// assert!(!limiter.try_acquire(1));
+///
+/// clock.advance(1_000);
+/// assert!(limiter.try_acquire(5));
+/// ```
+#[derive(Debug)]
+pub struct RateLimiter<C> {
+    options: RateLimiterOptions,
+    clock: C,
+    tokens: u64,
+    last_refill: i64,
+}
+
+impl<C: Clock> RateLimiter<C> {
+    /// Creates a limiter with a full bucket.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the refill interval is shorter than a millisecond.
+    pub fn new(options: RateLimiterOptions, clock: C) -> Self {
+        assert!(
+            options.refill_interval.as_millis() > 0,
+            "refill interval must be at least 1ms"
+        );
+        let last_refill = clock.now_millis();
+        Self {
+            tokens: options.burst,
+            options,
+            clock,
+            last_refill,
+        }
+    }
+
+    pub fn options(&self) -> &RateLimiterOptions {
+        &self.options
+    }
+
+    /// Takes `n` tokens if the bucket holds them, returns whether it did.
+    pub fn try_acquire(&mut self, n: u64) -> bool {
+        self.refill();
+        if self.tokens < n {
+            return false;
+        }
+        self.tokens -= n;
+        true
+    }
+
+    /// Tokens currently in the bucket.
+    pub fn available(&mut self) -> u64 {
+        self.refill();
+        self.tokens
+    }
+
+    /// How long until `n` tokens are available, `None` if they never will
+    /// be as `n` exceeds the burst.
+    pub fn wait_time(&mut self, n: u64) -> Option<Duration> {
+        self.refill();
+        let Some(missing) = n.checked_sub(self.tokens).filter(|&missing| missing > 0) else {
+            return Some(Duration::ZERO);
+        };
+        if n > self.options.burst || self.options.refill_tokens == 0 {
+            return None;
+        }
+        let refills = missing.div_ceil(self.options.refill_tokens);
+        let since_refill = (self.clock.now_millis() - self.last_refill).max(0) as u64;
+        let millis = refills
+            .saturating_mul(self.options.refill_interval.as_millis())
+            .saturating_sub(since_refill);
+        Some(Duration::from_millis(millis))
+    }
+
+    fn refill(&mut self) {
+        let now = self.clock.now_millis();
+        // A clock that went backwards only delays the next refill
+        let Some(elapsed) = now
+            .checked_sub(self.last_refill)
+            .filter(|&elapsed| elapsed > 0)
+        else {
+            return;
+        };
+        let interval = self.options.refill_interval.as_millis();
+        let refills = elapsed as u64 / interval;
+        if refills == 0 {
+            return;
+        }
+        let added = refills.saturating_mul(self.options.refill_tokens);
+        self.tokens = self.tokens.saturating_add(added).min(self.options.burst);
+        if self.tokens == self.options.burst {
+            // A full bucket does not bank time towards later refills
+            self.last_refill = now;
+        } else {
+            self.last_refill += (refills * interval) as i64;
+        }
+    }
+}
+
+/// Options of a [`Backoff`].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BackoffOptions {
+    /// Delay before the first retry.
+    pub initial_delay: Duration,
+    /// Longest delay between retries.
+    pub max_delay: Duration,
+    /// Factor applied to the delay after every retry.
+    pub multiplier: f64,
+    /// Fraction of each delay, between 0 and 1, that is randomly taken off.
+    /// A non-finite value disables the jitter.
+    pub jitter: f64,
+    /// Retries after which the backoff ends, unlimited if `None`.
+    pub max_retries: Option<u32>,
+    /// Time since the backoff started after which it ends, unlimited if
+    /// `None`.
+    pub max_elapsed: Option<Duration>,
+}
+
+impl Default for BackoffOptions {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: 0.5,
+            max_retries: Some(10),
+            max_elapsed: None,
+        }
+    }
+}
+
+/// Exponential backoff with jitter, yielding the delay before each retry.
+///
+/// The iterator ends once `max_retries` delays were yielded or
+/// `max_elapsed` passed since it was created or [`reset`](Self::reset).
+/// Jitter is drawn from [`Rng::new`], which is seeded by an active
+/// [`Determinism`](crate::testing::Determinism) guard.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::limit::Backoff;
+/// use pizza_common::utils::limit::BackoffOptions;
+/// use pizza_common::utils::time::ManualClock;
+/// use std::time::Duration;
+///
+/// let options = BackoffOptions {
+///     jitter: 0.0,
+///     max_retries: Some(4),
+///     ..BackoffOptions::default()
+/// };
+/// let delays: Vec<Duration> = Backoff::new(options, ManualClock::new(0)).collect();
+/// assert_eq!(delays, [100, 200, 400, 800].map(Duration::from_millis));
+/// ```
+#[derive(Debug)]
+pub struct Backoff<C> {
+    options: BackoffOptions,
+    clock: C,
+    rng: Rng,
+    started: i64,
+    retries: u32,
+    delay: core::time::Duration,
+}
+
+impl<C: Clock> Backoff<C> {
+    pub fn new(options: BackoffOptions, clock: C) -> Self {
+        let started = clock.now_millis();
+        Self {
+            delay: options.initial_delay.as_std(),
+            options,
+            clock,
+            rng: Rng::new(),
+            started,
+            retries: 0,
+        }
+    }
+
+    /// Draws the jitter from `rng` instead.
+    pub fn with_rng(mut self, rng: Rng) -> Self {
+        self.rng = rng;
+        self
+    }
+
+    pub fn options(&self) -> &BackoffOptions {
+        &self.options
+    }
+
+    /// Delays yielded so far.
+    pub fn retries(&self) -> u32 {
+        self.retries
+    }
+
+    /// Starts over from the initial delay, e.g. after a success.
+    pub fn reset(&mut self) {
+        self.started = self.clock.now_millis();
+        self.retries = 0;
+        self.delay = self.options.initial_delay.as_std();
+    }
+}
+
+impl<C: Clock> Iterator for Backoff<C> {
+    type Item = core::time::Duration;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self
+            .options
+            .max_retries
+            .is_some_and(|max| self.retries >= max)
+        {
+            return None;
+        }
+        if let Some(max_elapsed) = self.options.max_elapsed {
+            let elapsed = (self.clock.now_millis() - self.started).max(0) as u64;
+            if elapsed >= max_elapsed.as_millis() {
+                return None;
+            }
+        }
+
+        let max_delay = self.options.max_delay.as_std();
+        let delay = self.delay.min(max_delay);
+        self.delay =
+            core::time::Duration::try_from_secs_f64(delay.as_secs_f64() * self.options.multiplier)
+                .map_or(max_delay, |next| next.min(max_delay));
+        self.retries += 1;
+
+        // A NaN jitter would survive the clamp and panic in `mul_f64`.
+        let jitter = match self.options.jitter {
+            jitter if jitter.is_finite() => jitter.clamp(0.0, 1.0),
+            _ => 0.0,
+        };
+        if jitter == 0.0 {
+            return Some(delay);
+        }
+        Some(delay.mul_f64(1.0 - jitter * self.rng.gen::<f64>()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::time::ManualClock;
+    use alloc::vec::Vec;
+    use core::time::Duration as StdDuration;
+
+    fn options(burst: u64, refill_tokens: u64, refill_millis: u64) -> RateLimiterOptions {
+        RateLimiterOptions {
+            burst,
+            refill_tokens,
+            refill_interval: Duration::from_millis(refill_millis),
+        }
+    }
+
+    #[test]
+    fn test_rate_limiter_refills() {
+        let clock = ManualClock::new(1_000);
+        let mut limiter = RateLimiter::new(options(10, 2, 100), &clock);
+        assert!(limiter.try_acquire(7));
+        assert!(!limiter.try_acquire(4));
+        assert_eq!(limiter.available(), 3);
+
+        // Partial intervals carry over to the next refill.
+        clock.advance(150);
+        assert_eq!(limiter.available(), 5);
+        clock.advance(50);
+        assert_eq!(limiter.available(), 7);
+
+        // The bucket never holds more than the burst.
+        clock.advance(10_000);
+        assert_eq!(limiter.available(), 10);
+        assert!(limiter.try_acquire(10));
+        assert!(!limiter.try_acquire(11));
+    }
+
+    #[test]
+    fn test_rate_limiter_wait_time() {
+        let clock = ManualClock::new(0);
+        let mut limiter = RateLimiter::new(options(10, 3, 100), &clock);
+        assert_eq!(limiter.wait_time(10), Some(Duration::ZERO));
+        assert!(limiter.try_acquire(10));
+        clock.advance(40);
+        assert_eq!(limiter.wait_time(5), Some(Duration::from_millis(160)));
+        assert_eq!(limiter.wait_time(11), None);
+
+        clock.advance(160);
+        assert!(limiter.try_acquire(5));
+
+        // A clock going backwards does not refill.
+        clock.advance(-1_000);
+        assert_eq!(limiter.available(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "refill interval")]
+    fn test_rate_limiter_zero_interval() {
+        RateLimiter::new(options(1, 1, 0), ManualClock::new(0));
+    }
+
+    #[test]
+    fn test_backoff_grows_to_max_delay() {
+        let options = BackoffOptions {
+            initial_delay: Duration::from_millis(10),
+            max_delay: Duration::from_millis(50),
+            multiplier: 3.0,
+            jitter: 0.0,
+            max_retries: None,
+            max_elapsed: None,
+        };
+        let delays: Vec<u128> = Backoff::new(options, ManualClock::new(0))
+            .take(5)
+            .map(|delay| delay.as_millis())
+            .collect();
+        assert_eq!(delays, [10, 30, 50, 50, 50]);
+    }
+
+    #[test]
+    fn test_backoff_limits_and_reset() {
+        let clock = ManualClock::new(0);
+        let options = BackoffOptions {
+            max_retries: Some(3),
+            max_elapsed: Some(Duration::from_secs(1)),
+            ..BackoffOptions::default()
+        };
+        let mut backoff = Backoff::new(options, &clock);
+        assert_eq!(backoff.by_ref().count(), 3);
+        assert_eq!(backoff.retries(), 3);
+
+        backoff.reset();
+        assert!(backoff.next().is_some());
+        clock.advance(1_000);
+        assert_eq!(backoff.next(), None);
+        backoff.reset();
+        assert_eq!(backoff.retries(), 0);
+        assert!(backoff.next().is_some());
+    }
+
+    #[test]
+    fn test_backoff_jitter() {
+        let options = BackoffOptions {
+            jitter: 0.5,
+            max_retries: Some(8),
+            ..BackoffOptions::default()
+        };
+        let delays: Vec<StdDuration> = Backoff::new(options.clone(), ManualClock::new(0))
+            .with_rng(Rng::from_seed(7))
+            .collect();
+        let mut base = StdDuration::from_millis(100);
+        for delay in &delays {
+            assert!(*delay <= base && *delay >= base / 2, "{delay:?}");
+            base *= 2;
+        }
+        let again: Vec<StdDuration> = Backoff::new(options, ManualClock::new(0))
+            .with_rng(Rng::from_seed(7))
+            .collect();
+        assert_eq!(delays, again);
+
+        for jitter in [f64::NAN, f64::INFINITY] {
+            let options = BackoffOptions {
+                jitter,
+                max_retries: Some(1),
+                ..BackoffOptions::default()
+            };
+            let mut backoff = Backoff::new(options, ManualClock::new(0));
+            assert_eq!(backoff.next(), Some(StdDuration::from_millis(100)));
+        }
+    }
+
+    #[test]
+    fn test_options_serde() {
+        let options: RateLimiterOptions =
+            serde_json::from_str(r#"{"burst":100,"refill_interval":"250ms"}"#).unwrap();
+        assert_eq!(options, self::options(100, 1, 250));
+
+        let options: BackoffOptions =
+            serde_json::from_str(r#"{"max_delay":"1m","max_retries":null}"#).unwrap();
+        assert_eq!(options.max_delay, Duration::from_secs(60));
+        assert_eq!(options.max_retries, None);
+        assert_eq!(
+            options.initial_delay,
+            BackoffOptions::default().initial_delay
+        );
+        let json = serde_json::to_string(&options).unwrap();
+        assert_eq!(
+            serde_json::from_str::<BackoffOptions>(&json).unwrap(),
+            options
+        );
+    }
+}
@@ -36,15 +36,16 @@
 /// ```
 
 #[macro_export]
+#[allow(clippy::empty_line_after_doc_comments)]
 macro_rules! hashmap {
     (@single $($x:tt)*) => (());
-    (@count $($rest:expr),*) => (<[()]>::len(&[$(hashmap!(@single $rest)),*]));
+    (@count $($rest:expr),*) => (<[()]>::len(&[$($crate::hashmap!(@single $rest)),*]));
 
-    ($($key:expr => $value:expr,)+) => { hashmap!($($key => $value),+) };
+    ($($key:expr => $value:expr,)+) => { $crate::hashmap!($($key => $value),+) };
     ($($key:expr => $value:expr),*) => {
         {
-            let _cap = hashmap!(@count $($key),*);
-            let mut _map =  hashbrown::HashMap::with_capacity(_cap);
+            let _cap = $crate::hashmap!(@count $($key),*);
+            let mut _map = $crate::__private::HashMap::with_capacity(_cap);
             $(
                 _map.insert($key, $value);
             )*
@@ -53,6 +54,103 @@ macro_rules! hashmap {
     };
 }
 
+/// Creates a `HashSet` from a list of values.
+///
+/// # Examples
+///
+/// ```
+/// let set = pizza_common::hashset! { "a", "b", "a" };
+/// assert_eq!(set.len(), 2);
+/// assert!(set.contains("b"));
+/// ```
+#[macro_export]
+macro_rules! hashset {
+    ($($value:expr,)+) => { $crate::hashset!($($value),+) };
+    ($($value:expr),*) => {
+        {
+            let _cap = $crate::hashmap!(@count $($value),*);
+            let mut _set = $crate::__private::HashSet::with_capacity(_cap);
+            $(
+                _set.insert($value);
+            )*
+            _set
+        }
+    };
+}
+
+/// Creates a `BTreeMap` from a list of key-value pairs.
+///
+/// # Examples
+///
+/// ```
+/// let map = pizza_common::btreemap! { "b" => 2, "a" => 1 };
+/// assert_eq!(map.keys().collect::<Vec<_>>(), [&"a", &"b"]);
+/// ```
+#[macro_export]
+macro_rules! btreemap {
+    ($($key:expr => $value:expr,)+) => { $crate::btreemap!($($key => $value),+) };
+    ($($key:expr => $value:expr),*) => {
+        {
+            let mut _map = $crate::__private::BTreeMap::new();
+            $(
+                _map.insert($key, $value);
+            )*
+            _map
+        }
+    };
+}
+
+/// Creates a `BTreeSet` from a list of values.
+///
+/// # Examples
+///
+/// ```
+/// let set = pizza_common::btreeset! { 3, 1, 2 };
+/// assert_eq!(set.into_iter().collect::<Vec<_>>(), [1, 2, 3]);
+/// ```
+#[macro_export]
+macro_rules! btreeset {
+    ($($value:expr,)+) => { $crate::btreeset!($($value),+) };
+    ($($value:expr),*) => {
+        {
+            let mut _set = $crate::__private::BTreeSet::new();
+            $(
+                _set.insert($value);
+            )*
+            _set
+        }
+    };
+}
+
+/// Creates a `serde_json::Map` from a list of key-value pairs. Keys are
+/// converted into `String`s and values into `serde_json::Value`s.
+///
+/// # Examples
+///
+/// ```
+/// use serde_json::json;
+///
+/// let map = pizza_common::json_map! { "name" => "pizza", "tags" => json!(["hot"]) };
+/// assert_eq!(serde_json::Value::Object(map), json!({ "name": "pizza", "tags": ["hot"] }));
+/// ```
+#[macro_export]
+macro_rules! json_map {
+    ($($key:expr => $value:expr,)+) => { $crate::json_map!($($key => $value),+) };
+    ($($key:expr => $value:expr),*) => {
+        {
+            let _cap = $crate::hashmap!(@count $($key),*);
+            let mut _map = $crate::__private::Map::with_capacity(_cap);
+            $(
+                _map.insert(
+                    ::core::convert::Into::<$crate::__private::String>::into($key),
+                    ::core::convert::Into::<$crate::__private::Value>::into($value),
+                );
+            )*
+            _map
+        }
+    };
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -63,4 +161,36 @@ mod tests {
         expected.insert("b", 2);
         assert_eq!(map, expected);
     }
+
+    #[test]
+    fn test_set_macros() {
+        let set = hashset! { 1, 2, 2, };
+        assert_eq!(set.len(), 2);
+        assert!(set.capacity() >= 3);
+        let empty: hashbrown::HashSet<u8> = hashset! {};
+        assert!(empty.is_empty());
+
+        let set = btreeset! { "b", "a", "b", };
+        assert_eq!(set.into_iter().collect::<alloc::vec::Vec<_>>(), ["a", "b"]);
+    }
+
+    #[test]
+    fn test_btreemap_macro() {
+        let map = btreemap! { 2 => "b", 1 => "a", };
+        let mut expected = alloc::collections::BTreeMap::new();
+        expected.insert(1, "a");
+        expected.insert(2, "b");
+        assert_eq!(map, expected);
+    }
+
+    #[test]
+    fn test_json_map_macro() {
+        let key = alloc::string::String::from("owned");
+        let map = json_map! { "n" => 1, key => serde_json::json!([true]), "none" => (), };
+        assert_eq!(
+            serde_json::Value::Object(map),
+            serde_json::json!({ "n": 1, "owned": [true], "none": null })
+        );
+        assert!(json_map! {}.is_empty());
+    }
 }
@@ -24,16 +24,45 @@ pub mod rand;
 
 pub mod uuid;
 
+mod bitflags;
+pub mod checksum;
+pub mod compress;
+pub mod hash;
 pub mod json;
+pub mod limit;
 mod maplit;
+pub mod semver;
 pub mod strings;
+pub mod time;
+pub mod units;
+pub mod varint;
 
 pub mod sequencer {
+    use crate::store::PersistStore;
+    use crate::store::RecoverableStore;
+    use crate::store::StoreError;
+    use alloc::format;
+    use alloc::vec::Vec;
+    use core::fmt;
+    use core::ops::Range;
+    use core::sync::atomic::AtomicU64;
+    use core::sync::atomic::Ordering;
     use serde::Deserialize;
+    use serde::Deserializer;
     use serde::Serialize;
+    use serde::Serializer;
 
     #[derive(Clone, Debug, Default, Deserialize, Serialize)]
     #[serde(deny_unknown_fields)]
+    #[cfg_attr(
+        feature = "borsh",
+        derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+    )]
+    #[cfg_attr(
+        feature = "rkyv",
+        derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+        rkyv(derive(Debug, Hash, PartialEq, Eq), compare(PartialEq))
+    )]
     /// A `Sequencer` that has `Deserialize` and `Serialize` implemented.
     pub struct Sequencer {
         offset: u32,
@@ -44,8 +73,15 @@ pub mod sequencer {
 
     impl Sequencer {
         /// Create a new [`Sequencer`].
+        ///
+        /// `max` is capped at `u32::MAX - step`, so that stepping past it
+        /// cannot overflow.
         pub fn new(offset: u32, step: u32, max: u32) -> Sequencer {
-            Sequencer { offset, step, max }
+            Sequencer {
+                offset,
+                step,
+                max: max.min(u32::MAX.saturating_sub(step)),
+            }
         }
 
         /// Get the current value of this [`Sequencer`].
@@ -56,6 +92,128 @@ pub mod sequencer {
         pub fn free(&self) -> u32 {
             self.max.saturating_sub(self.offset)
         }
+
+        /// The last id to hand out. Deserialized sequencers are not capped
+        /// by [`new`](Self::new), so the cap is applied here as well.
+        fn last_id(&self) -> u32 {
+            self.max.min(u32::MAX - self.step)
+        }
+
+        /// Number of ids left to hand out.
+        fn remaining(&self) -> u64 {
+            assert!(self.step > 0, "sequencer step must be positive");
+            if self.offset > self.last_id() {
+                0
+            } else {
+                u64::from((self.last_id() - self.offset) / self.step) + 1
+            }
+        }
+
+        /// Reserves the next `n` ids in one call.
+        ///
+        /// The returned range starts at the first id and ends right after
+        /// the last one, iterate it with `step_by` for a step above one.
+        ///
+        /// # Panics
+        ///
+        /// Panics if the step is zero.
+        pub fn reserve(&mut self, n: u32) -> Result<Range<u32>, SequencerExhausted> {
+            let available = self.remaining();
+            let exhausted = SequencerExhausted {
+                requested: n.into(),
+                available,
+            };
+            if n == 0 {
+                return Ok(self.offset..self.offset);
+            }
+            if u64::from(n) > available {
+                return Err(exhausted);
+            }
+            let start = self.offset;
+            // Cannot fail, `remaining` leaves room to step past the last id.
+            let last = (n - 1)
+                .checked_mul(self.step)
+                .and_then(|distance| start.checked_add(distance));
+            let next = last.and_then(|last| last.checked_add(self.step));
+            let (Some(last), Some(next)) = (last, next) else {
+                return Err(exhausted);
+            };
+            self.offset = next;
+            Ok(start..last + 1)
+        }
+
+        /// Partitions the remaining ids into `parts` sequencers with
+        /// contiguous, disjoint ranges, e.g. one per writer.
+        ///
+        /// # Panics
+        ///
+        /// Panics if `parts` or the step is zero.
+        pub fn split(self, parts: u32) -> Result<Vec<Sequencer>, SequencerExhausted> {
+            assert!(parts > 0, "parts must be positive");
+            let available = self.remaining();
+            if u64::from(parts) > available {
+                return Err(SequencerExhausted {
+                    requested: parts.into(),
+                    available,
+                });
+            }
+            let id = |index: u64| (u64::from(self.offset) + index * u64::from(self.step)) as u32;
+            Ok((0..u64::from(parts))
+                .map(|part| {
+                    let first = part * available / u64::from(parts);
+                    let last = (part + 1) * available / u64::from(parts) - 1;
+                    Sequencer::new(id(first), self.step, id(last))
+                })
+                .collect())
+        }
+    }
+
+    /// Returned when a [`Sequencer`] has fewer ids left than requested.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct SequencerExhausted {
+        pub requested: u64,
+        pub available: u64,
+    }
+
+    impl fmt::Display for SequencerExhausted {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(
+                f,
+                "sequencer exhausted, requested {} ids but only {} are left",
+                self.requested, self.available
+            )
+        }
+    }
+
+    /// Format id of saved sequencers, see [`PersistStore`].
+    pub const SEQUENCER_FORMAT: [u8; 4] = *b"PZSQ";
+
+    impl PersistStore for Sequencer {
+        const FORMAT: [u8; 4] = SEQUENCER_FORMAT;
+        const VERSION: u16 = 1;
+
+        fn encode_payload(&self, out: &mut Vec<u8>) {
+            for value in [self.offset, self.step, self.max] {
+                out.extend_from_slice(&value.to_le_bytes());
+            }
+        }
+    }
+
+    impl RecoverableStore for Sequencer {
+        fn decode_payload(_version: u16, payload: &[u8]) -> Result<Self, StoreError> {
+            let [offset, step, max] = match payload.len() {
+                12 => {
+                    [0, 4, 8].map(|at| u32::from_le_bytes(payload[at..at + 4].try_into().unwrap()))
+                }
+                len => {
+                    return Err(StoreError::Payload(format!(
+                        "expected 12 bytes, got {}",
+                        len
+                    )))
+                }
+            };
+            Ok(Sequencer { offset, step, max })
+        }
     }
 
     impl Iterator for Sequencer {
@@ -64,7 +222,7 @@ pub mod sequencer {
         fn next(&mut self) -> Option<Self::Item> {
             let current = self.offset;
 
-            if current <= self.max {
+            if current <= self.last_id() {
                 self.offset += self.step;
                 Some(current)
             } else {
@@ -73,6 +231,172 @@ pub mod sequencer {
         }
     }
 
+    #[derive(Clone, Debug, Default, Deserialize, Serialize)]
+    #[serde(deny_unknown_fields)]
+    #[cfg_attr(
+        feature = "borsh",
+        derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+    )]
+    #[cfg_attr(
+        feature = "rkyv",
+        derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+        rkyv(derive(Debug, Hash, PartialEq, Eq), compare(PartialEq))
+    )]
+    /// A [`Sequencer`] over `u64`, for id spaces that outgrow `u32`.
+    pub struct Sequencer64 {
+        offset: u64,
+        step: u64,
+
+        max: u64,
+    }
+
+    impl Sequencer64 {
+        /// Create a new [`Sequencer64`].
+        ///
+        /// `max` is capped at `u64::MAX - step`, so that stepping past it
+        /// cannot overflow.
+        pub fn new(offset: u64, step: u64, max: u64) -> Sequencer64 {
+            Sequencer64 {
+                offset,
+                step,
+                max: max.min(u64::MAX - step),
+            }
+        }
+
+        /// Get the current value of this [`Sequencer64`].
+        pub fn current(&self) -> u64 {
+            self.offset
+        }
+
+        pub fn free(&self) -> u64 {
+            self.last_id().saturating_sub(self.offset)
+        }
+
+        /// The last id to hand out. Deserialized sequencers are not capped
+        /// by [`new`](Self::new), so the cap is applied here as well.
+        fn last_id(&self) -> u64 {
+            self.max.min(u64::MAX - self.step)
+        }
+    }
+
+    impl From<Sequencer> for Sequencer64 {
+        fn from(sequencer: Sequencer) -> Self {
+            Sequencer64::new(
+                sequencer.offset.into(),
+                sequencer.step.into(),
+                sequencer.max.into(),
+            )
+        }
+    }
+
+    impl Iterator for Sequencer64 {
+        type Item = u64;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            let current = self.offset;
+
+            if current <= self.last_id() {
+                self.offset = current + self.step;
+                Some(current)
+            } else {
+                None
+            }
+        }
+    }
+
+    /// A [`Sequencer64`] that hands out ids from several threads at once.
+    ///
+    /// It serializes like a [`Sequencer64`] holding the current offset, so
+    /// a checkpoint restores it to where it left off.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use pizza_common::utils::sequencer::AtomicSequencer;
+    ///
+    /// let ids = AtomicSequencer::new(1, 1, 1_000);
+    /// std::thread::scope(|scope| {
+    ///     for _ in 0..4 {
+    ///         scope.spawn(|| ids.next().unwrap());
+    ///     }
+    /// });
+    /// assert_eq!(ids.current(), 5);
+    /// ```
+    #[derive(Debug, Default)]
+    pub struct AtomicSequencer {
+        offset: AtomicU64,
+        step: u64,
+        max: u64,
+    }
+
+    impl AtomicSequencer {
+        /// Create a new [`AtomicSequencer`], see [`Sequencer64::new`].
+        pub fn new(offset: u64, step: u64, max: u64) -> AtomicSequencer {
+            Sequencer64::new(offset, step, max).into()
+        }
+
+        /// Hands out the next id, or `None` once the sequencer is exhausted.
+        pub fn next(&self) -> Option<u64> {
+            self.offset
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |current| {
+                    (current <= self.last_id()).then(|| current + self.step)
+                })
+                .ok()
+        }
+
+        /// Get the current value of this [`AtomicSequencer`].
+        pub fn current(&self) -> u64 {
+            self.offset.load(Ordering::Relaxed)
+        }
+
+        pub fn free(&self) -> u64 {
+            self.last_id().saturating_sub(self.current())
+        }
+
+        /// The last id to hand out, capped like [`Sequencer64::new`] caps
+        /// `max`, as deserialized sequencers skip that cap.
+        fn last_id(&self) -> u64 {
+            self.max.min(u64::MAX - self.step)
+        }
+
+        /// A copy of the current state.
+        pub fn snapshot(&self) -> Sequencer64 {
+            Sequencer64 {
+                offset: self.current(),
+                step: self.step,
+                max: self.max,
+            }
+        }
+    }
+
+    impl From<Sequencer64> for AtomicSequencer {
+        fn from(sequencer: Sequencer64) -> Self {
+            AtomicSequencer {
+                offset: AtomicU64::new(sequencer.offset),
+                step: sequencer.step,
+                max: sequencer.max,
+            }
+        }
+    }
+
+    impl Serialize for AtomicSequencer {
+        fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+        {
+            self.snapshot().serialize(serializer)
+        }
+    }
+
+    impl<'de> Deserialize<'de> for AtomicSequencer {
+        fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            Sequencer64::deserialize(deserializer).map(Into::into)
+        }
+    }
+
     #[test]
     fn check_first_value() {
         let mut sequencer = Sequencer::new(0, 1, 5);
@@ -131,4 +455,138 @@ pub mod sequencer {
         assert_eq!(sequencer.next().unwrap(), 0);
         assert_eq!(sequencer.free(), 0);
     }
+    #[test]
+    fn reserve_blocks() {
+        let mut sequencer = Sequencer::new(10, 1, 100);
+        assert_eq!(sequencer.reserve(5), Ok(10..15));
+        assert_eq!(sequencer.next(), Some(15));
+        assert_eq!(sequencer.reserve(0), Ok(16..16));
+        assert_eq!(sequencer.reserve(85), Ok(16..101));
+        assert_eq!(
+            sequencer.reserve(1),
+            Err(SequencerExhausted {
+                requested: 1,
+                available: 0
+            })
+        );
+
+        let mut sequencer = Sequencer::new(0, 3, 10);
+        let range = sequencer.reserve(3).unwrap();
+        assert_eq!(range.step_by(3).collect::<Vec<_>>(), [0, 3, 6]);
+        assert_eq!(sequencer.clone().reserve(2).unwrap_err().available, 1);
+        assert_eq!(sequencer.collect::<Vec<_>>(), [9]);
+
+        let mut top = Sequencer::new(u32::MAX - 2, 1, u32::MAX);
+        assert_eq!(
+            top.reserve(3),
+            Err(SequencerExhausted {
+                requested: 3,
+                available: 2
+            })
+        );
+        assert_eq!(top.reserve(2), Ok(u32::MAX - 2..u32::MAX));
+        assert_eq!(top.current(), u32::MAX);
+        assert_eq!(top.reserve(1).unwrap_err().available, 0);
+        assert_eq!(top.next(), None);
+
+        let mut top = Sequencer::new(u32::MAX - 9, 4, u32::MAX);
+        assert_eq!(top.reserve(2), Ok(u32::MAX - 9..u32::MAX - 4));
+        assert_eq!(top.current(), u32::MAX - 1);
+        assert_eq!(top.next(), None);
+
+        // Deserialized sequencers skip the cap of `new`.
+        let mut decoded = Sequencer::decode_payload(
+            1,
+            &[u32::MAX - 1, 1, u32::MAX].map(u32::to_le_bytes).concat(),
+        )
+        .unwrap();
+        assert_eq!(decoded.next(), Some(u32::MAX - 1));
+        assert_eq!(decoded.next(), None);
+        assert!(decoded.reserve(1).is_err());
+    }
+
+    #[test]
+    fn split_partitions() {
+        let parts = Sequencer::new(0, 2, 20).split(3).unwrap();
+        let ids: Vec<Vec<u32>> = parts.into_iter().map(|part| part.collect()).collect();
+        assert_eq!(ids, [&[0, 2, 4][..], &[6, 8, 10, 12], &[14, 16, 18, 20]]);
+
+        let all = Sequencer::new(0, 1, u32::MAX).split(4).unwrap();
+        assert_eq!(all[0].current(), 0);
+        assert_eq!(all[3].free(), (1 << 30) - 1);
+
+        assert_eq!(
+            Sequencer::new(5, 1, 6).split(3).unwrap_err(),
+            SequencerExhausted {
+                requested: 3,
+                available: 2
+            }
+        );
+    }
+
+    #[test]
+    fn sequencer64_works() {
+        let mut sequencer = Sequencer64::new(u32::MAX as u64, 1 << 32, 1 << 40);
+        assert_eq!(sequencer.next(), Some(u32::MAX as u64));
+        assert_eq!(sequencer.current(), (1 << 32) + u32::MAX as u64);
+        assert_eq!(sequencer.free(), (1 << 40) - sequencer.current());
+
+        // The capped maximum keeps the sequence from wrapping around.
+        let top = Sequencer64::new(u64::MAX - 10, 3, u64::MAX);
+        assert_eq!(
+            top.collect::<Vec<_>>(),
+            [u64::MAX - 10, u64::MAX - 7, u64::MAX - 4]
+        );
+
+        // Deserialized sequencers skip the cap of `new`.
+        let json = format!(
+            r#"{{"offset":{},"step":3,"max":{}}}"#,
+            u64::MAX - 4,
+            u64::MAX
+        );
+        let mut decoded: Sequencer64 = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.free(), 1);
+        assert_eq!(decoded.next(), Some(u64::MAX - 4));
+        assert_eq!(decoded.next(), None);
+        assert_eq!(decoded.free(), 0);
+        let atomic: AtomicSequencer = serde_json::from_str(&json).unwrap();
+        assert_eq!(atomic.next(), Some(u64::MAX - 4));
+        assert_eq!(atomic.next(), None);
+        assert_eq!(atomic.free(), 0);
+
+        let narrow: Sequencer64 = Sequencer::new(3, 2, 7).into();
+        assert_eq!(narrow.collect::<Vec<_>>(), [3, 5, 7]);
+    }
+
+    #[test]
+    fn atomic_sequencer_works() {
+        let sequencer = AtomicSequencer::new(0, 2, 10_000);
+        let mut ids: Vec<u64> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| {
+                    scope.spawn(|| {
+                        core::iter::from_fn(|| sequencer.next())
+                            .take(1000)
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap())
+                .collect()
+        });
+        ids.sort_unstable();
+        ids.dedup();
+        assert_eq!(ids.len(), 4000);
+        assert_eq!(sequencer.current(), 8000);
+
+        let json = serde_json::to_string(&sequencer).unwrap();
+        assert_eq!(json, r#"{"offset":8000,"step":2,"max":10000}"#);
+        let restored: AtomicSequencer = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored.next(), Some(8000));
+        assert_eq!(restored.free(), 1998);
+        assert_eq!(core::iter::from_fn(|| restored.next()).last(), Some(10_000));
+        assert_eq!(restored.next(), None);
+    }
 }
@@ -20,12 +20,21 @@
 // OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
 // SOFTWARE.
 
+//! Random helpers around an injectable [`Rng`].
+//!
+//! [`Rng::new`] is seeded from operating system entropy, or from an active
+//! [`Determinism`](crate::testing::Determinism) guard, and
+//! [`Rng::from_seed`] gives reproducible sequences. The free functions are
+//! shorthands drawing from a fresh [`Rng::new`].
+
 use alloc::borrow::ToOwned;
 use alloc::string::String;
 use rand::seq::SliceRandom;
+use rand::Rng as _;
 use rand_chacha::ChaCha8Rng;
 use rand_core::RngCore;
 use rand_core::SeedableRng;
+
 // random name seeds
 const HERO_NAMES: [&str; 40] = [
     "Spider-Man",
@@ -70,10 +79,121 @@ const HERO_NAMES: [&str; 40] = [
     "Scarlet Spider",
 ];
 
+/// A random number generator handle.
+///
+/// It implements [`RngCore`], so it also works with the `rand` APIs.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::rand::Rng;
+///
+/// let mut a = Rng::from_seed(7);
+/// let mut b = Rng::from_seed(7);
+/// assert_eq!(a.random_u32(1, 6), b.random_u32(1, 6));
+/// assert!((1..6).contains(&Rng::new().random_u32(1, 6)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Rng(ChaCha8Rng);
+
+impl Rng {
+    /// A generator seeded from operating system entropy, or from the seed
+    /// of an active [`Determinism`](crate::testing::Determinism) guard.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operating system has no entropy to offer.
+    pub fn new() -> Self {
+        #[cfg(feature = "testing")]
+        if let Some(seed) = crate::testing::determinism::next_rng_seed() {
+            return Self::from_seed(seed);
+        }
+        let mut seed = [0u8; 32];
+        getrandom::getrandom(&mut seed).expect("failed to gather entropy");
+        Self(ChaCha8Rng::from_seed(seed))
+    }
+
+    /// A generator that always produces the same sequence for `seed`.
+    pub fn from_seed(seed: u64) -> Self {
+        Self(ChaCha8Rng::seed_from_u64(seed))
+    }
+
+    /// A uniformly distributed number in `min..max`, or `min` if both are
+    /// equal.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `min > max`.
+    pub fn random_u32(&mut self, min: u32, max: u32) -> u32 {
+        assert!(min <= max, "invalid range {}..{}", min, max);
+        if min == max {
+            return min;
+        }
+        self.0.gen_range(min..max)
+    }
+
+    /// A random element of `items`, or `None` if it is empty.
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        items.choose(&mut self.0)
+    }
+
+    /// A random hero name.
+    pub fn name(&mut self) -> &'static str {
+        self.choose(&HERO_NAMES).copied().unwrap_or("Unknown")
+    }
+
+    /// Space-separated lowercase words, see [`generate_random_string`].
+    pub fn string(
+        &mut self,
+        word_count_range: (usize, usize),
+        word_length_range: (usize, usize),
+    ) -> String {
+        let word_count = self.0.gen_range(word_count_range.0..=word_count_range.1);
+
+        // Preallocate the space for the final string
+        let mut result = String::with_capacity(word_count * (word_length_range.1 + 1));
+
+        for i in 0..word_count {
+            if i > 0 {
+                result.push(' '); // Add space between words
+            }
+            let word_length = self.0.gen_range(word_length_range.0..=word_length_range.1);
+            for _ in 0..word_length {
+                result.push(self.0.gen_range(b'a'..=b'z') as char);
+            }
+        }
+
+        result
+    }
+}
+
+impl Default for Rng {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RngCore for Rng {
+    fn next_u32(&mut self) -> u32 {
+        self.0.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.0.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.0.try_fill_bytes(dest)
+    }
+}
+
 /// Generate random names
 pub fn generate_name() -> &'static str {
-    let mut rng = ChaCha8Rng::seed_from_u64(1234);
-    HERO_NAMES.choose(&mut rng).unwrap_or(&"Unknown")
+    Rng::new().name()
 }
 
 /// Generate uuid
@@ -81,54 +201,92 @@ pub fn generate_uuid() -> String {
     super::uuid::Uuid::new().encode_with(ToOwned::to_owned)
 }
 
+/// A uniformly distributed number in `min..max`, or `min` if both are
+/// equal.
+///
+/// # Panics
+///
+/// Panics if `min > max`.
 pub fn generate_random_u32(min: u32, max: u32) -> u32 {
-    let mut rng = ChaCha8Rng::seed_from_u64(1234);
-    rng.next_u32() % (max - min) + min
+    Rng::new().random_u32(min, max)
 }
 
 /// Generate a random string with space-separated words of random lengths.
 ///
 /// # Parameters
-/// - `word_count_range`: A tuple representing the range of the number of words.
-/// - `word_length_range`: A tuple representing the range of the length of each word.
+/// - `word_count_range`: A tuple representing the inclusive range of the number of words.
+/// - `word_length_range`: A tuple representing the inclusive range of the length of each word.
 ///
 /// # Returns
 /// A random string with space-separated words.
 ///
-/// # Parameters
-/// - `word_count_range`: A tuple representing the range of the number of words.
-/// - `word_length_range`: A tuple representing the range of the length of each word.
+/// # Panics
 ///
-/// # Returns
-/// A random string with space-separated words.
+/// Panics if a range is empty.
 pub fn generate_random_string(
     word_count_range: (usize, usize),
     word_length_range: (usize, usize),
 ) -> String {
-    let mut rng = ChaCha8Rng::seed_from_u64(1234);
-
-    // Generate random word count
-    let word_count = word_count_range.0
-        + (rng.next_u32() as usize % (word_count_range.1 - word_count_range.0 + 1));
-
-    // Preallocate the space for the final string
-    let mut result = String::with_capacity(word_count * word_length_range.1);
-
-    for i in 0..word_count {
-        let word_length = word_length_range.0
-            + (rng.next_u32() as usize % (word_length_range.1 - word_length_range.0 + 1));
-        let word: String = (0..word_length)
-            .map(|_| {
-                let rand_char = rng.next_u32() as u8 % 26 + b'a'; // Generate a random character between 'a' and 'z'
-                rand_char as char
-            })
-            .collect();
-
-        if i > 0 {
-            result.push(' '); // Add space between words
+    Rng::new().string(word_count_range, word_length_range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_seeded_is_reproducible() {
+        let sample = |rng: &mut Rng| {
+            (
+                rng.name(),
+                rng.random_u32(0, 1000),
+                rng.string((1, 3), (2, 5)),
+            )
+        };
+        assert_eq!(
+            sample(&mut Rng::from_seed(1)),
+            sample(&mut Rng::from_seed(1))
+        );
+
+        let names: Vec<&str> = (0..20).map(|seed| Rng::from_seed(seed).name()).collect();
+        assert!(names.iter().any(|name| *name != names[0]));
+    }
+
+    #[test]
+    fn test_random_u32_bounds() {
+        let mut rng = Rng::from_seed(3);
+        assert_eq!(rng.random_u32(5, 5), 5);
+        assert_eq!(generate_random_u32(7, 7), 7);
+
+        let mut seen = [false; 4];
+        for _ in 0..1000 {
+            let value = rng.random_u32(10, 14);
+            seen[(value - 10) as usize] = true;
+        }
+        assert_eq!(seen, [true; 4]);
+        assert!((0..1000).all(|_| generate_random_u32(0, 2) < 2));
+        // The full range must not overflow.
+        rng.random_u32(0, u32::MAX);
+    }
+
+    #[test]
+    fn test_random_string_shape() {
+        let mut rng = Rng::from_seed(4);
+        for _ in 0..100 {
+            let s = rng.string((2, 4), (3, 6));
+            let words: Vec<&str> = s.split(' ').collect();
+            assert!((2..=4).contains(&words.len()));
+            assert!(words
+                .iter()
+                .all(|w| (3..=6).contains(&w.len()) && w.bytes().all(|b| b.is_ascii_lowercase())));
         }
-        result.push_str(&word);
+        assert_eq!(rng.string((0, 0), (3, 6)), "");
     }
 
-    result
+    #[test]
+    fn test_entropy_seeded() {
+        let a: Vec<u64> = (0..4).map(|_| Rng::new().next_u64()).collect();
+        assert!(a.windows(2).any(|w| w[0] != w[1]));
+    }
 }
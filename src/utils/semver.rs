@@ -0,0 +1,720 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Semantic versions and version constraints.
+//!
+//! [`Version`] implements [SemVer 2.0](https://semver.org). [`Constraint`]
+//! uses the requirement syntax of Cargo, plus npm style hyphen ranges and
+//! `||` alternatives:
+//!
+//! | Constraint        | Matches                 |
+//! |-------------------|-------------------------|
+//! | `^1.2.3`, `1.2.3` | `>=1.2.3, <2.0.0`       |
+//! | `^0.2.3`          | `>=0.2.3, <0.3.0`       |
+//! | `~1.2.3`          | `>=1.2.3, <1.3.0`       |
+//! | `1.2.*`           | `>=1.2.0, <1.3.0`       |
+//! | `=1.2.3`          | exactly `1.2.3`         |
+//! | `1.2 - 2.3.4`     | `>=1.2.0, <=2.3.4`      |
+//! | `^1 \|\| ^3`      | either major version    |
+//!
+//! Pre-release versions only match a constraint that names a pre-release of
+//! the same `major.minor.patch`, so `^1.0.0` never selects `1.1.0-beta`.
+
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use core::fmt;
+use core::str::FromStr;
+use serde::Deserialize;
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SemverError {
+    InvalidVersion(String),
+    InvalidConstraint(String),
+}
+
+impl fmt::Display for SemverError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SemverError::InvalidVersion(v) => write!(f, "invalid version '{}'", v),
+            SemverError::InvalidConstraint(c) => write!(f, "invalid version constraint '{}'", c),
+        }
+    }
+}
+
+/// A dot-separated pre-release identifier.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Identifier {
+    Numeric(u64),
+    AlphaNumeric(String),
+}
+
+impl Ord for Identifier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (Identifier::Numeric(a), Identifier::Numeric(b)) => a.cmp(b),
+            (Identifier::Numeric(_), Identifier::AlphaNumeric(_)) => Ordering::Less,
+            (Identifier::AlphaNumeric(_), Identifier::Numeric(_)) => Ordering::Greater,
+            (Identifier::AlphaNumeric(a), Identifier::AlphaNumeric(b)) => a.cmp(b),
+        }
+    }
+}
+
+impl PartialOrd for Identifier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Identifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Identifier::Numeric(n) => write!(f, "{}", n),
+            Identifier::AlphaNumeric(s) => f.write_str(s),
+        }
+    }
+}
+
+/// A semantic version such as `1.4.0-rc.1+build.7`.
+///
+/// Versions order by SemVer precedence. Build metadata does not take part
+/// in precedence, it only breaks ties so that the order stays consistent
+/// with equality, see [`Version::cmp_precedence`].
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::semver::Version;
+///
+/// let rc: Version = "1.4.0-rc.1".parse().unwrap();
+/// let release: Version = "1.4.0".parse().unwrap();
+/// assert!(rc < release);
+/// assert!(release < Version::new(1, 10, 0));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    pub pre: Vec<Identifier>,
+    pub build: String,
+}
+
+impl Version {
+    pub const fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+            pre: Vec::new(),
+            build: String::new(),
+        }
+    }
+
+    pub fn is_prerelease(&self) -> bool {
+        !self.pre.is_empty()
+    }
+
+    /// Compares by SemVer precedence only, ignoring build metadata.
+    pub fn cmp_precedence(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => self.pre.cmp(&other.pre),
+            })
+    }
+
+    fn same_release(&self, other: &Self) -> bool {
+        (self.major, self.minor, self.patch) == (other.major, other.minor, other.patch)
+    }
+}
+
+impl Ord for Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.cmp_precedence(other)
+            .then_with(|| self.build.cmp(&other.build))
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        for (i, identifier) in self.pre.iter().enumerate() {
+            f.write_str(if i == 0 { "-" } else { "." })?;
+            write!(f, "{}", identifier)?;
+        }
+        if !self.build.is_empty() {
+            write!(f, "+{}", self.build)?;
+        }
+        Ok(())
+    }
+}
+
+fn parse_number(s: &str) -> Option<u64> {
+    let valid =
+        !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) && (s == "0" || !s.starts_with('0'));
+    if valid {
+        s.parse().ok()
+    } else {
+        None
+    }
+}
+
+fn is_identifier(s: &str) -> bool {
+    !s.is_empty() && s.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+}
+
+fn parse_pre(s: &str) -> Option<Vec<Identifier>> {
+    s.split('.')
+        .map(|part| {
+            if !is_identifier(part) {
+                None
+            } else if part.bytes().all(|b| b.is_ascii_digit()) {
+                parse_number(part).map(Identifier::Numeric)
+            } else {
+                Some(Identifier::AlphaNumeric(part.to_string()))
+            }
+        })
+        .collect()
+}
+
+impl FromStr for Version {
+    type Err = SemverError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || SemverError::InvalidVersion(s.to_string());
+        let (rest, build) = match s.split_once('+') {
+            Some((rest, build)) if build.split('.').all(is_identifier) => (rest, build),
+            Some(_) => return Err(invalid()),
+            None => (s, ""),
+        };
+        let (core, pre) = match rest.split_once('-') {
+            Some((core, pre)) => (core, parse_pre(pre).ok_or_else(invalid)?),
+            None => (rest, Vec::new()),
+        };
+        let mut numbers = core.split('.').map(parse_number);
+        match (
+            numbers.next(),
+            numbers.next(),
+            numbers.next(),
+            numbers.next(),
+        ) {
+            (Some(Some(major)), Some(Some(minor)), Some(Some(patch)), None) => Ok(Self {
+                major,
+                minor,
+                patch,
+                pre,
+                build: build.to_string(),
+            }),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl Serialize for Version {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Version {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct VersionVisitor;
+        impl serde::de::Visitor<'_> for VersionVisitor {
+            type Value = Version;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a semantic version such as \"1.2.3\"")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Version::from_str(v).map_err(E::custom)
+            }
+        }
+        deserializer.deserialize_str(VersionVisitor)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Op {
+    Exact,
+    Greater,
+    GreaterEq,
+    Less,
+    LessEq,
+    Tilde,
+    Caret,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Bound {
+    op: Op,
+    version: Version,
+}
+
+impl Bound {
+    fn matches(&self, version: &Version) -> bool {
+        let order = version.cmp_precedence(&self.version);
+        match self.op {
+            Op::Exact => order == Ordering::Equal,
+            Op::Greater => order == Ordering::Greater,
+            Op::GreaterEq => order != Ordering::Less,
+            Op::Less => order == Ordering::Less,
+            Op::LessEq => order != Ordering::Greater,
+            Op::Tilde | Op::Caret => unreachable!("expanded while parsing"),
+        }
+    }
+}
+
+/// A version with optional minor and patch numbers, as written in
+/// constraints.
+struct Partial {
+    major: u64,
+    minor: Option<u64>,
+    patch: Option<u64>,
+    pre: Vec<Identifier>,
+}
+
+impl Partial {
+    fn parse(s: &str) -> Option<Self> {
+        let (core, pre) = match s.split_once('-') {
+            Some((core, pre)) => (core, parse_pre(pre)?),
+            None => (s, Vec::new()),
+        };
+        let mut parts = core.split('.');
+        let major = parse_number(parts.next()?)?;
+        let mut next = || -> Option<Option<u64>> {
+            match parts.next() {
+                None | Some("*" | "x" | "X") => Some(None),
+                Some(part) => parse_number(part).map(Some),
+            }
+        };
+        let minor = next()?;
+        let patch = next()?;
+        if parts.next().is_some() || (minor.is_none() && patch.is_some()) {
+            return None;
+        }
+        if !pre.is_empty() && patch.is_none() {
+            return None;
+        }
+        Some(Self {
+            major,
+            minor,
+            patch,
+            pre,
+        })
+    }
+
+    fn version(&self) -> Version {
+        Version {
+            pre: self.pre.clone(),
+            ..Version::new(self.major, self.minor.unwrap_or(0), self.patch.unwrap_or(0))
+        }
+    }
+
+    /// Expands a comparator into plain bounds.
+    fn bounds(&self, op: Op) -> Vec<Bound> {
+        let bound = |op, version| Bound { op, version };
+        let lower = bound(Op::GreaterEq, self.version());
+        // An overflowing component carries into the next one, and past the
+        // largest version there is no next version: bounds below it are
+        // dropped and bounds above it match nothing.
+        let next_major = self
+            .major
+            .checked_add(1)
+            .map(|major| Version::new(major, 0, 0));
+        let next_minor = |minor: u64| match minor.checked_add(1) {
+            Some(minor) => Some(Version::new(self.major, minor, 0)),
+            None => next_major.clone(),
+        };
+        let below = |next: Option<Version>| next.map(|version| bound(Op::Less, version));
+        let at_least = |next: Option<Version>| match next {
+            Some(version) => bound(Op::GreaterEq, version),
+            None => bound(Op::Greater, Version::new(u64::MAX, u64::MAX, u64::MAX)),
+        };
+        let range = |upper: Option<Version>| {
+            core::iter::once(lower.clone())
+                .chain(below(upper))
+                .collect()
+        };
+
+        match (op, self.minor, self.patch) {
+            (Op::Exact, Some(_), Some(_)) => alloc::vec![bound(Op::Exact, self.version())],
+            (Op::Greater, Some(_), Some(_)) => alloc::vec![bound(Op::Greater, self.version())],
+            (Op::LessEq, Some(_), Some(_)) => alloc::vec![bound(Op::LessEq, self.version())],
+            (Op::GreaterEq, ..) => alloc::vec![lower],
+            (Op::Less, ..) => alloc::vec![bound(Op::Less, self.version())],
+
+            (Op::Greater, None, _) => alloc::vec![at_least(next_major.clone())],
+            (Op::Greater, Some(minor), None) => alloc::vec![at_least(next_minor(minor))],
+            (Op::LessEq, None, _) => below(next_major.clone()).into_iter().collect(),
+            (Op::LessEq, Some(minor), None) => below(next_minor(minor)).into_iter().collect(),
+
+            (Op::Exact | Op::Tilde | Op::Caret, None, _) => range(next_major.clone()),
+            (Op::Exact | Op::Tilde, Some(minor), _) => range(next_minor(minor)),
+            (Op::Caret, Some(minor), patch) => {
+                let upper = if self.major > 0 {
+                    next_major.clone()
+                } else if minor > 0 || patch.is_none() {
+                    next_minor(minor)
+                } else {
+                    match patch.unwrap().checked_add(1) {
+                        Some(patch) => Some(Version::new(0, 0, patch)),
+                        None => next_minor(0),
+                    }
+                };
+                range(upper)
+            }
+        }
+    }
+}
+
+/// A set of version requirements, see the [module documentation](self)
+/// for the syntax.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::semver::Constraint;
+/// use pizza_common::utils::semver::Version;
+///
+/// let plugin_api: Constraint = ">=1.4, <3 || ~0.9.2".parse().unwrap();
+/// assert!(plugin_api.matches(&Version::new(2, 7, 1)));
+/// assert!(plugin_api.matches(&Version::new(0, 9, 5)));
+/// assert!(!plugin_api.matches(&Version::new(1, 3, 9)));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Constraint {
+    source: String,
+    alternatives: Vec<Vec<Bound>>,
+}
+
+impl Constraint {
+    /// A constraint every release matches.
+    pub fn any() -> Self {
+        Self {
+            source: "*".to_string(),
+            alternatives: alloc::vec![Vec::new()],
+        }
+    }
+
+    pub fn matches(&self, version: &Version) -> bool {
+        self.alternatives.iter().any(|bounds| {
+            bounds.iter().all(|bound| bound.matches(version))
+                && (!version.is_prerelease()
+                    || bounds.iter().any(|bound| {
+                        bound.version.is_prerelease() && bound.version.same_release(version)
+                    }))
+        })
+    }
+
+    fn parse_alternative(s: &str) -> Option<Vec<Bound>> {
+        if let Some((low, high)) = s.split_once(" - ") {
+            let low = Partial::parse(low.trim())?;
+            let high = Partial::parse(high.trim())?;
+            let mut bounds = low.bounds(Op::GreaterEq);
+            bounds.extend(high.bounds(Op::LessEq));
+            return Some(bounds);
+        }
+
+        let mut bounds = Vec::new();
+        let mut tokens = s
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|token| !token.is_empty());
+        while let Some(token) = tokens.next() {
+            let split = token
+                .find(|c: char| c.is_ascii_alphanumeric() || c == '*')
+                .unwrap_or(token.len());
+            let (op, version) = token.split_at(split);
+            let version = if version.is_empty() {
+                tokens.next()?
+            } else {
+                version
+            };
+            let op = match op {
+                "" | "^" => Op::Caret,
+                "=" => Op::Exact,
+                ">" => Op::Greater,
+                ">=" => Op::GreaterEq,
+                "<" => Op::Less,
+                "<=" => Op::LessEq,
+                "~" => Op::Tilde,
+                _ => return None,
+            };
+            if matches!(version, "*" | "x" | "X") && op == Op::Caret {
+                continue;
+            }
+            let partial = Partial::parse(version)?;
+            // A bare version with wildcards, such as `1.2.*`, pins the
+            // given numbers rather than allowing compatible updates. Only
+            // the numeric core counts; `1.2.3-next` is still a caret range.
+            let core = version.split('-').next().unwrap_or(version);
+            let op = if op == Op::Caret && !token.starts_with('^') && core.contains(['*', 'x', 'X'])
+            {
+                Op::Exact
+            } else {
+                op
+            };
+            bounds.extend(partial.bounds(op));
+        }
+        Some(bounds)
+    }
+}
+
+impl FromStr for Constraint {
+    type Err = SemverError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let alternatives = s
+            .split("||")
+            .map(|alternative| {
+                let alternative = alternative.trim();
+                if alternative.is_empty() {
+                    None
+                } else {
+                    Self::parse_alternative(alternative)
+                }
+            })
+            .collect::<Option<Vec<_>>>()
+            .ok_or_else(|| SemverError::InvalidConstraint(s.to_string()))?;
+        Ok(Self {
+            source: s.trim().to_string(),
+            alternatives,
+        })
+    }
+}
+
+impl fmt::Display for Constraint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl Serialize for Constraint {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Constraint {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        struct ConstraintVisitor;
+        impl serde::de::Visitor<'_> for ConstraintVisitor {
+            type Value = Constraint;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("a version constraint such as \"^1.2\"")
+            }
+
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                Constraint::from_str(v).map_err(E::custom)
+            }
+        }
+        deserializer.deserialize_str(ConstraintVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(s: &str) -> Version {
+        s.parse().unwrap()
+    }
+
+    fn matches(constraint: &str, version: &str) -> bool {
+        constraint
+            .parse::<Constraint>()
+            .unwrap()
+            .matches(&v(version))
+    }
+
+    #[test]
+    fn test_parse_version() {
+        let version = v("1.2.3-alpha.10.beta+build.5");
+        assert_eq!((version.major, version.minor, version.patch), (1, 2, 3));
+        assert_eq!(
+            version.pre,
+            [
+                Identifier::AlphaNumeric("alpha".into()),
+                Identifier::Numeric(10),
+                Identifier::AlphaNumeric("beta".into()),
+            ]
+        );
+        assert_eq!(version.build, "build.5");
+        assert_eq!(version.to_string(), "1.2.3-alpha.10.beta+build.5");
+
+        for invalid in [
+            "",
+            "1",
+            "1.2",
+            "1.2.3.4",
+            "01.2.3",
+            "1.2.3-",
+            "1.2.3-01",
+            "1.2.3+",
+            "a.b.c",
+            "1.2.3-a..b",
+        ] {
+            assert!(invalid.parse::<Version>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_precedence() {
+        // The example chain from the SemVer specification.
+        let chain = [
+            "1.0.0-alpha",
+            "1.0.0-alpha.1",
+            "1.0.0-alpha.beta",
+            "1.0.0-beta",
+            "1.0.0-beta.2",
+            "1.0.0-beta.11",
+            "1.0.0-rc.1",
+            "1.0.0",
+            "2.0.0",
+            "2.1.0",
+            "2.1.1",
+        ];
+        for pair in chain.windows(2) {
+            assert!(v(pair[0]) < v(pair[1]), "{} < {}", pair[0], pair[1]);
+        }
+        assert_eq!(v("1.0.0+a").cmp_precedence(&v("1.0.0+b")), Ordering::Equal);
+        assert!(v("1.0.0+a") < v("1.0.0+b"));
+    }
+
+    #[test]
+    fn test_caret_and_tilde() {
+        assert!(matches("^1.2.3", "1.9.0"));
+        assert!(matches("1.2.3", "1.2.3"));
+        assert!(!matches("^1.2.3", "2.0.0"));
+        assert!(!matches("^1.2.3", "1.2.2"));
+        assert!(matches("^0.2.3", "0.2.9"));
+        assert!(!matches("^0.2.3", "0.3.0"));
+        assert!(matches("^0.0.3", "0.0.3"));
+        assert!(!matches("^0.0.3", "0.0.4"));
+        assert!(matches("^0.0", "0.0.7"));
+        assert!(!matches("^0.0", "0.1.0"));
+        assert!(matches("^0", "0.9.9"));
+
+        assert!(matches("~1.2.3", "1.2.9"));
+        assert!(!matches("~1.2.3", "1.3.0"));
+        assert!(matches("~1", "1.9.0"));
+        assert!(!matches("~1", "2.0.0"));
+    }
+
+    #[test]
+    fn test_bounds_at_u64_max() {
+        use alloc::format;
+
+        let max = u64::MAX;
+        assert!(matches(&format!("^{}", max), &format!("{}.7.0", max)));
+        assert!(matches(&format!("~1.{}", max), &format!("1.{}.3", max)));
+        assert!(!matches(&format!("~1.{}", max), "2.0.0"));
+        assert!(matches(&format!("^0.0.{}", max), &format!("0.0.{}", max)));
+        assert!(!matches(&format!("^0.0.{}", max), "0.1.0"));
+        assert!(matches(&format!("<={}", max), &format!("{0}.{0}.{0}", max)));
+        assert!(!matches(&format!(">{}", max), &format!("{0}.{0}.{0}", max)));
+        assert!(!matches(
+            &format!(">{0}.{0}", max),
+            &format!("{0}.{0}.{0}", max)
+        ));
+        assert!(matches(&format!(">1.{}", max), "2.0.0"));
+    }
+
+    #[test]
+    fn test_ranges_and_wildcards() {
+        assert!(matches(">=1.2, <2", "1.5.0"));
+        assert!(!matches(">=1.2, <2", "2.0.0"));
+        assert!(matches(">= 1.2.0 < 1.3", "1.2.8"));
+        assert!(matches(">1.2", "1.3.0"));
+        assert!(!matches(">1.2", "1.2.9"));
+        assert!(matches("<=1.2", "1.2.9"));
+        assert!(!matches("<=1.2", "1.3.0"));
+        assert!(matches("=1.2", "1.2.5"));
+        assert!(!matches("=1.2.3", "1.2.4"));
+        assert!(matches("1.2 - 2.3.4", "2.3.4"));
+        assert!(!matches("1.2 - 2.3.4", "2.3.5"));
+        assert!(!matches("1.2 - 2.3.4", "1.1.9"));
+        assert!(matches("1.2.*", "1.2.7"));
+        assert!(!matches("1.2.*", "1.3.0"));
+        assert!(matches("1.x", "1.9.0"));
+        assert!(matches("1.2.3-next", "1.2.5"));
+        assert!(matches("2.0.0-xyz", "2.1.0"));
+        assert!(matches("*", "42.0.0"));
+        assert!(matches("^1 || ^3", "3.1.0"));
+        assert!(!matches("^1 || ^3", "2.1.0"));
+        assert!(Constraint::any().matches(&v("0.0.1")));
+
+        for invalid in ["", ">>1", "1.2.3.4", "^x.1", "1.*.3", "^1 ||", "1.2 -"] {
+            assert!(invalid.parse::<Constraint>().is_err(), "{:?}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_prerelease_matching() {
+        assert!(!matches("^1.0.0", "1.1.0-beta"));
+        assert!(!matches("*", "1.0.0-rc.1"));
+        assert!(matches(">=1.1.0-alpha", "1.1.0-beta"));
+        assert!(!matches(">=1.1.0-alpha", "1.2.0-beta"));
+        assert!(matches(">=1.1.0-alpha", "1.2.0"));
+        assert!(matches("=1.0.0-rc.1", "1.0.0-rc.1"));
+    }
+
+    #[test]
+    fn test_serde() {
+        let version: Version = serde_json::from_str(r#""1.2.3-rc.1""#).unwrap();
+        assert_eq!(serde_json::to_string(&version).unwrap(), r#""1.2.3-rc.1""#);
+        let constraint: Constraint = serde_json::from_str(r#"" ^1.2 ""#).unwrap();
+        assert_eq!(serde_json::to_string(&constraint).unwrap(), r#""^1.2""#);
+        assert!(serde_json::from_str::<Version>(r#""1.2""#).is_err());
+    }
+}
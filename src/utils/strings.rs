@@ -23,6 +23,9 @@
 use alloc::format;
 use alloc::string::String;
 use alloc::string::ToString;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::fmt;
 
 /// Removes the last occurrence of a specified character or substring from the input string.
 ///
@@ -66,6 +69,7 @@ pub fn remove_suffix_str(input_string: &str, find: &str) -> String {
 ///
 /// A new `String` with the prefix removed if it was present, or the original string
 /// if the prefix was not found.
+#[allow(clippy::manual_strip)]
 pub fn remove_prefix_str(input_string: &str, prefix: &str) -> String {
     if input_string.starts_with(prefix) {
         input_string[prefix.len()..].to_string()
@@ -74,6 +78,611 @@ pub fn remove_prefix_str(input_string: &str, prefix: &str) -> String {
     }
 }
 
+/// Suffixes used by [`humanize_number`], each a factor of 1000 above the
+/// previous one.
+const NUMBER_SUFFIXES: [(char, i128); 5] = [
+    ('K', 1_000),
+    ('M', 1_000_000),
+    ('B', 1_000_000_000),
+    ('T', 1_000_000_000_000),
+    ('P', 1_000_000_000_000_000),
+];
+
+/// Formats a number compactly with one decimal and a `K`, `M`, `B`, `T` or
+/// `P` suffix, e.g. `1234567` becomes `"1.2M"`.
+///
+/// Values below 1000 are printed as is and a trailing `.0` is dropped, so
+/// `1_000_000` becomes `"1M"`. The output does not depend on the locale and
+/// can be read back with [`parse_human_number`].
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::strings::humanize_number;
+///
+/// assert_eq!(humanize_number(950), "950");
+/// assert_eq!(humanize_number(1_234_567), "1.2M");
+/// assert_eq!(humanize_number(-15_300), "-15.3K");
+/// assert_eq!(humanize_number(999_960), "1M");
+/// ```
+pub fn humanize_number(n: i64) -> String {
+    let sign = if n < 0 { "-" } else { "" };
+    let abs = (n as i128).abs();
+    if abs < 1_000 {
+        return n.to_string();
+    }
+    // Pick the suffix after rounding, so 999_960 becomes "1M", not "1000K".
+    let mut index = 0;
+    let mut tenths = rounded_tenths(abs, NUMBER_SUFFIXES[0].1);
+    while index + 1 < NUMBER_SUFFIXES.len() && tenths >= 10_000 {
+        index += 1;
+        tenths = rounded_tenths(abs, NUMBER_SUFFIXES[index].1);
+    }
+    let suffix = NUMBER_SUFFIXES[index].0;
+    if tenths % 10 == 0 {
+        format!("{}{}{}", sign, tenths / 10, suffix)
+    } else {
+        format!("{}{}.{}{}", sign, tenths / 10, tenths % 10, suffix)
+    }
+}
+
+fn rounded_tenths(value: i128, unit: i128) -> i128 {
+    (value * 10 + unit / 2) / unit
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseNumberError {
+    Empty,
+    Invalid(String),
+    Overflow(String),
+}
+
+impl fmt::Display for ParseNumberError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseNumberError::Empty => write!(f, "empty number"),
+            ParseNumberError::Invalid(s) => write!(f, "invalid number '{}'", s),
+            ParseNumberError::Overflow(s) => write!(f, "number '{}' is out of range", s),
+        }
+    }
+}
+
+/// Parses numbers as written by [`humanize_number`] and
+/// [`format_thousands`].
+///
+/// Suffixes are case-insensitive, `,`, `_` and `'` are accepted as digit
+/// separators and fractional results are rounded to the nearest integer.
+/// Since [`humanize_number`] keeps a single decimal, round trips are exact
+/// only up to that precision.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::strings::parse_human_number;
+///
+/// assert_eq!(parse_human_number("1.2M"), Ok(1_200_000));
+/// assert_eq!(parse_human_number("-15.3k"), Ok(-15_300));
+/// assert_eq!(parse_human_number("1,234,567"), Ok(1_234_567));
+/// ```
+pub fn parse_human_number(input: &str) -> Result<i64, ParseNumberError> {
+    let s = input.trim();
+    if s.is_empty() {
+        return Err(ParseNumberError::Empty);
+    }
+    let invalid = || ParseNumberError::Invalid(input.to_string());
+    let overflow = || ParseNumberError::Overflow(input.to_string());
+
+    let (negative, s) = match s.as_bytes()[0] {
+        b'-' => (true, &s[1..]),
+        b'+' => (false, &s[1..]),
+        _ => (false, s),
+    };
+    let (digits, unit) = match s.chars().last() {
+        Some(c) if c.is_ascii_alphabetic() => {
+            let upper = c.to_ascii_uppercase();
+            let (_, unit) = NUMBER_SUFFIXES
+                .iter()
+                .find(|(suffix, _)| *suffix == upper)
+                .ok_or_else(invalid)?;
+            (s[..s.len() - 1].trim_end(), *unit)
+        }
+        _ => (s, 1),
+    };
+    let (whole, fraction) = digits.split_once('.').unwrap_or((digits, ""));
+    let is_separator = |c: char| matches!(c, ',' | '_' | '\'');
+    if whole.is_empty()
+        || !whole.starts_with(|c: char| c.is_ascii_digit())
+        || !whole.chars().all(|c| c.is_ascii_digit() || is_separator(c))
+        || !fraction.chars().all(|c| c.is_ascii_digit())
+        || (digits.contains('.') && fraction.is_empty())
+    {
+        return Err(invalid());
+    }
+
+    let mut value: i128 = 0;
+    for c in whole.chars().filter(|c| c.is_ascii_digit()) {
+        value = value
+            .checked_mul(10)
+            .and_then(|v| v.checked_add(c as i128 - '0' as i128))
+            .ok_or_else(overflow)?;
+    }
+    value = value.checked_mul(unit).ok_or_else(overflow)?;
+
+    // Scale the fraction digit by digit, rounding half away from zero on
+    // the first digit that falls below one.
+    let mut scale = unit;
+    for c in fraction.chars() {
+        let digit = c as i128 - '0' as i128;
+        if scale == 1 {
+            if digit >= 5 {
+                value += 1;
+            }
+            break;
+        }
+        scale /= 10;
+        value += digit * scale;
+    }
+
+    let value = if negative { -value } else { value };
+    i64::try_from(value).map_err(|_| overflow())
+}
+
+/// Formats an integer with `,` between groups of three digits, e.g.
+/// `1234567` becomes `"1,234,567"`.
+///
+/// The grouping is fixed and does not follow the system locale, so logs and
+/// API responses look the same everywhere.
+pub fn format_thousands(n: i64) -> String {
+    format_thousands_with(n, ',')
+}
+
+/// Like [`format_thousands`], with a custom separator such as `'_'` or a
+/// narrow no-break space.
+pub fn format_thousands_with(n: i64, separator: char) -> String {
+    let digits = n.unsigned_abs().to_string();
+    let mut out = String::with_capacity(digits.len() + digits.len() / 3 * separator.len_utf8() + 1);
+    if n < 0 {
+        out.push('-');
+    }
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i).is_multiple_of(3) {
+            out.push(separator);
+        }
+        out.push(c);
+    }
+    out
+}
+
+/// Code point ranges rendered in two terminal columns: the East Asian Wide
+/// and Fullwidth blocks and emoji presented as pictographs.
+const WIDE_RANGES: &[(u32, u32)] = &[
+    (0x1100, 0x115f),
+    (0x231a, 0x231b),
+    (0x2329, 0x232a),
+    (0x23e9, 0x23ec),
+    (0x23f0, 0x23f0),
+    (0x23f3, 0x23f3),
+    (0x25fd, 0x25fe),
+    (0x2614, 0x2615),
+    (0x2648, 0x2653),
+    (0x267f, 0x267f),
+    (0x2693, 0x2693),
+    (0x26a1, 0x26a1),
+    (0x26aa, 0x26ab),
+    (0x26bd, 0x26be),
+    (0x26c4, 0x26c5),
+    (0x26ce, 0x26ce),
+    (0x26d4, 0x26d4),
+    (0x26ea, 0x26ea),
+    (0x26f2, 0x26f3),
+    (0x26f5, 0x26f5),
+    (0x26fa, 0x26fa),
+    (0x26fd, 0x26fd),
+    (0x2705, 0x2705),
+    (0x270a, 0x270b),
+    (0x2728, 0x2728),
+    (0x274c, 0x274c),
+    (0x274e, 0x274e),
+    (0x2753, 0x2755),
+    (0x2757, 0x2757),
+    (0x2795, 0x2797),
+    (0x27b0, 0x27b0),
+    (0x27bf, 0x27bf),
+    (0x2b1b, 0x2b1c),
+    (0x2b50, 0x2b50),
+    (0x2b55, 0x2b55),
+    (0x2e80, 0x303e),
+    (0x3041, 0x33ff),
+    (0x3400, 0x4dbf),
+    (0x4e00, 0x9fff),
+    (0xa000, 0xa4cf),
+    (0xa960, 0xa97f),
+    (0xac00, 0xd7a3),
+    (0xf900, 0xfaff),
+    (0xfe10, 0xfe19),
+    (0xfe30, 0xfe6f),
+    (0xff00, 0xff60),
+    (0xffe0, 0xffe6),
+    (0x16fe0, 0x16fe4),
+    (0x17000, 0x18aff),
+    (0x1b000, 0x1b2ff),
+    (0x1f004, 0x1f004),
+    (0x1f0cf, 0x1f0cf),
+    (0x1f18e, 0x1f18e),
+    (0x1f191, 0x1f19a),
+    (0x1f1e6, 0x1f1ff),
+    (0x1f200, 0x1f251),
+    (0x1f300, 0x1f320),
+    (0x1f32d, 0x1f335),
+    (0x1f337, 0x1f37c),
+    (0x1f37e, 0x1f393),
+    (0x1f3a0, 0x1f3ca),
+    (0x1f3cf, 0x1f3d3),
+    (0x1f3e0, 0x1f3f0),
+    (0x1f3f4, 0x1f3f4),
+    (0x1f3f8, 0x1f43e),
+    (0x1f440, 0x1f440),
+    (0x1f442, 0x1f4fc),
+    (0x1f4ff, 0x1f53d),
+    (0x1f54b, 0x1f54e),
+    (0x1f550, 0x1f567),
+    (0x1f57a, 0x1f57a),
+    (0x1f595, 0x1f596),
+    (0x1f5a4, 0x1f5a4),
+    (0x1f5fb, 0x1f64f),
+    (0x1f680, 0x1f6c5),
+    (0x1f6cc, 0x1f6cc),
+    (0x1f6d0, 0x1f6d2),
+    (0x1f6d5, 0x1f6d7),
+    (0x1f6dc, 0x1f6df),
+    (0x1f6eb, 0x1f6ec),
+    (0x1f6f4, 0x1f6fc),
+    (0x1f7e0, 0x1f7eb),
+    (0x1f7f0, 0x1f7f0),
+    (0x1f90c, 0x1f93a),
+    (0x1f93c, 0x1f945),
+    (0x1f947, 0x1f9ff),
+    (0x1fa70, 0x1faff),
+    (0x20000, 0x2fffd),
+    (0x30000, 0x3fffd),
+];
+
+/// Code point ranges that take no column of their own: combining marks,
+/// Hangul medial and final jamo, format characters such as the zero-width
+/// joiner, variation selectors and emoji skin tone modifiers.
+const ZERO_WIDTH_RANGES: &[(u32, u32)] = &[
+    (0x0300, 0x036f),
+    (0x0483, 0x0489),
+    (0x0591, 0x05bd),
+    (0x05bf, 0x05bf),
+    (0x05c1, 0x05c2),
+    (0x05c4, 0x05c5),
+    (0x05c7, 0x05c7),
+    (0x0610, 0x061a),
+    (0x064b, 0x065f),
+    (0x0670, 0x0670),
+    (0x06d6, 0x06dc),
+    (0x06df, 0x06e4),
+    (0x06e7, 0x06e8),
+    (0x06ea, 0x06ed),
+    (0x0900, 0x0902),
+    (0x093a, 0x093a),
+    (0x093c, 0x093c),
+    (0x0941, 0x0948),
+    (0x094d, 0x094d),
+    (0x0951, 0x0957),
+    (0x0962, 0x0963),
+    (0x0e31, 0x0e31),
+    (0x0e34, 0x0e3a),
+    (0x0e47, 0x0e4e),
+    (0x1160, 0x11ff),
+    (0x1ab0, 0x1aff),
+    (0x1dc0, 0x1dff),
+    (0x200b, 0x200f),
+    (0x2028, 0x202e),
+    (0x2060, 0x2064),
+    (0x20d0, 0x20ff),
+    (0x302a, 0x302d),
+    (0x3099, 0x309a),
+    (0xd7b0, 0xd7ff),
+    (0xfe00, 0xfe0f),
+    (0xfe20, 0xfe2f),
+    (0xfeff, 0xfeff),
+    (0x1f3fb, 0x1f3ff),
+    (0xe0000, 0xe007f),
+    (0xe0100, 0xe01ef),
+];
+
+const ZERO_WIDTH_JOINER: char = '\u{200d}';
+
+fn in_ranges(ranges: &[(u32, u32)], c: char) -> bool {
+    let c = c as u32;
+    ranges
+        .binary_search_by(|&(start, end)| {
+            if end < c {
+                core::cmp::Ordering::Less
+            } else if start > c {
+                core::cmp::Ordering::Greater
+            } else {
+                core::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1f1e6}'..='\u{1f1ff}').contains(&c)
+}
+
+/// Returns the number of terminal columns a character occupies on its own:
+/// 0 for control characters and combining marks, 2 for wide East Asian
+/// characters and emoji, 1 otherwise.
+pub fn char_width(c: char) -> usize {
+    if c.is_control() || in_ranges(ZERO_WIDTH_RANGES, c) {
+        0
+    } else if in_ranges(WIDE_RANGES, c) {
+        2
+    } else {
+        1
+    }
+}
+
+/// Returns the number of terminal columns a string occupies, for aligning
+/// table-formatted output.
+///
+/// Characters joined by a zero-width joiner, such as family emoji, and
+/// pairs of regional indicators forming a flag count as a single wide
+/// character. The width tables approximate Unicode East Asian Width and are
+/// not tied to the Unicode version of the terminal, so exotic scripts may
+/// still be off by a column.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::strings::display_width;
+///
+/// assert_eq!(display_width("pizza"), 5);
+/// assert_eq!(display_width("搜索"), 4);
+/// assert_eq!(display_width("cafe\u{301}"), 4);
+/// assert_eq!(display_width("👩\u{200d}💻"), 2);
+/// ```
+pub fn display_width(s: &str) -> usize {
+    let mut width = 0;
+    let mut joined = false;
+    let mut open_flag = false;
+    for c in s.chars() {
+        if c == ZERO_WIDTH_JOINER {
+            joined = true;
+            continue;
+        }
+        let char_width = char_width(c);
+        if char_width == 0 {
+            continue;
+        }
+        if is_regional_indicator(c) && open_flag {
+            open_flag = false;
+        } else if !core::mem::take(&mut joined) {
+            open_flag = is_regional_indicator(c);
+            width += char_width;
+        }
+    }
+    width
+}
+
+/// Pads a string with trailing spaces to the given display width. Strings
+/// that are already as wide or wider are returned unchanged.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::strings::pad_to_width;
+///
+/// assert_eq!(pad_to_width("名前", 6), "名前  ");
+/// assert_eq!(pad_to_width("name", 6), "name  ");
+/// ```
+pub fn pad_to_width(s: &str, width: usize) -> String {
+    let padding = width.saturating_sub(display_width(s));
+    let mut padded = String::with_capacity(s.len() + padding);
+    padded.push_str(s);
+    padded.extend(core::iter::repeat_n(' ', padding));
+    padded
+}
+
+/// Returns the length in bytes of the longest common prefix of two strings,
+/// always at a character boundary of both.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::strings::common_prefix_len;
+///
+/// let (a, b) = ("pizzeria", "pizza");
+/// assert_eq!(&a[..common_prefix_len(a, b)], "pizz");
+/// assert_eq!(common_prefix_len("café", "cafè"), 3);
+/// ```
+pub fn common_prefix_len(a: &str, b: &str) -> usize {
+    a.char_indices()
+        .zip(b.chars())
+        .find(|((_, x), y)| x != y)
+        .map_or(a.len().min(b.len()), |((index, _), _)| index)
+}
+
+/// Returns the Levenshtein distance between two strings: the number of
+/// single character insertions, deletions and substitutions that turn one
+/// into the other.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::strings::levenshtein;
+///
+/// assert_eq!(levenshtein("kitten", "sitting"), 3);
+/// assert_eq!(levenshtein("", "abc"), 3);
+/// ```
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    edit_distance(a, b, false, usize::MAX).unwrap()
+}
+
+/// Like [`levenshtein`], but gives up as soon as the distance is known to
+/// exceed `max`, returning `None`.
+///
+/// Checking whether a term is within a small distance, as fuzzy queries
+/// do, takes time proportional to the length of the strings rather than
+/// their product when they are far apart.
+pub fn levenshtein_bounded(a: &str, b: &str, max: usize) -> Option<usize> {
+    edit_distance(a, b, false, max)
+}
+
+/// Returns the Damerau-Levenshtein distance between two strings, which also
+/// counts swapping two adjacent characters as a single edit.
+///
+/// This is the restricted variant, also known as the optimal string
+/// alignment distance: a transposed pair is not edited again, so
+/// `"ca"` to `"abc"` takes 3 edits rather than 2.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::strings::damerau_levenshtein;
+///
+/// assert_eq!(damerau_levenshtein("pizza", "pziza"), 1);
+/// assert_eq!(damerau_levenshtein("ca", "abc"), 3);
+/// ```
+pub fn damerau_levenshtein(a: &str, b: &str) -> usize {
+    edit_distance(a, b, true, usize::MAX).unwrap()
+}
+
+/// Like [`damerau_levenshtein`], but gives up as soon as the distance is
+/// known to exceed `max`, returning `None`.
+pub fn damerau_levenshtein_bounded(a: &str, b: &str, max: usize) -> Option<usize> {
+    edit_distance(a, b, true, max)
+}
+
+fn edit_distance(a: &str, b: &str, transpositions: bool, max: usize) -> Option<usize> {
+    // A shared prefix or suffix never adds to the distance
+    let prefix = common_prefix_len(a, b);
+    let (a, b) = (&a[prefix..], &b[prefix..]);
+    let suffix = a
+        .chars()
+        .rev()
+        .zip(b.chars().rev())
+        .take_while(|(x, y)| x == y)
+        .map(|(x, _)| x.len_utf8())
+        .sum::<usize>();
+    let a: Vec<char> = a[..a.len() - suffix].chars().collect();
+    let b: Vec<char> = b[..b.len() - suffix].chars().collect();
+    if a.len().abs_diff(b.len()) > max {
+        return None;
+    }
+    if a.is_empty() || b.is_empty() {
+        return Some(a.len().max(b.len()));
+    }
+
+    // Rows of the edit matrix: the one before last, the last and the
+    // current one. Only transpositions look two rows back.
+    let mut before_last = vec![0; b.len() + 1];
+    let mut last: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+    for (i, &x) in a.iter().enumerate() {
+        current[0] = i + 1;
+        let mut row_min = current[0];
+        for (j, &y) in b.iter().enumerate() {
+            let cost = usize::from(x != y);
+            let mut distance = (last[j] + cost).min(last[j + 1] + 1).min(current[j] + 1);
+            if transpositions && i > 0 && j > 0 && x == b[j - 1] && a[i - 1] == y {
+                distance = distance.min(before_last[j - 1] + 1);
+            }
+            current[j + 1] = distance;
+            row_min = row_min.min(distance);
+        }
+        // Distances never decrease from one row to the next
+        if row_min > max {
+            return None;
+        }
+        core::mem::swap(&mut before_last, &mut last);
+        core::mem::swap(&mut last, &mut current);
+    }
+    let distance = last[b.len()];
+    (distance <= max).then_some(distance)
+}
+
+/// Returns the Jaro similarity of two strings, from 0 for no similarity to
+/// 1 for equal strings.
+pub fn jaro(a: &str, b: &str) -> f64 {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    if a.is_empty() && b.is_empty() {
+        return 1.0;
+    }
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+
+    // Characters match if they are equal and not too far apart
+    let window = (a.len().max(b.len()) / 2).saturating_sub(1);
+    let mut b_matched = vec![false; b.len()];
+    let mut a_matches = Vec::with_capacity(a.len().min(b.len()));
+    for (i, &x) in a.iter().enumerate() {
+        let start = i.saturating_sub(window);
+        let end = (i + window + 1).min(b.len());
+        if start >= end {
+            continue;
+        }
+        if let Some(j) = (start..end).find(|&j| !b_matched[j] && b[j] == x) {
+            b_matched[j] = true;
+            a_matches.push(x);
+        }
+    }
+    if a_matches.is_empty() {
+        return 0.0;
+    }
+
+    // Matched characters that appear in a different order
+    let b_matches = b
+        .iter()
+        .zip(&b_matched)
+        .filter(|(_, &matched)| matched)
+        .map(|(&y, _)| y);
+    let half_transpositions = a_matches
+        .iter()
+        .zip(b_matches)
+        .filter(|(&x, y)| x != *y)
+        .count();
+
+    let matches = a_matches.len() as f64;
+    let transpositions = (half_transpositions / 2) as f64;
+    (matches / a.len() as f64 + matches / b.len() as f64 + (matches - transpositions) / matches)
+        / 3.0
+}
+
+/// Returns the Jaro-Winkler similarity of two strings, from 0 for no
+/// similarity to 1 for equal strings.
+///
+/// It boosts the [`jaro`] similarity of strings sharing a prefix of up to
+/// four characters, which suits short strings such as names and terms
+/// with typos towards the end.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::strings::jaro_winkler;
+///
+/// assert!((jaro_winkler("martha", "marhta") - 0.961).abs() < 0.001);
+/// assert_eq!(jaro_winkler("pizza", "pizza"), 1.0);
+/// assert_eq!(jaro_winkler("abc", "xyz"), 0.0);
+/// ```
+pub fn jaro_winkler(a: &str, b: &str) -> f64 {
+    const PREFIX_SCALE: f64 = 0.1;
+    let jaro = jaro(a, b);
+    let prefix = a
+        .chars()
+        .zip(b.chars())
+        .take_while(|(x, y)| x == y)
+        .take(4)
+        .count();
+    jaro + prefix as f64 * PREFIX_SCALE * (1.0 - jaro)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -102,6 +711,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::needless_borrow)]
     fn test_remove_prefix_str() {
         let s = "hello world";
         let result = remove_prefix_str(&s, "hello ");
@@ -115,4 +725,196 @@ mod tests {
         let result = remove_prefix_str(&s, "");
         assert_eq!(result, "hello world");
     }
+
+    #[test]
+    fn test_humanize_number() {
+        assert_eq!(humanize_number(0), "0");
+        assert_eq!(humanize_number(999), "999");
+        assert_eq!(humanize_number(-999), "-999");
+        assert_eq!(humanize_number(1_000), "1K");
+        assert_eq!(humanize_number(1_049), "1K");
+        assert_eq!(humanize_number(1_050), "1.1K");
+        assert_eq!(humanize_number(123_456), "123.5K");
+        assert_eq!(humanize_number(999_949), "999.9K");
+        assert_eq!(humanize_number(999_950), "1M");
+        assert_eq!(humanize_number(1_234_567), "1.2M");
+        assert_eq!(humanize_number(7_000_000_000), "7B");
+        assert_eq!(humanize_number(2_500_000_000_000_000), "2.5P");
+        assert_eq!(humanize_number(i64::MAX), "9223.4P");
+        assert_eq!(humanize_number(i64::MIN), "-9223.4P");
+    }
+
+    #[test]
+    fn test_parse_human_number() {
+        assert_eq!(parse_human_number("42"), Ok(42));
+        assert_eq!(parse_human_number(" +42 "), Ok(42));
+        assert_eq!(parse_human_number("1K"), Ok(1_000));
+        assert_eq!(parse_human_number("1.2M"), Ok(1_200_000));
+        assert_eq!(parse_human_number("1.25 k"), Ok(1_250));
+        assert_eq!(parse_human_number("1.0005K"), Ok(1_001));
+        assert_eq!(parse_human_number("-1.0004K"), Ok(-1_000));
+        assert_eq!(parse_human_number("1_000_000"), Ok(1_000_000));
+        assert_eq!(parse_human_number("1'234"), Ok(1_234));
+        assert_eq!(parse_human_number("0.5"), Ok(1));
+
+        assert_eq!(parse_human_number(""), Err(ParseNumberError::Empty));
+        for invalid in ["K", "1.2X", ".5K", "1.K", ",100", "1.2.3", "--1", "1e3"] {
+            assert!(
+                matches!(
+                    parse_human_number(invalid),
+                    Err(ParseNumberError::Invalid(_))
+                ),
+                "{}",
+                invalid
+            );
+        }
+        assert!(matches!(
+            parse_human_number("9300P"),
+            Err(ParseNumberError::Overflow(_))
+        ));
+
+        for n in [0, 999, 1_200, -35_000, 4_100_000, 9_000_000_000] {
+            assert_eq!(parse_human_number(&humanize_number(n)), Ok(n));
+        }
+    }
+
+    #[test]
+    fn test_format_thousands() {
+        assert_eq!(format_thousands(0), "0");
+        assert_eq!(format_thousands(999), "999");
+        assert_eq!(format_thousands(1_000), "1,000");
+        assert_eq!(format_thousands(1_234_567), "1,234,567");
+        assert_eq!(format_thousands(-123_456), "-123,456");
+        assert_eq!(format_thousands(i64::MIN), "-9,223,372,036,854,775,808");
+        assert_eq!(format_thousands_with(1_234_567, '_'), "1_234_567");
+        assert_eq!(
+            format_thousands_with(1_234_567, '\u{202f}'),
+            "1\u{202f}234\u{202f}567"
+        );
+        assert_eq!(
+            parse_human_number(&format_thousands(-98_765_432)),
+            Ok(-98_765_432)
+        );
+    }
+
+    #[test]
+    fn test_width_ranges_are_sorted() {
+        for ranges in [WIDE_RANGES, ZERO_WIDTH_RANGES] {
+            assert!(ranges.iter().all(|(start, end)| start <= end));
+            assert!(ranges.windows(2).all(|w| w[0].1 < w[1].0));
+        }
+    }
+
+    #[test]
+    fn test_char_width() {
+        assert_eq!(char_width('a'), 1);
+        assert_eq!(char_width('é'), 1);
+        assert_eq!(char_width('\t'), 0);
+        assert_eq!(char_width('\u{301}'), 0);
+        assert_eq!(char_width('\u{200b}'), 0);
+        assert_eq!(char_width('中'), 2);
+        assert_eq!(char_width('ｱ'), 1);
+        assert_eq!(char_width('Ａ'), 2);
+        assert_eq!(char_width('한'), 2);
+        assert_eq!(char_width('🍕'), 2);
+        assert_eq!(char_width('\u{1f3fd}'), 0);
+        assert_eq!(char_width('\u{20000}'), 2);
+    }
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width(""), 0);
+        assert_eq!(display_width("hello, world"), 12);
+        assert_eq!(display_width("日本語テキスト"), 14);
+        assert_eq!(display_width("mixed 文字"), 10);
+        // Decomposed Hangul: an initial consonant followed by a vowel.
+        assert_eq!(display_width("\u{1100}\u{1161}"), 2);
+        // Thumbs up with a skin tone, and a family joined by ZWJs.
+        assert_eq!(display_width("👍\u{1f3fd}"), 2);
+        assert_eq!(display_width("👨\u{200d}👩\u{200d}👧\u{200d}👦"), 2);
+        // Heart with an emoji presentation selector.
+        assert_eq!(display_width("❤\u{fe0f}"), 1);
+        // Two flags, then a lone regional indicator.
+        assert_eq!(display_width("🇨🇳🇩🇪🇫"), 6);
+    }
+
+    #[test]
+    fn test_pad_to_width() {
+        assert_eq!(pad_to_width("", 3), "   ");
+        assert_eq!(pad_to_width("ab", 3), "ab ");
+        assert_eq!(pad_to_width("中文", 3), "中文");
+        assert_eq!(pad_to_width("中", 3), "中 ");
+        assert_eq!(pad_to_width("toolong", 3), "toolong");
+        let rows = ["id", "名字", "🍕"].map(|cell| pad_to_width(cell, 6));
+        assert!(rows.iter().all(|row| display_width(row) == 6));
+    }
+
+    #[test]
+    fn test_common_prefix_len() {
+        assert_eq!(common_prefix_len("", "abc"), 0);
+        assert_eq!(common_prefix_len("abc", "abc"), 3);
+        assert_eq!(common_prefix_len("abcd", "abc"), 3);
+        assert_eq!(common_prefix_len("搜索", "搜集"), "搜".len());
+        // Different characters sharing a leading byte are not a prefix.
+        assert_eq!(common_prefix_len("é", "è"), 0);
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        let cases = [
+            ("", "", 0),
+            ("abc", "", 3),
+            ("kitten", "sitting", 3),
+            ("flaw", "lawn", 2),
+            ("pizza", "pizza", 0),
+            ("ab", "ba", 2),
+            ("搜索引擎", "搜寻引擎", 1),
+            ("prefix-abc-suffix", "prefix-xbcy-suffix", 2),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(levenshtein(a, b), expected, "{} {}", a, b);
+            assert_eq!(levenshtein(b, a), expected, "{} {}", b, a);
+            assert_eq!(levenshtein_bounded(a, b, expected), Some(expected));
+            if expected > 0 {
+                assert_eq!(levenshtein_bounded(a, b, expected - 1), None);
+            }
+        }
+        assert_eq!(levenshtein_bounded("a", "abcdefgh", 2), None);
+    }
+
+    #[test]
+    fn test_damerau_levenshtein() {
+        let cases = [
+            ("", "", 0),
+            ("ab", "ba", 1),
+            ("abcdef", "badcfe", 3),
+            ("ca", "abc", 3),
+            ("kitten", "sitting", 3),
+            ("pizza", "pziza", 1),
+        ];
+        for (a, b, expected) in cases {
+            assert_eq!(damerau_levenshtein(a, b), expected, "{} {}", a, b);
+            assert_eq!(damerau_levenshtein(b, a), expected, "{} {}", b, a);
+            assert_eq!(damerau_levenshtein_bounded(a, b, expected), Some(expected));
+            if expected > 0 {
+                assert_eq!(damerau_levenshtein_bounded(a, b, expected - 1), None);
+            }
+        }
+    }
+
+    #[test]
+    fn test_jaro_winkler() {
+        let close = |actual: f64, expected: f64| (actual - expected).abs() < 1e-3;
+        assert!(close(jaro("martha", "marhta"), 0.944));
+        assert!(close(jaro("dixon", "dicksonx"), 0.767));
+        assert!(close(jaro_winkler("dixon", "dicksonx"), 0.813));
+        assert!(close(jaro_winkler("dwayne", "duane"), 0.840));
+        assert_eq!(jaro("", ""), 1.0);
+        assert_eq!(jaro("abc", ""), 0.0);
+        assert_eq!(jaro_winkler("a", "b"), 0.0);
+        assert_eq!(
+            jaro_winkler("crate", "trace"),
+            jaro_winkler("trace", "crate")
+        );
+    }
 }
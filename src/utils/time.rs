@@ -0,0 +1,827 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Time sources.
+//!
+//! Code that needs the current time should take a [`Clock`] rather than
+//! reading the system time directly, so it can run on targets without an
+//! operating system and be tested with a [`ManualClock`].
+//!
+//! Timestamps are exchanged as milliseconds since the Unix epoch.
+//! [`parse_datetime`] turns the textual forms found in documents into them,
+//! and [`format_datetime`] turns them back.
+
+use alloc::format;
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+use core::fmt;
+use core::sync::atomic::AtomicI64;
+use core::sync::atomic::Ordering;
+
+/// A source of the current time in milliseconds since the Unix epoch.
+pub trait Clock: Send + Sync {
+    fn now_millis(&self) -> i64;
+}
+
+/// The operating system clock, or the JavaScript clock on
+/// `wasm32-unknown-unknown` with the `js` feature.
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+    #[cfg(not(all(target_arch = "wasm32", target_os = "unknown", feature = "js")))]
+    fn now_millis(&self) -> i64 {
+        let now = std::time::SystemTime::now();
+        match now.duration_since(std::time::UNIX_EPOCH) {
+            Ok(elapsed) => elapsed.as_millis() as i64,
+            Err(before) => -(before.duration().as_millis() as i64),
+        }
+    }
+
+    // `SystemTime::now` panics on wasm32-unknown-unknown.
+    #[cfg(all(target_arch = "wasm32", target_os = "unknown", feature = "js"))]
+    fn now_millis(&self) -> i64 {
+        crate::js::now_millis()
+    }
+}
+
+/// A clock that only moves when told to.
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    millis: AtomicI64,
+}
+
+impl ManualClock {
+    pub fn new(millis: i64) -> Self {
+        Self {
+            millis: AtomicI64::new(millis),
+        }
+    }
+
+    pub fn set(&self, millis: i64) {
+        self.millis.store(millis, Ordering::SeqCst);
+    }
+
+    /// Moves the clock forward, or backward for a negative `millis`.
+    pub fn advance(&self, millis: i64) {
+        self.millis.fetch_add(millis, Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    fn now_millis(&self) -> i64 {
+        self.millis.load(Ordering::SeqCst)
+    }
+}
+
+impl<C: Clock + ?Sized> Clock for &C {
+    fn now_millis(&self) -> i64 {
+        (**self).now_millis()
+    }
+}
+
+/// The current time according to the system clock, or to the
+/// [`ManualClock`] of an active
+/// [`Determinism`](crate::testing::Determinism) guard.
+#[cfg(feature = "std")]
+pub fn now_millis() -> i64 {
+    #[cfg(feature = "testing")]
+    if let Some(millis) = crate::testing::determinism::now_millis() {
+        return millis;
+    }
+    SystemClock.now_millis()
+}
+
+/// A textual timestamp format accepted by [`parse_datetime`] and
+/// [`format_datetime`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format<'a> {
+    /// Milliseconds since the epoch, e.g. `1700000000000`.
+    EpochMillis,
+    /// Seconds since the epoch with up to three decimals, e.g.
+    /// `1700000000.25`.
+    EpochSeconds,
+    /// RFC 3339, e.g. `2023-11-14T22:13:20.5+01:00`.
+    Rfc3339,
+    /// `yyyy-MM-dd HH:mm:ss` in UTC.
+    DateTime,
+    /// A custom pattern, see [`parse_datetime`].
+    Pattern(&'a str),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DateTimeError {
+    /// The input matched none of the formats.
+    Unparseable(String),
+    /// A [`Format::Pattern`] uses unknown syntax.
+    InvalidPattern(String),
+    /// The timestamp falls outside of the years 0 to 9999, which the
+    /// format cannot represent.
+    OutOfRange(i64),
+}
+
+impl fmt::Display for DateTimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DateTimeError::Unparseable(s) => write!(f, "unable to parse date '{}'", s),
+            DateTimeError::InvalidPattern(p) => write!(f, "invalid date pattern '{}'", p),
+            DateTimeError::OutOfRange(millis) => {
+                write!(f, "timestamp {} is out of the formattable range", millis)
+            }
+        }
+    }
+}
+
+/// Parses a timestamp into milliseconds since the epoch, trying each format
+/// in order and returning the first match.
+///
+/// Custom patterns are strict: every field must have exactly the number of
+/// digits given in the pattern and the whole input must be consumed. Fields
+/// that are left out default to the start of their range and the time zone
+/// to UTC. The pattern letters are:
+///
+/// | Pattern | Meaning                                       |
+/// |---------|-----------------------------------------------|
+/// | `yyyy`  | year                                          |
+/// | `MM`    | month, `01`-`12`                              |
+/// | `dd`    | day of month                                  |
+/// | `HH`    | hour, `00`-`23`                               |
+/// | `mm`    | minute                                        |
+/// | `ss`    | second                                        |
+/// | `S`...  | fraction of a second, one digit per `S`       |
+/// | `XXX`   | offset as `Z` or `+hh:mm`                     |
+/// | `Z`     | offset as `+hhmm`                             |
+/// | `'...'` | literal text, `''` is a single quote          |
+///
+/// Any other letter is rejected, other characters match themselves.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::time::parse_datetime;
+/// use pizza_common::utils::time::Format;
+///
+/// let formats = [Format::Rfc3339, Format::Pattern("dd/MM/yyyy"), Format::EpochMillis];
+/// assert_eq!(parse_datetime("2023-11-14T22:13:20Z", &formats), Ok(1_700_000_000_000));
+/// assert_eq!(parse_datetime("14/11/2023", &formats), Ok(1_699_920_000_000));
+/// assert_eq!(parse_datetime("1700000000000", &formats), Ok(1_700_000_000_000));
+/// assert!(parse_datetime("yesterday", &formats).is_err());
+/// ```
+pub fn parse_datetime(input: &str, formats: &[Format]) -> Result<i64, DateTimeError> {
+    for format in formats {
+        let millis = match format {
+            Format::EpochMillis => parse_epoch(input, 0),
+            Format::EpochSeconds => parse_epoch(input, 3),
+            Format::Rfc3339 => parse_rfc3339(input),
+            Format::DateTime => {
+                parse_pattern(input, &DATE_TIME_PATTERN).and_then(|fields| fields.to_millis())
+            }
+            Format::Pattern(pattern) => {
+                let tokens = compile_pattern(pattern)
+                    .ok_or_else(|| DateTimeError::InvalidPattern(pattern.to_string()))?;
+                parse_pattern(input, &tokens).and_then(|fields| fields.to_millis())
+            }
+        };
+        if let Some(millis) = millis {
+            return Ok(millis);
+        }
+    }
+    Err(DateTimeError::Unparseable(input.to_string()))
+}
+
+/// Formats milliseconds since the epoch, the inverse of [`parse_datetime`].
+///
+/// Dates are written in UTC. [`Format::Rfc3339`] only includes milliseconds
+/// when they are not zero, and [`Format::EpochSeconds`] drops trailing
+/// zeros of the fraction.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::time::format_datetime;
+/// use pizza_common::utils::time::Format;
+///
+/// let millis = 1_700_000_000_500;
+/// assert_eq!(format_datetime(millis, Format::Rfc3339).unwrap(), "2023-11-14T22:13:20.500Z");
+/// assert_eq!(format_datetime(millis, Format::EpochSeconds).unwrap(), "1700000000.5");
+/// assert_eq!(format_datetime(millis, Format::Pattern("dd/MM/yyyy")).unwrap(), "14/11/2023");
+/// ```
+pub fn format_datetime(millis: i64, format: Format) -> Result<String, DateTimeError> {
+    let tokens = match format {
+        Format::EpochMillis => return Ok(millis.to_string()),
+        Format::EpochSeconds => return Ok(format_epoch_seconds(millis)),
+        Format::Rfc3339 => {
+            let fields = Fields::from_millis(millis)?;
+            let mut out = String::new();
+            fields.write(&mut out, &DATE_TIME_PATTERN[..5]);
+            out.push('T');
+            fields.write(&mut out, &DATE_TIME_PATTERN[6..]);
+            if fields.millis != 0 {
+                out.push('.');
+                fields.write(&mut out, &[Token::Fraction(3)]);
+            }
+            out.push('Z');
+            return Ok(out);
+        }
+        Format::DateTime => DATE_TIME_PATTERN.to_vec(),
+        Format::Pattern(pattern) => compile_pattern(pattern)
+            .ok_or_else(|| DateTimeError::InvalidPattern(pattern.to_string()))?,
+    };
+    let mut out = String::new();
+    Fields::from_millis(millis)?.write(&mut out, &tokens);
+    Ok(out)
+}
+
+fn format_epoch_seconds(millis: i64) -> String {
+    let sign = if millis < 0 { "-" } else { "" };
+    let millis = millis.unsigned_abs();
+    let (seconds, fraction) = (millis / 1_000, millis % 1_000);
+    if fraction == 0 {
+        return format!("{}{}", sign, seconds);
+    }
+    let fraction = format!("{:03}", fraction);
+    format!("{}{}.{}", sign, seconds, fraction.trim_end_matches('0'))
+}
+
+/// Days from 1970-01-01 to the given proleptic Gregorian date.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let month = month as i64;
+    let shifted_month = if month > 2 { month - 3 } else { month + 9 };
+    let day_of_year = (153 * shifted_month + 2) / 5 + day as i64 - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146_097 + day_of_era - 719_468
+}
+
+/// The proleptic Gregorian date `days` after 1970-01-01, the inverse of
+/// [`days_from_civil`].
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let days = days + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * shifted_month + 2) / 5 + 1) as u32;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// The broken-down fields of a parsed timestamp.
+struct Fields {
+    year: i64,
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+    millis: u32,
+    offset_minutes: i64,
+}
+
+impl Default for Fields {
+    fn default() -> Self {
+        Self {
+            year: 1970,
+            month: 1,
+            day: 1,
+            hour: 0,
+            minute: 0,
+            second: 0,
+            millis: 0,
+            offset_minutes: 0,
+        }
+    }
+}
+
+impl Fields {
+    fn to_millis(&self) -> Option<i64> {
+        let valid = (1..=12).contains(&self.month)
+            && (1..=days_in_month(self.year, self.month)).contains(&self.day)
+            && self.hour < 24
+            && self.minute < 60
+            && self.second < 60;
+        if !valid {
+            return None;
+        }
+        let days = days_from_civil(self.year, self.month, self.day);
+        let seconds =
+            days * 86_400 + self.hour as i64 * 3_600 + self.minute as i64 * 60 + self.second as i64
+                - self.offset_minutes * 60;
+        Some(seconds * 1_000 + self.millis as i64)
+    }
+
+    /// The UTC fields of a timestamp with a four digit year.
+    fn from_millis(millis: i64) -> Result<Self, DateTimeError> {
+        let seconds = millis.div_euclid(1_000);
+        let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+        if !(0..=9999).contains(&year) {
+            return Err(DateTimeError::OutOfRange(millis));
+        }
+        let second_of_day = seconds.rem_euclid(86_400) as u32;
+        Ok(Self {
+            year,
+            month,
+            day,
+            hour: second_of_day / 3_600,
+            minute: second_of_day / 60 % 60,
+            second: second_of_day % 60,
+            millis: millis.rem_euclid(1_000) as u32,
+            offset_minutes: 0,
+        })
+    }
+
+    /// Writes the fields as the pattern `tokens` describe, see
+    /// [`compile_pattern`].
+    fn write(&self, out: &mut String, tokens: &[Token]) {
+        for token in tokens {
+            match token {
+                Token::Year => out.push_str(&format!("{:04}", self.year)),
+                Token::Month => out.push_str(&format!("{:02}", self.month)),
+                Token::Day => out.push_str(&format!("{:02}", self.day)),
+                Token::Hour => out.push_str(&format!("{:02}", self.hour)),
+                Token::Minute => out.push_str(&format!("{:02}", self.minute)),
+                Token::Second => out.push_str(&format!("{:02}", self.second)),
+                Token::Fraction(count) => {
+                    let fraction = format!("{:03}", self.millis);
+                    out.push_str(&format!("{:0<count$.count$}", fraction, count = *count));
+                }
+                Token::OffsetColon => out.push('Z'),
+                Token::Offset => out.push_str("+0000"),
+                Token::Literal(text) => out.push_str(text),
+            }
+        }
+    }
+}
+
+struct Cursor<'a> {
+    input: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input: input.as_bytes(),
+            pos: 0,
+        }
+    }
+
+    fn is_done(&self) -> bool {
+        self.pos == self.input.len()
+    }
+
+    fn eat_any(&mut self, bytes: &[u8]) -> Option<u8> {
+        let byte = self.input.get(self.pos).filter(|b| bytes.contains(b))?;
+        self.pos += 1;
+        Some(*byte)
+    }
+
+    fn eat(&mut self, byte: u8) -> Option<()> {
+        self.eat_any(&[byte]).map(|_| ())
+    }
+
+    fn eat_str(&mut self, s: &str) -> Option<()> {
+        let end = self.pos + s.len();
+        (self.input.get(self.pos..end) == Some(s.as_bytes())).then(|| self.pos = end)
+    }
+
+    /// Reads exactly `count` digits.
+    fn digits(&mut self, count: usize) -> Option<u32> {
+        let digits = self.input.get(self.pos..self.pos + count)?;
+        if !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        self.pos += count;
+        Some(digits.iter().fold(0, |n, d| n * 10 + (d - b'0') as u32))
+    }
+
+    /// Reads exactly `count` fraction digits as milliseconds, truncating
+    /// anything finer.
+    fn fraction(&mut self, count: usize) -> Option<u32> {
+        let digits = self.input.get(self.pos..self.pos + count)?;
+        if count == 0 || !digits.iter().all(u8::is_ascii_digit) {
+            return None;
+        }
+        self.pos += count;
+        Some(
+            (0..3)
+                .map(|i| digits.get(i).map_or(0, |d| (d - b'0') as u32))
+                .fold(0, |n, d| n * 10 + d),
+        )
+    }
+
+    fn remaining_digits(&self) -> usize {
+        self.input[self.pos..]
+            .iter()
+            .take_while(|b| b.is_ascii_digit())
+            .count()
+    }
+
+    /// Reads `+hh:mm`, `-hh:mm` or, without `colon`, `+hhmm`.
+    fn offset(&mut self, colon: bool) -> Option<i64> {
+        let sign = if self.eat_any(b"+-")? == b'-' { -1 } else { 1 };
+        let hours = self.digits(2)?;
+        if colon {
+            self.eat(b':')?;
+        }
+        let minutes = self.digits(2)?;
+        (hours < 24 && minutes < 60).then(|| sign * (hours as i64 * 60 + minutes as i64))
+    }
+}
+
+fn parse_epoch(input: &str, decimals: usize) -> Option<i64> {
+    let (whole, fraction) = input.split_once('.').unwrap_or((input, ""));
+    let (negative, digits) = match whole.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, whole),
+    };
+    if digits.is_empty()
+        || !digits.bytes().all(|b| b.is_ascii_digit())
+        || !fraction.bytes().all(|b| b.is_ascii_digit())
+        || fraction.len() > decimals
+        || (input.contains('.') && fraction.is_empty())
+    {
+        return None;
+    }
+    let mut millis = digits
+        .parse::<i64>()
+        .ok()?
+        .checked_mul(10i64.pow(decimals as u32))?;
+    if !fraction.is_empty() {
+        let scale = 10i64.pow((decimals - fraction.len()) as u32);
+        millis = millis.checked_add(fraction.parse::<i64>().ok()? * scale)?;
+    }
+    Some(if negative { -millis } else { millis })
+}
+
+fn parse_rfc3339(input: &str) -> Option<i64> {
+    let mut cursor = Cursor::new(input);
+    let mut fields = Fields {
+        year: cursor.digits(4)? as i64,
+        ..Fields::default()
+    };
+    cursor.eat(b'-')?;
+    fields.month = cursor.digits(2)?;
+    cursor.eat(b'-')?;
+    fields.day = cursor.digits(2)?;
+    cursor.eat_any(b"Tt ")?;
+    fields.hour = cursor.digits(2)?;
+    cursor.eat(b':')?;
+    fields.minute = cursor.digits(2)?;
+    cursor.eat(b':')?;
+    fields.second = cursor.digits(2)?;
+    if cursor.eat(b'.').is_some() {
+        fields.millis = cursor.fraction(cursor.remaining_digits())?;
+    }
+    if cursor.eat_any(b"Zz").is_none() {
+        fields.offset_minutes = cursor.offset(true)?;
+    }
+    if !cursor.is_done() {
+        return None;
+    }
+    fields.to_millis()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token<'a> {
+    Year,
+    Month,
+    Day,
+    Hour,
+    Minute,
+    Second,
+    Fraction(usize),
+    OffsetColon,
+    Offset,
+    Literal(&'a str),
+}
+
+const DATE_TIME_PATTERN: [Token<'static>; 11] = [
+    Token::Year,
+    Token::Literal("-"),
+    Token::Month,
+    Token::Literal("-"),
+    Token::Day,
+    Token::Literal(" "),
+    Token::Hour,
+    Token::Literal(":"),
+    Token::Minute,
+    Token::Literal(":"),
+    Token::Second,
+];
+
+fn compile_pattern(pattern: &str) -> Option<Vec<Token<'_>>> {
+    let mut tokens = Vec::new();
+    let mut rest = pattern;
+    while let Some(c) = rest.chars().next() {
+        if c == '\'' {
+            // Quoted text runs to the next lone quote, `''` is a quote both
+            // inside and outside of it.
+            if let Some(after) = rest.strip_prefix("''") {
+                tokens.push(Token::Literal("'"));
+                rest = after;
+                continue;
+            }
+            rest = &rest[1..];
+            loop {
+                let close = rest.find('\'')?;
+                tokens.push(Token::Literal(&rest[..close]));
+                rest = &rest[close + 1..];
+                match rest.strip_prefix('\'') {
+                    Some(after) => {
+                        tokens.push(Token::Literal("'"));
+                        rest = after;
+                    }
+                    None => break,
+                }
+            }
+            continue;
+        }
+        let run = rest.len() - rest.trim_start_matches(c).len();
+        let (token, len) = match (c, run) {
+            ('y', 4) => (Token::Year, 4),
+            ('M', 2) => (Token::Month, 2),
+            ('d', 2) => (Token::Day, 2),
+            ('H', 2) => (Token::Hour, 2),
+            ('m', 2) => (Token::Minute, 2),
+            ('s', 2) => (Token::Second, 2),
+            ('S', n) => (Token::Fraction(n), n),
+            ('X', 3) => (Token::OffsetColon, 3),
+            ('Z', 1) => (Token::Offset, 1),
+            (c, _) if c.is_ascii_alphabetic() => return None,
+            (c, _) => (Token::Literal(&rest[..c.len_utf8()]), c.len_utf8()),
+        };
+        tokens.push(token);
+        rest = &rest[len..];
+    }
+    Some(tokens)
+}
+
+fn parse_pattern(input: &str, tokens: &[Token]) -> Option<Fields> {
+    let mut cursor = Cursor::new(input);
+    let mut fields = Fields::default();
+    for token in tokens {
+        match token {
+            Token::Year => fields.year = cursor.digits(4)? as i64,
+            Token::Month => fields.month = cursor.digits(2)?,
+            Token::Day => fields.day = cursor.digits(2)?,
+            Token::Hour => fields.hour = cursor.digits(2)?,
+            Token::Minute => fields.minute = cursor.digits(2)?,
+            Token::Second => fields.second = cursor.digits(2)?,
+            Token::Fraction(count) => fields.millis = cursor.fraction(*count)?,
+            Token::OffsetColon => {
+                if cursor.eat(b'Z').is_none() {
+                    fields.offset_minutes = cursor.offset(true)?;
+                }
+            }
+            Token::Offset => fields.offset_minutes = cursor.offset(false)?,
+            Token::Literal(text) => cursor.eat_str(text)?,
+        }
+    }
+    cursor.is_done().then_some(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(clock: impl Clock) -> i64 {
+        clock.now_millis()
+    }
+
+    #[test]
+    fn test_manual_clock() {
+        let clock = ManualClock::new(1_000);
+        assert_eq!(clock.now_millis(), 1_000);
+        clock.advance(250);
+        assert_eq!(read(&clock), 1_250);
+        clock.advance(-1_250);
+        assert_eq!(clock.now_millis(), 0);
+        clock.set(42);
+        assert_eq!(clock.now_millis(), 42);
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_system_clock() {
+        // 2020-01-01T00:00:00Z
+        assert!(SystemClock.now_millis() > 1_577_836_800_000);
+    }
+
+    #[test]
+    fn test_days_from_civil() {
+        assert_eq!(days_from_civil(1970, 1, 1), 0);
+        assert_eq!(days_from_civil(2000, 3, 1), 11_017);
+        assert_eq!(days_from_civil(1969, 12, 31), -1);
+        assert_eq!(days_from_civil(1600, 2, 29), -135_081);
+    }
+
+    #[test]
+    fn test_parse_epoch() {
+        let millis = [Format::EpochMillis];
+        let seconds = [Format::EpochSeconds];
+        assert_eq!(
+            parse_datetime("1700000000123", &millis),
+            Ok(1_700_000_000_123)
+        );
+        assert_eq!(parse_datetime("-1000", &millis), Ok(-1_000));
+        assert!(parse_datetime("1.5", &millis).is_err());
+        assert_eq!(
+            parse_datetime("1700000000", &seconds),
+            Ok(1_700_000_000_000)
+        );
+        assert_eq!(
+            parse_datetime("1700000000.25", &seconds),
+            Ok(1_700_000_000_250)
+        );
+        assert_eq!(parse_datetime("-1.5", &seconds), Ok(-1_500));
+        for invalid in ["", "-", "1.", "1.2345", "+1", "1e3", "99999999999999999999"] {
+            assert!(parse_datetime(invalid, &seconds).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_parse_rfc3339() {
+        let formats = [Format::Rfc3339];
+        let parse = |s| parse_datetime(s, &formats);
+        assert_eq!(parse("1970-01-01T00:00:00Z"), Ok(0));
+        assert_eq!(parse("2023-11-14T22:13:20Z"), Ok(1_700_000_000_000));
+        assert_eq!(parse("2023-11-14t22:13:20.123456z"), Ok(1_700_000_000_123));
+        assert_eq!(parse("2023-11-14 23:13:20.5+01:00"), Ok(1_700_000_000_500));
+        assert_eq!(parse("2023-11-14T16:43:20-05:30"), Ok(1_700_000_000_000));
+        assert_eq!(parse("1969-12-31T23:59:59.9Z"), Ok(-100));
+        assert_eq!(parse("2024-02-29T00:00:00Z"), Ok(1_709_164_800_000));
+        for invalid in [
+            "2023-02-29T00:00:00Z",
+            "2023-11-14T24:00:00Z",
+            "2023-11-14T22:13:20",
+            "2023-11-14T22:13:20.Z",
+            "2023-11-14T22:13:20+0100",
+            "2023-1-14T22:13:20Z",
+            "2023-11-14",
+        ] {
+            assert!(parse(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_parse_patterns() {
+        assert_eq!(
+            parse_datetime("2023-11-14 22:13:20", &[Format::DateTime]),
+            Ok(1_700_000_000_000)
+        );
+        assert!(parse_datetime("2023-11-14T22:13:20", &[Format::DateTime]).is_err());
+
+        let parse = |input, pattern| parse_datetime(input, &[Format::Pattern(pattern)]);
+        assert_eq!(parse("20231114", "yyyyMMdd"), Ok(1_699_920_000_000));
+        assert_eq!(
+            parse("14.11.2023 22:13:20,042 +0100", "dd.MM.yyyy HH:mm:ss,SSS Z"),
+            Ok(1_699_996_400_042)
+        );
+        assert_eq!(
+            parse("2023-11-14T22:13Z", "yyyy-MM-dd'T'HH:mmXXX"),
+            Ok(1_699_999_980_000)
+        );
+        assert_eq!(parse("22 o'clock", "HH 'o''clock'"), Ok(79_200_000));
+        assert_eq!(parse("22'", "HH''"), Ok(79_200_000));
+        assert!(parse("2023-11-14", "yyyyMMdd").is_err());
+        assert!(parse("2023111", "yyyyMMdd").is_err());
+        assert!(parse("20231114 ", "yyyyMMdd").is_err());
+        assert!(parse("20231131", "yyyyMMdd").is_err());
+
+        for invalid in ["yy-MM-dd", "yyyy-M-d", "EEE, dd MMM yyyy", "'unterminated"] {
+            assert_eq!(
+                parse("2023-11-14", invalid),
+                Err(DateTimeError::InvalidPattern(invalid.to_string()))
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_datetime_order() {
+        let formats = [Format::EpochSeconds, Format::EpochMillis];
+        assert_eq!(
+            parse_datetime("1700000000", &formats),
+            Ok(1_700_000_000_000)
+        );
+        let formats = [Format::EpochMillis, Format::EpochSeconds];
+        assert_eq!(parse_datetime("1700000000", &formats), Ok(1_700_000_000));
+        assert_eq!(
+            parse_datetime("now", &formats),
+            Err(DateTimeError::Unparseable("now".to_string()))
+        );
+        assert_eq!(
+            parse_datetime("now", &[]),
+            Err(DateTimeError::Unparseable("now".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_civil_from_days() {
+        assert_eq!(civil_from_days(0), (1970, 1, 1));
+        assert_eq!(civil_from_days(-1), (1969, 12, 31));
+        assert_eq!(civil_from_days(11_016), (2000, 2, 29));
+        for days in (-800_000..800_000).step_by(97) {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn test_format_datetime() {
+        let millis = 1_700_000_000_000;
+        let format = |format| format_datetime(millis, format).unwrap();
+        assert_eq!(format(Format::EpochMillis), "1700000000000");
+        assert_eq!(format(Format::EpochSeconds), "1700000000");
+        assert_eq!(format(Format::Rfc3339), "2023-11-14T22:13:20Z");
+        assert_eq!(format(Format::DateTime), "2023-11-14 22:13:20");
+        assert_eq!(
+            format(Format::Pattern("yyyy-MM-dd'T'HH:mm:ss.SSSSZ")),
+            "2023-11-14T22:13:20.0000+0000"
+        );
+
+        assert_eq!(
+            format_datetime(-1_500, Format::EpochSeconds).unwrap(),
+            "-1.5"
+        );
+        assert_eq!(
+            format_datetime(-1, Format::Rfc3339).unwrap(),
+            "1969-12-31T23:59:59.999Z"
+        );
+        assert_eq!(
+            format_datetime(0, Format::Pattern("HH:mm:ss.S XXX")).unwrap(),
+            "00:00:00.0 Z"
+        );
+        assert_eq!(
+            format_datetime(0, Format::Pattern("yyyy-MM-dd hh")),
+            Err(DateTimeError::InvalidPattern("yyyy-MM-dd hh".to_string()))
+        );
+        assert_eq!(
+            format_datetime(i64::MAX, Format::Rfc3339),
+            Err(DateTimeError::OutOfRange(i64::MAX))
+        );
+    }
+
+    #[test]
+    fn test_format_parse_roundtrip() {
+        let formats = [
+            Format::EpochMillis,
+            Format::EpochSeconds,
+            Format::Rfc3339,
+            Format::Pattern("yyyyMMddHHmmssSSS"),
+        ];
+        for millis in [
+            0,
+            1,
+            -1,
+            951_782_400_123,
+            1_700_000_000_500,
+            253_402_300_799_999,
+        ] {
+            for format in formats {
+                let text = format_datetime(millis, format).unwrap();
+                assert_eq!(parse_datetime(&text, &[format]), Ok(millis), "{}", text);
+            }
+        }
+    }
+}
@@ -0,0 +1,687 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Byte sizes and durations written the way people write them in
+//! configuration files, such as `"512mb"`, `"1.5gb"`, `"30s"` or `"5m"`.
+//!
+//! Both types serialize as strings in human-readable formats and as plain
+//! numbers, bytes and milliseconds, otherwise. Deserializing accepts either
+//! form.
+
+use core::fmt;
+use core::iter::Sum;
+use core::ops::Add;
+use core::ops::AddAssign;
+use core::ops::Div;
+use core::ops::Mul;
+use core::ops::Sub;
+use core::ops::SubAssign;
+use core::str::FromStr;
+use serde::de;
+use serde::Deserialize;
+use serde::Deserializer;
+use serde::Serialize;
+use serde::Serializer;
+
+/// Why a [`ByteSize`] or [`Duration`] could not be parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseUnitError {
+    /// The value does not start with a non-negative decimal number.
+    InvalidNumber,
+    /// The unit after the number is not known.
+    UnknownUnit,
+    /// The value does not fit the type.
+    Overflow,
+}
+
+impl fmt::Display for ParseUnitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseUnitError::InvalidNumber => write!(f, "expected a non-negative number"),
+            ParseUnitError::UnknownUnit => write!(f, "unknown unit"),
+            ParseUnitError::Overflow => write!(f, "value is too large"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ParseUnitError {}
+
+/// Splits `"1.5 gb"` into the amount and the lowercase unit, and scales the
+/// amount by the multiplier of the unit.
+fn parse_scaled(s: &str, multiplier: impl Fn(&str) -> Option<u64>) -> Result<u128, ParseUnitError> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    if number.is_empty() {
+        return Err(ParseUnitError::InvalidNumber);
+    }
+    let unit = unit.trim();
+    let scale = if unit.bytes().any(|b| b.is_ascii_uppercase()) {
+        multiplier(&unit.to_ascii_lowercase())
+    } else {
+        multiplier(unit)
+    }
+    .ok_or(ParseUnitError::UnknownUnit)?;
+
+    if let Ok(whole) = number.parse::<u64>() {
+        return Ok(whole as u128 * scale as u128);
+    }
+    let amount: f64 = number.parse().map_err(|_| ParseUnitError::InvalidNumber)?;
+    let value = libm::round(amount * scale as f64);
+    if value >= u128::MAX as f64 {
+        return Err(ParseUnitError::Overflow);
+    }
+    Ok(value as u128)
+}
+
+/// Writes `value` in the largest unit that represents it exactly.
+fn fmt_scaled(f: &mut fmt::Formatter<'_>, value: u128, units: &[(&str, u64)]) -> fmt::Result {
+    let (name, scale) = units
+        .iter()
+        .find(|(_, scale)| value.is_multiple_of(*scale as u128))
+        .unwrap_or(&units[units.len() - 1]);
+    write!(f, "{}{}", value / *scale as u128, name)
+}
+
+const KB: u64 = 1 << 10;
+const MB: u64 = 1 << 20;
+const GB: u64 = 1 << 30;
+const TB: u64 = 1 << 40;
+const PB: u64 = 1 << 50;
+
+/// Largest first, for [`fmt_scaled`].
+const BYTE_UNITS: [(&str, u64); 6] = [
+    ("pb", PB),
+    ("tb", TB),
+    ("gb", GB),
+    ("mb", MB),
+    ("kb", KB),
+    ("b", 1),
+];
+
+/// A number of bytes.
+///
+/// Units are binary multiples regardless of spelling, so `kb`, `k` and
+/// `kib` all mean 1024 bytes. A bare number is bytes. `Display` uses the
+/// largest unit that is exact, so it parses back to the same value.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::units::ByteSize;
+///
+/// let size: ByteSize = "1.5gb".parse().unwrap();
+/// assert_eq!(size, ByteSize::mb(1536));
+/// assert_eq!(size.to_string(), "1536mb");
+/// assert_eq!(size + ByteSize::kb(1), ByteSize::b(1_610_613_760));
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ByteSize(pub u64);
+
+impl ByteSize {
+    pub const ZERO: ByteSize = ByteSize(0);
+
+    pub const fn b(bytes: u64) -> Self {
+        Self(bytes)
+    }
+
+    pub const fn kb(n: u64) -> Self {
+        Self(n * KB)
+    }
+
+    pub const fn mb(n: u64) -> Self {
+        Self(n * MB)
+    }
+
+    pub const fn gb(n: u64) -> Self {
+        Self(n * GB)
+    }
+
+    pub const fn tb(n: u64) -> Self {
+        Self(n * TB)
+    }
+
+    pub const fn as_u64(self) -> u64 {
+        self.0
+    }
+
+    /// The size as `usize`, saturating on 32-bit targets.
+    pub fn as_usize(self) -> usize {
+        usize::try_from(self.0).unwrap_or(usize::MAX)
+    }
+
+    pub fn checked_add(self, rhs: ByteSize) -> Option<ByteSize> {
+        self.0.checked_add(rhs.0).map(ByteSize)
+    }
+
+    pub fn checked_sub(self, rhs: ByteSize) -> Option<ByteSize> {
+        self.0.checked_sub(rhs.0).map(ByteSize)
+    }
+
+    pub fn saturating_sub(self, rhs: ByteSize) -> ByteSize {
+        ByteSize(self.0.saturating_sub(rhs.0))
+    }
+}
+
+impl FromStr for ByteSize {
+    type Err = ParseUnitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = parse_scaled(s, |unit| match unit {
+            "" | "b" => Some(1),
+            "k" | "kb" | "kib" => Some(KB),
+            "m" | "mb" | "mib" => Some(MB),
+            "g" | "gb" | "gib" => Some(GB),
+            "t" | "tb" | "tib" => Some(TB),
+            "p" | "pb" | "pib" => Some(PB),
+            _ => None,
+        })?;
+        u64::try_from(bytes)
+            .map(ByteSize)
+            .map_err(|_| ParseUnitError::Overflow)
+    }
+}
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 == 0 {
+            return f.write_str("0b");
+        }
+        fmt_scaled(f, self.0 as u128, &BYTE_UNITS)
+    }
+}
+
+impl From<usize> for ByteSize {
+    fn from(bytes: usize) -> Self {
+        ByteSize(bytes as u64)
+    }
+}
+
+impl From<ByteSize> for u64 {
+    fn from(size: ByteSize) -> Self {
+        size.0
+    }
+}
+
+impl Add for ByteSize {
+    type Output = ByteSize;
+
+    fn add(self, rhs: ByteSize) -> ByteSize {
+        ByteSize(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for ByteSize {
+    fn add_assign(&mut self, rhs: ByteSize) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sub for ByteSize {
+    type Output = ByteSize;
+
+    fn sub(self, rhs: ByteSize) -> ByteSize {
+        ByteSize(self.0 - rhs.0)
+    }
+}
+
+impl SubAssign for ByteSize {
+    fn sub_assign(&mut self, rhs: ByteSize) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Mul<u64> for ByteSize {
+    type Output = ByteSize;
+
+    fn mul(self, rhs: u64) -> ByteSize {
+        ByteSize(self.0 * rhs)
+    }
+}
+
+impl Div<u64> for ByteSize {
+    type Output = ByteSize;
+
+    fn div(self, rhs: u64) -> ByteSize {
+        ByteSize(self.0 / rhs)
+    }
+}
+
+impl Sum for ByteSize {
+    fn sum<I: Iterator<Item = ByteSize>>(iter: I) -> Self {
+        iter.fold(ByteSize::ZERO, Add::add)
+    }
+}
+
+impl Serialize for ByteSize {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_u64(self.0)
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for ByteSize {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let visitor = UnitVisitor {
+            expecting: "a byte size such as 1024 or \"512mb\"",
+            from_u64: |n| Some(ByteSize(n)),
+        };
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(visitor)
+        } else {
+            deserializer.deserialize_u64(visitor)
+        }
+    }
+}
+
+/// Accepts a number, converted by `from_u64`, or a string parsed with
+/// `FromStr`.
+struct UnitVisitor<T> {
+    expecting: &'static str,
+    from_u64: fn(u64) -> Option<T>,
+}
+
+impl<T> de::Visitor<'_> for UnitVisitor<T>
+where
+    T: FromStr<Err = ParseUnitError>,
+{
+    type Value = T;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.expecting)
+    }
+
+    fn visit_u64<E: de::Error>(self, v: u64) -> Result<T, E> {
+        (self.from_u64)(v).ok_or_else(|| E::custom(ParseUnitError::Overflow))
+    }
+
+    fn visit_i64<E: de::Error>(self, v: i64) -> Result<T, E> {
+        let v = u64::try_from(v).map_err(|_| E::custom(ParseUnitError::InvalidNumber))?;
+        self.visit_u64(v)
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<T, E> {
+        v.parse().map_err(E::custom)
+    }
+}
+
+const NANOS_PER_MICRO: u64 = 1_000;
+const NANOS_PER_MILLI: u64 = 1_000_000;
+const NANOS_PER_SECOND: u64 = 1_000_000_000;
+const NANOS_PER_MINUTE: u64 = 60 * NANOS_PER_SECOND;
+const NANOS_PER_HOUR: u64 = 60 * NANOS_PER_MINUTE;
+const NANOS_PER_DAY: u64 = 24 * NANOS_PER_HOUR;
+
+/// Largest first, for [`fmt_scaled`].
+const DURATION_UNITS: [(&str, u64); 7] = [
+    ("d", NANOS_PER_DAY),
+    ("h", NANOS_PER_HOUR),
+    ("m", NANOS_PER_MINUTE),
+    ("s", NANOS_PER_SECOND),
+    ("ms", NANOS_PER_MILLI),
+    ("us", NANOS_PER_MICRO),
+    ("ns", 1),
+];
+
+/// A [`core::time::Duration`] that parses from and displays as `"30s"`,
+/// `"5m"`, `"1.5h"` and the like.
+///
+/// Units are `ns`, `us` (or `µs`), `ms`, `s`, `m` (or `min`), `h` and `d`;
+/// a bare number is milliseconds.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::units::Duration;
+///
+/// let timeout: Duration = "1.5m".parse().unwrap();
+/// assert_eq!(timeout.as_std(), std::time::Duration::from_secs(90));
+/// assert_eq!(timeout.to_string(), "90s");
+/// assert_eq!((timeout * 2).to_string(), "3m");
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Duration(pub core::time::Duration);
+
+impl Duration {
+    pub const ZERO: Duration = Duration(core::time::Duration::ZERO);
+
+    pub const fn from_millis(millis: u64) -> Self {
+        Self(core::time::Duration::from_millis(millis))
+    }
+
+    pub const fn from_secs(secs: u64) -> Self {
+        Self(core::time::Duration::from_secs(secs))
+    }
+
+    pub const fn as_std(self) -> core::time::Duration {
+        self.0
+    }
+
+    /// Whole milliseconds, saturating at `u64::MAX`.
+    pub fn as_millis(self) -> u64 {
+        u64::try_from(self.0.as_millis()).unwrap_or(u64::MAX)
+    }
+
+    pub fn checked_add(self, rhs: Duration) -> Option<Duration> {
+        self.0.checked_add(rhs.0).map(Duration)
+    }
+
+    pub fn checked_sub(self, rhs: Duration) -> Option<Duration> {
+        self.0.checked_sub(rhs.0).map(Duration)
+    }
+
+    pub fn saturating_sub(self, rhs: Duration) -> Duration {
+        Duration(self.0.saturating_sub(rhs.0))
+    }
+
+    fn from_nanos_u128(nanos: u128) -> Option<Self> {
+        let secs = u64::try_from(nanos / NANOS_PER_SECOND as u128).ok()?;
+        let subsec = (nanos % NANOS_PER_SECOND as u128) as u32;
+        Some(Self(core::time::Duration::new(secs, subsec)))
+    }
+}
+
+impl FromStr for Duration {
+    type Err = ParseUnitError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let nanos = parse_scaled(s, |unit| match unit {
+            "ns" => Some(1),
+            "us" | "µs" => Some(NANOS_PER_MICRO),
+            "" | "ms" => Some(NANOS_PER_MILLI),
+            "s" | "sec" => Some(NANOS_PER_SECOND),
+            "m" | "min" => Some(NANOS_PER_MINUTE),
+            "h" => Some(NANOS_PER_HOUR),
+            "d" => Some(NANOS_PER_DAY),
+            _ => None,
+        })?;
+        Duration::from_nanos_u128(nanos).ok_or(ParseUnitError::Overflow)
+    }
+}
+
+impl fmt::Display for Duration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0.is_zero() {
+            return f.write_str("0s");
+        }
+        fmt_scaled(f, self.0.as_nanos(), &DURATION_UNITS)
+    }
+}
+
+impl From<core::time::Duration> for Duration {
+    fn from(duration: core::time::Duration) -> Self {
+        Duration(duration)
+    }
+}
+
+impl From<Duration> for core::time::Duration {
+    fn from(duration: Duration) -> Self {
+        duration.0
+    }
+}
+
+impl Add for Duration {
+    type Output = Duration;
+
+    fn add(self, rhs: Duration) -> Duration {
+        Duration(self.0 + rhs.0)
+    }
+}
+
+impl AddAssign for Duration {
+    fn add_assign(&mut self, rhs: Duration) {
+        self.0 += rhs.0;
+    }
+}
+
+impl Sub for Duration {
+    type Output = Duration;
+
+    fn sub(self, rhs: Duration) -> Duration {
+        Duration(self.0 - rhs.0)
+    }
+}
+
+impl SubAssign for Duration {
+    fn sub_assign(&mut self, rhs: Duration) {
+        self.0 -= rhs.0;
+    }
+}
+
+impl Mul<u32> for Duration {
+    type Output = Duration;
+
+    fn mul(self, rhs: u32) -> Duration {
+        Duration(self.0 * rhs)
+    }
+}
+
+impl Div<u32> for Duration {
+    type Output = Duration;
+
+    fn div(self, rhs: u32) -> Duration {
+        Duration(self.0 / rhs)
+    }
+}
+
+impl Sum for Duration {
+    fn sum<I: Iterator<Item = Duration>>(iter: I) -> Self {
+        iter.fold(Duration::ZERO, Add::add)
+    }
+}
+
+impl Serialize for Duration {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_str(self)
+        } else {
+            serializer.serialize_u64(self.as_millis())
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Duration {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let visitor = UnitVisitor {
+            expecting: "a duration in milliseconds or a string such as \"30s\"",
+            from_u64: |n| Some(Duration::from_millis(n)),
+        };
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_any(visitor)
+        } else {
+            deserializer.deserialize_u64(visitor)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    #[test]
+    fn test_parse_byte_size() {
+        let cases = [
+            ("0", 0),
+            ("17", 17),
+            ("17b", 17),
+            ("512mb", 512 << 20),
+            ("512 MB", 512 << 20),
+            ("1.5gb", 3 << 29),
+            ("2k", 2048),
+            ("2KiB", 2048),
+            (" 1tb ", 1 << 40),
+            ("0.5b", 1),
+            ("16pb", 16 << 50),
+        ];
+        for (input, bytes) in cases {
+            assert_eq!(input.parse(), Ok(ByteSize(bytes)), "{}", input);
+        }
+        assert_eq!("".parse::<ByteSize>(), Err(ParseUnitError::InvalidNumber));
+        assert_eq!("mb".parse::<ByteSize>(), Err(ParseUnitError::InvalidNumber));
+        assert_eq!(
+            "-1mb".parse::<ByteSize>(),
+            Err(ParseUnitError::InvalidNumber)
+        );
+        assert_eq!(
+            "1.2.3mb".parse::<ByteSize>(),
+            Err(ParseUnitError::InvalidNumber)
+        );
+        assert_eq!(
+            "12 apples".parse::<ByteSize>(),
+            Err(ParseUnitError::UnknownUnit)
+        );
+        assert_eq!("16384pb".parse::<ByteSize>(), Err(ParseUnitError::Overflow));
+    }
+
+    #[test]
+    fn test_display_byte_size() {
+        for (size, text) in [
+            (ByteSize(0), "0b"),
+            (ByteSize(1000), "1000b"),
+            (ByteSize::kb(1), "1kb"),
+            (ByteSize::kb(1536), "1536kb"),
+            (ByteSize::gb(2048), "2tb"),
+            (ByteSize(u64::MAX), "18446744073709551615b"),
+        ] {
+            assert_eq!(size.to_string(), text);
+            assert_eq!(text.parse(), Ok(size));
+        }
+    }
+
+    #[test]
+    fn test_byte_size_arithmetic() {
+        let mut size = ByteSize::mb(1) + ByteSize::kb(512);
+        assert_eq!(size, ByteSize::kb(1536));
+        size -= ByteSize::kb(512);
+        size += ByteSize::kb(1);
+        assert_eq!(size * 2 / 2, ByteSize::kb(1025));
+        assert_eq!(ByteSize::mb(1) - ByteSize::kb(1), ByteSize::kb(1023));
+        assert_eq!(
+            [ByteSize::kb(1), ByteSize::kb(2)]
+                .into_iter()
+                .sum::<ByteSize>(),
+            ByteSize::kb(3)
+        );
+        assert_eq!(ByteSize(1).checked_sub(ByteSize(2)), None);
+        assert_eq!(ByteSize(1).saturating_sub(ByteSize(2)), ByteSize::ZERO);
+        assert_eq!(ByteSize(u64::MAX).checked_add(ByteSize(1)), None);
+        assert_eq!(ByteSize::from(7usize).as_usize(), 7);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        let ms = Duration::from_millis;
+        let cases = [
+            ("30s", ms(30_000)),
+            ("5m", ms(300_000)),
+            ("5min", ms(300_000)),
+            ("1.5h", ms(5_400_000)),
+            ("2d", ms(172_800_000)),
+            ("250", ms(250)),
+            ("250ms", ms(250)),
+            ("0.5ms", Duration(core::time::Duration::from_micros(500))),
+            ("7us", Duration(core::time::Duration::from_micros(7))),
+            ("7µs", Duration(core::time::Duration::from_micros(7))),
+            ("3NS", Duration(core::time::Duration::from_nanos(3))),
+        ];
+        for (input, duration) in cases {
+            assert_eq!(input.parse(), Ok(duration), "{}", input);
+        }
+        assert_eq!("s".parse::<Duration>(), Err(ParseUnitError::InvalidNumber));
+        assert_eq!(
+            "3 weeks".parse::<Duration>(),
+            Err(ParseUnitError::UnknownUnit)
+        );
+        assert_eq!(
+            "1e30d".parse::<Duration>(),
+            Err(ParseUnitError::UnknownUnit)
+        );
+        assert_eq!(
+            "999999999999999999999d".parse::<Duration>(),
+            Err(ParseUnitError::Overflow)
+        );
+    }
+
+    #[test]
+    fn test_display_duration() {
+        for (duration, text) in [
+            (Duration::ZERO, "0s"),
+            (Duration::from_secs(90), "90s"),
+            (Duration::from_secs(3600), "1h"),
+            (Duration::from_millis(1500), "1500ms"),
+            (Duration(core::time::Duration::from_nanos(1001)), "1001ns"),
+            (Duration::from_secs(86_400 * 3), "3d"),
+        ] {
+            assert_eq!(duration.to_string(), text);
+            assert_eq!(text.parse(), Ok(duration));
+        }
+    }
+
+    #[test]
+    fn test_duration_arithmetic() {
+        let mut d = Duration::from_secs(1) + Duration::from_millis(500);
+        d -= Duration::from_millis(250);
+        d += Duration::from_millis(750);
+        assert_eq!(d, Duration::from_secs(2));
+        assert_eq!(d * 3 / 2, Duration::from_secs(3));
+        assert_eq!(d - Duration::from_secs(1), Duration::from_secs(1));
+        assert_eq!(Duration::ZERO.checked_sub(d), None);
+        assert_eq!(Duration::ZERO.saturating_sub(d), Duration::ZERO);
+        assert_eq!([d, d].into_iter().sum::<Duration>(), Duration::from_secs(4));
+        assert_eq!(d.as_millis(), 2000);
+    }
+
+    #[test]
+    fn test_serde_forms() {
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Config {
+            cache: ByteSize,
+            timeout: Duration,
+        }
+
+        let config: Config =
+            serde_json::from_str(r#"{"cache": "512mb", "timeout": "30s"}"#).unwrap();
+        assert_eq!(
+            config,
+            Config {
+                cache: ByteSize::mb(512),
+                timeout: Duration::from_secs(30)
+            }
+        );
+        let numbers: Config = serde_json::from_str(r#"{"cache": 1024, "timeout": 1500}"#).unwrap();
+        assert_eq!(numbers.cache, ByteSize::kb(1));
+        assert_eq!(numbers.timeout, Duration::from_millis(1500));
+        assert_eq!(
+            serde_json::to_string(&config).unwrap(),
+            r#"{"cache":"512mb","timeout":"30s"}"#
+        );
+        assert!(serde_json::from_str::<ByteSize>("-1").is_err());
+        assert!(serde_json::from_str::<ByteSize>(r#""1 parsec""#).is_err());
+    }
+}
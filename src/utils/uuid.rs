@@ -33,6 +33,7 @@
 //!
 //! This module is adapted from the original project <https://github.com/uuid-rs/uuid>.
 
+use crate::error::Error;
 use alloc::string::String;
 use core::fmt;
 use core::str::from_utf8_unchecked;
@@ -40,20 +41,15 @@ use core::str::FromStr;
 use serde::Deserialize;
 use serde::Serialize;
 
-#[derive(Debug)]
-pub struct ParseError {
-    pub message: String,
-}
-
-impl fmt::Display for ParseError {
-    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "invalid format: {}", self.message)
-    }
-}
-
 const UUID_LEN: usize = 10;
 const ASCII_LEN: usize = UUID_LEN * 2;
 
+/// Version nibble of sortable ids, in the same position as in UUIDv7. The
+/// first 10 bytes of a version 4 UUID carry a 4 there.
+const SORTABLE_VERSION: u8 = 7;
+/// Largest timestamp that fits the 48 leading bits of a sortable id.
+const MAX_SORTABLE_MILLIS: u64 = (1 << 48) - 1;
+
 /// Adapted from: https://github.com/uuid-rs/uuid/blob/fe11291/src/fmt.rs#L152
 ///
 /// Map numeric values of hex characters into their characters, and split 1 byte
@@ -96,7 +92,7 @@ const fn encode(src: &[u8; UUID_LEN]) -> [u8; ASCII_LEN] {
 ///
 /// `s` should have length 20, and the bytes should be numeric values of lowercase
 /// hex characters '0' - '9', 'a' - 'f', or an error would be returned.
-fn decode(s: &[u8]) -> Result<[u8; UUID_LEN], ParseError> {
+fn decode(s: &[u8]) -> Result<[u8; UUID_LEN], Error> {
     const HEX_TABLE: [u8; 256] = {
         let mut buf = [0u8; 256];
         let mut i = 0u8;
@@ -118,13 +114,11 @@ fn decode(s: &[u8]) -> Result<[u8; UUID_LEN], ParseError> {
 
     // This length check here removes all subsequent bounds checks.
     if s.len() != Uuid::LENGTH {
-        return Err(ParseError {
-            message: alloc::format!(
-                "Invalid UUID length, expected: {}, found: {}",
-                Uuid::LENGTH,
-                s.len()
-            ),
-        });
+        return Err(Error::Parse(alloc::format!(
+            "Invalid UUID length, expected: {}, found: {}",
+            Uuid::LENGTH,
+            s.len()
+        )));
     }
 
     let mut buf = [0u8; UUID_LEN];
@@ -138,13 +132,11 @@ fn decode(s: &[u8]) -> Result<[u8; UUID_LEN], ParseError> {
         // We use `0xff` as a sentinel value to indicate an invalid hex
         // character sequence (like the letter `g`)
         if h1 | h2 == 0xff {
-            return Err(ParseError {
-                message: alloc::format!(
-                    "invalid UUID character found: expect '0'-'9' or 'a'-'f', found: {} and {}",
-                    char::from_u32(s[i * 2] as u32).expect("should be a valid char"),
-                    char::from_u32(s[i * 2 + 1] as u32).expect("should be a valid char")
-                ),
-            });
+            return Err(Error::Parse(alloc::format!(
+                "invalid UUID character found: expect '0'-'9' or 'a'-'f', found: {} and {}",
+                char::from_u32(s[i * 2] as u32).expect("should be a valid char"),
+                char::from_u32(s[i * 2 + 1] as u32).expect("should be a valid char")
+            )));
         }
 
         // The upper nibble needs to be shifted into position to produce the
@@ -158,6 +150,62 @@ fn decode(s: &[u8]) -> Result<[u8; UUID_LEN], ParseError> {
     }
 }
 
+const BASE62_ALPHABET: &[u8; 62] =
+    b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+const BASE32_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+fn to_u128(bytes: &[u8; UUID_LEN]) -> u128 {
+    let mut buf = [0u8; 16];
+    buf[16 - UUID_LEN..].copy_from_slice(bytes);
+    u128::from_be_bytes(buf)
+}
+
+fn from_u128(value: u128) -> [u8; UUID_LEN] {
+    value.to_be_bytes()[16 - UUID_LEN..].try_into().unwrap()
+}
+
+/// Writes `value` in `alphabet`, most significant digit first and padded
+/// with zeros to the length of the buffer.
+fn encode_radix(mut value: u128, alphabet: &[u8], buf: &mut [u8]) {
+    let radix = alphabet.len() as u128;
+    for digit in buf.iter_mut().rev() {
+        *digit = alphabet[(value % radix) as usize];
+        value /= radix;
+    }
+}
+
+fn decode_radix(
+    s: &[u8],
+    len: usize,
+    radix: u128,
+    name: &str,
+    digit: impl Fn(u8) -> Option<u8>,
+) -> Result<[u8; UUID_LEN], Error> {
+    if s.len() != len {
+        return Err(Error::Parse(alloc::format!(
+            "Invalid {} UUID length, expected: {}, found: {}",
+            name,
+            len,
+            s.len()
+        )));
+    }
+    let mut value = 0u128;
+    for &c in s {
+        let d = digit(c).ok_or_else(|| {
+            Error::Parse(alloc::format!(
+                "invalid {} UUID character found: {}",
+                name,
+                char::from(c).escape_default()
+            ))
+        })?;
+        value = value * radix + u128::from(d);
+    }
+    if value >> (UUID_LEN * 8) != 0 {
+        return Err(Error::Parse(alloc::format!("{} UUID out of range", name)));
+    }
+    Ok(from_u128(value))
+}
+
 /// A short version of [`uuid::Uuid`], retaining only the first 10 bytes.
 ///
 /// A version 4 UUID contains timestamp (by microseconds) and address family in
@@ -167,17 +215,99 @@ fn decode(s: &[u8]) -> Result<[u8; UUID_LEN], ParseError> {
 /// # NOTE
 ///
 /// The encoded ascii version is still 20 bytes long.
+///
+/// Shorter encodings for URLs and file names are available through
+/// [`Uuid::encode_base62`] (14 characters) and [`Uuid::encode_base32`]
+/// (16 characters, Crockford's alphabet). Both preserve the byte order, so
+/// sortable ids stay sortable, and [`FromStr`] accepts all three encodings.
+///
+/// # Sortable ids
+///
+/// [`Uuid::new_sortable`] lays the id out like a UUIDv7 instead: a 48 bit
+/// big endian millisecond timestamp, the version nibble `7` and 28 random
+/// bits. Such ids sort by creation time (in any of their encodings) and
+/// [`Uuid::timestamp`] recovers when they were created.
 #[derive(Clone, Copy, Eq, Hash, Ord, PartialEq, PartialOrd)]
+#[cfg_attr(
+    feature = "borsh",
+    derive(borsh::BorshSerialize, borsh::BorshDeserialize)
+)]
+#[cfg_attr(
+    feature = "rkyv",
+    derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize),
+    rkyv(derive(Debug, Hash, PartialEq, Eq), compare(PartialEq))
+)]
 pub struct Uuid([u8; UUID_LEN]);
 
 impl Uuid {
     /// The length of the ASCII encoded string.
     pub const LENGTH: usize = ASCII_LEN;
+    /// The length of the base62 encoded string.
+    pub const BASE62_LENGTH: usize = 14;
+    /// The length of the Crockford base32 encoded string.
+    pub const BASE32_LENGTH: usize = 16;
 
     pub fn new() -> Self {
+        #[cfg(feature = "testing")]
+        if let Some(bytes) = crate::testing::determinism::uuid_bytes() {
+            return Self(bytes);
+        }
         Self::from_uuid(uuid::Uuid::new_v4())
     }
 
+    /// Creates an id that sorts after every id created in an earlier
+    /// millisecond, see [Sortable ids](Uuid#sortable-ids).
+    ///
+    /// Ids created within the same millisecond are ordered arbitrarily.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the operating system has no entropy to offer.
+    #[cfg(feature = "std")]
+    pub fn new_sortable() -> Self {
+        let millis = crate::utils::time::now_millis().max(0) as u64;
+        // The tail of a v4 id carries its variant bits, so draw fresh bytes.
+        #[cfg(feature = "testing")]
+        if let Some(bytes) = crate::testing::determinism::uuid_bytes() {
+            return Self::sortable_from_parts(millis, bytes[..4].try_into().unwrap());
+        }
+        let mut random = [0u8; 4];
+        getrandom::getrandom(&mut random).expect("failed to gather entropy");
+        Self::sortable_from_parts(millis, random)
+    }
+
+    /// Builds a sortable id from a millisecond timestamp and the random
+    /// bytes to fill the rest with. The high nibble of `random[0]` is
+    /// replaced by the version.
+    ///
+    /// Timestamps beyond the year 10889 saturate.
+    pub fn sortable_from_parts(millis: u64, random: [u8; 4]) -> Self {
+        let millis = millis.min(MAX_SORTABLE_MILLIS);
+        let mut bytes = [0u8; UUID_LEN];
+        bytes[..6].copy_from_slice(&millis.to_be_bytes()[2..]);
+        bytes[6] = (SORTABLE_VERSION << 4) | (random[0] & 0x0f);
+        bytes[7..].copy_from_slice(&random[1..]);
+        Self(bytes)
+    }
+
+    /// Whether the id carries the version of [`Uuid::new_sortable`].
+    ///
+    /// Ids from [`Uuid::new`] never do, but ids built from arbitrary bytes
+    /// or integers may by chance.
+    pub fn is_sortable(&self) -> bool {
+        self.0[6] >> 4 == SORTABLE_VERSION
+    }
+
+    /// The creation time of a sortable id in milliseconds since the epoch.
+    pub fn timestamp(&self) -> Option<u64> {
+        if !self.is_sortable() {
+            return None;
+        }
+        let mut millis = [0u8; 8];
+        millis[2..].copy_from_slice(&self.0[..6]);
+        Some(u64::from_be_bytes(millis))
+    }
+
     pub const fn empty() -> Self {
         Self([0; UUID_LEN])
     }
@@ -193,6 +323,56 @@ impl Uuid {
         f(str)
     }
 
+    /// Encodes the id with the digits `0-9A-Za-z`, padded to
+    /// [`BASE62_LENGTH`](Self::BASE62_LENGTH).
+    pub fn encode_base62(&self) -> String {
+        let mut buf = [0u8; Self::BASE62_LENGTH];
+        encode_radix(to_u128(&self.0), BASE62_ALPHABET, &mut buf);
+        // SAFETY: The buffer is ASCII encoded
+        unsafe { from_utf8_unchecked(&buf) }.into()
+    }
+
+    pub fn from_base62(s: &str) -> Result<Self, Error> {
+        decode_radix(
+            s.as_bytes(),
+            Self::BASE62_LENGTH,
+            62,
+            "base62",
+            |c| match c {
+                b'0'..=b'9' => Some(c - b'0'),
+                b'A'..=b'Z' => Some(c - b'A' + 10),
+                b'a'..=b'z' => Some(c - b'a' + 36),
+                _ => None,
+            },
+        )
+        .map(Self)
+    }
+
+    /// Encodes the id in uppercase Crockford base32, which avoids the
+    /// ambiguous letters I, L, O and U.
+    pub fn encode_base32(&self) -> String {
+        let mut buf = [0u8; Self::BASE32_LENGTH];
+        encode_radix(to_u128(&self.0), BASE32_ALPHABET, &mut buf);
+        // SAFETY: The buffer is ASCII encoded
+        unsafe { from_utf8_unchecked(&buf) }.into()
+    }
+
+    /// Decodes Crockford base32 case insensitively, reading `I` and `L` as
+    /// `1` and `O` as `0`.
+    pub fn from_base32(s: &str) -> Result<Self, Error> {
+        decode_radix(s.as_bytes(), Self::BASE32_LENGTH, 32, "base32", |c| match c
+            .to_ascii_uppercase()
+        {
+            b'O' => Some(0),
+            b'I' | b'L' => Some(1),
+            c => BASE32_ALPHABET
+                .iter()
+                .position(|&d| d == c)
+                .map(|d| d as u8),
+        })
+        .map(Self)
+    }
+
     /// View this UUID as a slice of u8 bytes.
     pub fn as_bytes(&self) -> &[u8] {
         &self.0
@@ -250,6 +430,44 @@ impl fmt::Display for Uuid {
     }
 }
 
+#[cfg(feature = "msgpack")]
+std::thread_local! {
+    static BINARY_REPR: core::cell::Cell<bool> = const { core::cell::Cell::new(false) };
+}
+
+/// Runs `f` with the raw byte representation of [`Uuid`] enabled for binary
+/// serializers on this thread, see [`serde_ext::msgpack`](crate::serde_ext::msgpack).
+#[cfg(feature = "msgpack")]
+pub(crate) fn with_binary_repr<R>(f: impl FnOnce() -> R) -> R {
+    struct Restore(bool);
+
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            BINARY_REPR.with(|binary| binary.set(self.0));
+        }
+    }
+
+    let _restore = Restore(BINARY_REPR.with(|binary| binary.replace(true)));
+    f()
+}
+
+/// Whether `Uuid`s are written as their 10 raw bytes.
+fn binary_repr(human_readable: bool) -> bool {
+    #[cfg(feature = "msgpack")]
+    {
+        !human_readable && BINARY_REPR.with(core::cell::Cell::get)
+    }
+    #[cfg(not(feature = "msgpack"))]
+    {
+        let _ = human_readable;
+        false
+    }
+}
+
+/// Serialized as the 20 character hex string, except for the 10 raw bytes
+/// in the MessagePack helpers of [`serde_ext::msgpack`](crate::serde_ext::msgpack).
+/// Both the string, as text or bytes, and the raw bytes are accepted when
+/// deserializing.
 impl<'de> Deserialize<'de> for Uuid {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -269,8 +487,43 @@ impl<'de> Deserialize<'de> for Uuid {
             {
                 Uuid::from_str(v).map_err(E::custom)
             }
+
+            fn visit_bytes<E>(self, v: &[u8]) -> Result<Self::Value, E>
+            where
+                E: serde::de::Error,
+            {
+                // Formats without a distinct string type, e.g. bincode,
+                // hand the hex string over as bytes.
+                if v.len() == ASCII_LEN {
+                    return decode(v).map(Uuid).map_err(E::custom);
+                }
+                v.try_into()
+                    .map(Uuid)
+                    .map_err(|_| E::invalid_length(v.len(), &self))
+            }
+
+            fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+            where
+                A: serde::de::SeqAccess<'de>,
+            {
+                let mut buf = [0u8; UUID_LEN];
+                for (i, byte) in buf.iter_mut().enumerate() {
+                    *byte = seq
+                        .next_element()?
+                        .ok_or_else(|| serde::de::Error::invalid_length(i, &self))?;
+                }
+                if seq.next_element::<u8>()?.is_some() {
+                    return Err(serde::de::Error::invalid_length(UUID_LEN + 1, &self));
+                }
+                Ok(Uuid(buf))
+            }
+        }
+
+        if binary_repr(deserializer.is_human_readable()) {
+            deserializer.deserialize_bytes(UuidVisitor)
+        } else {
+            deserializer.deserialize_str(UuidVisitor)
         }
-        deserializer.deserialize_str(UuidVisitor)
     }
 }
 
@@ -279,19 +532,31 @@ impl Serialize for Uuid {
     where
         S: serde::Serializer,
     {
-        self.encode_with(|s| serializer.serialize_str(s))
+        if binary_repr(serializer.is_human_readable()) {
+            serializer.serialize_bytes(&self.0)
+        } else {
+            self.encode_with(|s| serializer.serialize_str(s))
+        }
     }
 }
 
 impl FromStr for Uuid {
-    type Err = ParseError;
+    type Err = Error;
 
+    /// Detects the encoding by length: 14 characters are parsed as base62,
+    /// 16 as Crockford base32, anything else as hex.
+    ///
     /// # NOTE
     ///
-    /// The bytes in `s` should be numeric values of lowercase hex characters
-    /// '0' - '9', 'a' - 'f', or `Err(invalid hex found)` would be returned.
+    /// The bytes of a hex id should be numeric values of lowercase hex
+    /// characters '0' - '9', 'a' - 'f', or `Err(invalid hex found)` would be
+    /// returned.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        decode(s.as_bytes()).map(Self)
+        match s.len() {
+            Self::BASE62_LENGTH => Self::from_base62(s),
+            Self::BASE32_LENGTH => Self::from_base32(s),
+            _ => decode(s.as_bytes()).map(Self),
+        }
     }
 }
 
@@ -299,8 +564,11 @@ impl FromStr for Uuid {
 mod tests {
     use crate::utils::uuid::Uuid;
     use crate::utils::uuid::ASCII_LEN;
+    use alloc::string::String;
     use alloc::string::ToString;
+    use alloc::vec::Vec;
     use core::str::FromStr;
+    use serde::Deserialize;
 
     #[test]
     fn test_encode_decode() {
@@ -317,4 +585,130 @@ mod tests {
             assert_eq!(parsed, short);
         }
     }
+
+    #[test]
+    fn test_sortable_layout() {
+        let id = Uuid::sortable_from_parts(0x0123_4567_89ab, [0xff, 1, 2, 3]);
+        assert_eq!(id.to_string(), "0123456789ab7f010203");
+        assert!(id.is_sortable());
+        assert_eq!(id.timestamp(), Some(0x0123_4567_89ab));
+        assert_eq!(
+            Uuid::from_str(&id.to_string()).unwrap().timestamp(),
+            Some(0x0123_4567_89ab)
+        );
+
+        assert!(!Uuid::new().is_sortable());
+        assert_eq!(Uuid::empty().timestamp(), None);
+        assert_eq!(
+            Uuid::sortable_from_parts(u64::MAX, [0; 4]).timestamp(),
+            Some((1 << 48) - 1)
+        );
+    }
+
+    #[test]
+    fn test_sortable_ordering() {
+        let mut ids: Vec<Uuid> = (0..100u64)
+            .rev()
+            .map(|i| Uuid::sortable_from_parts(1_700_000_000_000 + i * 7, [0xff; 4]))
+            .collect();
+        let strings: Vec<String> = ids.iter().map(ToString::to_string).collect();
+        ids.sort();
+        let mut sorted_strings = strings.clone();
+        sorted_strings.sort();
+
+        let timestamps: Vec<u64> = ids.iter().map(|id| id.timestamp().unwrap()).collect();
+        assert!(timestamps.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(
+            sorted_strings,
+            ids.iter().map(ToString::to_string).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_compact_encodings() {
+        let max = unsafe { Uuid::from_bytes([0xff; 10]) };
+        assert_eq!(max.encode_base62(), "62iEp5bu9VZbsV");
+        assert_eq!(max.encode_base32(), "ZZZZZZZZZZZZZZZZ");
+        assert_eq!(Uuid::empty().encode_base62(), "00000000000000");
+        assert_eq!(Uuid::empty().encode_base32(), "0000000000000000");
+
+        for _ in 0..1000 {
+            let id = Uuid::new();
+            let base62 = id.encode_base62();
+            let base32 = id.encode_base32();
+            assert_eq!(Uuid::from_base62(&base62).unwrap(), id);
+            assert_eq!(Uuid::from_base32(&base32).unwrap(), id);
+            assert_eq!(Uuid::from_str(&base62).unwrap(), id);
+            assert_eq!(Uuid::from_str(&base32.to_lowercase()).unwrap(), id);
+            assert_eq!(Uuid::from_str(&id.to_string()).unwrap(), id);
+        }
+
+        // Lexicographic order follows the byte order.
+        let low = Uuid::sortable_from_parts(1_000, [0; 4]);
+        let high = Uuid::sortable_from_parts(1_001, [0; 4]);
+        assert!(low.encode_base62() < high.encode_base62());
+        assert!(low.encode_base32() < high.encode_base32());
+
+        assert_eq!(
+            Uuid::from_base32("0000000000000O1L").unwrap(),
+            Uuid::from_base32("0000000000000011").unwrap()
+        );
+        assert!(Uuid::from_base62("62iEp5bu9VZbsW").is_err());
+        assert!(Uuid::from_base62("0000000000000-").is_err());
+        assert!(Uuid::from_base32("000000000000000U").is_err());
+        assert!(Uuid::from_base32("ZZZZZZZZZZZZZZZ").is_err());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_new_sortable() {
+        let before = crate::utils::time::now_millis() as u64;
+        let first = Uuid::new_sortable();
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        let second = Uuid::new_sortable();
+        let after = crate::utils::time::now_millis() as u64;
+
+        assert!(first < second);
+        assert!((before..=after).contains(&first.timestamp().unwrap()));
+        assert_ne!(first.as_bytes()[6..], second.as_bytes()[6..]);
+
+        // No bits of the random tail are fixed, unlike the variant of v4.
+        let ids: Vec<Uuid> = (0..64).map(|_| Uuid::new_sortable()).collect();
+        assert!(ids.iter().any(|id| id.as_bytes()[8] >> 6 != 0b10));
+    }
+
+    #[cfg(feature = "borsh")]
+    #[test]
+    fn test_borsh_round_trip() {
+        let id = Uuid::new();
+        let bytes = borsh::to_vec(&id).unwrap();
+        assert_eq!(bytes, id.as_bytes());
+        assert_eq!(borsh::from_slice::<Uuid>(&bytes).unwrap(), id);
+    }
+
+    #[test]
+    fn test_deserialize_from_bytes() {
+        use serde::de::value::BytesDeserializer;
+        use serde::de::value::Error;
+
+        let id = Uuid::from(7);
+        let hex = id.to_string();
+        let from_hex = Uuid::deserialize(BytesDeserializer::<Error>::new(hex.as_bytes()));
+        assert_eq!(from_hex.unwrap(), id);
+        let from_raw = Uuid::deserialize(BytesDeserializer::<Error>::new(id.as_bytes()));
+        assert_eq!(from_raw.unwrap(), id);
+        assert!(Uuid::deserialize(BytesDeserializer::<Error>::new(&[1, 2, 3])).is_err());
+    }
+
+    #[cfg(feature = "cbor")]
+    #[test]
+    fn test_cbor_keeps_hex_string() {
+        let id = Uuid::from(7);
+        let mut bytes = Vec::new();
+        ciborium::into_writer(&id, &mut bytes).unwrap();
+        let mut expected = Vec::new();
+        ciborium::into_writer(&id.to_string(), &mut expected).unwrap();
+        assert_eq!(bytes, expected);
+        assert_eq!(ciborium::from_reader::<Uuid, _>(&bytes[..]).unwrap(), id);
+    }
 }
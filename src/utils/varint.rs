@@ -0,0 +1,472 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Variable-length integer encodings.
+//!
+//! Integers are written LEB128 style, seven bits per byte with the high bit
+//! set on every byte but the last. Signed values go through zig-zag encoding
+//! first so small magnitudes stay short. [`encode_group`] packs `u32`s four
+//! at a time behind a tag byte, which decodes without per-byte branches.
+
+use alloc::vec::Vec;
+use bytes::Buf;
+use bytes::BufMut;
+use core::fmt;
+
+/// The longest encoding of a `u32`.
+pub const MAX_LEN_U32: usize = 5;
+/// The longest encoding of a `u64`.
+pub const MAX_LEN_U64: usize = 10;
+
+/// Why a varint could not be decoded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VarintError {
+    /// The input ended in the middle of a value.
+    UnexpectedEof,
+    /// The value does not fit the requested type.
+    Overflow,
+}
+
+impl fmt::Display for VarintError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VarintError::UnexpectedEof => write!(f, "varint is truncated"),
+            VarintError::Overflow => write!(f, "varint overflows its type"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for VarintError {}
+
+#[cfg(feature = "std")]
+impl From<VarintError> for std::io::Error {
+    fn from(e: VarintError) -> Self {
+        let kind = match e {
+            VarintError::UnexpectedEof => std::io::ErrorKind::UnexpectedEof,
+            VarintError::Overflow => std::io::ErrorKind::InvalidData,
+        };
+        std::io::Error::new(kind, e)
+    }
+}
+
+/// The number of bytes [`encode_u64`] writes for `value`.
+pub fn encoded_len(value: u64) -> usize {
+    let bits = 64 - (value | 1).leading_zeros() as usize;
+    bits.div_ceil(7)
+}
+
+/// Appends `value` to `out`.
+pub fn encode_u32(value: u32, out: &mut Vec<u8>) {
+    encode_u64(value as u64, out)
+}
+
+/// Appends `value` to `out`.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::varint::decode_u64;
+/// use pizza_common::utils::varint::encode_u64;
+///
+/// let mut out = Vec::new();
+/// encode_u64(300, &mut out);
+/// assert_eq!(out, [0xac, 0x02]);
+/// assert_eq!(decode_u64(&out), Ok((300, 2)));
+/// ```
+pub fn encode_u64(value: u64, out: &mut Vec<u8>) {
+    put_u64(value, out)
+}
+
+/// Appends the zig-zag encoding of `value` to `out`.
+pub fn encode_i32(value: i32, out: &mut Vec<u8>) {
+    encode_u32(zigzag_encode_32(value), out)
+}
+
+/// Appends the zig-zag encoding of `value` to `out`.
+pub fn encode_i64(value: i64, out: &mut Vec<u8>) {
+    encode_u64(zigzag_encode_64(value), out)
+}
+
+/// Decodes a value from the start of `bytes`, returning it with the number
+/// of bytes read.
+pub fn decode_u32(bytes: &[u8]) -> Result<(u32, usize), VarintError> {
+    let (value, len) = decode_u64(bytes)?;
+    let value = u32::try_from(value).map_err(|_| VarintError::Overflow)?;
+    Ok((value, len))
+}
+
+/// Decodes a value from the start of `bytes`, returning it with the number
+/// of bytes read.
+pub fn decode_u64(bytes: &[u8]) -> Result<(u64, usize), VarintError> {
+    let mut bytes = bytes;
+    let before = bytes.len();
+    let value = get_u64(&mut bytes)?;
+    Ok((value, before - bytes.len()))
+}
+
+/// Decodes a zig-zag encoded value, see [`decode_u32`].
+pub fn decode_i32(bytes: &[u8]) -> Result<(i32, usize), VarintError> {
+    decode_u32(bytes).map(|(value, len)| (zigzag_decode_32(value), len))
+}
+
+/// Decodes a zig-zag encoded value, see [`decode_u64`].
+pub fn decode_i64(bytes: &[u8]) -> Result<(i64, usize), VarintError> {
+    decode_u64(bytes).map(|(value, len)| (zigzag_decode_64(value), len))
+}
+
+/// Maps signed to unsigned values, `0, -1, 1, -2, ...` to `0, 1, 2, 3, ...`.
+pub fn zigzag_encode_32(value: i32) -> u32 {
+    ((value << 1) ^ (value >> 31)) as u32
+}
+
+/// The inverse of [`zigzag_encode_32`].
+pub fn zigzag_decode_32(value: u32) -> i32 {
+    ((value >> 1) as i32) ^ -((value & 1) as i32)
+}
+
+/// Maps signed to unsigned values, `0, -1, 1, -2, ...` to `0, 1, 2, 3, ...`.
+pub fn zigzag_encode_64(value: i64) -> u64 {
+    ((value << 1) ^ (value >> 63)) as u64
+}
+
+/// The inverse of [`zigzag_encode_64`].
+pub fn zigzag_decode_64(value: u64) -> i64 {
+    ((value >> 1) as i64) ^ -((value & 1) as i64)
+}
+
+/// Appends `values` in group varint format.
+///
+/// Each group of four values starts with a tag byte holding the byte length
+/// minus one of every value in two bits, lowest bits first, followed by the
+/// values in little-endian order using that many bytes. The last group may
+/// hold fewer values; the decoder is told the count.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::varint::decode_group;
+/// use pizza_common::utils::varint::encode_group;
+///
+/// let mut out = Vec::new();
+/// encode_group(&[1, 300, 70000, 5, 9], &mut out);
+/// assert_eq!(out.len(), 1 + 1 + 2 + 3 + 1 + 1 + 1);
+///
+/// let mut values = Vec::new();
+/// assert_eq!(decode_group(&out, 5, &mut values), Ok(out.len()));
+/// assert_eq!(values, [1, 300, 70000, 5, 9]);
+/// ```
+pub fn encode_group(values: &[u32], out: &mut Vec<u8>) {
+    for group in values.chunks(4) {
+        let tag_at = out.len();
+        out.push(0);
+        let mut tag = 0u8;
+        for (i, &value) in group.iter().enumerate() {
+            let len = (4 - value.leading_zeros() as usize / 8).max(1);
+            tag |= ((len - 1) as u8) << (i * 2);
+            out.extend_from_slice(&value.to_le_bytes()[..len]);
+        }
+        out[tag_at] = tag;
+    }
+}
+
+/// Decodes `count` values written by [`encode_group`] from the start of
+/// `bytes`, appending them to `out` and returning the number of bytes read.
+pub fn decode_group(bytes: &[u8], count: usize, out: &mut Vec<u32>) -> Result<usize, VarintError> {
+    let mut at = 0;
+    let mut left = count;
+    out.reserve(count);
+    while left > 0 {
+        let tag = *bytes.get(at).ok_or(VarintError::UnexpectedEof)?;
+        at += 1;
+        for i in 0..left.min(4) {
+            let len = ((tag >> (i * 2)) & 0b11) as usize + 1;
+            let value = bytes.get(at..at + len).ok_or(VarintError::UnexpectedEof)?;
+            let mut word = [0u8; 4];
+            word[..len].copy_from_slice(value);
+            out.push(u32::from_le_bytes(word));
+            at += len;
+        }
+        left -= left.min(4);
+    }
+    Ok(at)
+}
+
+fn put_u64(mut value: u64, out: &mut impl BufMut) {
+    while value >= 0x80 {
+        out.put_u8(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.put_u8(value as u8);
+}
+
+fn get_u64(buf: &mut impl Buf) -> Result<u64, VarintError> {
+    let mut value = 0u64;
+    for i in 0..MAX_LEN_U64 {
+        if !buf.has_remaining() {
+            return Err(VarintError::UnexpectedEof);
+        }
+        let byte = buf.get_u8();
+        // The tenth byte only has room for the top bit of a u64.
+        if i == MAX_LEN_U64 - 1 && byte > 1 {
+            return Err(VarintError::Overflow);
+        }
+        value |= ((byte & 0x7f) as u64) << (i * 7);
+        if byte < 0x80 {
+            return Ok(value);
+        }
+    }
+    Err(VarintError::Overflow)
+}
+
+/// Writes varints to any [`BufMut`], such as a `Vec<u8>` or `BytesMut`.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::utils::varint::VarintReader;
+/// use pizza_common::utils::varint::VarintWriter;
+///
+/// let mut writer = VarintWriter::new(Vec::new());
+/// writer.write_u32(7);
+/// writer.write_i64(-3);
+/// let bytes = writer.into_inner();
+///
+/// let mut reader = VarintReader::new(&bytes[..]);
+/// assert_eq!(reader.read_u32(), Ok(7));
+/// assert_eq!(reader.read_i64(), Ok(-3));
+/// assert!(!reader.has_remaining());
+/// ```
+#[derive(Debug, Default)]
+pub struct VarintWriter<B> {
+    buf: B,
+}
+
+impl<B: BufMut> VarintWriter<B> {
+    pub fn new(buf: B) -> Self {
+        Self { buf }
+    }
+
+    pub fn write_u32(&mut self, value: u32) {
+        put_u64(value as u64, &mut self.buf)
+    }
+
+    pub fn write_u64(&mut self, value: u64) {
+        put_u64(value, &mut self.buf)
+    }
+
+    pub fn write_i32(&mut self, value: i32) {
+        self.write_u32(zigzag_encode_32(value))
+    }
+
+    pub fn write_i64(&mut self, value: i64) {
+        self.write_u64(zigzag_encode_64(value))
+    }
+
+    /// The underlying buffer.
+    pub fn get_mut(&mut self) -> &mut B {
+        &mut self.buf
+    }
+
+    pub fn into_inner(self) -> B {
+        self.buf
+    }
+}
+
+/// Reads varints from any [`Buf`], such as a byte slice or a
+/// [`Chain`](crate::bytes::Chain) of segments.
+///
+/// A failed read leaves the buffer positioned after the bytes it consumed.
+#[derive(Debug, Default)]
+pub struct VarintReader<B> {
+    buf: B,
+}
+
+impl<B: Buf> VarintReader<B> {
+    pub fn new(buf: B) -> Self {
+        Self { buf }
+    }
+
+    pub fn read_u32(&mut self) -> Result<u32, VarintError> {
+        u32::try_from(self.read_u64()?).map_err(|_| VarintError::Overflow)
+    }
+
+    pub fn read_u64(&mut self) -> Result<u64, VarintError> {
+        get_u64(&mut self.buf)
+    }
+
+    pub fn read_i32(&mut self) -> Result<i32, VarintError> {
+        self.read_u32().map(zigzag_decode_32)
+    }
+
+    pub fn read_i64(&mut self) -> Result<i64, VarintError> {
+        self.read_u64().map(zigzag_decode_64)
+    }
+
+    /// Returns `true` if there are bytes left to read.
+    pub fn has_remaining(&self) -> bool {
+        self.buf.has_remaining()
+    }
+
+    /// The underlying buffer.
+    pub fn get_mut(&mut self) -> &mut B {
+        &mut self.buf
+    }
+
+    pub fn into_inner(self) -> B {
+        self.buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytes::Bytes;
+    use crate::bytes::Chain;
+
+    fn boundaries() -> Vec<u64> {
+        let mut values = Vec::new();
+        for shift in 0..64 {
+            let power = 1u64 << shift;
+            values.extend([power - 1, power, power + 1]);
+        }
+        values.push(u64::MAX);
+        values
+    }
+
+    #[test]
+    fn test_u32_exhaustive_small() {
+        let mut out = Vec::new();
+        for value in 0..=(1u32 << 16) {
+            out.clear();
+            encode_u32(value, &mut out);
+            assert_eq!(out.len(), encoded_len(value as u64));
+            assert_eq!(decode_u32(&out), Ok((value, out.len())));
+        }
+    }
+
+    #[test]
+    fn test_u64_roundtrip() {
+        let mut out = Vec::new();
+        for value in boundaries() {
+            out.clear();
+            encode_u64(value, &mut out);
+            assert_eq!(out.len(), encoded_len(value));
+            assert!(out.len() <= MAX_LEN_U64);
+            assert_eq!(decode_u64(&out), Ok((value, out.len())));
+            if let Ok(value) = u32::try_from(value) {
+                assert!(out.len() <= MAX_LEN_U32);
+                assert_eq!(decode_u32(&out), Ok((value, out.len())));
+            } else {
+                assert_eq!(decode_u32(&out), Err(VarintError::Overflow));
+            }
+        }
+    }
+
+    #[test]
+    fn test_signed_roundtrip() {
+        assert_eq!(zigzag_encode_32(0), 0);
+        assert_eq!(zigzag_encode_32(-1), 1);
+        assert_eq!(zigzag_encode_32(1), 2);
+        assert_eq!(zigzag_encode_32(i32::MIN), u32::MAX);
+        assert_eq!(zigzag_encode_64(i64::MAX), u64::MAX - 1);
+
+        let mut out = Vec::new();
+        for value in (-70_000..70_000).chain([i32::MIN, i32::MAX]) {
+            out.clear();
+            encode_i32(value, &mut out);
+            assert_eq!(decode_i32(&out), Ok((value, out.len())));
+        }
+        for value in boundaries() {
+            for value in [value as i64, (value as i64).wrapping_neg(), i64::MIN] {
+                out.clear();
+                encode_i64(value, &mut out);
+                assert_eq!(decode_i64(&out), Ok((value, out.len())));
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        assert_eq!(decode_u64(&[]), Err(VarintError::UnexpectedEof));
+        assert_eq!(decode_u64(&[0x80, 0x80]), Err(VarintError::UnexpectedEof));
+        assert_eq!(decode_u64(&[0xff; 11]), Err(VarintError::Overflow));
+        let mut too_big = [0xffu8; 10];
+        too_big[9] = 0x02;
+        assert_eq!(decode_u64(&too_big), Err(VarintError::Overflow));
+        too_big[9] = 0x01;
+        assert_eq!(decode_u64(&too_big), Ok((u64::MAX, 10)));
+        // Trailing bytes are left for the caller.
+        assert_eq!(decode_u64(&[0x05, 0x06]), Ok((5, 1)));
+    }
+
+    #[test]
+    fn test_group_roundtrip() {
+        let values: Vec<u32> = boundaries()
+            .into_iter()
+            .filter_map(|value| u32::try_from(value).ok())
+            .collect();
+        for count in 0..values.len() {
+            let mut out = Vec::new();
+            encode_group(&values[..count], &mut out);
+            let mut decoded = Vec::new();
+            assert_eq!(decode_group(&out, count, &mut decoded), Ok(out.len()));
+            assert_eq!(decoded, values[..count]);
+            if !out.is_empty() {
+                assert_eq!(
+                    decode_group(&out[..out.len() - 1], count, &mut decoded),
+                    Err(VarintError::UnexpectedEof)
+                );
+            }
+        }
+        let mut out = Vec::new();
+        encode_group(&[0, 0, 0, 0], &mut out);
+        assert_eq!(out, [0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn test_reader_writer() {
+        let mut writer = VarintWriter::new(Vec::new());
+        for value in boundaries() {
+            writer.write_u64(value);
+            writer.write_i64((value as i64).wrapping_neg());
+        }
+        writer.write_u32(u32::MAX);
+        writer.write_i32(i32::MIN);
+        let bytes = writer.into_inner();
+
+        // Split the encoding across segments so values straddle them.
+        let mut chain = Chain::new();
+        for piece in bytes.chunks(3) {
+            chain.push(Bytes::copy_from_slice(piece));
+        }
+        let mut reader = VarintReader::new(chain);
+        for value in boundaries() {
+            assert_eq!(reader.read_u64(), Ok(value));
+            assert_eq!(reader.read_i64(), Ok((value as i64).wrapping_neg()));
+        }
+        assert_eq!(reader.read_u32(), Ok(u32::MAX));
+        assert_eq!(reader.read_i32(), Ok(i32::MIN));
+        assert!(!reader.has_remaining());
+        assert_eq!(reader.read_u64(), Err(VarintError::UnexpectedEof));
+    }
+}
@@ -0,0 +1,233 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use super::distance::dot;
+use super::distance::l2_squared;
+use alloc::vec::Vec;
+
+/// The score computed between a query and stored vectors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    /// See [`dot`](super::dot).
+    Dot,
+    /// See [`l2_squared`](super::l2_squared).
+    L2Squared,
+    /// See [`cosine`](super::cosine).
+    Cosine,
+}
+
+/// A query prepared once for scoring many vectors.
+struct Scorer<'a> {
+    metric: Metric,
+    query: &'a [f32],
+    query_norm: f32,
+}
+
+impl<'a> Scorer<'a> {
+    fn new(metric: Metric, query: &'a [f32]) -> Self {
+        let query_norm = match metric {
+            Metric::Cosine => libm::sqrtf(dot(query, query)),
+            _ => 0.0,
+        };
+        Self {
+            metric,
+            query,
+            query_norm,
+        }
+    }
+
+    fn score(&self, vector: &[f32]) -> f32 {
+        match self.metric {
+            Metric::Dot => dot(self.query, vector),
+            Metric::L2Squared => l2_squared(self.query, vector),
+            Metric::Cosine => {
+                let norm = libm::sqrtf(dot(vector, vector));
+                if self.query_norm == 0.0 || norm == 0.0 {
+                    0.0
+                } else {
+                    (dot(self.query, vector) / (self.query_norm * norm)).clamp(-1.0, 1.0)
+                }
+            }
+        }
+    }
+
+    fn score_chunk(&self, chunk: &[f32], out: &mut [f32]) {
+        for (vector, score) in chunk.chunks_exact(self.query.len()).zip(out) {
+            *score = self.score(vector);
+        }
+    }
+}
+
+fn check_chunk(chunk: &[f32], dim: usize) -> usize {
+    assert!(
+        chunk.len().is_multiple_of(dim),
+        "chunk length {} is not a multiple of the dimension {}",
+        chunk.len(),
+        dim
+    );
+    chunk.len() / dim
+}
+
+/// Scores `query` against every vector stored in `chunks` and appends the
+/// results to `out`, in storage order.
+///
+/// Each chunk holds vectors of `dim` values laid out back to back, such as
+/// the chunks of an `Arena<f32>`. Chunks are scanned sequentially, and the
+/// per-query work (e.g. its norm for [`Metric::Cosine`]) is done only once.
+///
+/// # Panics
+///
+/// Panics if `dim` is zero, the query is not `dim` long, or a chunk length is
+/// not a multiple of `dim`.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::vector::{batch_distances, Metric};
+///
+/// let chunks: [&[f32]; 2] = [&[1.0, 0.0, 0.0, 1.0], &[2.0, 2.0]];
+/// let mut out = Vec::new();
+/// batch_distances(Metric::Dot, &[1.0, 2.0], chunks.into_iter(), 2, &mut out);
+/// assert_eq!(out, [1.0, 2.0, 6.0]);
+/// ```
+pub fn batch_distances<'a>(
+    metric: Metric,
+    query: &[f32],
+    chunks: impl Iterator<Item = &'a [f32]>,
+    dim: usize,
+    out: &mut Vec<f32>,
+) {
+    assert!(dim > 0, "dimension must be positive");
+    assert_eq!(query.len(), dim, "vector dimensions differ");
+
+    let scorer = Scorer::new(metric, query);
+    for chunk in chunks {
+        let count = check_chunk(chunk, dim);
+        let start = out.len();
+        out.resize(start + count, 0.0);
+        scorer.score_chunk(chunk, &mut out[start..]);
+    }
+}
+
+/// Parallel version of [`batch_distances`], scoring chunks on the rayon
+/// thread pool.
+///
+/// Results are appended to `out` in storage order, exactly as the sequential
+/// version does.
+#[cfg(feature = "rayon")]
+pub fn par_batch_distances(
+    metric: Metric,
+    query: &[f32],
+    chunks: &[&[f32]],
+    dim: usize,
+    out: &mut Vec<f32>,
+) {
+    use rayon::prelude::*;
+
+    assert!(dim > 0, "dimension must be positive");
+    assert_eq!(query.len(), dim, "vector dimensions differ");
+
+    let counts: Vec<usize> = chunks.iter().map(|chunk| check_chunk(chunk, dim)).collect();
+    let start = out.len();
+    out.resize(start + counts.iter().sum::<usize>(), 0.0);
+
+    let mut outputs = Vec::with_capacity(chunks.len());
+    let mut rest = &mut out[start..];
+    for count in counts {
+        let (head, tail) = rest.split_at_mut(count);
+        outputs.push(head);
+        rest = tail;
+    }
+
+    let scorer = Scorer::new(metric, query);
+    chunks
+        .par_iter()
+        .zip(outputs)
+        .for_each(|(chunk, out)| scorer.score_chunk(chunk, out));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vector::cosine;
+    use alloc::vec;
+
+    fn chunked(vectors: &[[f32; 3]], sizes: &[usize]) -> Vec<Vec<f32>> {
+        let mut iter = vectors.iter();
+        sizes
+            .iter()
+            .map(|&size| iter.by_ref().take(size).flatten().copied().collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_batch_matches_single_calls() {
+        let vectors: Vec<[f32; 3]> = (0..20)
+            .map(|i| [i as f32, (i % 3) as f32 - 1.0, 0.5])
+            .collect();
+        let chunks = chunked(&vectors, &[1, 2, 4, 0, 13]);
+        let query = [0.25, -1.0, 2.0];
+
+        for metric in [Metric::Dot, Metric::L2Squared, Metric::Cosine] {
+            let mut out = vec![42.0];
+            batch_distances(
+                metric,
+                &query,
+                chunks.iter().map(Vec::as_slice),
+                3,
+                &mut out,
+            );
+            assert_eq!(out.len(), 21);
+            assert_eq!(out[0], 42.0);
+
+            for (vector, score) in vectors.iter().zip(&out[1..]) {
+                let expected = match metric {
+                    Metric::Dot => dot(&query, vector),
+                    Metric::L2Squared => l2_squared(&query, vector),
+                    Metric::Cosine => cosine(&query, vector),
+                };
+                assert!((expected - score).abs() < 1e-5);
+            }
+
+            #[cfg(feature = "rayon")]
+            {
+                let slices: Vec<&[f32]> = chunks.iter().map(Vec::as_slice).collect();
+                let mut par_out = vec![42.0];
+                par_batch_distances(metric, &query, &slices, 3, &mut par_out);
+                assert_eq!(par_out, out);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "not a multiple of the dimension")]
+    fn test_ragged_chunk() {
+        let chunk: &[f32] = &[1.0, 2.0, 3.0];
+        batch_distances(
+            Metric::Dot,
+            &[1.0, 1.0],
+            [chunk].into_iter(),
+            2,
+            &mut Vec::new(),
+        );
+    }
+}
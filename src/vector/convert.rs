@@ -0,0 +1,235 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Conversions between `f32` and the 16-bit float formats used to store
+//! embeddings compactly.
+//!
+//! Half precision values are passed around as their raw `u16` bit patterns,
+//! so they can be stored directly without an extra dependency. Conversions
+//! from `f32` round to the nearest value, ties to even.
+
+/// Converts an `f32` to IEEE 754 half precision (binary16) bits.
+///
+/// Values too large for `f16` become infinity, values too small become zero.
+pub fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exp = ((bits >> 23) & 0xff) as i32;
+    let man = bits & 0x007f_ffff;
+
+    if exp == 0xff {
+        // Infinity stays infinity, NaN stays a (quiet) NaN.
+        return if man == 0 {
+            sign | 0x7c00
+        } else {
+            sign | 0x7e00 | (man >> 13) as u16
+        };
+    }
+
+    let half_exp = exp - 127 + 15;
+    if half_exp >= 0x1f {
+        return sign | 0x7c00;
+    }
+
+    if half_exp <= 0 {
+        if half_exp < -10 {
+            return sign;
+        }
+        // Subnormal result, make the implicit leading bit explicit.
+        let man = man | 0x0080_0000;
+        let shift = (14 - half_exp) as u32;
+        let half_man = man >> shift;
+        let rest = man & ((1 << shift) - 1);
+        let halfway = 1 << (shift - 1);
+        let round_up = rest > halfway || (rest == halfway && half_man & 1 == 1);
+        return sign | (half_man + round_up as u32) as u16;
+    }
+
+    let half = ((half_exp as u32) << 10) | (man >> 13);
+    let rest = man & 0x1fff;
+    let round_up = rest > 0x1000 || (rest == 0x1000 && half & 1 == 1);
+    // A carry out of the mantissa correctly bumps the exponent, up to infinity.
+    sign | (half + round_up as u32) as u16
+}
+
+/// Converts IEEE 754 half precision (binary16) bits to an `f32`, exactly.
+pub fn f16_to_f32(half: u16) -> f32 {
+    let sign = ((half & 0x8000) as u32) << 16;
+    let exp = ((half >> 10) & 0x1f) as u32;
+    let man = (half & 0x03ff) as u32;
+
+    match exp {
+        0 => {
+            // Zero or subnormal: man * 2^-24.
+            let value = man as f32 * f32::from_bits(0x3380_0000);
+            f32::from_bits(value.to_bits() | sign)
+        }
+        0x1f => f32::from_bits(sign | 0x7f80_0000 | (man << 13)),
+        _ => f32::from_bits(sign | ((exp + 127 - 15) << 23) | (man << 13)),
+    }
+}
+
+/// Converts an `f32` to bfloat16 bits.
+pub fn f32_to_bf16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    if value.is_nan() {
+        // Keep it a quiet NaN even if the payload lives in the low bits.
+        return ((bits >> 16) | 0x0040) as u16;
+    }
+    let round = 0x7fff + ((bits >> 16) & 1);
+    (bits.wrapping_add(round) >> 16) as u16
+}
+
+/// Converts bfloat16 bits to an `f32`, exactly.
+pub fn bf16_to_f32(bf16: u16) -> f32 {
+    f32::from_bits((bf16 as u32) << 16)
+}
+
+/// Converts every value of `src` to half precision bits in `dst`.
+///
+/// # Panics
+///
+/// Panics if the slices have different lengths.
+pub fn f32_to_f16_slice(src: &[f32], dst: &mut [u16]) {
+    assert_eq!(src.len(), dst.len(), "slice lengths differ");
+    for (d, s) in dst.iter_mut().zip(src) {
+        *d = f32_to_f16(*s);
+    }
+}
+
+/// Converts half precision bits in `src` to `f32` values in `dst`.
+///
+/// # Panics
+///
+/// Panics if the slices have different lengths.
+pub fn f16_to_f32_slice(src: &[u16], dst: &mut [f32]) {
+    assert_eq!(src.len(), dst.len(), "slice lengths differ");
+    for (d, s) in dst.iter_mut().zip(src) {
+        *d = f16_to_f32(*s);
+    }
+}
+
+/// Converts every value of `src` to bfloat16 bits in `dst`.
+///
+/// # Panics
+///
+/// Panics if the slices have different lengths.
+pub fn f32_to_bf16_slice(src: &[f32], dst: &mut [u16]) {
+    assert_eq!(src.len(), dst.len(), "slice lengths differ");
+    for (d, s) in dst.iter_mut().zip(src) {
+        *d = f32_to_bf16(*s);
+    }
+}
+
+/// Converts bfloat16 bits in `src` to `f32` values in `dst`.
+///
+/// # Panics
+///
+/// Panics if the slices have different lengths.
+pub fn bf16_to_f32_slice(src: &[u16], dst: &mut [f32]) {
+    assert_eq!(src.len(), dst.len(), "slice lengths differ");
+    for (d, s) in dst.iter_mut().zip(src) {
+        *d = bf16_to_f32(*s);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_f16_known_values() {
+        let cases: [(f32, u16); 12] = [
+            (0.0, 0x0000),
+            (-0.0, 0x8000),
+            (1.0, 0x3c00),
+            (-2.0, 0xc000),
+            (0.333_251_95, 0x3555),
+            (65504.0, 0x7bff),
+            (f32::INFINITY, 0x7c00),
+            (f32::NEG_INFINITY, 0xfc00),
+            // Smallest normal and subnormal values.
+            (6.103_515_6e-5, 0x0400),
+            (5.960_464_5e-8, 0x0001),
+            // Overflow to infinity after rounding.
+            (65520.0, 0x7c00),
+            // Halfway to the smallest subnormal rounds to even, i.e. zero.
+            (2.980_232_2e-8, 0x0000),
+        ];
+        for (value, half) in cases {
+            assert_eq!(f32_to_f16(value), half, "{}", value);
+        }
+        assert!(f16_to_f32(f32_to_f16(f32::NAN)).is_nan());
+        // Ties round to even.
+        assert_eq!(f32_to_f16(f32::from_bits(0x3f80_1000)), 0x3c00);
+        assert_eq!(f32_to_f16(f32::from_bits(0x3f80_3000)), 0x3c02);
+        assert_eq!(f32_to_f16(f32::from_bits(0x3f80_1001)), 0x3c01);
+    }
+
+    #[test]
+    fn test_f16_round_trip_is_exact() {
+        for half in 0..=u16::MAX {
+            let value = f16_to_f32(half);
+            if value.is_nan() {
+                assert!(f16_to_f32(f32_to_f16(value)).is_nan());
+            } else {
+                assert_eq!(f32_to_f16(value), half, "{:#06x}", half);
+            }
+        }
+    }
+
+    #[test]
+    fn test_bf16() {
+        assert_eq!(f32_to_bf16(1.0), 0x3f80);
+        assert_eq!(f32_to_bf16(-2.0), 0xc000);
+        assert_eq!(bf16_to_f32(0x3f80), 1.0);
+        // 1 + 2^-8 is halfway between two bf16 values and rounds to even.
+        assert_eq!(f32_to_bf16(1.0 + 1.0 / 256.0), 0x3f80);
+        assert_eq!(f32_to_bf16(f32::MAX), 0x7f80);
+        assert!(bf16_to_f32(f32_to_bf16(f32::from_bits(0x7f80_0001))).is_nan());
+        for bf16 in 0..=u16::MAX {
+            let value = bf16_to_f32(bf16);
+            if !value.is_nan() {
+                assert_eq!(f32_to_bf16(value), bf16);
+            }
+        }
+    }
+
+    #[test]
+    fn test_slices() {
+        let values = [0.5f32, -1.25, 3.0, 1e-3];
+        let mut half = [0u16; 4];
+        let mut back = [0f32; 4];
+
+        f32_to_f16_slice(&values, &mut half);
+        f16_to_f32_slice(&half, &mut back);
+        for (a, b) in values.iter().zip(&back) {
+            assert!((a - b).abs() <= a.abs() / 1024.0);
+        }
+
+        f32_to_bf16_slice(&values, &mut half);
+        bf16_to_f32_slice(&half, &mut back);
+        for (a, b) in values.iter().zip(&back) {
+            assert!((a - b).abs() <= a.abs() / 128.0);
+        }
+    }
+}
@@ -0,0 +1,394 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+/// Dot product of two vectors.
+///
+/// # Panics
+///
+/// Panics if the vectors have different lengths.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::vector::dot;
+///
+/// assert_eq!(dot(&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]), 32.0);
+/// ```
+pub fn dot(a: &[f32], b: &[f32]) -> f32 {
+    assert_eq!(a.len(), b.len(), "vector dimensions differ");
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if x86::has_avx2_fma() {
+        // SAFETY: The required CPU features were detected above.
+        return unsafe { x86::dot(a, b) };
+    }
+    #[cfg(all(feature = "simd", target_arch = "aarch64"))]
+    {
+        // SAFETY: NEON is always available on aarch64.
+        return unsafe { neon::dot(a, b) };
+    }
+    #[allow(unreachable_code)]
+    scalar::dot(a, b)
+}
+
+/// Squared euclidean distance between two vectors.
+///
+/// # Panics
+///
+/// Panics if the vectors have different lengths.
+pub fn l2_squared(a: &[f32], b: &[f32]) -> f32 {
+    assert_eq!(a.len(), b.len(), "vector dimensions differ");
+
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    if x86::has_avx2_fma() {
+        // SAFETY: The required CPU features were detected above.
+        return unsafe { x86::l2_squared(a, b) };
+    }
+    #[cfg(all(feature = "simd", target_arch = "aarch64"))]
+    {
+        // SAFETY: NEON is always available on aarch64.
+        return unsafe { neon::l2_squared(a, b) };
+    }
+    #[allow(unreachable_code)]
+    scalar::l2_squared(a, b)
+}
+
+/// Cosine similarity of two vectors, in `[-1, 1]`.
+///
+/// Returns `0.0` if either vector has a zero norm.
+///
+/// # Panics
+///
+/// Panics if the vectors have different lengths.
+pub fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    assert_eq!(a.len(), b.len(), "vector dimensions differ");
+
+    let (dot, norm_a, norm_b) = {
+        #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+        if x86::has_avx2_fma() {
+            // SAFETY: The required CPU features were detected above.
+            unsafe { x86::dot_and_norms(a, b) }
+        } else {
+            scalar::dot_and_norms(a, b)
+        }
+        #[cfg(all(feature = "simd", target_arch = "aarch64"))]
+        // SAFETY: NEON is always available on aarch64.
+        unsafe {
+            neon::dot_and_norms(a, b)
+        }
+        #[cfg(not(all(feature = "simd", any(target_arch = "x86_64", target_arch = "aarch64"))))]
+        scalar::dot_and_norms(a, b)
+    };
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+    (dot / (libm::sqrtf(norm_a) * libm::sqrtf(norm_b))).clamp(-1.0, 1.0)
+}
+
+/// Scales `vector` to unit length and returns its original norm.
+///
+/// A zero vector is left untouched.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::vector::normalize_in_place;
+///
+/// let mut v = [3.0, 4.0];
+/// assert_eq!(normalize_in_place(&mut v), 5.0);
+/// assert_eq!(v, [0.6, 0.8]);
+/// ```
+pub fn normalize_in_place(vector: &mut [f32]) -> f32 {
+    let norm = libm::sqrtf(dot(vector, vector));
+    if norm > 0.0 {
+        let inv = 1.0 / norm;
+        for x in vector.iter_mut() {
+            *x *= inv;
+        }
+    }
+    norm
+}
+
+/// Portable implementations, written so that the compiler can auto-vectorize
+/// them with whatever the target supports.
+mod scalar {
+    const LANES: usize = 8;
+
+    pub(super) fn dot(a: &[f32], b: &[f32]) -> f32 {
+        let mut acc = [0.0f32; LANES];
+        let (a_chunks, b_chunks) = (a.chunks_exact(LANES), b.chunks_exact(LANES));
+        let tail: f32 = a_chunks
+            .remainder()
+            .iter()
+            .zip(b_chunks.remainder())
+            .map(|(x, y)| x * y)
+            .sum();
+        for (x, y) in a_chunks.zip(b_chunks) {
+            for i in 0..LANES {
+                acc[i] += x[i] * y[i];
+            }
+        }
+        acc.iter().sum::<f32>() + tail
+    }
+
+    pub(super) fn l2_squared(a: &[f32], b: &[f32]) -> f32 {
+        let mut acc = [0.0f32; LANES];
+        let (a_chunks, b_chunks) = (a.chunks_exact(LANES), b.chunks_exact(LANES));
+        let tail: f32 = a_chunks
+            .remainder()
+            .iter()
+            .zip(b_chunks.remainder())
+            .map(|(x, y)| (x - y) * (x - y))
+            .sum();
+        for (x, y) in a_chunks.zip(b_chunks) {
+            for i in 0..LANES {
+                let d = x[i] - y[i];
+                acc[i] += d * d;
+            }
+        }
+        acc.iter().sum::<f32>() + tail
+    }
+
+    pub(super) fn dot_and_norms(a: &[f32], b: &[f32]) -> (f32, f32, f32) {
+        (dot(a, b), dot(a, a), dot(b, b))
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "x86_64"))]
+mod x86 {
+    use core::arch::x86_64::*;
+
+    pub(super) fn has_avx2_fma() -> bool {
+        #[cfg(feature = "std")]
+        {
+            std::is_x86_feature_detected!("avx2") && std::is_x86_feature_detected!("fma")
+        }
+        #[cfg(not(feature = "std"))]
+        {
+            cfg!(all(target_feature = "avx2", target_feature = "fma"))
+        }
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    unsafe fn sum(v: __m256) -> f32 {
+        let low = _mm256_castps256_ps128(v);
+        let high = _mm256_extractf128_ps::<1>(v);
+        let sum = _mm_add_ps(low, high);
+        let sum = _mm_add_ps(sum, _mm_movehl_ps(sum, sum));
+        let sum = _mm_add_ss(sum, _mm_shuffle_ps::<0b01>(sum, sum));
+        _mm_cvtss_f32(sum)
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    pub(super) unsafe fn dot(a: &[f32], b: &[f32]) -> f32 {
+        let n = a.len() / 16 * 16;
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        let mut acc0 = _mm256_setzero_ps();
+        let mut acc1 = _mm256_setzero_ps();
+        let mut i = 0;
+        while i < n {
+            acc0 = _mm256_fmadd_ps(_mm256_loadu_ps(pa.add(i)), _mm256_loadu_ps(pb.add(i)), acc0);
+            acc1 = _mm256_fmadd_ps(
+                _mm256_loadu_ps(pa.add(i + 8)),
+                _mm256_loadu_ps(pb.add(i + 8)),
+                acc1,
+            );
+            i += 16;
+        }
+        sum(_mm256_add_ps(acc0, acc1)) + super::scalar::dot(&a[n..], &b[n..])
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    pub(super) unsafe fn l2_squared(a: &[f32], b: &[f32]) -> f32 {
+        let n = a.len() / 16 * 16;
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        let mut acc0 = _mm256_setzero_ps();
+        let mut acc1 = _mm256_setzero_ps();
+        let mut i = 0;
+        while i < n {
+            let d0 = _mm256_sub_ps(_mm256_loadu_ps(pa.add(i)), _mm256_loadu_ps(pb.add(i)));
+            let d1 = _mm256_sub_ps(
+                _mm256_loadu_ps(pa.add(i + 8)),
+                _mm256_loadu_ps(pb.add(i + 8)),
+            );
+            acc0 = _mm256_fmadd_ps(d0, d0, acc0);
+            acc1 = _mm256_fmadd_ps(d1, d1, acc1);
+            i += 16;
+        }
+        sum(_mm256_add_ps(acc0, acc1)) + super::scalar::l2_squared(&a[n..], &b[n..])
+    }
+
+    #[target_feature(enable = "avx2,fma")]
+    pub(super) unsafe fn dot_and_norms(a: &[f32], b: &[f32]) -> (f32, f32, f32) {
+        let n = a.len() / 8 * 8;
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        let mut ab = _mm256_setzero_ps();
+        let mut aa = _mm256_setzero_ps();
+        let mut bb = _mm256_setzero_ps();
+        let mut i = 0;
+        while i < n {
+            let va = _mm256_loadu_ps(pa.add(i));
+            let vb = _mm256_loadu_ps(pb.add(i));
+            ab = _mm256_fmadd_ps(va, vb, ab);
+            aa = _mm256_fmadd_ps(va, va, aa);
+            bb = _mm256_fmadd_ps(vb, vb, bb);
+            i += 8;
+        }
+        let (dot, norm_a, norm_b) = super::scalar::dot_and_norms(&a[n..], &b[n..]);
+        (sum(ab) + dot, sum(aa) + norm_a, sum(bb) + norm_b)
+    }
+}
+
+#[cfg(all(feature = "simd", target_arch = "aarch64"))]
+mod neon {
+    use core::arch::aarch64::*;
+
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn dot(a: &[f32], b: &[f32]) -> f32 {
+        let n = a.len() / 8 * 8;
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        let mut acc0 = vdupq_n_f32(0.0);
+        let mut acc1 = vdupq_n_f32(0.0);
+        let mut i = 0;
+        while i < n {
+            acc0 = vfmaq_f32(acc0, vld1q_f32(pa.add(i)), vld1q_f32(pb.add(i)));
+            acc1 = vfmaq_f32(acc1, vld1q_f32(pa.add(i + 4)), vld1q_f32(pb.add(i + 4)));
+            i += 8;
+        }
+        vaddvq_f32(vaddq_f32(acc0, acc1)) + super::scalar::dot(&a[n..], &b[n..])
+    }
+
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn l2_squared(a: &[f32], b: &[f32]) -> f32 {
+        let n = a.len() / 8 * 8;
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        let mut acc0 = vdupq_n_f32(0.0);
+        let mut acc1 = vdupq_n_f32(0.0);
+        let mut i = 0;
+        while i < n {
+            let d0 = vsubq_f32(vld1q_f32(pa.add(i)), vld1q_f32(pb.add(i)));
+            let d1 = vsubq_f32(vld1q_f32(pa.add(i + 4)), vld1q_f32(pb.add(i + 4)));
+            acc0 = vfmaq_f32(acc0, d0, d0);
+            acc1 = vfmaq_f32(acc1, d1, d1);
+            i += 8;
+        }
+        vaddvq_f32(vaddq_f32(acc0, acc1)) + super::scalar::l2_squared(&a[n..], &b[n..])
+    }
+
+    #[target_feature(enable = "neon")]
+    pub(super) unsafe fn dot_and_norms(a: &[f32], b: &[f32]) -> (f32, f32, f32) {
+        let n = a.len() / 4 * 4;
+        let (pa, pb) = (a.as_ptr(), b.as_ptr());
+        let mut ab = vdupq_n_f32(0.0);
+        let mut aa = vdupq_n_f32(0.0);
+        let mut bb = vdupq_n_f32(0.0);
+        let mut i = 0;
+        while i < n {
+            let va = vld1q_f32(pa.add(i));
+            let vb = vld1q_f32(pb.add(i));
+            ab = vfmaq_f32(ab, va, vb);
+            aa = vfmaq_f32(aa, va, va);
+            bb = vfmaq_f32(bb, vb, vb);
+            i += 4;
+        }
+        let (dot, norm_a, norm_b) = super::scalar::dot_and_norms(&a[n..], &b[n..]);
+        (
+            vaddvq_f32(ab) + dot,
+            vaddvq_f32(aa) + norm_a,
+            vaddvq_f32(bb) + norm_b,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec::Vec;
+
+    fn sample(len: usize, seed: u32) -> Vec<f32> {
+        // A tiny LCG keeps the test deterministic without extra dependencies.
+        let mut state = seed;
+        (0..len)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                (state >> 8) as f32 / (1u32 << 24) as f32 - 0.5
+            })
+            .collect()
+    }
+
+    fn naive_dot(a: &[f32], b: &[f32]) -> f64 {
+        a.iter().zip(b).map(|(x, y)| *x as f64 * *y as f64).sum()
+    }
+
+    fn naive_l2(a: &[f32], b: &[f32]) -> f64 {
+        a.iter()
+            .zip(b)
+            .map(|(x, y)| (*x as f64 - *y as f64).powi(2))
+            .sum()
+    }
+
+    #[test]
+    fn test_kernels_match_naive() {
+        // Cover lengths around every unrolling boundary.
+        for len in (0..40).chain([127, 128, 129, 768, 1536]) {
+            let a = sample(len, 1);
+            let b = sample(len, 2);
+            assert!((dot(&a, &b) as f64 - naive_dot(&a, &b)).abs() < 1e-3);
+            assert!((l2_squared(&a, &b) as f64 - naive_l2(&a, &b)).abs() < 1e-3);
+
+            let expected = naive_dot(&a, &b) / (naive_dot(&a, &a) * naive_dot(&b, &b)).sqrt();
+            if len > 0 {
+                assert!((cosine(&a, &b) as f64 - expected).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn test_simple_values() {
+        assert_eq!(dot(&[1.0, 2.0, 3.0], &[4.0, 5.0, 6.0]), 32.0);
+        assert_eq!(l2_squared(&[1.0, 2.0], &[4.0, 6.0]), 25.0);
+        assert_eq!(cosine(&[1.0, 0.0], &[0.0, 1.0]), 0.0);
+        assert_eq!(cosine(&[2.0, 0.0], &[3.0, 0.0]), 1.0);
+        assert_eq!(cosine(&[2.0, 0.0], &[-3.0, 0.0]), -1.0);
+        assert_eq!(cosine(&[0.0, 0.0], &[1.0, 1.0]), 0.0);
+        assert_eq!(dot(&[], &[]), 0.0);
+    }
+
+    #[test]
+    fn test_normalize_in_place() {
+        let mut v = sample(384, 3);
+        let norm = normalize_in_place(&mut v);
+        assert!(norm > 0.0);
+        assert!((dot(&v, &v) - 1.0).abs() < 1e-5);
+
+        let mut zero = [0.0f32; 4];
+        assert_eq!(normalize_in_place(&mut zero), 0.0);
+        assert_eq!(zero, [0.0; 4]);
+    }
+
+    #[test]
+    #[should_panic(expected = "vector dimensions differ")]
+    fn test_dimension_mismatch() {
+        dot(&[1.0], &[1.0, 2.0]);
+    }
+}
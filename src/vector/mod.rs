@@ -0,0 +1,51 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+//! Dense `f32` vector helpers for vector search scoring.
+//!
+//! The distance kernels use AVX2/FMA on x86_64 (detected at runtime under
+//! `std`, at compile time otherwise) and NEON on aarch64 when the `simd`
+//! feature is enabled, and fall back to portable scalar code otherwise.
+
+mod batch;
+mod convert;
+mod distance;
+mod topk;
+
+pub use batch::batch_distances;
+#[cfg(feature = "rayon")]
+pub use batch::par_batch_distances;
+pub use batch::Metric;
+pub use convert::bf16_to_f32;
+pub use convert::bf16_to_f32_slice;
+pub use convert::f16_to_f32;
+pub use convert::f16_to_f32_slice;
+pub use convert::f32_to_bf16;
+pub use convert::f32_to_bf16_slice;
+pub use convert::f32_to_f16;
+pub use convert::f32_to_f16_slice;
+pub use distance::cosine;
+pub use distance::dot;
+pub use distance::l2_squared;
+pub use distance::normalize_in_place;
+pub use topk::top_k;
+pub use topk::TopKCollector;
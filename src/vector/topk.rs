@@ -0,0 +1,239 @@
+// MIT License
+//
+// Copyright (C) INFINI Labs & INFINI LIMITED. <hello@infini.ltd>
+//
+// Permission is hereby granted, free of charge, to any person obtaining a copy
+// of this software and associated documentation files (the "Software"), to deal
+// in the Software without restriction, including without limitation the rights
+// to use, copy, modify, merge, publish, distribute, sublicense, and/or sell
+// copies of the Software, and to permit persons to whom the Software is
+// furnished to do so, subject to the following conditions:
+//
+// The above copyright notice and this permission notice shall be included in all
+// copies or substantial portions of the Software.
+//
+// THE SOFTWARE IS PROVIDED "AS IS", WITHOUT WARRANTY OF ANY KIND, EXPRESS OR
+// IMPLIED, INCLUDING BUT NOT LIMITED TO THE WARRANTIES OF MERCHANTABILITY,
+// FITNESS FOR A PARTICULAR PURPOSE AND NONINFRINGEMENT. IN NO EVENT SHALL THE
+// AUTHORS OR COPYRIGHT HOLDERS BE LIABLE FOR ANY CLAIM, DAMAGES OR OTHER
+// LIABILITY, WHETHER IN AN ACTION OF CONTRACT, TORT OR OTHERWISE, ARISING FROM,
+// OUT OF OR IN CONNECTION WITH THE SOFTWARE OR THE USE OR OTHER DEALINGS IN THE
+// SOFTWARE.
+
+use crate::collections::TopK;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+/// Below this `k`, a bounded heap beats quickselect because it avoids
+/// copying all `n` scores.
+const HEAP_MAX_K: usize = 64;
+
+/// Orders `(index, score)` pairs best first: higher scores first, NaN last,
+/// ties broken by the lower index.
+fn best_first(a: &(usize, f32), b: &(usize, f32)) -> Ordering {
+    match (a.1.is_nan(), b.1.is_nan()) {
+        (false, false) => b.1.partial_cmp(&a.1).unwrap(),
+        (true, false) => Ordering::Greater,
+        (false, true) => Ordering::Less,
+        (true, true) => Ordering::Equal,
+    }
+    .then(a.0.cmp(&b.0))
+}
+
+/// Returns the `k` highest scores as `(index, score)` pairs, best first.
+///
+/// Only the selected entries are sorted: small `k` use a bounded heap, larger
+/// ones a partial quickselect, both cheaper than sorting all scores. Equal
+/// scores are ordered by index and NaN ranks below everything else.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::vector::top_k;
+///
+/// let scores = [0.1, 0.9, 0.3, 0.7];
+/// assert_eq!(top_k(&scores, 2), vec![(1, 0.9), (3, 0.7)]);
+/// ```
+pub fn top_k(scores: &[f32], k: usize) -> Vec<(usize, f32)> {
+    if k == 0 {
+        return Vec::new();
+    }
+
+    if k <= HEAP_MAX_K && k < scores.len() {
+        let mut collector = TopKCollector::new(k);
+        for (index, &score) in scores.iter().enumerate() {
+            collector.push(index, score);
+        }
+        return collector.into_sorted_vec();
+    }
+
+    let mut entries: Vec<(usize, f32)> = scores.iter().copied().enumerate().collect();
+    if k < entries.len() {
+        entries.select_nth_unstable_by(k - 1, best_first);
+        entries.truncate(k);
+    }
+    entries.sort_unstable_by(best_first);
+    entries
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry(usize, f32);
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    /// The best entry is the greatest.
+    fn cmp(&self, other: &Self) -> Ordering {
+        best_first(&(other.0, other.1), &(self.0, self.1))
+    }
+}
+
+/// Keeps the `k` best `(index, score)` pairs of a stream of scores.
+///
+/// Useful when scores are produced incrementally, e.g. chunk by chunk, and
+/// never materialized in one slice.
+///
+/// # Examples
+///
+/// ```
+/// use pizza_common::vector::TopKCollector;
+///
+/// let mut collector = TopKCollector::new(2);
+/// for (index, score) in [0.5, 0.2, 0.8, 0.6].into_iter().enumerate() {
+///     collector.push(index, score);
+/// }
+/// assert_eq!(collector.threshold(), Some(0.6));
+/// assert_eq!(collector.into_sorted_vec(), vec![(2, 0.8), (3, 0.6)]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct TopKCollector {
+    top: TopK<Entry>,
+}
+
+impl TopKCollector {
+    pub fn new(k: usize) -> Self {
+        Self { top: TopK::new(k) }
+    }
+
+    /// Offers a score, returns `true` if it is currently kept.
+    pub fn push(&mut self, index: usize, score: f32) -> bool {
+        self.top.push(Entry(index, score))
+    }
+
+    /// The lowest kept score once `k` scores are collected.
+    ///
+    /// Scores below the threshold can no longer make it into the result,
+    /// which lets callers skip computing them.
+    pub fn threshold(&self) -> Option<f32> {
+        self.top.threshold().map(|entry| entry.1)
+    }
+
+    /// Adds the pairs of another collector, e.g. the results of another
+    /// segment, with the same tie breaking as if they had been pushed here.
+    pub fn merge(&mut self, other: TopKCollector) {
+        self.top.merge(other.top)
+    }
+
+    pub fn len(&self) -> usize {
+        self.top.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.top.is_empty()
+    }
+
+    /// Consumes the collector, returning the kept pairs best first.
+    pub fn into_sorted_vec(self) -> Vec<(usize, f32)> {
+        self.top
+            .into_sorted_vec()
+            .into_iter()
+            .map(|entry| (entry.0, entry.1))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+
+    fn full_sort(scores: &[f32], k: usize) -> Vec<(usize, f32)> {
+        let mut entries: Vec<(usize, f32)> = scores.iter().copied().enumerate().collect();
+        entries.sort_by(best_first);
+        entries.truncate(k);
+        entries
+    }
+
+    #[test]
+    fn test_top_k_matches_full_sort() {
+        let mut state = 7u32;
+        let scores: Vec<f32> = (0..1000)
+            .map(|_| {
+                state = state.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+                // Few distinct values, to exercise tie breaking.
+                (state >> 24) as f32 / 16.0
+            })
+            .collect();
+
+        for k in [1, 2, 10, 64, 65, 200, 999, 1000, 5000] {
+            assert_eq!(top_k(&scores, k), full_sort(&scores, k), "k = {}", k);
+        }
+    }
+
+    #[test]
+    fn test_top_k_edge_cases() {
+        assert!(top_k(&[], 3).is_empty());
+        assert!(top_k(&[1.0], 0).is_empty());
+        assert_eq!(
+            top_k(&[f32::NAN, 1.0, f32::NEG_INFINITY], 3)[..2],
+            [(1, 1.0), (2, f32::NEG_INFINITY)]
+        );
+        assert_eq!(top_k(&[2.0, f32::NAN, 2.0], 2), vec![(0, 2.0), (2, 2.0)]);
+    }
+
+    #[test]
+    fn test_collector() {
+        let mut collector = TopKCollector::new(3);
+        assert!(collector.is_empty());
+        assert!(collector.push(0, 1.0));
+        assert!(collector.push(1, 3.0));
+        assert_eq!(collector.threshold(), None);
+        assert!(collector.push(2, 2.0));
+        assert_eq!(collector.threshold(), Some(1.0));
+        assert!(!collector.push(3, 0.5));
+        assert!(collector.push(4, 5.0));
+        // An equal score with a higher index does not replace anything.
+        assert!(!collector.push(5, 2.0));
+        assert_eq!(collector.len(), 3);
+        assert_eq!(
+            collector.into_sorted_vec(),
+            vec![(4, 5.0), (1, 3.0), (2, 2.0)]
+        );
+
+        let mut other = TopKCollector::new(2);
+        other.push(7, 5.0);
+        other.push(0, 4.0);
+        let mut merged = TopKCollector::new(3);
+        merged.push(6, 5.0);
+        merged.push(2, 1.0);
+        merged.merge(other);
+        // Equal scores keep the lower index, wherever they come from.
+        assert_eq!(merged.into_sorted_vec(), vec![(6, 5.0), (7, 5.0), (0, 4.0)]);
+
+        let mut empty = TopKCollector::new(0);
+        assert!(!empty.push(0, 1.0));
+        assert!(empty.into_sorted_vec().is_empty());
+    }
+}